@@ -39,6 +39,31 @@ define_error! {
                     e.expected, e.actual)
             },
 
+        MultihopConnectionMismatch
+            { hop: usize, expected: ConnectionId, actual: ConnectionId }
+            | e | {
+                format_args!(
+                    "the proof of hop {0} of the multi-hop path is for connection {2}, expected connection {1}",
+                    e.hop, e.expected, e.actual)
+            },
+
+        MultihopHeightMismatch
+            { hop: usize, expected: Height, actual: Height }
+            | e | {
+                format_args!(
+                    "the proof of hop {0} of the multi-hop path is at height {2}, expected the consensus height {1} proven by the previous hop",
+                    e.hop, e.expected, e.actual)
+            },
+
+        VerifyMultihopFailed
+            { hop: usize }
+            [ client_error::Error ]
+            | e | {
+                format_args!(
+                    "error verifying the proof of hop {0} of the multi-hop path",
+                    e.hop)
+            },
+
         InvalidPacketCounterparty
             { port_id: PortId, channel_id: ChannelId }
             | e | {
@@ -69,6 +69,18 @@ pub fn try_from_tx(event: &tendermint::abci::Event) -> Option<IbcEvent> {
                 })
                 .ok()
         }
+        Ok(IbcEventType::ReceivePacket) => {
+            extract_packet_and_write_ack_from_tx(event)
+                .map(|(packet, write_ack)| {
+                    // This event should not have a write ack.
+                    debug_assert_eq!(write_ack.len(), 0);
+                    IbcEvent::ReceivePacket(ReceivePacket {
+                        height: Default::default(),
+                        packet,
+                    })
+                })
+                .ok()
+        }
         Ok(IbcEventType::WriteAck) => extract_packet_and_write_ack_from_tx(event)
             .map(|(packet, write_ack)| {
                 IbcEvent::WriteAcknowledgement(WriteAcknowledgement {
@@ -496,6 +508,10 @@ impl From<OpenConfirm> for AbciEvent {
 pub struct CloseInit(pub Attributes);
 
 impl CloseInit {
+    pub fn attributes(&self) -> &Attributes {
+        &self.0
+    }
+
     pub fn port_id(&self) -> &PortId {
         &self.0.port_id
     }
@@ -563,6 +579,10 @@ impl core::fmt::Display for CloseInit {
 pub struct CloseConfirm(pub Attributes);
 
 impl CloseConfirm {
+    pub fn attributes(&self) -> &Attributes {
+        &self.0
+    }
+
     pub fn channel_id(&self) -> Option<&ChannelId> {
         self.0.channel_id.as_ref()
     }
@@ -694,6 +714,18 @@ impl From<ReceivePacket> for IbcEvent {
     }
 }
 
+impl TryFrom<ReceivePacket> for AbciEvent {
+    type Error = Error;
+
+    fn try_from(v: ReceivePacket) -> Result<Self, Self::Error> {
+        let attributes = Vec::<Tag>::try_from(v.packet)?;
+        Ok(AbciEvent {
+            type_str: IbcEventType::ReceivePacket.as_str().to_string(),
+            attributes,
+        })
+    }
+}
+
 impl core::fmt::Display for ReceivePacket {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> Result<(), core::fmt::Error> {
         write!(f, "ReceivePacket - h:{}, {}", self.height, self.packet)
@@ -704,7 +736,10 @@ impl core::fmt::Display for ReceivePacket {
 pub struct WriteAcknowledgement {
     pub height: Height,
     pub packet: Packet,
-    #[serde(serialize_with = "crate::serializers::ser_hex_upper")]
+    #[serde(
+        serialize_with = "crate::serializers::ser_hex_upper",
+        deserialize_with = "crate::serializers::deser_hex_upper"
+    )]
     pub ack: Vec<u8>,
 }
 
@@ -968,6 +1003,11 @@ mod tests {
             packet: packet.clone(),
         };
         abci_events.push(AbciEvent::try_from(send_packet.clone()).unwrap());
+        let recv_packet = ReceivePacket {
+            height: Height::default(),
+            packet: packet.clone(),
+        };
+        abci_events.push(AbciEvent::try_from(recv_packet.clone()).unwrap());
         let write_ack = WriteAcknowledgement {
             height: Height::default(),
             packet: packet.clone(),
@@ -989,6 +1029,7 @@ mod tests {
             match try_from_tx(&event) {
                 Some(e) => match e {
                     IbcEvent::SendPacket(e) => assert_eq!(e.packet, send_packet.packet),
+                    IbcEvent::ReceivePacket(e) => assert_eq!(e.packet, recv_packet.packet),
                     IbcEvent::WriteAcknowledgement(e) => {
                         assert_eq!(e.packet, write_ack.packet);
                         assert_eq!(e.ack, write_ack.ack);
@@ -1001,4 +1042,16 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn recv_packet_event_type() {
+        // ibc-go emits the `recv_packet` event type for received packets,
+        // and no longer `receive_packet`.
+        assert!(matches!(
+            "recv_packet".parse::<IbcEventType>(),
+            Ok(IbcEventType::ReceivePacket)
+        ));
+        assert_eq!(IbcEventType::ReceivePacket.as_str(), "recv_packet");
+        assert!("receive_packet".parse::<IbcEventType>().is_err());
+    }
 }
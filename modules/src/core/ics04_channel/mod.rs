@@ -8,6 +8,7 @@ pub mod events;
 
 pub mod handler;
 pub mod msgs;
+pub mod multihop;
 pub mod packet;
 
 mod version;
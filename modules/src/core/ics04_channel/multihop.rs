@@ -0,0 +1,762 @@
+//! Proofs of multi-hop channels, which prove that a value is stored on the chain at the end
+//! of a path of connections to the chain at the start of that path, through the consensus
+//! states stored on every intermediate chain (see ADR 008).
+//!
+//! Only the domain types and their verification are implemented so far. The handlers
+//! still reject channels with more than one connection hop.
+
+use crate::core::ics02_client::client_consensus::{AnyConsensusState, ConsensusState};
+use crate::core::ics02_client::client_def::{AnyClient, ClientDef};
+use crate::core::ics02_client::client_state::{AnyClientState, ClientState};
+use crate::core::ics02_client::error::Error as ClientError;
+use crate::core::ics03_connection::connection::ConnectionEnd;
+use crate::core::ics04_channel::context::ChannelReader;
+use crate::core::ics04_channel::error::Error;
+use crate::core::ics23_commitment::commitment::{
+    CommitmentPrefix, CommitmentProofBytes, CommitmentRoot,
+};
+use crate::core::ics24_host::identifier::ConnectionId;
+use crate::prelude::*;
+use crate::Height;
+
+/// The proofs of the state of an intermediate chain of a multi-hop path,
+/// e.g. of chain `B` on the path `A -> B -> C`.
+///
+/// All the proofs are computed at `proof_height` on the intermediate chain, and are
+/// verified against the consensus state of the intermediate chain at that height.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HopProof {
+    /// The height of the intermediate chain at which the proofs were computed
+    pub proof_height: Height,
+    /// The connection on the intermediate chain towards the next chain of the path
+    pub connection_id: ConnectionId,
+    pub connection_end: ConnectionEnd,
+    pub connection_proof: CommitmentProofBytes,
+    /// The state of the client of that connection, which tracks the next chain of the path
+    pub client_state: AnyClientState,
+    pub client_state_proof: CommitmentProofBytes,
+    /// The consensus state of the next chain of the path stored in that client, against
+    /// which the proofs of the next hop (or the key proof) are verified
+    pub consensus_height: Height,
+    pub consensus_state: AnyConsensusState,
+    pub consensus_proof: CommitmentProofBytes,
+}
+
+/// The proof that a key/value is stored on the last chain of a multi-hop path.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MultihopProof {
+    /// The proofs of the intermediate chains, ordered from the start of the path
+    /// (the chain next to the verifying chain) towards its end.
+    pub hops: Vec<HopProof>,
+    /// The proof of the key/value on the last chain of the path, computed at the
+    /// consensus height of the last hop.
+    pub key_proof: CommitmentProofBytes,
+}
+
+impl MultihopProof {
+    /// The height of the chain next to the verifying chain at which the proofs
+    /// of the first hop were computed, if the proof has any hop.
+    pub fn proof_height(&self) -> Option<Height> {
+        self.hops.first().map(|hop| hop.proof_height)
+    }
+}
+
+/// The client and consensus state of the last chain of a multi-hop path, as proven by
+/// [`verify_multihop_proof`], against which the key proof is verified.
+pub struct KeyVerifier<'a> {
+    pub client_def: &'a AnyClient,
+    pub client_state: &'a AnyClientState,
+    /// The height of the last chain at which the key proof was computed
+    pub height: Height,
+    /// The commitment prefix of the last chain
+    pub prefix: &'a CommitmentPrefix,
+    pub proof: &'a CommitmentProofBytes,
+    pub root: &'a CommitmentRoot,
+}
+
+/// Verifies a multi-hop proof for a channel whose connection hops on the verifying
+/// chain are `connection_hops`, namely the identifiers of the connections on every chain
+/// of the path, starting with the connection on the verifying chain.
+///
+/// Every hop proves the connection end on an intermediate chain, which must match the
+/// next connection hop and be open, as well as the client state and consensus state of
+/// the next chain stored in the client of that connection. The proofs of each hop are
+/// verified against the consensus state proven by the previous hop, and must have been
+/// computed at its height, starting with the consensus state of the client of the first
+/// connection hop on the verifying chain.
+///
+/// Once all the hops are verified, `verify_key` is called with the client and consensus
+/// state of the last chain to verify the key proof, as done for single-hop proofs by
+/// e.g. [`ClientDef::verify_channel_state`].
+pub fn verify_multihop_proof<F>(
+    ctx: &dyn ChannelReader,
+    connection_hops: &[ConnectionId],
+    proof: &MultihopProof,
+    verify_key: F,
+) -> Result<(), Error>
+where
+    F: FnOnce(KeyVerifier<'_>) -> Result<(), ClientError>,
+{
+    if connection_hops.len() < 2 || connection_hops.len() != proof.hops.len() + 1 {
+        return Err(Error::invalid_connection_hops_length(
+            proof.hops.len() + 1,
+            connection_hops.len(),
+        ));
+    }
+
+    let connection_end = ctx.connection_end(&connection_hops[0])?;
+    if !connection_end.is_open() {
+        return Err(Error::connection_not_open(connection_hops[0].clone()));
+    }
+
+    let client_id = connection_end.client_id();
+    let mut client_state = ctx.client_state(client_id)?;
+    if client_state.is_frozen() {
+        return Err(Error::frozen_client(client_id.clone()));
+    }
+
+    let mut consensus_state = ctx.client_consensus_state(client_id, proof.hops[0].proof_height)?;
+    let mut client_def = AnyClient::from_client_type(client_state.client_type());
+    let mut prefix = connection_end.counterparty().prefix().clone();
+
+    for (i, hop) in proof.hops.iter().enumerate() {
+        let expected_connection_id = &connection_hops[i + 1];
+        if &hop.connection_id != expected_connection_id {
+            return Err(Error::multihop_connection_mismatch(
+                i,
+                expected_connection_id.clone(),
+                hop.connection_id.clone(),
+            ));
+        }
+
+        if !hop.connection_end.is_open() {
+            return Err(Error::connection_not_open(hop.connection_id.clone()));
+        }
+
+        // The proofs of this hop are verified against the consensus state proven by the
+        // previous hop, so they must have been computed at the height of that consensus state
+        if i > 0 && hop.proof_height != proof.hops[i - 1].consensus_height {
+            return Err(Error::multihop_height_mismatch(
+                i,
+                proof.hops[i - 1].consensus_height,
+                hop.proof_height,
+            ));
+        }
+
+        let root = consensus_state.root();
+        let next_client_id = hop.connection_end.client_id();
+
+        client_def
+            .verify_connection_state(
+                &client_state,
+                hop.proof_height,
+                &prefix,
+                &hop.connection_proof,
+                root,
+                &hop.connection_id,
+                &hop.connection_end,
+            )
+            .map_err(|e| Error::verify_multihop_failed(i, e))?;
+
+        client_def
+            .verify_client_full_state(
+                &client_state,
+                hop.proof_height,
+                &prefix,
+                &hop.client_state_proof,
+                root,
+                next_client_id,
+                &hop.client_state,
+            )
+            .map_err(|e| Error::verify_multihop_failed(i, e))?;
+
+        client_def
+            .verify_client_consensus_state(
+                &client_state,
+                hop.proof_height,
+                &prefix,
+                &hop.consensus_proof,
+                root,
+                next_client_id,
+                hop.consensus_height,
+                &hop.consensus_state,
+            )
+            .map_err(|e| Error::verify_multihop_failed(i, e))?;
+
+        if hop.client_state.is_frozen() {
+            return Err(Error::frozen_client(next_client_id.clone()));
+        }
+
+        client_state = hop.client_state.clone();
+        consensus_state = hop.consensus_state.clone();
+        client_def = AnyClient::from_client_type(client_state.client_type());
+        prefix = hop.connection_end.counterparty().prefix().clone();
+    }
+
+    let last_hop = proof.hops.len() - 1;
+
+    verify_key(KeyVerifier {
+        client_def: &client_def,
+        client_state: &client_state,
+        height: proof.hops[last_hop].consensus_height,
+        prefix: &prefix,
+        proof: &proof.key_proof,
+        root: consensus_state.root(),
+    })
+    .map_err(|e| Error::verify_multihop_failed(last_hop + 1, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    use core::time::Duration;
+
+    use ibc_proto::ibc::core::commitment::v1::MerkleProof as RawMerkleProof;
+    use ics23::{
+        calculate_existence_root, commitment_proof, tendermint_spec, CommitmentProof,
+        ExistenceProof, HashOp, InnerOp,
+    };
+    use tendermint::{Hash, Time};
+    use tendermint_proto::Protobuf;
+    use test_log::test;
+
+    use crate::clients::ics07_tendermint::client_state::{
+        AllowUpdate, ClientState as TendermintClientState,
+    };
+    use crate::clients::ics07_tendermint::consensus_state::ConsensusState as TendermintConsensusState;
+    use crate::core::ics02_client::client_consensus::AnyConsensusState;
+    use crate::core::ics02_client::client_def::ClientDef;
+    use crate::core::ics02_client::client_state::AnyClientState;
+    use crate::core::ics02_client::context::ClientKeeper;
+    use crate::core::ics02_client::error::Error as ClientError;
+    use crate::core::ics02_client::trust_threshold::TrustThreshold;
+    use crate::core::ics03_connection::connection::{
+        ConnectionEnd, Counterparty as ConnectionCounterparty, State as ConnectionState,
+    };
+    use crate::core::ics03_connection::version::get_compatible_versions;
+    use crate::core::ics04_channel::error::{Error, ErrorDetail};
+    use crate::core::ics23_commitment::commitment::{
+        CommitmentPrefix, CommitmentProofBytes, CommitmentRoot,
+    };
+    use crate::core::ics23_commitment::error::Error as Ics23Error;
+    use crate::core::ics23_commitment::merkle::apply_prefix;
+    use crate::core::ics23_commitment::specs::ProofSpecs;
+    use crate::core::ics24_host::identifier::{ChainId, ClientId, ConnectionId};
+    use crate::core::ics24_host::Path;
+    use crate::mock::client_state::{MockClientState, MockConsensusState};
+    use crate::mock::context::MockContext;
+    use crate::mock::header::MockHeader;
+    use crate::test_utils::get_dummy_proof;
+    use crate::timestamp::ZERO_DURATION;
+    use crate::Height;
+
+    use super::{verify_multihop_proof, HopProof, MultihopProof};
+
+    fn connection_end(state: ConnectionState, client_id: &str) -> ConnectionEnd {
+        ConnectionEnd::new(
+            state,
+            client_id.parse().unwrap(),
+            ConnectionCounterparty::new(
+                ClientId::default(),
+                Some(ConnectionId::default()),
+                Default::default(),
+            ),
+            get_compatible_versions(),
+            ZERO_DURATION,
+        )
+    }
+
+    /// The proof of an intermediate chain at `proof_height`, whose connection `connection_id`
+    /// is backed by a mock client of the next chain at `consensus_height`.
+    fn hop(proof_height: u64, connection_id: &str, consensus_height: u64) -> HopProof {
+        let header = MockHeader::new(Height::new(0, consensus_height));
+
+        HopProof {
+            proof_height: Height::new(0, proof_height),
+            connection_id: connection_id.parse().unwrap(),
+            connection_end: connection_end(ConnectionState::Open, "9999-mock-0"),
+            connection_proof: get_dummy_proof().into(),
+            client_state: AnyClientState::Mock(MockClientState::new(header)),
+            client_state_proof: get_dummy_proof().into(),
+            consensus_height: Height::new(0, consensus_height),
+            consensus_state: AnyConsensusState::Mock(MockConsensusState::new(header)),
+            consensus_proof: get_dummy_proof().into(),
+        }
+    }
+
+    /// A context for chain `A` of the path `A -> B -> C -> D`, with a client
+    /// of `B` which has a consensus state at height 10.
+    fn context() -> MockContext {
+        let client_id = "9999-mock-0".parse().unwrap();
+
+        MockContext::default()
+            .with_client(&client_id, Height::new(0, 10))
+            .with_connection(
+                ConnectionId::new(0),
+                connection_end(ConnectionState::Open, "9999-mock-0"),
+            )
+    }
+
+    fn connection_hops() -> Vec<ConnectionId> {
+        vec![
+            ConnectionId::new(0),
+            ConnectionId::new(1),
+            ConnectionId::new(2),
+        ]
+    }
+
+    fn proof() -> MultihopProof {
+        MultihopProof {
+            hops: vec![hop(10, "connection-1", 20), hop(20, "connection-2", 30)],
+            key_proof: get_dummy_proof().into(),
+        }
+    }
+
+    #[test]
+    fn verify_multihop_proof_through_hops() {
+        let proof = proof();
+
+        let mut key_height = None;
+        let res = verify_multihop_proof(&context(), &connection_hops(), &proof, |verifier| {
+            key_height = Some(verifier.height);
+            assert_eq!(verifier.client_state, &proof.hops[1].client_state);
+            assert_eq!(verifier.proof, &proof.key_proof);
+            Ok(())
+        });
+
+        assert!(res.is_ok(), "verification failed: {:?}", res);
+
+        // The key proof is verified at the consensus height of the last hop
+        assert_eq!(key_height, Some(Height::new(0, 30)));
+    }
+
+    #[test]
+    fn reject_multihop_proof_with_mismatched_hops() {
+        let verify = |proof: &MultihopProof, hops: &[ConnectionId]| {
+            verify_multihop_proof(&context(), hops, proof, |_| Ok(()))
+                .map_err(|Error(detail, _)| detail)
+        };
+
+        // One proof per intermediate chain is required
+        let mut missing_hop = proof();
+        missing_hop.hops.pop();
+        assert!(matches!(
+            verify(&missing_hop, &connection_hops()),
+            Err(ErrorDetail::InvalidConnectionHopsLength(_))
+        ));
+
+        // Single-hop channels do not have multi-hop proofs
+        let mut single_hop = proof();
+        single_hop.hops.clear();
+        assert!(matches!(
+            verify(&single_hop, &connection_hops()[..1]),
+            Err(ErrorDetail::InvalidConnectionHopsLength(_))
+        ));
+
+        // The connection of the second hop is not the one of the channel
+        let mut wrong_connection = proof();
+        wrong_connection.hops[1].connection_id = ConnectionId::new(7);
+        assert!(matches!(
+            verify(&wrong_connection, &connection_hops()),
+            Err(ErrorDetail::MultihopConnectionMismatch(e)) if e.hop == 1
+        ));
+
+        // The connection on an intermediate chain is not open
+        let mut closed_connection = proof();
+        closed_connection.hops[0].connection_end =
+            connection_end(ConnectionState::TryOpen, "9999-mock-0");
+        assert!(matches!(
+            verify(&closed_connection, &connection_hops()),
+            Err(ErrorDetail::ConnectionNotOpen(_))
+        ));
+
+        // The client of the next chain on an intermediate chain is frozen
+        let mut frozen_client = proof();
+        if let AnyClientState::Mock(client_state) = &mut frozen_client.hops[0].client_state {
+            client_state.frozen_height = Some(Height::new(0, 15));
+        }
+        assert!(matches!(
+            verify(&frozen_client, &connection_hops()),
+            Err(ErrorDetail::FrozenClient(_))
+        ));
+
+        // There is no consensus state of `B` at the proof height of the first hop
+        let mut missing_consensus_state = proof();
+        missing_consensus_state.hops[0].proof_height = Height::new(0, 11);
+        assert!(verify(&missing_consensus_state, &connection_hops()).is_err());
+    }
+
+    #[test]
+    fn reject_multihop_proof_with_mismatched_heights() {
+        // The second hop is not proven at the height of the consensus state of `C`
+        // proven by the first hop
+        let mut proof = proof();
+        proof.hops[1].proof_height = Height::new(0, 25);
+
+        let res = verify_multihop_proof(&context(), &connection_hops(), &proof, |_| Ok(()));
+
+        assert!(matches!(
+            res.map_err(|Error(detail, _)| detail),
+            Err(ErrorDetail::MultihopHeightMismatch(e))
+                if e.hop == 1
+                    && e.expected == Height::new(0, 20)
+                    && e.actual == Height::new(0, 25)
+        ));
+    }
+
+    #[test]
+    fn reject_multihop_proof_with_invalid_key_proof() {
+        let res = verify_multihop_proof(&context(), &connection_hops(), &proof(), |_| {
+            Err(ClientError::invalid_commitment_proof(
+                Ics23Error::empty_merkle_proof(),
+            ))
+        });
+
+        assert!(matches!(
+            res.map_err(|Error(detail, _)| detail),
+            Err(ErrorDetail::VerifyMultihopFailed(e)) if e.hop == 2
+        ));
+    }
+
+    const TM_CLIENT_ID: &str = "07-tendermint-0";
+
+    fn prefix() -> CommitmentPrefix {
+        CommitmentPrefix::from_bytes(b"ibc")
+    }
+
+    /// The specs of the proofs of the stores built by `commit`, which are accepted by
+    /// the Tendermint clients of `tm_client_state`
+    fn proof_specs() -> ProofSpecs {
+        vec![tendermint_spec(), tendermint_spec()].into()
+    }
+
+    fn tm_client_state(chain_id: &str) -> AnyClientState {
+        let client_state = TendermintClientState::new(
+            ChainId::new(chain_id.to_string(), 0),
+            TrustThreshold::ONE_THIRD,
+            Duration::from_secs(64000),
+            Duration::from_secs(128000),
+            Duration::from_secs(3),
+            Height::new(0, 100),
+            proof_specs(),
+            vec![],
+            AllowUpdate {
+                after_expiry: false,
+                after_misbehaviour: false,
+            },
+        )
+        .unwrap();
+
+        AnyClientState::Tendermint(client_state)
+    }
+
+    fn tm_consensus_state(root: CommitmentRoot) -> AnyConsensusState {
+        AnyConsensusState::Tendermint(TendermintConsensusState::new(
+            root,
+            Time::unix_epoch(),
+            Hash::None,
+        ))
+    }
+
+    fn tm_connection_end(delay_period: Duration) -> ConnectionEnd {
+        ConnectionEnd::new(
+            ConnectionState::Open,
+            TM_CLIENT_ID.parse().unwrap(),
+            ConnectionCounterparty::new(
+                ClientId::default(),
+                Some(ConnectionId::default()),
+                prefix(),
+            ),
+            get_compatible_versions(),
+            delay_period,
+        )
+    }
+
+    fn raw_proof(proof: ExistenceProof) -> ibc_proto::ics23::CommitmentProof {
+        let proof = CommitmentProof {
+            proof: Some(commitment_proof::Proof::Exist(proof)),
+        };
+
+        let mut encoded = Vec::new();
+        prost::Message::encode(&proof, &mut encoded).unwrap();
+        prost::Message::decode(encoded.as_slice()).unwrap()
+    }
+
+    /// The root of the Merkle tree of the given leaves, whose number must be a power of two,
+    /// and the existence proofs of the leaves, following the Tendermint proof spec.
+    fn merkle_tree(leaves: Vec<(Vec<u8>, Vec<u8>)>) -> (Vec<u8>, Vec<ExistenceProof>) {
+        let mut proofs: Vec<ExistenceProof> = leaves
+            .into_iter()
+            .map(|(key, value)| ExistenceProof {
+                key,
+                value,
+                leaf: tendermint_spec().leaf_spec,
+                path: vec![],
+            })
+            .collect();
+
+        // The leaves under each node of the current level of the tree
+        let mut nodes: Vec<Vec<usize>> = (0..proofs.len()).map(|i| vec![i]).collect();
+
+        while nodes.len() > 1 {
+            nodes = nodes
+                .chunks(2)
+                .map(|pair| {
+                    let (left, right) = (&pair[0], &pair[1]);
+                    let left_hash = calculate_existence_root(&proofs[left[0]]).unwrap();
+                    let right_hash = calculate_existence_root(&proofs[right[0]]).unwrap();
+
+                    for &i in left {
+                        proofs[i].path.push(InnerOp {
+                            hash: HashOp::Sha256 as i32,
+                            prefix: vec![1],
+                            suffix: right_hash.clone(),
+                        });
+                    }
+
+                    for &i in right {
+                        proofs[i].path.push(InnerOp {
+                            hash: HashOp::Sha256 as i32,
+                            prefix: [vec![1], left_hash.clone()].concat(),
+                            suffix: vec![],
+                        });
+                    }
+
+                    [left.as_slice(), right.as_slice()].concat()
+                })
+                .collect();
+        }
+
+        (calculate_existence_root(&proofs[0]).unwrap(), proofs)
+    }
+
+    /// Commits the given values in a store under the IBC prefix, which is itself committed
+    /// in the root store of the chain, as done by the Cosmos SDK.
+    /// Returns the root of the chain and the proofs of the values.
+    fn commit(values: Vec<(Path, Vec<u8>)>) -> (CommitmentRoot, Vec<CommitmentProofBytes>) {
+        let count = values.len();
+
+        let mut leaves: Vec<(Vec<u8>, Vec<u8>)> = values
+            .into_iter()
+            .map(|(path, value)| (path.to_string().into_bytes(), value))
+            .collect();
+
+        while !leaves.len().is_power_of_two() {
+            let padding = format!("padding-{}", leaves.len()).into_bytes();
+            leaves.push((padding.clone(), padding));
+        }
+
+        let (store_root, store_proofs) = merkle_tree(leaves);
+
+        let store_key = apply_prefix(&prefix(), vec![]).unwrap().key_path.remove(0);
+        let (root, mut root_proofs) = merkle_tree(vec![(store_key.into_bytes(), store_root)]);
+        let root_proof = root_proofs.remove(0);
+
+        let proofs = store_proofs
+            .into_iter()
+            .take(count)
+            .map(|store_proof| {
+                RawMerkleProof {
+                    proofs: vec![raw_proof(store_proof), raw_proof(root_proof.clone())],
+                }
+                .into()
+            })
+            .collect();
+
+        (root.into(), proofs)
+    }
+
+    /// The proofs of an intermediate chain at `proof_height`, whose connection `connection_id`
+    /// is backed by a Tendermint client of `next_chain_id` with the given consensus state at
+    /// `consensus_height`. Returns the root of the intermediate chain at `proof_height` too.
+    fn tm_hop(
+        proof_height: u64,
+        connection_id: &str,
+        next_chain_id: &str,
+        consensus_height: u64,
+        consensus_state: AnyConsensusState,
+    ) -> (CommitmentRoot, HopProof) {
+        let connection_id: ConnectionId = connection_id.parse().unwrap();
+        let client_id: ClientId = TM_CLIENT_ID.parse().unwrap();
+        let connection_end = tm_connection_end(ZERO_DURATION);
+        let client_state = tm_client_state(next_chain_id);
+
+        let (root, mut proofs) = commit(vec![
+            (
+                Path::Connections(connection_id.clone()),
+                connection_end.encode_vec().unwrap(),
+            ),
+            (
+                Path::ClientState(client_id.clone()),
+                client_state.encode_vec().unwrap(),
+            ),
+            (
+                Path::ClientConsensusState {
+                    client_id,
+                    epoch: 0,
+                    height: consensus_height,
+                },
+                consensus_state.encode_vec().unwrap(),
+            ),
+        ]);
+
+        let consensus_proof = proofs.pop().unwrap();
+        let client_state_proof = proofs.pop().unwrap();
+        let connection_proof = proofs.pop().unwrap();
+
+        let hop = HopProof {
+            proof_height: Height::new(0, proof_height),
+            connection_id,
+            connection_end,
+            connection_proof,
+            client_state,
+            client_state_proof,
+            consensus_height: Height::new(0, consensus_height),
+            consensus_state,
+            consensus_proof,
+        };
+
+        (root, hop)
+    }
+
+    /// A multi-hop proof made of Merkle proofs over the path `A -> B -> C -> D`,
+    /// of the connection end `connection-9` on `D`, and the context of `A`.
+    fn tm_proof() -> (MockContext, MultihopProof) {
+        let (root_d, mut key_proofs) = commit(vec![(
+            Path::Connections(ConnectionId::new(9)),
+            tm_connection_end(ZERO_DURATION).encode_vec().unwrap(),
+        )]);
+
+        let (root_c, hop_c) = tm_hop(
+            20,
+            "connection-2",
+            "chain-d",
+            30,
+            tm_consensus_state(root_d),
+        );
+        let (root_b, hop_b) = tm_hop(
+            10,
+            "connection-1",
+            "chain-c",
+            20,
+            tm_consensus_state(root_c),
+        );
+
+        let client_id: ClientId = TM_CLIENT_ID.parse().unwrap();
+        let mut context = MockContext::default()
+            .with_connection(ConnectionId::new(0), tm_connection_end(ZERO_DURATION));
+
+        context
+            .store_client_state(client_id.clone(), tm_client_state("chain-b"))
+            .unwrap();
+        context
+            .store_consensus_state(client_id, Height::new(0, 10), tm_consensus_state(root_b))
+            .unwrap();
+
+        let proof = MultihopProof {
+            hops: vec![hop_b, hop_c],
+            key_proof: key_proofs.remove(0),
+        };
+
+        (context, proof)
+    }
+
+    /// Verifies a proof of the connection end `connection-9` on `D`
+    fn verify_tm_proof(context: &MockContext, proof: &MultihopProof) -> Result<(), ErrorDetail> {
+        verify_multihop_proof(context, &connection_hops(), proof, |verifier| {
+            verifier.client_def.verify_connection_state(
+                verifier.client_state,
+                verifier.height,
+                verifier.prefix,
+                verifier.proof,
+                verifier.root,
+                &ConnectionId::new(9),
+                &tm_connection_end(ZERO_DURATION),
+            )
+        })
+        .map_err(|Error(detail, _)| detail)
+    }
+
+    fn failed_hop(res: Result<(), ErrorDetail>) -> Option<usize> {
+        match res {
+            Err(ErrorDetail::VerifyMultihopFailed(e)) => Some(e.hop),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn verify_multihop_merkle_proof() {
+        let (context, proof) = tm_proof();
+
+        let res = verify_tm_proof(&context, &proof);
+        assert!(res.is_ok(), "verification failed: {:?}", res);
+    }
+
+    #[test]
+    fn reject_multihop_merkle_proof_with_substituted_state() {
+        let (context, proof) = tm_proof();
+
+        // A connection end of `B` other than the one committed
+        let mut connection_end = proof.clone();
+        connection_end.hops[0].connection_end = tm_connection_end(Duration::from_secs(10));
+        assert_eq!(
+            failed_hop(verify_tm_proof(&context, &connection_end)),
+            Some(0)
+        );
+
+        // A client state of `D` on `C` other than the one committed
+        let mut client_state = proof.clone();
+        client_state.hops[1].client_state = tm_client_state("chain-e");
+        assert_eq!(
+            failed_hop(verify_tm_proof(&context, &client_state)),
+            Some(1)
+        );
+
+        // A consensus state of `C` on `B` other than the one committed, whose root
+        // would otherwise be used to verify the next hop
+        let mut consensus_state = proof.clone();
+        consensus_state.hops[0].consensus_state = tm_consensus_state(vec![1; 32].into());
+        assert_eq!(
+            failed_hop(verify_tm_proof(&context, &consensus_state)),
+            Some(0)
+        );
+
+        // A value of `D` other than the one committed
+        let res = verify_multihop_proof(&context, &connection_hops(), &proof, |verifier| {
+            verifier.client_def.verify_connection_state(
+                verifier.client_state,
+                verifier.height,
+                verifier.prefix,
+                verifier.proof,
+                verifier.root,
+                &ConnectionId::new(9),
+                &tm_connection_end(Duration::from_secs(10)),
+            )
+        })
+        .map_err(|Error(detail, _)| detail);
+        assert_eq!(failed_hop(res), Some(2));
+    }
+
+    #[test]
+    fn reject_multihop_merkle_proof_with_mismatched_heights() {
+        let (context, proof) = tm_proof();
+
+        // The consensus state of `C` is not stored at the declared height on `B`
+        let mut consensus_height = proof.clone();
+        consensus_height.hops[0].consensus_height = Height::new(0, 25);
+        consensus_height.hops[1].proof_height = Height::new(0, 25);
+        assert_eq!(
+            failed_hop(verify_tm_proof(&context, &consensus_height)),
+            Some(0)
+        );
+
+        // The proofs of `C` are not computed at the height of the consensus state proven on `B`
+        let mut proof_height = proof;
+        proof_height.hops[1].proof_height = Height::new(0, 25);
+        assert!(matches!(
+            verify_tm_proof(&context, &proof_height),
+            Err(ErrorDetail::MultihopHeightMismatch(e)) if e.hop == 1
+        ));
+    }
+}
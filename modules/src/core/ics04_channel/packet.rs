@@ -104,7 +104,10 @@ pub struct Packet {
     pub source_channel: ChannelId,
     pub destination_port: PortId,
     pub destination_channel: ChannelId,
-    #[serde(serialize_with = "crate::serializers::ser_hex_upper")]
+    #[serde(
+        serialize_with = "crate::serializers::ser_hex_upper",
+        deserialize_with = "crate::serializers::deser_hex_upper"
+    )]
     pub data: Vec<u8>,
     pub timeout_height: Height,
     pub timeout_timestamp: Timestamp,
@@ -426,4 +429,15 @@ mod tests {
         assert_eq!(raw, raw_back);
         assert_eq!(msg, msg_back);
     }
+
+    #[test]
+    fn serde_roundtrip() {
+        let packet = Packet::try_from(get_dummy_raw_packet(15, 0)).unwrap();
+
+        let json = serde_json::to_string(&packet).unwrap();
+        assert!(json.contains(r#""data":"00""#));
+
+        let packet_back: Packet = serde_json::from_str(&json).unwrap();
+        assert_eq!(packet, packet_back);
+    }
 }
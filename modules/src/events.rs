@@ -72,7 +72,10 @@ pub enum WithBlockDataType {
     CreateClient,
     UpdateClient,
     SendPacket,
+    RecvPacket,
     WriteAck,
+    AckPacket,
+    Timeout,
 }
 
 impl WithBlockDataType {
@@ -81,7 +84,10 @@ impl WithBlockDataType {
             WithBlockDataType::CreateClient => "create_client",
             WithBlockDataType::UpdateClient => "update_client",
             WithBlockDataType::SendPacket => "send_packet",
+            WithBlockDataType::RecvPacket => "recv_packet",
             WithBlockDataType::WriteAck => "write_acknowledgement",
+            WithBlockDataType::AckPacket => "acknowledge_packet",
+            WithBlockDataType::Timeout => "timeout_packet",
         }
     }
 }
@@ -108,7 +114,7 @@ const CHANNEL_CLOSE_INIT_EVENT: &str = "channel_close_init";
 const CHANNEL_CLOSE_CONFIRM_EVENT: &str = "channel_close_confirm";
 /// Packet event types
 const SEND_PACKET_EVENT: &str = "send_packet";
-const RECEIVE_PACKET_EVENT: &str = "receive_packet";
+const RECEIVE_PACKET_EVENT: &str = "recv_packet";
 const WRITE_ACK_EVENT: &str = "write_acknowledgement";
 const ACK_PACKET_EVENT: &str = "acknowledge_packet";
 const TIMEOUT_EVENT: &str = "timeout_packet";
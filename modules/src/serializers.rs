@@ -1,3 +1,6 @@
+use crate::prelude::*;
+
+use serde::de::{Deserialize, Deserializer, Error as _};
 use serde::ser::{Serialize, Serializer};
 use subtle_encoding::{Encoding, Hex};
 
@@ -9,3 +12,14 @@ where
     let hex = Hex::upper_case().encode_to_string(data).unwrap();
     hex.serialize(serializer)
 }
+
+/// Deserializes the bytes serialized by [`ser_hex_upper`]
+pub fn deser_hex_upper<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let hex = String::deserialize(deserializer)?;
+    Hex::upper_case()
+        .decode(hex)
+        .map_err(|_| D::Error::custom("invalid hex string"))
+}
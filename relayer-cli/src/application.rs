@@ -47,6 +47,9 @@ pub struct CliApp {
     /// Toggle json output on/off. Changed with the global config option `-j` / `--json`.
     json_output: bool,
 
+    /// Toggle dry-run mode on/off. Changed with the global option `--dry-run`.
+    dry_run: bool,
+
     /// Path to the config file.
     config_path: Option<PathBuf>,
 }
@@ -61,6 +64,7 @@ impl Default for CliApp {
             config: CfgCell::default(),
             state: application::State::default(),
             json_output: false,
+            dry_run: false,
             config_path: None,
         }
     }
@@ -72,6 +76,11 @@ impl CliApp {
         self.json_output
     }
 
+    /// Whether or not transactions are simulated instead of sent
+    pub fn dry_run(&self) -> bool {
+        self.dry_run
+    }
+
     /// Returns the path to the configuration file
     pub fn config_path(&self) -> Option<&PathBuf> {
         self.config_path.as_ref()
@@ -171,6 +180,9 @@ impl Application for CliApp {
         // Update the `json_output` flag used by `conclude::Output`
         self.json_output = command.json;
 
+        // Update the `dry_run` flag used by the tx and create commands
+        self.dry_run = command.dry_run;
+
         if command.json {
             // Enable JSON by using the crate-level `Tracing`
             let tracing = JsonTracing::new(config.global)?;
@@ -14,6 +14,7 @@ use ibc_relayer::{
     config::Config,
 };
 
+use crate::application::app_reader;
 use crate::error::Error;
 
 #[derive(Clone, Debug)]
@@ -97,3 +98,9 @@ pub fn spawn_chain_counterparty<Chain: ChainHandle>(
         channel_connection_client.channel,
     ))
 }
+
+/// Returns true if the application global flag `--dry-run` is enabled,
+/// in which case transactions must be simulated instead of sent.
+pub fn is_dry_run() -> bool {
+    app_reader().dry_run()
+}
@@ -15,11 +15,15 @@ use crate::DEFAULT_CONFIG_PATH;
 use ibc_relayer::config::Config;
 
 use self::{
-    config::ConfigCmd, create::CreateCmds, health::HealthCheckCmd, keys::KeysCmd,
+    clear::ClearCmds, config::ConfigCmd, create::CreateCmds, health::HealthCheckCmd, keys::KeysCmd,
     listen::ListenCmd, misbehaviour::MisbehaviourCmd, query::QueryCmd, start::StartCmd, tx::TxCmd,
     update::UpdateCmds, upgrade::UpgradeCmds, version::VersionCmd,
 };
 
+#[cfg(feature = "replay")]
+use self::replay::ReplayCmd;
+
+mod clear;
 mod config;
 mod create;
 mod health;
@@ -27,6 +31,8 @@ mod keys;
 mod listen;
 mod misbehaviour;
 mod query;
+#[cfg(feature = "replay")]
+mod replay;
 mod start;
 mod tx;
 mod update;
@@ -77,6 +83,13 @@ pub enum CliCmd {
     #[clap(subcommand, about = "Create and send IBC transactions")]
     Tx(TxCmd),
 
+    /// The `clear` subcommand
+    #[clap(
+        subcommand,
+        about = "Clear objects, such as outstanding packets on a channel"
+    )]
+    Clear(ClearCmds),
+
     /// The `listen` subcommand
     #[clap(about = "Listen to and display IBC events emitted by a chain")]
     Listen(ListenCmd),
@@ -85,6 +98,14 @@ pub enum CliCmd {
     #[clap(about = "Listen to client update IBC events and handles misbehaviour")]
     Misbehaviour(MisbehaviourCmd),
 
+    /// The `replay` subcommand
+    #[cfg(feature = "replay")]
+    #[clap(
+        about = "Replay recorded event batches against mock chains, showing which events are \
+                 dropped and which messages are built on a path"
+    )]
+    Replay(ReplayCmd),
+
     /// The `version` subcommand, retained for backward compatibility.
     #[clap(setting(Hidden))]
     Version(VersionCmd),
@@ -95,6 +116,14 @@ pub enum CliCmd {
 }
 
 /// This trait allows you to define how application configuration is loaded.
+impl CliCmd {
+    /// Whether the command sends transactions which cannot be simulated,
+    /// in which case it must not run in dry-run mode.
+    pub fn rejects_dry_run(&self) -> bool {
+        matches!(self, CliCmd::Start(_) | CliCmd::Misbehaviour(_))
+    }
+}
+
 impl Configurable<Config> for CliCmd {
     /// Location of the configuration file
     /// This is called only when the `-c` command-line option is omitted.
@@ -0,0 +1,195 @@
+//! `clear` subcommand
+
+use abscissa_core::{Clap, Command, Runnable};
+use serde::Serialize;
+
+use ibc::core::ics24_host::identifier::{ChainId, ChannelId, PortId};
+use ibc::Height;
+use ibc_relayer::chain::handle::{ChainHandle, ProdChainHandle};
+use ibc_relayer::link::{Link, LinkParameters, PacketSelection, RelaySummary, SequenceRange};
+
+use crate::cli_utils::{is_dry_run, spawn_chain_counterparty};
+use crate::commands::tx::packet::dry_run_link;
+use crate::conclude::Output;
+use crate::error::Error;
+use crate::prelude::*;
+
+#[derive(Command, Debug, Clap, Runnable)]
+pub enum ClearCmds {
+    /// Subcommand for clearing the pending `packets` of a channel
+    #[clap(about = "Clear the pending packets and acknowledgments of a channel")]
+    Packets(ClearPacketsCmd),
+}
+
+/// The relay summaries of both steps of the clearing
+#[derive(Serialize, Debug)]
+struct ClearPacketsSummary {
+    /// The packets received on, or timed out from, the counterparty chain
+    recv: RelaySummary,
+    /// The acknowledgments relayed back to the chain
+    ack: RelaySummary,
+}
+
+/// This command clears the pending packets sent on a channel in two steps:
+/// 1. relays the RecvPacket messages to the counterparty chain, or the Timeout
+///    messages back to the chain for the packets which timed out
+/// 2. relays the acknowledgments of the packets received on the counterparty back to the chain
+///
+/// Only the packets picked by the sequence ranges, the maximum number of packets and
+/// the height window are cleared, so that large backlogs can be cleared gradually.
+#[derive(Clone, Command, Debug, Clap)]
+pub struct ClearPacketsCmd {
+    #[clap(
+        required = true,
+        about = "identifier of the chain which sent the packets"
+    )]
+    chain_id: ChainId,
+
+    #[clap(
+        required = true,
+        about = "identifier of the port which sent the packets"
+    )]
+    port_id: PortId,
+
+    #[clap(
+        required = true,
+        about = "identifier of the channel which sent the packets"
+    )]
+    channel_id: ChannelId,
+
+    #[clap(
+        long,
+        use_delimiter = true,
+        value_name = "RANGES",
+        about = "comma-separated sequences or ranges of sequences of the packets to clear, e.g. '1..100,205' (default: all the pending packets)"
+    )]
+    sequences: Vec<SequenceRange>,
+
+    #[clap(
+        long,
+        value_name = "COUNT",
+        about = "maximum number of packets to clear in each step, starting with the lowest sequences"
+    )]
+    max_packets: Option<usize>,
+
+    #[clap(
+        long,
+        value_name = "HEIGHT",
+        about = "only clear the packets sent at or after this height of the chain"
+    )]
+    min_height: Option<u64>,
+
+    #[clap(
+        long,
+        value_name = "HEIGHT",
+        about = "only clear the packets sent at or before this height of the chain, which is also the height of the packet proofs"
+    )]
+    max_height: Option<u64>,
+}
+
+impl ClearPacketsCmd {
+    /// The selection of the packets to receive on, or time out from, the counterparty
+    fn recv_selection(&self, chain: &impl ChainHandle) -> PacketSelection {
+        let version = chain.id().version();
+
+        PacketSelection {
+            min_height: self.min_height.map(|h| Height::new(version, h)),
+            max_height: self.max_height.map(|h| Height::new(version, h)),
+            ..self.ack_selection()
+        }
+    }
+
+    /// The selection of the acknowledgments to relay back to the chain. The height window
+    /// only applies to the packets sent on the chain, as the acknowledgments are written
+    /// at the heights of the counterparty chain.
+    fn ack_selection(&self) -> PacketSelection {
+        PacketSelection {
+            sequences: self.sequences.clone(),
+            max_packets: self.max_packets,
+            ..Default::default()
+        }
+    }
+
+    fn links(
+        &self,
+    ) -> Result<
+        (
+            Link<ProdChainHandle, ProdChainHandle>,
+            Link<ProdChainHandle, ProdChainHandle>,
+        ),
+        Error,
+    > {
+        let config = app_config();
+
+        let (chains, _) = spawn_chain_counterparty::<ProdChainHandle>(
+            &config,
+            &self.chain_id,
+            &self.port_id,
+            &self.channel_id,
+        )?;
+
+        let opts = LinkParameters {
+            src_port_id: self.port_id.clone(),
+            src_channel_id: self.channel_id.clone(),
+        };
+        let recv_link = Link::new_from_opts(chains.src.clone(), chains.dst.clone(), opts, false)
+            .map_err(Error::link)?;
+
+        // The acknowledgments are relayed from the counterparty back to the chain
+        let opts = LinkParameters {
+            src_port_id: recv_link.a_to_b.dst_port_id().clone(),
+            src_channel_id: recv_link.a_to_b.dst_channel_id().clone(),
+        };
+        let ack_link =
+            Link::new_from_opts(chains.dst, chains.src, opts, false).map_err(Error::link)?;
+
+        Ok((recv_link, ack_link))
+    }
+}
+
+impl Runnable for ClearPacketsCmd {
+    fn run(&self) {
+        let (mut recv_link, mut ack_link) = match self.links() {
+            Ok(links) => links,
+            Err(e) => return Output::error(format!("{}", e)).exit(),
+        };
+
+        let recv_selection = self.recv_selection(recv_link.a_to_b.src_chain());
+        let ack_selection = self.ack_selection();
+
+        if is_dry_run() {
+            let res = recv_link
+                .build_recv_packet_messages(&recv_selection)
+                .map(|msgs| dry_run_link(&recv_link, msgs))
+                .and_then(|mut reports| {
+                    let msgs = ack_link.build_ack_packet_messages(&ack_selection)?;
+                    reports.extend(dry_run_link(&ack_link, msgs));
+                    Ok(reports)
+                })
+                .map_err(Error::link);
+
+            return match res {
+                Ok(reports) => Output::success(reports).exit(),
+                Err(e) => Output::error(format!("{}", e)).exit(),
+            };
+        }
+
+        let recv = match recv_link.send_recv_packet_messages(&recv_selection) {
+            Ok(summary) => summary,
+            Err(e) => return Output::error(format!("{}", Error::link(e))).exit(),
+        };
+
+        info!(
+            "relayed {} events while clearing the packets of {}/{} on {}",
+            recv.events.len(),
+            self.port_id,
+            self.channel_id,
+            self.chain_id
+        );
+
+        match ack_link.send_ack_packet_messages(&ack_selection) {
+            Ok(ack) => Output::success(ClearPacketsSummary { recv, ack }).exit(),
+            Err(e) => Output::error(format!("{}", Error::link(e))).exit(),
+        }
+    }
+}
@@ -1,12 +1,20 @@
 //! `create` subcommand
 use abscissa_core::{Clap, Command, Runnable};
 
+use ibc::core::ics24_host::identifier::ClientId;
+use ibc::tx_msg::Msg;
+use ibc_relayer::dry_run::{dry_run, DryRunReport};
+use ibc_relayer::foreign_client::{ForeignClient, ForeignClientError};
+
+use crate::cli_utils::ChainHandlePair;
 use crate::commands::create::channel::CreateChannelCommand;
 use crate::commands::create::connection::CreateConnectionCommand;
+use crate::commands::create::path::CreatePathCommand;
 use crate::commands::tx::client::TxCreateClientCmd;
 
 mod channel;
 mod connection;
+mod path;
 
 /// `create` subcommands
 #[derive(Command, Debug, Clap, Runnable)]
@@ -22,4 +30,29 @@ pub enum CreateCmds {
     /// Subcommand for creating a `channel`
     #[clap(about = "Create a new channel between two chains")]
     Channel(CreateChannelCommand),
+
+    /// Subcommand for creating a `path`
+    #[clap(
+        about = "Create a channel between two chains, reusing their existing clients, connection and channel"
+    )]
+    Path(CreatePathCommand),
+}
+
+/// Simulates the creation of a client on each of the two chains. The rest of
+/// the handshakes cannot be simulated, since they depend on the identifiers
+/// assigned to the clients on creation.
+fn dry_run_create_clients(
+    chains: ChainHandlePair,
+) -> Result<Vec<DryRunReport>, ForeignClientError> {
+    let client_a =
+        ForeignClient::restore(ClientId::default(), chains.src.clone(), chains.dst.clone());
+    let client_b = ForeignClient::restore(ClientId::default(), chains.dst, chains.src);
+
+    let msg_a = client_a.build_create_client()?;
+    let msg_b = client_b.build_create_client()?;
+
+    Ok(vec![
+        dry_run(&client_a.dst_chain(), vec![msg_a.to_any()]),
+        dry_run(&client_b.dst_chain(), vec![msg_b.to_any()]),
+    ])
 }
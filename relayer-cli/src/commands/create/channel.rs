@@ -11,7 +11,10 @@ use ibc_relayer::channel::Channel;
 use ibc_relayer::connection::Connection;
 use ibc_relayer::foreign_client::ForeignClient;
 
-use crate::cli_utils::{spawn_chain_runtime, ChainHandlePair};
+use ibc_relayer::dry_run::dry_run;
+
+use crate::cli_utils::{is_dry_run, spawn_chain_runtime, ChainHandlePair};
+use crate::commands::create::dry_run_create_clients;
 use crate::conclude::{exit_with_unrecoverable_error, Output};
 use crate::prelude::*;
 use ibc_relayer::config::default::connection_delay;
@@ -93,6 +96,13 @@ impl CreateChannelCommand {
 
         // let version = self.chain_a_id.version();
 
+        if is_dry_run() {
+            return match dry_run_create_clients(chains) {
+                Ok(reports) => Output::success(reports).exit(),
+                Err(e) => Output::error(format!("{}", e)).exit(),
+            };
+        }
+
         info!(
             "Creating new clients, new connection, and a new channel with order {}",
             self.order
@@ -166,6 +176,22 @@ impl CreateChannelCommand {
         let connection = Connection::find(client_a, client_b, &identified_end)
             .unwrap_or_else(exit_with_unrecoverable_error);
 
+        if is_dry_run() {
+            let chain_a = connection.src_chain();
+            let res = Channel::build_handshake_init(
+                connection,
+                self.order,
+                self.port_a.clone(),
+                self.port_b.clone(),
+                self.version.clone(),
+            );
+
+            return match res {
+                Ok(msgs) => Output::success(vec![dry_run(&chain_a, msgs)]).exit(),
+                Err(e) => Output::error(format!("{}", e)).exit(),
+            };
+        }
+
         let channel = Channel::new(
             connection,
             self.order,
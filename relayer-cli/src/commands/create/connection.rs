@@ -9,7 +9,10 @@ use ibc_relayer::chain::handle::ChainHandle;
 use ibc_relayer::connection::Connection;
 use ibc_relayer::foreign_client::ForeignClient;
 
-use crate::cli_utils::{spawn_chain_runtime, ChainHandlePair};
+use ibc_relayer::dry_run::dry_run;
+
+use crate::cli_utils::{is_dry_run, spawn_chain_runtime, ChainHandlePair};
+use crate::commands::create::dry_run_create_clients;
 use crate::conclude::{exit_with_unrecoverable_error, Output};
 use crate::prelude::*;
 
@@ -78,6 +81,13 @@ impl CreateConnectionCommand {
             .exit();
         }
 
+        if is_dry_run() {
+            return match dry_run_create_clients(chains) {
+                Ok(reports) => Output::success(reports).exit(),
+                Err(e) => Output::error(format!("{}", e)).exit(),
+            };
+        }
+
         info!(
             "Creating new clients hosted on chains {} and {}",
             self.chain_a_id, chain_b_id
@@ -160,6 +170,15 @@ impl CreateConnectionCommand {
 
         // All verification passed. Create the Connection object & do the handshake.
         let delay = Duration::from_secs(self.delay);
+
+        if is_dry_run() {
+            let chain_a = client_a.dst_chain();
+            return match Connection::build_handshake_init(client_a, client_b, delay) {
+                Ok(msgs) => Output::success(vec![dry_run(&chain_a, msgs)]).exit(),
+                Err(e) => Output::error(format!("{}", e)).exit(),
+            };
+        }
+
         match Connection::new(client_a, client_b, delay) {
             Ok(conn) => Output::success(conn).exit(),
             Err(e) => Output::error(format!("{}", e)).exit(),
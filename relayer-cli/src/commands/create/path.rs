@@ -0,0 +1,708 @@
+use std::path::{Path, PathBuf};
+
+use abscissa_core::{Clap, Command, Runnable};
+use clap::AppSettings;
+use serde::Serialize;
+
+use ibc::core::ics02_client::client_state::{
+    AnyClientState, ClientState, IdentifiedAnyClientState,
+};
+use ibc::core::ics03_connection::connection::{IdentifiedConnectionEnd, State as ConnectionState};
+use ibc::core::ics04_channel::channel::{Order, State as ChannelState};
+use ibc::core::ics24_host::identifier::{ChainId, ChannelId, ClientId, ConnectionId, PortId};
+use ibc::timestamp::Timestamp;
+use ibc::tx_msg::Msg;
+use ibc::Height;
+use ibc_proto::ibc::core::channel::v1::QueryConnectionChannelsRequest;
+use ibc_proto::ibc::core::client::v1::QueryClientStatesRequest;
+use ibc_proto::ibc::core::connection::v1::QueryClientConnectionsRequest;
+use ibc_relayer::chain::handle::ChainHandle;
+use ibc_relayer::channel::Channel;
+use ibc_relayer::config::default::connection_delay;
+use ibc_relayer::connection::{Connection, ConnectionSide};
+use ibc_relayer::dry_run::{dry_run, DryRunReport};
+use ibc_relayer::foreign_client::ForeignClient;
+
+use crate::cli_utils::{is_dry_run, ChainHandlePair};
+use crate::conclude::{exit_with_unrecoverable_error, Output};
+use crate::error::Error;
+use crate::prelude::*;
+
+#[derive(Clone, Command, Debug, Clap)]
+#[clap(setting(AppSettings::DisableVersionFlag))]
+pub struct CreatePathCommand {
+    #[clap(
+        required = true,
+        about = "identifier of the side `a` chain for the path"
+    )]
+    chain_a_id: ChainId,
+
+    #[clap(
+        required = true,
+        about = "identifier of the side `b` chain for the path"
+    )]
+    chain_b_id: ChainId,
+
+    #[clap(
+        long,
+        required = true,
+        about = "identifier of the side `a` port for the channel"
+    )]
+    port_a: PortId,
+
+    #[clap(
+        long,
+        required = true,
+        about = "identifier of the side `b` port for the channel"
+    )]
+    port_b: PortId,
+
+    #[clap(
+        short,
+        long,
+        about = "the channel ordering, valid options 'unordered' (default) and 'ordered'",
+        default_value_t
+    )]
+    order: Order,
+
+    #[clap(
+        short,
+        long = "channel-version",
+        alias = "version",
+        about = "the version for the channel"
+    )]
+    version: Option<String>,
+
+    #[clap(
+        short = 'f',
+        long,
+        about = "file to which the identifiers of the path are written, in JSON"
+    )]
+    output_file: Option<PathBuf>,
+}
+
+/// The identifiers of the objects making up one end of a path.
+/// The identifiers of the objects which do not exist yet are left empty.
+#[derive(Clone, Debug, Serialize)]
+pub struct PathEnd {
+    pub chain_id: ChainId,
+    pub client_id: Option<ClientId>,
+    pub connection_id: Option<ConnectionId>,
+    pub port_id: PortId,
+    pub channel_id: Option<ChannelId>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PathIdentifiers {
+    pub a: PathEnd,
+    pub b: PathEnd,
+}
+
+/// The outcome of `create path --dry-run`: the identifiers of the objects of the path
+/// which would be reused, and the simulation of the transactions creating the missing
+/// objects of the next step of the path. The later steps cannot be simulated, since
+/// they depend on the identifiers assigned to the objects created by that step.
+#[derive(Clone, Debug, Serialize)]
+pub struct PathDryRun {
+    pub path: PathIdentifiers,
+    pub reports: Vec<DryRunReport>,
+}
+
+/// The next step in creating a path, given the objects of the path which already exist.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum PathStep {
+    /// Create the clients which are missing on side `a` and on side `b`
+    CreateClients {
+        a: bool,
+        b: bool,
+    },
+    OpenConnection,
+    OpenChannel,
+    /// All the objects of the path exist
+    Done,
+}
+
+impl PathIdentifiers {
+    fn next_step(&self) -> PathStep {
+        if self.a.channel_id.is_some() {
+            PathStep::Done
+        } else if self.a.connection_id.is_some() {
+            PathStep::OpenChannel
+        } else if self.a.client_id.is_some() && self.b.client_id.is_some() {
+            PathStep::OpenConnection
+        } else {
+            PathStep::CreateClients {
+                a: self.a.client_id.is_none(),
+                b: self.b.client_id.is_none(),
+            }
+        }
+    }
+}
+
+impl Runnable for CreatePathCommand {
+    fn run(&self) {
+        let config = app_config();
+
+        let chains = ChainHandlePair::spawn(&config, &self.chain_a_id, &self.chain_b_id)
+            .unwrap_or_else(exit_with_unrecoverable_error);
+
+        if is_dry_run() {
+            let outcome = match self.dry_run_path(&chains) {
+                Ok(outcome) => outcome,
+                Err(e) => return Output::error(format!("{}", e)).exit(),
+            };
+
+            self.write_output_file(&outcome.path);
+
+            return Output::success(outcome).exit();
+        }
+
+        let path = match self.create_path(chains) {
+            Ok(path) => path,
+            Err(e) => return Output::error(format!("{}", e)).exit(),
+        };
+
+        self.write_output_file(&path);
+
+        Output::success(path).exit()
+    }
+}
+
+impl CreatePathCommand {
+    fn write_output_file(&self, path: &PathIdentifiers) {
+        if let Some(output_file) = &self.output_file {
+            if let Err(e) = write_path(output_file, path) {
+                Output::error(format!(
+                    "failed to write the identifiers of the path to {}: {}",
+                    output_file.display(),
+                    e
+                ))
+                .exit();
+            }
+        }
+    }
+
+    /// Returns the identifiers of the clients, connection and channel which would be
+    /// reused by [`Self::create_path`], and simulates the transactions of the next step
+    /// of the creation of the path, without creating anything.
+    fn dry_run_path<Chain: ChainHandle>(
+        &self,
+        chains: &ChainHandlePair<Chain>,
+    ) -> Result<PathDryRun, Error> {
+        let mut path = self.empty_path();
+
+        let connection = find_connection(chains)?;
+
+        match &connection {
+            Some(connection) => {
+                path.a.client_id = Some(connection.src_client_id().clone());
+                path.b.client_id = Some(connection.dst_client_id().clone());
+                path.a.connection_id = connection.src_connection_id().cloned();
+                path.b.connection_id = connection.dst_connection_id().cloned();
+
+                if let Some((channel_a, channel_b)) = self.find_channel(connection)? {
+                    path.a.channel_id = Some(channel_a);
+                    path.b.channel_id = Some(channel_b);
+                }
+            }
+            None => {
+                path.a.client_id = find_client(&chains.src, &chains.dst)?.map(|c| c.id);
+                path.b.client_id = find_client(&chains.dst, &chains.src)?.map(|c| c.id);
+            }
+        }
+
+        let reports = match path.next_step() {
+            PathStep::Done => vec![],
+            PathStep::CreateClients { a, b } => {
+                let mut reports = vec![];
+
+                if a {
+                    let client = ForeignClient::restore(
+                        ClientId::default(),
+                        chains.src.clone(),
+                        chains.dst.clone(),
+                    );
+                    let msg = client
+                        .build_create_client()
+                        .map_err(Error::foreign_client)?;
+                    reports.push(dry_run(&chains.src, vec![msg.to_any()]));
+                }
+
+                if b {
+                    let client = ForeignClient::restore(
+                        ClientId::default(),
+                        chains.dst.clone(),
+                        chains.src.clone(),
+                    );
+                    let msg = client
+                        .build_create_client()
+                        .map_err(Error::foreign_client)?;
+                    reports.push(dry_run(&chains.dst, vec![msg.to_any()]));
+                }
+
+                reports
+            }
+            PathStep::OpenConnection => {
+                // The connection handshake is initialized on side `a`
+                let connection = Connection {
+                    delay_period: connection_delay(),
+                    a_side: ConnectionSide::new(
+                        chains.dst.clone(),
+                        path.b.client_id.clone().unwrap_or_default(),
+                        None,
+                    ),
+                    b_side: ConnectionSide::new(
+                        chains.src.clone(),
+                        path.a.client_id.clone().unwrap_or_default(),
+                        None,
+                    ),
+                };
+
+                let msgs = connection.build_conn_init().map_err(Error::connection)?;
+                vec![dry_run(&chains.src, msgs)]
+            }
+            PathStep::OpenChannel => {
+                let connection = connection.expect("the path has a connection");
+
+                let msgs = Channel::build_handshake_init(
+                    connection,
+                    self.order,
+                    self.port_a.clone(),
+                    self.port_b.clone(),
+                    self.version.clone(),
+                )
+                .map_err(Error::channel)?;
+
+                vec![dry_run(&chains.src, msgs)]
+            }
+        };
+
+        Ok(PathDryRun { path, reports })
+    }
+
+    /// Creates the clients, connection and channel of the path which do not exist yet,
+    /// and returns the identifiers of all of them.
+    fn create_path<Chain: ChainHandle>(
+        &self,
+        chains: ChainHandlePair<Chain>,
+    ) -> Result<PathIdentifiers, Error> {
+        let connection = match find_connection(&chains)? {
+            Some(connection) => {
+                info!(
+                    "reusing connection {:?} between {} and {}",
+                    connection.src_connection_id(),
+                    chains.src.id(),
+                    chains.dst.id()
+                );
+
+                connection
+            }
+            None => {
+                let client_a = match find_client(&chains.src, &chains.dst)? {
+                    Some(client) => client,
+                    None => ForeignClient::new(chains.src.clone(), chains.dst.clone())
+                        .map_err(Error::foreign_client)?,
+                };
+
+                let client_b = match find_client(&chains.dst, &chains.src)? {
+                    Some(client) => client,
+                    None => ForeignClient::new(chains.dst.clone(), chains.src.clone())
+                        .map_err(Error::foreign_client)?,
+                };
+
+                Connection::new(client_a, client_b, connection_delay())
+                    .map_err(Error::connection)?
+            }
+        };
+
+        let mut path = self.empty_path();
+        path.a.client_id = Some(connection.src_client_id().clone());
+        path.b.client_id = Some(connection.dst_client_id().clone());
+        path.a.connection_id = connection.src_connection_id().cloned();
+        path.b.connection_id = connection.dst_connection_id().cloned();
+
+        let (channel_a, channel_b) = match self.find_channel(&connection)? {
+            Some(channel_ids) => {
+                info!(
+                    "reusing channel {} between {} and {}",
+                    channel_ids.0,
+                    chains.src.id(),
+                    chains.dst.id()
+                );
+
+                channel_ids
+            }
+            None => {
+                let channel = Channel::new(
+                    connection,
+                    self.order,
+                    self.port_a.clone(),
+                    self.port_b.clone(),
+                    self.version.clone(),
+                )
+                .map_err(Error::channel)?;
+
+                (
+                    channel.src_channel_id().cloned().unwrap_or_default(),
+                    channel.dst_channel_id().cloned().unwrap_or_default(),
+                )
+            }
+        };
+
+        path.a.channel_id = Some(channel_a);
+        path.b.channel_id = Some(channel_b);
+
+        Ok(path)
+    }
+
+    /// Finds an open channel over the given connection which has the requested
+    /// ports, ordering and version.
+    fn find_channel<Chain: ChainHandle>(
+        &self,
+        connection: &Connection<Chain, Chain>,
+    ) -> Result<Option<(ChannelId, ChannelId)>, Error> {
+        let connection_id = match connection.src_connection_id() {
+            Some(connection_id) => connection_id,
+            None => return Ok(None),
+        };
+
+        let req = QueryConnectionChannelsRequest {
+            connection: connection_id.to_string(),
+            pagination: ibc_proto::cosmos::base::query::pagination::all(),
+        };
+
+        let channels = connection
+            .src_chain()
+            .query_connection_channels(req)
+            .map_err(Error::relayer)?;
+
+        let found = channels.into_iter().find_map(|channel| {
+            let end = &channel.channel_end;
+
+            let compatible = end.state_matches(&ChannelState::Open)
+                && channel.port_id == self.port_a
+                && end.counterparty().port_id() == &self.port_b
+                && end.ordering() == &self.order
+                && self
+                    .version
+                    .as_ref()
+                    .map_or(true, |v| &end.version().to_string() == v);
+
+            if compatible {
+                let counterparty_channel_id = end.counterparty().channel_id()?.clone();
+                Some((channel.channel_id, counterparty_channel_id))
+            } else {
+                None
+            }
+        });
+
+        Ok(found)
+    }
+
+    fn empty_path(&self) -> PathIdentifiers {
+        PathIdentifiers {
+            a: PathEnd {
+                chain_id: self.chain_a_id.clone(),
+                client_id: None,
+                connection_id: None,
+                port_id: self.port_a.clone(),
+                channel_id: None,
+            },
+            b: PathEnd {
+                chain_id: self.chain_b_id.clone(),
+                client_id: None,
+                connection_id: None,
+                port_id: self.port_b.clone(),
+                channel_id: None,
+            },
+        }
+    }
+}
+
+/// Returns true if the client tracks `target_chain`, is not frozen, and its trusting
+/// period did not elapse since `last_update`, the timestamp of its latest consensus state.
+fn is_usable_client(
+    client_state: &AnyClientState,
+    target_chain: &ChainId,
+    last_update: Timestamp,
+    now: Timestamp,
+) -> bool {
+    let elapsed = now.duration_since(&last_update).unwrap_or_default();
+
+    client_state.chain_id() == *target_chain
+        && client_state.frozen_height().is_none()
+        && !client_state.expired(elapsed)
+}
+
+/// Returns true if the client hosted on `host_chain` tracks `target_chain`,
+/// and is neither frozen nor expired.
+fn client_usable<Chain: ChainHandle>(
+    host_chain: &Chain,
+    target_chain: &ChainId,
+    client: &IdentifiedAnyClientState,
+) -> bool {
+    if client.client_state.chain_id() != *target_chain {
+        return false;
+    }
+
+    let consensus_state = host_chain.query_consensus_state(
+        client.client_id.clone(),
+        client.client_state.latest_height(),
+        Height::zero(),
+    );
+
+    match consensus_state {
+        Ok(consensus_state) => is_usable_client(
+            &client.client_state,
+            target_chain,
+            consensus_state.timestamp(),
+            Timestamp::now(),
+        ),
+        Err(e) => {
+            warn!(
+                "skipping client {} on chain {}, failed to query its latest consensus state: {}",
+                client.client_id,
+                host_chain.id(),
+                e
+            );
+
+            false
+        }
+    }
+}
+
+/// Finds a client hosted on `host_chain` which tracks `target_chain`,
+/// and is neither frozen nor expired.
+fn find_client<Chain: ChainHandle>(
+    host_chain: &Chain,
+    target_chain: &Chain,
+) -> Result<Option<ForeignClient<Chain, Chain>>, Error> {
+    let req = QueryClientStatesRequest {
+        pagination: ibc_proto::cosmos::base::query::pagination::all(),
+    };
+
+    let clients = host_chain.query_clients(req).map_err(Error::relayer)?;
+
+    let found = clients
+        .into_iter()
+        .find(|client| client_usable(host_chain, &target_chain.id(), client));
+
+    Ok(found.map(|client| {
+        ForeignClient::restore(client.client_id, host_chain.clone(), target_chain.clone())
+    }))
+}
+
+/// Finds an open connection on the side `a` chain to the side `b` chain, with the default
+/// delay period, whose clients track each other's chain and are neither frozen nor expired.
+fn find_connection<Chain: ChainHandle>(
+    chains: &ChainHandlePair<Chain>,
+) -> Result<Option<Connection<Chain, Chain>>, Error> {
+    let req = QueryClientStatesRequest {
+        pagination: ibc_proto::cosmos::base::query::pagination::all(),
+    };
+
+    let clients = chains.src.query_clients(req).map_err(Error::relayer)?;
+
+    let candidate_clients = clients
+        .into_iter()
+        .filter(|client| client_usable(&chains.src, &chains.dst.id(), client));
+
+    for client in candidate_clients {
+        let req = QueryClientConnectionsRequest {
+            client_id: client.client_id.to_string(),
+        };
+
+        let connection_ids = chains
+            .src
+            .query_client_connections(req)
+            .map_err(Error::relayer)?;
+
+        for connection_id in connection_ids {
+            let connection_end = chains
+                .src
+                .query_connection(&connection_id, Height::zero())
+                .map_err(Error::relayer)?;
+
+            if !connection_end.state_matches(&ConnectionState::Open)
+                || connection_end.delay_period() != connection_delay()
+            {
+                continue;
+            }
+
+            let counterparty_client_id = connection_end.counterparty().client_id().clone();
+
+            let counterparty_client_usable = chains
+                .dst
+                .query_client_state(&counterparty_client_id, Height::zero())
+                .map_or(false, |client_state| {
+                    let client =
+                        IdentifiedAnyClientState::new(counterparty_client_id.clone(), client_state);
+                    client_usable(&chains.dst, &chains.src.id(), &client)
+                });
+
+            if !counterparty_client_usable {
+                continue;
+            }
+
+            let client_a =
+                ForeignClient::find(chains.dst.clone(), chains.src.clone(), &client.client_id);
+            let client_b = ForeignClient::find(
+                chains.src.clone(),
+                chains.dst.clone(),
+                &counterparty_client_id,
+            );
+
+            if let (Ok(client_a), Ok(client_b)) = (client_a, client_b) {
+                let identified_end = IdentifiedConnectionEnd::new(connection_id, connection_end);
+
+                if let Ok(connection) = Connection::find(client_a, client_b, &identified_end) {
+                    return Ok(Some(connection));
+                }
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+fn write_path(output_file: &Path, path: &PathIdentifiers) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(path).map_err(|e| e.to_string())?;
+
+    std::fs::write(output_file, json).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::time::Duration;
+
+    use ibc::clients::ics07_tendermint::client_state::{
+        AllowUpdate, ClientState as TendermintClientState,
+    };
+    use ibc::core::ics02_client::trust_threshold::TrustThreshold;
+    use ibc::core::ics23_commitment::specs::ProofSpecs;
+
+    const TRUSTING_PERIOD: Duration = Duration::from_secs(14 * 24 * 3600);
+
+    fn client_state(chain_id: &ChainId, frozen: bool) -> AnyClientState {
+        let client_state = TendermintClientState::new(
+            chain_id.clone(),
+            TrustThreshold::ONE_THIRD,
+            TRUSTING_PERIOD,
+            TRUSTING_PERIOD * 2,
+            Duration::from_secs(3),
+            Height::new(chain_id.version(), 10),
+            ProofSpecs::default(),
+            vec![],
+            AllowUpdate {
+                after_expiry: false,
+                after_misbehaviour: false,
+            },
+        )
+        .unwrap();
+
+        if frozen {
+            AnyClientState::Tendermint(client_state.with_frozen_height(Height::new(0, 1)).unwrap())
+        } else {
+            AnyClientState::Tendermint(client_state)
+        }
+    }
+
+    fn path_end(chain: &str, client: bool, connection: bool, channel: bool) -> PathEnd {
+        PathEnd {
+            chain_id: ChainId::new(chain.to_string(), 0),
+            client_id: client.then(ClientId::default),
+            connection_id: connection.then(ConnectionId::default),
+            port_id: PortId::transfer(),
+            channel_id: channel.then(ChannelId::default),
+        }
+    }
+
+    #[test]
+    fn usable_clients() {
+        let chain_b = ChainId::new("chain_b".to_string(), 0);
+        let last_update = Timestamp::from_nanoseconds(1_000_000_000_000_000_000).unwrap();
+        let after = |elapsed| (last_update + elapsed).unwrap();
+
+        let client = client_state(&chain_b, false);
+
+        assert!(is_usable_client(
+            &client,
+            &chain_b,
+            last_update,
+            after(Duration::from_secs(3600))
+        ));
+
+        // The trusting period elapsed since the latest consensus state
+        assert!(!is_usable_client(
+            &client,
+            &chain_b,
+            last_update,
+            after(TRUSTING_PERIOD + Duration::from_secs(1))
+        ));
+
+        // The client tracks another chain
+        let chain_c = ChainId::new("chain_c".to_string(), 0);
+        assert!(!is_usable_client(
+            &client,
+            &chain_c,
+            last_update,
+            last_update
+        ));
+
+        let frozen = client_state(&chain_b, true);
+        assert!(!is_usable_client(
+            &frozen,
+            &chain_b,
+            last_update,
+            last_update
+        ));
+    }
+
+    #[test]
+    fn next_path_step() {
+        let path = |a: PathEnd, b: PathEnd| PathIdentifiers { a, b };
+
+        assert_eq!(
+            path(
+                path_end("chain_a", false, false, false),
+                path_end("chain_b", true, false, false)
+            )
+            .next_step(),
+            PathStep::CreateClients { a: true, b: false }
+        );
+        assert_eq!(
+            path(
+                path_end("chain_a", false, false, false),
+                path_end("chain_b", false, false, false)
+            )
+            .next_step(),
+            PathStep::CreateClients { a: true, b: true }
+        );
+        assert_eq!(
+            path(
+                path_end("chain_a", true, false, false),
+                path_end("chain_b", true, false, false)
+            )
+            .next_step(),
+            PathStep::OpenConnection
+        );
+        assert_eq!(
+            path(
+                path_end("chain_a", true, true, false),
+                path_end("chain_b", true, true, false)
+            )
+            .next_step(),
+            PathStep::OpenChannel
+        );
+        assert_eq!(
+            path(
+                path_end("chain_a", true, true, true),
+                path_end("chain_b", true, true, true)
+            )
+            .next_step(),
+            PathStep::Done
+        );
+    }
+}
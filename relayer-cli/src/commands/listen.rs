@@ -1,5 +1,6 @@
 use alloc::sync::Arc;
 use core::{fmt, ops::Deref, str::FromStr};
+use std::path::PathBuf;
 use std::thread;
 
 use abscissa_core::{application::fatal_error, Runnable};
@@ -12,7 +13,8 @@ use ibc::{core::ics24_host::identifier::ChainId, events::IbcEvent};
 
 use ibc_relayer::{
     config::ChainConfig,
-    event::monitor::{EventMonitor, EventReceiver},
+    event::monitor::{EventMonitor, EventReceiver, NodeAddr},
+    event::recording::EventRecorder,
 };
 
 use crate::prelude::*;
@@ -65,6 +67,9 @@ pub struct ListenCmd {
 
     /// Event types to listen for
     events: Vec<EventFilter>,
+
+    /// File to which the event batches are appended
+    record: Option<PathBuf>,
 }
 
 impl ListenCmd {
@@ -81,7 +86,13 @@ impl ListenCmd {
             self.events.as_slice()
         };
 
-        listen(chain_config, events)
+        let recorder = self
+            .record
+            .as_deref()
+            .map(EventRecorder::create)
+            .transpose()?;
+
+        listen(chain_config, events, recorder)
     }
 }
 
@@ -119,13 +130,22 @@ fn augment_args(app: App<'_>, required: bool) -> App<'_> {
             )
             .validator(EventFilter::from_str),
     )
+    .arg(Arg::new("record").long("record").value_name("FILE").about(
+        "File to which all the event batches received are appended,\n\
+        to be replayed with `hermes replay`",
+    ))
 }
 
 impl FromArgMatches for ListenCmd {
     fn from_arg_matches(matches: &ArgMatches) -> Option<Self> {
         let chain_id = parse_chain_id(matches).expect("the required argument should be present");
         let events = parse_event_filters(matches).unwrap_or_default();
-        Some(ListenCmd { chain_id, events })
+        let record = parse_record(matches);
+        Some(ListenCmd {
+            chain_id,
+            events,
+            record,
+        })
     }
 
     fn update_from_arg_matches(&mut self, matches: &ArgMatches) {
@@ -135,6 +155,9 @@ impl FromArgMatches for ListenCmd {
         if let Some(events) = parse_event_filters(matches) {
             self.events = events;
         }
+        if let Some(record) = parse_record(matches) {
+            self.record = Some(record);
+        }
     }
 }
 
@@ -148,6 +171,10 @@ fn parse_event_filters(matches: &ArgMatches) -> Option<Vec<EventFilter>> {
     Some(vals.map(|s| EventFilter::from_str(s).unwrap()).collect())
 }
 
+fn parse_record(matches: &ArgMatches) -> Option<PathBuf> {
+    matches.value_of("record").map(PathBuf::from)
+}
+
 impl Runnable for ListenCmd {
     fn run(&self) {
         self.cmd()
@@ -155,10 +182,11 @@ impl Runnable for ListenCmd {
     }
 }
 
-/// Listen to events
+/// Listen to events, recording all the event batches received if a recorder is given
 pub fn listen(
     config: &ChainConfig,
     filters: &[EventFilter],
+    mut recorder: Option<EventRecorder>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let rt = Arc::new(TokioRuntime::new()?);
     let (event_monitor, rx) = subscribe(config, rt)?;
@@ -174,6 +202,10 @@ pub fn listen(
     while let Ok(event_batch) = rx.recv() {
         match event_batch {
             Ok(batch) => {
+                if let Some(recorder) = recorder.as_mut() {
+                    recorder.record(&batch);
+                }
+
                 let matching_events = batch
                     .events
                     .into_iter()
@@ -207,9 +239,22 @@ fn subscribe(
     chain_config: &ChainConfig,
     rt: Arc<TokioRuntime>,
 ) -> Result<(EventMonitor, EventReceiver), Box<dyn std::error::Error>> {
+    let primary = NodeAddr {
+        websocket_addr: chain_config.websocket_addr.clone(),
+        rpc_addr: chain_config.rpc_addr.clone(),
+    };
+
+    let node_addrs = core::iter::once(primary)
+        .chain(chain_config.backup_nodes.iter().map(|n| NodeAddr {
+            websocket_addr: n.websocket_addr.clone(),
+            rpc_addr: n.rpc_addr.clone(),
+        }))
+        .collect();
+
     let (mut event_monitor, rx, _) = EventMonitor::new(
         chain_config.id.clone(),
-        chain_config.websocket_addr.clone(),
+        node_addrs,
+        chain_config.event_source.clone(),
         rt,
     )
     .map_err(|e| format!("could not initialize event monitor: {}", e))?;
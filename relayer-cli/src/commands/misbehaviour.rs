@@ -6,7 +6,9 @@ use ibc::events::IbcEvent;
 use ibc_relayer::chain::handle::ChainHandle;
 use ibc_relayer::config::Config;
 use ibc_relayer::foreign_client::{ForeignClient, MisbehaviourResults};
+use serde::Serialize;
 use std::ops::Deref;
+use std::path::{Path, PathBuf};
 
 use crate::cli_utils::{spawn_chain_runtime, spawn_chain_runtime_generic};
 use crate::conclude::Output;
@@ -26,20 +28,79 @@ pub struct MisbehaviourCmd {
         about = "identifier of the client to be monitored for misbehaviour"
     )]
     client_id: ClientId,
+
+    #[clap(
+        long,
+        about = "check all the consensus states of the client for misbehaviour once, instead of monitoring client updates"
+    )]
+    scan_all: bool,
+
+    #[clap(
+        long,
+        requires = "scan-all",
+        about = "directory where evidence of misbehaviour is written to before it is submitted"
+    )]
+    evidence_dir: Option<PathBuf>,
 }
 
 impl Runnable for MisbehaviourCmd {
     fn run(&self) {
         let config = app_config();
 
-        let res = monitor_misbehaviour(&self.chain_id, &self.client_id, &config);
-        match res {
-            Ok(some_event) => Output::success(some_event).exit(),
-            Err(e) => Output::error(format!("{}", e)).exit(),
+        if self.scan_all {
+            let res = scan_misbehaviour(
+                &self.chain_id,
+                &self.client_id,
+                self.evidence_dir.as_deref(),
+                &config,
+            );
+
+            match res {
+                Ok(report) => Output::success(report).exit(),
+                Err(e) => Output::error(format!("{}", e)).exit(),
+            }
+        } else {
+            let res = monitor_misbehaviour(&self.chain_id, &self.client_id, &config);
+            match res {
+                Ok(some_event) => Output::success(some_event).exit(),
+                Err(e) => Output::error(format!("{}", e)).exit(),
+            }
         }
     }
 }
 
+/// Summary of a scan of all the consensus states of a client
+#[derive(Debug, Serialize)]
+pub struct ScanReport {
+    pub checked_heights: Vec<Height>,
+    pub skipped_heights: Vec<Height>,
+    pub misbehaviour: Option<String>,
+    pub evidence_file: Option<PathBuf>,
+    pub events: Vec<IbcEvent>,
+}
+
+pub fn scan_misbehaviour(
+    chain_id: &ChainId,
+    client_id: &ClientId,
+    evidence_dir: Option<&Path>,
+    config: &Config,
+) -> Result<ScanReport, Box<dyn std::error::Error>> {
+    let chain = spawn_chain_runtime(config, chain_id)
+        .map_err(|e| format!("could not spawn the chain runtime for {}: {}", chain_id, e))?;
+
+    let client = restore_client(chain, config, client_id.clone())?;
+
+    let scan = client.scan_misbehaviour_and_submit_evidence(evidence_dir)?;
+
+    Ok(ScanReport {
+        checked_heights: scan.checked_heights,
+        skipped_heights: scan.skipped_heights,
+        misbehaviour: scan.evidence.map(|e| e.misbehaviour.to_string()),
+        evidence_file: scan.evidence_file,
+        events: scan.submitted,
+    })
+}
+
 pub fn monitor_misbehaviour(
     chain_id: &ChainId,
     client_id: &ClientId,
@@ -97,6 +158,22 @@ fn misbehaviour_handling<Chain: ChainHandle>(
     client_id: ClientId,
     update: Option<UpdateClient>,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    let client = restore_client(chain, config, client_id)?;
+    let result = client.detect_misbehaviour_and_submit_evidence(update);
+    if let MisbehaviourResults::EvidenceSubmitted(events) = result {
+        info!("evidence submission result {:?}", events);
+    }
+
+    Ok(())
+}
+
+/// Restores the client with the given identifier hosted on `chain`, after
+/// checking that it is not frozen already.
+fn restore_client<Chain: ChainHandle>(
+    chain: Chain,
+    config: &Config,
+    client_id: ClientId,
+) -> Result<ForeignClient<Chain, Chain>, Box<dyn std::error::Error>> {
     let client_state = chain
         .query_client_state(&client_id, Height::zero())
         .map_err(|e| format!("could not query client state for {}: {}", client_id, e))?;
@@ -114,11 +191,5 @@ fn misbehaviour_handling<Chain: ChainHandle>(
             )
         })?;
 
-    let client = ForeignClient::restore(client_id, chain, counterparty_chain);
-    let result = client.detect_misbehaviour_and_submit_evidence(update);
-    if let MisbehaviourResults::EvidenceSubmitted(events) = result {
-        info!("evidence submission result {:?}", events);
-    }
-
-    Ok(())
+    Ok(ForeignClient::restore(client_id, chain, counterparty_chain))
 }
@@ -4,6 +4,7 @@ mod ack;
 mod acks;
 mod commitment;
 mod commitments;
+mod trace;
 mod unreceived_acks;
 mod unreceived_packets;
 
@@ -32,4 +33,8 @@ pub enum QueryPacketCmds {
     /// The `query packet unreceived-acks` subcommand
     #[clap(about = "Query unreceived acknowledgments")]
     UnreceivedAcks(unreceived_acks::QueryUnreceivedAcknowledgementCmd),
+
+    /// The `query packet trace` subcommand
+    #[clap(about = "Trace the lifecycle of a packet across both chains")]
+    Trace(trace::QueryPacketTraceCmd),
 }
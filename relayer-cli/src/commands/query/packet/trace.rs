@@ -0,0 +1,291 @@
+use abscissa_core::{Clap, Command, Runnable};
+use serde::Serialize;
+use subtle_encoding::{Encoding, Hex};
+
+use ibc::core::ics04_channel::channel::QueryPacketEventDataRequest;
+use ibc::core::ics04_channel::packet::Sequence;
+use ibc::core::ics24_host::identifier::{ChainId, ChannelId, PortId};
+use ibc::events::WithBlockDataType;
+use ibc::Height;
+use ibc_proto::ibc::core::channel::v1::{
+    PacketState as RawPacketState, QueryPacketAcknowledgementsRequest,
+    QueryPacketCommitmentsRequest, QueryUnreceivedPacketsRequest,
+};
+use ibc_relayer::chain::handle::{ChainHandle, ProdChainHandle};
+use ibc_relayer::chain::PacketTx;
+
+use crate::cli_utils::spawn_chain_counterparty;
+use crate::conclude::Output;
+use crate::error::Error;
+use crate::prelude::*;
+
+/// Where a packet stands in its lifecycle
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum PacketStatus {
+    /// No `SendPacket` event was found for the packet
+    NotFound,
+    /// The packet was sent but not received yet
+    Pending,
+    /// The packet was received, but the acknowledgement was not relayed back yet
+    Received,
+    Acknowledged,
+    TimedOut,
+}
+
+impl PacketStatus {
+    /// Derives the status of a packet from whether the transactions which sent, acknowledged
+    /// or timed out the packet were found, and from the current state of the packet.
+    /// A packet acknowledged or timed out is not found if these transactions were pruned.
+    fn derive(send: bool, ack: bool, timeout: bool, state: &PacketState) -> Self {
+        if ack {
+            PacketStatus::Acknowledged
+        } else if timeout {
+            PacketStatus::TimedOut
+        } else if state.received {
+            PacketStatus::Received
+        } else if send || state.commitment.is_some() {
+            PacketStatus::Pending
+        } else {
+            PacketStatus::NotFound
+        }
+    }
+}
+
+/// The current state of the packet on both chains
+#[derive(Serialize, Debug)]
+struct PacketState {
+    /// The packet commitment on the source chain, cleared once acknowledged or timed out
+    commitment: Option<String>,
+    /// Whether the destination chain has a receipt for the packet
+    received: bool,
+    /// The acknowledgement commitment on the destination chain
+    acknowledgement: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+struct PacketTrace {
+    src_chain_id: ChainId,
+    dst_chain_id: ChainId,
+    status: PacketStatus,
+    send: Option<PacketTx>,
+    recv: Option<PacketTx>,
+    write_ack: Option<PacketTx>,
+    ack: Option<PacketTx>,
+    timeout: Option<PacketTx>,
+    state: PacketState,
+}
+
+/// This command does the following:
+/// 1. queries the chain to get its counterparty chain, channel and port identifiers
+/// 2. queries the transactions that sent, acknowledged or timed out the packet on the chain,
+///    and the transactions that received it and wrote its acknowledgement on the counterparty
+/// 3. queries the current packet commitment on the chain, and the receipt and
+///    acknowledgement on the counterparty
+#[derive(Clone, Command, Debug, Clap)]
+pub struct QueryPacketTraceCmd {
+    #[clap(
+        required = true,
+        about = "identifier of the chain which sent the packet"
+    )]
+    chain_id: ChainId,
+
+    #[clap(
+        required = true,
+        about = "identifier of the port which sent the packet"
+    )]
+    port_id: PortId,
+
+    #[clap(
+        required = true,
+        about = "identifier of the channel which sent the packet"
+    )]
+    channel_id: ChannelId,
+
+    #[clap(required = true, about = "sequence of the packet to trace")]
+    sequence: Sequence,
+}
+
+impl QueryPacketTraceCmd {
+    fn execute(&self) -> Result<PacketTrace, Error> {
+        let config = app_config();
+
+        debug!("Options: {:?}", self);
+
+        let (chains, channel) = spawn_chain_counterparty::<ProdChainHandle>(
+            &config,
+            &self.chain_id,
+            &self.port_id,
+            &self.channel_id,
+        )?;
+
+        let dst_port_id = channel.channel_end.remote.port_id.clone();
+        let dst_channel_id = channel
+            .channel_end
+            .remote
+            .channel_id
+            .clone()
+            .ok_or_else(|| Error::missing_counterparty_channel_id(channel.clone()))?;
+
+        let query_tx = |chain: &ProdChainHandle,
+                        event_id: WithBlockDataType|
+         -> Result<Option<PacketTx>, Error> {
+            let txs = chain
+                .query_packet_txs(QueryPacketEventDataRequest {
+                    event_id,
+                    source_port_id: self.port_id.clone(),
+                    source_channel_id: self.channel_id.clone(),
+                    destination_port_id: dst_port_id.clone(),
+                    destination_channel_id: dst_channel_id.clone(),
+                    sequences: vec![self.sequence],
+                    height: Height::zero(),
+                })
+                .map_err(Error::relayer)?;
+
+            Ok(txs.into_iter().next())
+        };
+
+        let send = query_tx(&chains.src, WithBlockDataType::SendPacket)?;
+        let recv = query_tx(&chains.dst, WithBlockDataType::RecvPacket)?;
+        let write_ack = query_tx(&chains.dst, WithBlockDataType::WriteAck)?;
+        let ack = query_tx(&chains.src, WithBlockDataType::AckPacket)?;
+        let timeout = query_tx(&chains.src, WithBlockDataType::Timeout)?;
+
+        let (commitments, _) = chains
+            .src
+            .query_packet_commitments(QueryPacketCommitmentsRequest {
+                port_id: self.port_id.to_string(),
+                channel_id: self.channel_id.to_string(),
+                pagination: ibc_proto::cosmos::base::query::pagination::all(),
+            })
+            .map_err(Error::relayer)?;
+
+        let received = chains
+            .dst
+            .query_unreceived_packets(QueryUnreceivedPacketsRequest {
+                port_id: dst_port_id.to_string(),
+                channel_id: dst_channel_id.to_string(),
+                packet_commitment_sequences: vec![self.sequence.into()],
+            })
+            .map_err(Error::relayer)?
+            .is_empty();
+
+        let (acknowledgements, _) = chains
+            .dst
+            .query_packet_acknowledgements(QueryPacketAcknowledgementsRequest {
+                port_id: dst_port_id.to_string(),
+                channel_id: dst_channel_id.to_string(),
+                pagination: ibc_proto::cosmos::base::query::pagination::all(),
+                packet_commitment_sequences: vec![self.sequence.into()],
+            })
+            .map_err(Error::relayer)?;
+
+        let state = PacketState {
+            commitment: packet_value(&commitments, self.sequence),
+            received,
+            acknowledgement: packet_value(&acknowledgements, self.sequence),
+        };
+
+        let status = PacketStatus::derive(send.is_some(), ack.is_some(), timeout.is_some(), &state);
+
+        Ok(PacketTrace {
+            src_chain_id: chains.src.id(),
+            dst_chain_id: chains.dst.id(),
+            status,
+            send,
+            recv,
+            write_ack,
+            ack,
+            timeout,
+            state,
+        })
+    }
+}
+
+/// Finds the value stored for the packet with the given sequence amongst the packet
+/// commitments or acknowledgements of a channel, returning `None` when there is no such value.
+fn packet_value(values: &[RawPacketState], sequence: Sequence) -> Option<String> {
+    values
+        .iter()
+        .find(|value| value.sequence == u64::from(sequence))
+        .map(|value| {
+            Hex::upper_case()
+                .encode_to_string(&value.data)
+                .unwrap_or_else(|_| format!("{:?}", value.data))
+        })
+}
+
+impl Runnable for QueryPacketTraceCmd {
+    fn run(&self) {
+        match self.execute() {
+            Ok(trace) => Output::success(trace).exit(),
+            Err(e) => Output::error(format!("{}", e)).exit(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(commitment: bool, received: bool, acknowledgement: bool) -> PacketState {
+        PacketState {
+            commitment: commitment.then(|| "AB".to_string()),
+            received,
+            acknowledgement: acknowledgement.then(|| "CD".to_string()),
+        }
+    }
+
+    #[test]
+    fn status_of_packet_not_sent() {
+        let status = PacketStatus::derive(false, false, false, &state(false, false, false));
+        assert_eq!(status, PacketStatus::NotFound);
+    }
+
+    #[test]
+    fn status_of_packet_in_flight() {
+        let status = PacketStatus::derive(true, false, false, &state(true, false, false));
+        assert_eq!(status, PacketStatus::Pending);
+
+        // The commitment is found even if the transaction which sent the packet was pruned
+        let status = PacketStatus::derive(false, false, false, &state(true, false, false));
+        assert_eq!(status, PacketStatus::Pending);
+
+        let status = PacketStatus::derive(true, false, false, &state(true, true, true));
+        assert_eq!(status, PacketStatus::Received);
+    }
+
+    #[test]
+    fn status_of_packet_completed() {
+        let status = PacketStatus::derive(true, true, false, &state(false, true, true));
+        assert_eq!(status, PacketStatus::Acknowledged);
+
+        let status = PacketStatus::derive(true, false, true, &state(false, false, false));
+        assert_eq!(status, PacketStatus::TimedOut);
+
+        // The packet was received, but not found as its transactions were pruned
+        let status = PacketStatus::derive(false, false, false, &state(false, true, true));
+        assert_eq!(status, PacketStatus::Received);
+    }
+
+    #[test]
+    fn packet_value_is_found_by_sequence() {
+        let values = vec![
+            RawPacketState {
+                port_id: "transfer".to_string(),
+                channel_id: "channel-0".to_string(),
+                sequence: 1,
+                data: vec![0x01],
+            },
+            RawPacketState {
+                port_id: "transfer".to_string(),
+                channel_id: "channel-0".to_string(),
+                sequence: 2,
+                data: vec![0xab, 0xcd],
+            },
+        ];
+
+        assert_eq!(packet_value(&values, 2.into()), Some("ABCD".to_string()));
+        assert_eq!(packet_value(&values, 3.into()), None);
+    }
+}
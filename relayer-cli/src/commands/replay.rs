@@ -0,0 +1,84 @@
+use std::path::PathBuf;
+
+use abscissa_core::{Clap, Command, Runnable};
+
+use ibc::core::ics04_channel::channel::Order;
+use ibc::core::ics24_host::identifier::{ChainId, ChannelId, PortId};
+use ibc_relayer::event::recording::read_recording;
+use ibc_relayer::link::replay::{replay, ReplayPath};
+
+use crate::conclude::Output;
+use crate::prelude::*;
+
+#[derive(Clone, Command, Debug, Clap)]
+pub struct ReplayCmd {
+    #[clap(
+        required = true,
+        about = "recording of event batches, written by `hermes start --record` or `hermes listen --record`"
+    )]
+    file: PathBuf,
+
+    #[clap(long, required = true, about = "identifier of the source chain")]
+    src_chain: ChainId,
+
+    #[clap(long, required = true, about = "identifier of the source port")]
+    src_port: PortId,
+
+    #[clap(long, required = true, about = "identifier of the source channel")]
+    src_channel: ChannelId,
+
+    #[clap(long, required = true, about = "identifier of the destination chain")]
+    dst_chain: ChainId,
+
+    #[clap(long, required = true, about = "identifier of the destination port")]
+    dst_port: PortId,
+
+    #[clap(long, required = true, about = "identifier of the destination channel")]
+    dst_channel: ChannelId,
+
+    #[clap(
+        short,
+        long,
+        about = "the channel ordering, valid options 'unordered' (default) and 'ordered'",
+        default_value_t
+    )]
+    order: Order,
+}
+
+impl Runnable for ReplayCmd {
+    fn run(&self) {
+        let config = app_config();
+
+        let recording = match read_recording(&self.file) {
+            Ok(recording) => recording,
+            Err(e) => return Output::error(format!("{}", e)).exit(),
+        };
+
+        let path = ReplayPath {
+            src_chain_id: self.src_chain.clone(),
+            src_port_id: self.src_port.clone(),
+            src_channel_id: self.src_channel.clone(),
+            dst_chain_id: self.dst_chain.clone(),
+            dst_port_id: self.dst_port.clone(),
+            dst_channel_id: self.dst_channel.clone(),
+            ordering: self.order,
+        };
+
+        info!(
+            "replaying {} event batches from {} on path {}:{}/{} -> {}:{}/{}",
+            recording.len(),
+            self.file.display(),
+            path.src_chain_id,
+            path.src_port_id,
+            path.src_channel_id,
+            path.dst_chain_id,
+            path.dst_port_id,
+            path.dst_channel_id
+        );
+
+        match replay(&config, &path, recording) {
+            Ok(replays) => Output::success(replays).exit(),
+            Err(e) => Output::error(format!("{}", e)).exit(),
+        }
+    }
+}
@@ -1,14 +1,16 @@
 use alloc::sync::Arc;
 use std::error::Error;
 use std::io;
+use std::path::{Path, PathBuf};
 use std::sync::RwLock;
 
 use abscissa_core::{Clap, Command, Runnable};
 use crossbeam_channel::Sender;
 
-use ibc_relayer::chain::handle::{ChainHandle, ProdChainHandle};
+use ibc_relayer::chain::handle::{CachingChainHandle, ChainHandle};
 use ibc_relayer::config::reload::ConfigReload;
 use ibc_relayer::config::Config;
+use ibc_relayer::event::recording::EventRecorder;
 use ibc_relayer::registry::SharedRegistry;
 use ibc_relayer::rest;
 use ibc_relayer::supervisor::{cmd::SupervisorCmd, spawn_supervisor, SupervisorHandle};
@@ -18,7 +20,14 @@ use crate::conclude::Output;
 use crate::prelude::*;
 
 #[derive(Clone, Command, Debug, Clap)]
-pub struct StartCmd {}
+pub struct StartCmd {
+    #[clap(
+        long,
+        value_name = "FILE",
+        about = "file to which the event batches received from the chains are appended, to be replayed with `hermes replay`"
+    )]
+    record: Option<PathBuf>,
+}
 
 impl Runnable for StartCmd {
     fn run(&self) {
@@ -26,10 +35,11 @@ impl Runnable for StartCmd {
         let config = Arc::new(RwLock::new(config));
 
         let supervisor_handle =
-            make_supervisor::<ProdChainHandle>(config.clone()).unwrap_or_else(|e| {
-                Output::error(format!("Hermes failed to start, last error: {}", e)).exit();
-                unreachable!()
-            });
+            make_supervisor::<CachingChainHandle>(config.clone(), self.record.as_deref())
+                .unwrap_or_else(|e| {
+                    Output::error(format!("Hermes failed to start, last error: {}", e)).exit();
+                    unreachable!()
+                });
 
         match crate::config::config_path() {
             Some(config_path) => {
@@ -175,11 +185,14 @@ fn spawn_telemetry_server(
 
 fn make_supervisor<Chain: ChainHandle>(
     config: Arc<RwLock<Config>>,
+    record: Option<&Path>,
 ) -> Result<SupervisorHandle, Box<dyn Error + Send + Sync>> {
     let registry = SharedRegistry::<Chain>::new(config.clone());
     spawn_telemetry_server(&config)?;
 
     let rest = spawn_rest_server(&config);
 
-    Ok(spawn_supervisor(config, registry, rest, true)?)
+    let recorder = record.map(EventRecorder::create).transpose()?;
+
+    Ok(spawn_supervisor(config, registry, rest, recorder, true)?)
 }
@@ -3,13 +3,14 @@ use abscissa_core::{config::Override, Clap, Command, Runnable};
 use ibc_relayer::config::Config;
 
 use crate::commands::tx::client::{
-    TxCreateClientCmd, TxUpdateClientCmd, TxUpgradeClientCmd, TxUpgradeClientsCmd,
+    TxCreateClientCmd, TxRecoverClientCmd, TxUpdateClientCmd, TxUpgradeClientCmd,
+    TxUpgradeClientsCmd,
 };
 
 mod channel;
 pub(crate) mod client;
 mod connection;
-mod packet;
+pub(crate) mod packet;
 mod transfer;
 mod upgrade;
 
@@ -23,6 +24,12 @@ pub enum TxCmd {
         about = "Raw commands for sending transactions to a configured chain."
     )]
     Raw(TxRawCommands),
+
+    /// The `tx chan-close` subcommand
+    #[clap(
+        about = "Flush the pending packets of a channel, then close both of its ends, timing out on close the packets still in flight"
+    )]
+    ChanClose(channel::TxChanCloseCmd),
 }
 
 #[derive(Command, Debug, Clap, Runnable)]
@@ -43,6 +50,11 @@ pub enum TxRawCommands {
     #[clap(about = "Upgrade all IBC clients that target a specific chain")]
     UpgradeClients(TxUpgradeClientsCmd),
 
+    /// The `tx raw recover-client` subcommand. Creates a substitute client and submits a
+    /// ClientUpdateProposal to recover an expired or frozen client.
+    #[clap(about = "Recover an expired or frozen client via a governance proposal")]
+    RecoverClient(TxRecoverClientCmd),
+
     /// The `tx raw conn-init` subcommand
     #[clap(about = "Initialize a connection (ConnectionOpenInit)")]
     ConnInit(connection::TxRawConnInitCmd),
@@ -104,6 +116,7 @@ impl Override<Config> for TxCmd {
     fn override_config(&self, config: Config) -> Result<Config, abscissa_core::FrameworkError> {
         match self {
             Self::Raw(cmd) => cmd.override_config(config),
+            Self::ChanClose(_) => Ok(config),
         }
     }
 }
@@ -1,20 +1,24 @@
 use abscissa_core::{Clap, Command, Runnable};
+use serde::Serialize;
 
 use ibc::core::ics03_connection::connection::ConnectionEnd;
 use ibc::core::ics04_channel::channel::Order;
 use ibc::core::ics24_host::identifier::{ChainId, ChannelId, ClientId, ConnectionId, PortId};
 use ibc::events::IbcEvent;
 use ibc::Height;
-use ibc_relayer::chain::handle::ChainHandle;
+use ibc_relayer::chain::handle::{ChainHandle, ProdChainHandle};
 use ibc_relayer::channel::{Channel, ChannelSide};
+use ibc_relayer::dry_run::{dry_run, DryRunReport};
+use ibc_relayer::link::{Link, LinkParameters, PacketSelection, RelaySummary};
 
-use crate::cli_utils::ChainHandlePair;
+use crate::cli_utils::{is_dry_run, spawn_chain_counterparty, ChainHandlePair};
+use crate::commands::tx::packet::dry_run_link;
 use crate::conclude::Output;
 use crate::error::Error;
 use crate::prelude::*;
 
 macro_rules! tx_chan_cmd {
-    ($dbg_string:literal, $func:ident, $self:expr, $chan:expr) => {
+    ($dbg_string:literal, $build:ident, $func:ident, $self:expr, $chan:expr) => {
         let config = app_config();
 
         let chains = match ChainHandlePair::spawn(&config, &$self.src_chain_id, &$self.dst_chain_id)
@@ -36,6 +40,18 @@ macro_rules! tx_chan_cmd {
 
         info!("Message {}: {:?}", $dbg_string, channel);
 
+        if is_dry_run() {
+            let res = channel
+                .$build()
+                .map(|msgs| vec![dry_run(channel.dst_chain(), msgs)])
+                .map_err(Error::channel);
+
+            return match res {
+                Ok(reports) => Output::success(reports).exit(),
+                Err(e) => Output::error(format!("{}", e)).exit(),
+            };
+        }
+
         let res: Result<IbcEvent, Error> = channel.$func().map_err(Error::channel);
 
         match res {
@@ -112,6 +128,18 @@ impl Runnable for TxRawChanOpenInitCmd {
 
         info!("Message ChanOpenInit: {:?}", channel);
 
+        if is_dry_run() {
+            let res = channel
+                .build_chan_open_init()
+                .map(|msgs| vec![dry_run(channel.dst_chain(), msgs)])
+                .map_err(Error::channel);
+
+            return match res {
+                Ok(reports) => Output::success(reports).exit(),
+                Err(e) => Output::error(format!("{}", e)).exit(),
+            };
+        }
+
         let res: Result<IbcEvent, Error> = channel
             .build_chan_open_init_and_send()
             .map_err(Error::channel);
@@ -162,6 +190,7 @@ impl Runnable for TxRawChanOpenTryCmd {
     fn run(&self) {
         tx_chan_cmd!(
             "ChanOpenTry",
+            build_chan_open_try,
             build_chan_open_try_and_send,
             self,
             |chains: ChainHandlePair, dst_connection: ConnectionEnd| {
@@ -230,6 +259,7 @@ impl Runnable for TxRawChanOpenAckCmd {
     fn run(&self) {
         tx_chan_cmd!(
             "ChanOpenAck",
+            build_chan_open_ack,
             build_chan_open_ack_and_send,
             self,
             |chains: ChainHandlePair, dst_connection: ConnectionEnd| {
@@ -298,6 +328,7 @@ impl Runnable for TxRawChanOpenConfirmCmd {
     fn run(&self) {
         tx_chan_cmd!(
             "ChanOpenConfirm",
+            build_chan_open_confirm,
             build_chan_open_confirm_and_send,
             self,
             |chains: ChainHandlePair, dst_connection: ConnectionEnd| {
@@ -366,6 +397,7 @@ impl Runnable for TxRawChanCloseInitCmd {
     fn run(&self) {
         tx_chan_cmd!(
             "ChanCloseInit",
+            build_chan_close_init,
             build_chan_close_init_and_send,
             self,
             |chains: ChainHandlePair, dst_connection: ConnectionEnd| {
@@ -434,6 +466,7 @@ impl Runnable for TxRawChanCloseConfirmCmd {
     fn run(&self) {
         tx_chan_cmd!(
             "ChanCloseConfirm",
+            build_chan_close_confirm,
             build_chan_close_confirm_and_send,
             self,
             |chains: ChainHandlePair, dst_connection: ConnectionEnd| {
@@ -461,3 +494,208 @@ impl Runnable for TxRawChanCloseConfirmCmd {
         );
     }
 }
+
+/// The outcome of each step of the closing of a channel
+#[derive(Serialize, Debug)]
+struct ChanCloseSummary {
+    /// The packets and acknowledgments relayed from the chain to the counterparty chain
+    /// before closing the channel
+    flushed_to_counterparty: RelaySummary,
+    /// The packets and acknowledgments relayed from the counterparty chain to the chain
+    /// before closing the channel
+    flushed_from_counterparty: RelaySummary,
+    close_init: IbcEvent,
+    close_confirm: IbcEvent,
+    /// The packets which were still in flight once the channel was closed, timed out on close
+    timed_out: RelaySummary,
+}
+
+/// This command closes both ends of a channel in the following steps:
+/// 1. relays the pending packets and acknowledgments in both directions
+/// 2. closes the channel on the chain (ChannelCloseInit)
+/// 3. closes the channel on the counterparty chain (ChannelCloseConfirm)
+/// 4. times out on close the packets which are still in flight, in both directions
+///
+/// In dry-run mode, only the messages of the first two steps are reported,
+/// as the last steps depend on the chain state left by the previous ones.
+#[derive(Clone, Command, Debug, Clap)]
+pub struct TxChanCloseCmd {
+    #[clap(
+        required = true,
+        about = "identifier of the chain on which the closing is initiated"
+    )]
+    chain_id: ChainId,
+
+    #[clap(required = true, about = "identifier of the port of the channel")]
+    port_id: PortId,
+
+    #[clap(required = true, about = "identifier of the channel to close")]
+    channel_id: ChannelId,
+}
+
+impl TxChanCloseCmd {
+    /// The links relaying the packets sent by the chain, and the packets sent by the counterparty
+    fn links(
+        &self,
+    ) -> Result<
+        (
+            Link<ProdChainHandle, ProdChainHandle>,
+            Link<ProdChainHandle, ProdChainHandle>,
+        ),
+        Error,
+    > {
+        let config = app_config();
+
+        let (chains, _) = spawn_chain_counterparty::<ProdChainHandle>(
+            &config,
+            &self.chain_id,
+            &self.port_id,
+            &self.channel_id,
+        )?;
+
+        let opts = LinkParameters {
+            src_port_id: self.port_id.clone(),
+            src_channel_id: self.channel_id.clone(),
+        };
+        let link = Link::new_from_opts(chains.src.clone(), chains.dst.clone(), opts, false)
+            .map_err(Error::link)?;
+
+        let opts = LinkParameters {
+            src_port_id: link.a_to_b.dst_port_id().clone(),
+            src_channel_id: link.a_to_b.dst_channel_id().clone(),
+        };
+        let counterparty_link =
+            Link::new_from_opts(chains.dst, chains.src, opts, false).map_err(Error::link)?;
+
+        Ok((link, counterparty_link))
+    }
+
+    fn dry_run(&self) -> Result<Vec<DryRunReport>, Error> {
+        let (mut link, mut counterparty_link) = self.links()?;
+
+        let all = PacketSelection::default();
+        let mut reports = vec![];
+
+        let msgs = link.build_recv_packet_messages(&all).map_err(Error::link)?;
+        reports.extend(dry_run_link(&link, msgs));
+
+        let msgs = counterparty_link
+            .build_recv_packet_messages(&all)
+            .map_err(Error::link)?;
+        reports.extend(dry_run_link(&counterparty_link, msgs));
+
+        let close_init = link.a_to_b.channel().flipped();
+        let msgs = close_init.build_chan_close_init().map_err(Error::channel)?;
+        reports.push(dry_run(close_init.dst_chain(), msgs));
+
+        Ok(reports)
+    }
+
+    fn execute(&self) -> Result<ChanCloseSummary, Error> {
+        let (mut link, mut counterparty_link) = self.links()?;
+
+        let all = PacketSelection::default();
+
+        // The packets are received before the acknowledgments are relayed back,
+        // so that the acknowledgments of the packets received while flushing are relayed too
+        let mut flushed_to_counterparty =
+            link.send_recv_packet_messages(&all).map_err(Error::link)?;
+        let mut flushed_from_counterparty = counterparty_link
+            .send_recv_packet_messages(&all)
+            .map_err(Error::link)?;
+
+        flushed_to_counterparty.extend(
+            counterparty_link
+                .send_ack_packet_messages(&all)
+                .map_err(Error::link)?,
+        );
+        flushed_from_counterparty.extend(link.send_ack_packet_messages(&all).map_err(Error::link)?);
+
+        info!(
+            "flushed {} events before closing {}/{} on {}",
+            flushed_to_counterparty.events.len() + flushed_from_counterparty.events.len(),
+            self.port_id,
+            self.channel_id,
+            self.chain_id
+        );
+
+        let channel = link.a_to_b.channel().clone();
+
+        let close_init = channel
+            .flipped()
+            .build_chan_close_init_and_send()
+            .map_err(Error::channel)?;
+
+        let close_confirm = channel
+            .build_chan_close_confirm_and_send()
+            .map_err(Error::channel)?;
+
+        // The packets which could not be received are timed out on close
+        let mut timed_out = link.send_recv_packet_messages(&all).map_err(Error::link)?;
+        timed_out.extend(
+            counterparty_link
+                .send_recv_packet_messages(&all)
+                .map_err(Error::link)?,
+        );
+
+        Ok(ChanCloseSummary {
+            flushed_to_counterparty,
+            flushed_from_counterparty,
+            close_init,
+            close_confirm,
+            timed_out,
+        })
+    }
+}
+
+impl Runnable for TxChanCloseCmd {
+    fn run(&self) {
+        if is_dry_run() {
+            return match self.dry_run() {
+                Ok(reports) => Output::success(reports).exit(),
+                Err(e) => Output::error(format!("{}", e)).exit(),
+            };
+        }
+
+        match self.execute() {
+            Ok(summary) => Output::success(summary).exit(),
+            Err(e) => Output::error(format!("{}", e)).exit(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::str::FromStr;
+
+    use abscissa_core::config::Override;
+    use ibc_relayer::config::Config;
+
+    use crate::commands::tx::TxCmd;
+
+    #[test]
+    fn chan_close_is_routed_to_its_command() {
+        let cmd =
+            TxCmd::try_parse_from(&["tx", "chan-close", "ibc-0", "transfer", "channel-3"]).unwrap();
+
+        let close = match &cmd {
+            TxCmd::ChanClose(close) => close,
+            _ => panic!("expected the chan-close command, got {:?}", cmd),
+        };
+
+        assert_eq!(close.chain_id, ChainId::from_str("ibc-0").unwrap());
+        assert_eq!(close.port_id, PortId::transfer());
+        assert_eq!(close.channel_id, ChannelId::new(3));
+
+        // The command does not override the configuration
+        let config = cmd.override_config(Config::default()).unwrap();
+        assert!(config.chains.is_empty());
+    }
+
+    #[test]
+    fn chan_close_requires_the_channel() {
+        assert!(TxCmd::try_parse_from(&["tx", "chan-close", "ibc-0", "transfer"]).is_err());
+    }
+}
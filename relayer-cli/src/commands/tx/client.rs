@@ -6,13 +6,18 @@ use clap::AppSettings::DisableHelpFlag;
 use ibc::core::ics02_client::client_state::ClientState;
 use ibc::core::ics24_host::identifier::{ChainId, ClientId};
 use ibc::events::IbcEvent;
+use ibc::tx_msg::Msg;
 use ibc_proto::ibc::core::client::v1::QueryClientStatesRequest;
 use ibc_relayer::chain::handle::ChainHandle;
 use ibc_relayer::config::Config;
+use ibc_relayer::dry_run::{dry_run, DryRunReport};
 use ibc_relayer::foreign_client::ForeignClient;
+use ibc_relayer::recover_client::{build_and_send_recover_client_proposal, RecoverClientOptions};
 
 use crate::application::app_config;
-use crate::cli_utils::{spawn_chain_runtime, spawn_chain_runtime_generic, ChainHandlePair};
+use crate::cli_utils::{
+    is_dry_run, spawn_chain_runtime, spawn_chain_runtime_generic, ChainHandlePair,
+};
 use crate::conclude::{exit_with_unrecoverable_error, Output};
 use crate::error::Error;
 
@@ -42,6 +47,18 @@ impl Runnable for TxCreateClientCmd {
 
         let client = ForeignClient::restore(ClientId::default(), chains.dst, chains.src);
 
+        if is_dry_run() {
+            let res = client
+                .build_create_client()
+                .map(|msg| vec![dry_run(&client.dst_chain, vec![msg.to_any()])])
+                .map_err(Error::foreign_client);
+
+            return match res {
+                Ok(reports) => Output::success(reports).exit(),
+                Err(e) => Output::error(format!("{}", e)).exit(),
+            };
+        }
+
         // Trigger client creation via the "build" interface, so that we obtain the resulting event
         let res: Result<IbcEvent, Error> = client
             .build_create_client_and_send()
@@ -113,6 +130,15 @@ impl Runnable for TxUpdateClientCmd {
         let client = ForeignClient::find(src_chain, dst_chain, &self.dst_client_id)
             .unwrap_or_else(exit_with_unrecoverable_error);
 
+        if is_dry_run() {
+            let res = dry_run_update_client(&client, height, trusted_height);
+
+            return match res {
+                Ok(reports) => Output::success(reports).exit(),
+                Err(e) => Output::error(format!("{}", e)).exit(),
+            };
+        }
+
         let res = client
             .build_update_client_and_send(height, trusted_height)
             .map_err(Error::foreign_client);
@@ -165,6 +191,15 @@ impl Runnable for TxUpgradeClientCmd {
         let client = ForeignClient::find(src_chain, dst_chain, &self.client_id)
             .unwrap_or_else(exit_with_unrecoverable_error);
 
+        if is_dry_run() {
+            let res = dry_run_upgrade_client(&client).map(|report| vec![report]);
+
+            return match res {
+                Ok(reports) => Output::success(reports).exit(),
+                Err(e) => Output::error(format!("{}", e)).exit(),
+            };
+        }
+
         let outcome = client.upgrade();
 
         match outcome {
@@ -174,6 +209,118 @@ impl Runnable for TxUpgradeClientCmd {
     }
 }
 
+#[derive(Clone, Command, Debug, Clap)]
+pub struct TxRecoverClientCmd {
+    #[clap(
+        required = true,
+        about = "identifier of the chain that hosts the client"
+    )]
+    chain_id: ChainId,
+
+    #[clap(
+        required = true,
+        about = "identifier of the expired or frozen client to be recovered"
+    )]
+    client_id: ClientId,
+
+    #[clap(required = true, about = "amount of the proposal deposit")]
+    amount: u64,
+
+    #[clap(
+        short = 'd',
+        long,
+        value_name = "DENOM",
+        about = "denomination of the proposal deposit (default: 'stake')"
+    )]
+    denom: Option<String>,
+
+    #[clap(
+        short = 't',
+        long,
+        value_name = "TITLE",
+        about = "title of the client update proposal (optional)"
+    )]
+    title: Option<String>,
+
+    #[clap(
+        long,
+        value_name = "DESCRIPTION",
+        about = "description of the client update proposal (optional)"
+    )]
+    description: Option<String>,
+}
+
+/// Sample to run this tx:
+///     `hermes tx raw recover-client ibc-0 07-tendermint-0 10000000`
+impl Runnable for TxRecoverClientCmd {
+    fn run(&self) {
+        let config = app_config();
+
+        let dst_chain = match spawn_chain_runtime(&config, &self.chain_id) {
+            Ok(handle) => handle,
+            Err(e) => return Output::error(format!("{}", e)).exit(),
+        };
+
+        let src_chain_id = match dst_chain.query_client_state(&self.client_id, ibc::Height::zero())
+        {
+            Ok(cs) => cs.chain_id(),
+            Err(e) => {
+                return Output::error(format!(
+                    "Query of client '{}' on chain '{}' failed with error: {}",
+                    self.client_id, self.chain_id, e
+                ))
+                .exit();
+            }
+        };
+
+        let src_chain = match spawn_chain_runtime(&config, &src_chain_id) {
+            Ok(handle) => handle,
+            Err(e) => return Output::error(format!("{}", e)).exit(),
+        };
+
+        let opts = RecoverClientOptions {
+            title: self
+                .title
+                .clone()
+                .unwrap_or_else(|| format!("Recover client {}", self.client_id)),
+            description: self.description.clone().unwrap_or_else(|| {
+                format!(
+                    "Recover the expired or frozen client {} tracking chain {}",
+                    self.client_id, src_chain_id
+                )
+            }),
+            deposit_amount: self.amount,
+            deposit_denom: self.denom.clone().unwrap_or_else(|| "stake".to_string()),
+        };
+
+        // Restore rather than find the client, since the source chain may have
+        // been upgraded to a new chain identifier in the meantime.
+        let client = ForeignClient::restore(self.client_id.clone(), dst_chain, src_chain);
+
+        // Only the creation of the substitute client can be simulated, since
+        // the proposal refers to the identifier of the substitute client.
+        if is_dry_run() {
+            let res = client
+                .build_create_substitute_client()
+                .map(|msg| vec![dry_run(&client.dst_chain, vec![msg.to_any()])])
+                .map_err(Error::foreign_client);
+
+            return match res {
+                Ok(reports) => Output::success(reports).exit(),
+                Err(e) => Output::error(format!("{}", e)).exit(),
+            };
+        }
+
+        let res =
+            build_and_send_recover_client_proposal(&client, &opts).map_err(Error::recover_client);
+
+        match res {
+            Ok(recovery) => Output::success(recovery).exit(),
+            Err(e) => Output::error(format!("{}", e)).exit(),
+        }
+    }
+}
+
 #[derive(Clone, Command, Debug, Clap)]
 pub struct TxUpgradeClientsCmd {
     #[clap(
@@ -191,6 +338,22 @@ impl Runnable for TxUpgradeClientsCmd {
             Err(e) => return Output::error(format!("{}", e)).exit(),
         };
 
+        if is_dry_run() {
+            let res: Result<Vec<Vec<DryRunReport>>, Error> = config
+                .chains
+                .iter()
+                .filter(|chain| self.src_chain_id != chain.id)
+                .map(|chain| {
+                    self.dry_run_upgrade_clients_for_chain(&config, src_chain.clone(), &chain.id)
+                })
+                .collect();
+
+            return match res {
+                Ok(reports) => Output::success(reports.concat()).exit(),
+                Err(e) => Output::error(format!("{}", e)).exit(),
+            };
+        }
+
         let results = config
             .chains
             .iter()
@@ -231,6 +394,31 @@ impl TxUpgradeClientsCmd {
         Ok(outputs)
     }
 
+    fn dry_run_upgrade_clients_for_chain<Chain: ChainHandle>(
+        &self,
+        config: &Config,
+        src_chain: Chain,
+        dst_chain_id: &ChainId,
+    ) -> Result<Vec<DryRunReport>, Error> {
+        let dst_chain = spawn_chain_runtime_generic::<Chain>(config, dst_chain_id)?;
+
+        let req = QueryClientStatesRequest {
+            pagination: ibc_proto::cosmos::base::query::pagination::all(),
+        };
+
+        dst_chain
+            .query_clients(req)
+            .map_err(Error::relayer)?
+            .into_iter()
+            .filter(|c| self.src_chain_id == c.client_state.chain_id())
+            .map(|c| {
+                let client =
+                    ForeignClient::restore(c.client_id, dst_chain.clone(), src_chain.clone());
+                dry_run_upgrade_client(&client)
+            })
+            .collect()
+    }
+
     fn upgrade_client<Chain: ChainHandle>(
         client_id: ClientId,
         dst_chain: Chain,
@@ -241,6 +429,44 @@ impl TxUpgradeClientsCmd {
     }
 }
 
+/// Simulates the client update which `update-client` would send
+fn dry_run_update_client<DstChain: ChainHandle, SrcChain: ChainHandle>(
+    client: &ForeignClient<DstChain, SrcChain>,
+    height: ibc::Height,
+    trusted_height: ibc::Height,
+) -> Result<Vec<DryRunReport>, Error> {
+    let height = if height == ibc::Height::zero() {
+        client
+            .src_chain
+            .query_latest_height()
+            .map_err(Error::relayer)?
+    } else {
+        height
+    };
+
+    let msgs = client
+        .build_update_client_with_trusted(height, trusted_height)
+        .map_err(Error::foreign_client)?;
+
+    Ok(vec![dry_run(&client.dst_chain, msgs)])
+}
+
+/// Simulates the client upgrade which `upgrade-client` would send
+fn dry_run_upgrade_client<DstChain: ChainHandle, SrcChain: ChainHandle>(
+    client: &ForeignClient<DstChain, SrcChain>,
+) -> Result<DryRunReport, Error> {
+    let src_height = client
+        .src_chain
+        .query_latest_height()
+        .map_err(Error::relayer)?;
+
+    let msgs = client
+        .build_upgrade_client(src_height)
+        .map_err(Error::foreign_client)?;
+
+    Ok(dry_run(&client.dst_chain, msgs))
+}
+
 type UpgradeClientResult = Result<Vec<IbcEvent>, Error>;
 type UpgradeClientsForChainResult = Result<Vec<UpgradeClientResult>, Error>;
 
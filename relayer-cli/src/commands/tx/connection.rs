@@ -4,14 +4,15 @@ use ibc::core::ics24_host::identifier::{ChainId, ClientId, ConnectionId};
 use ibc::events::IbcEvent;
 use ibc::timestamp::ZERO_DURATION;
 use ibc_relayer::connection::{Connection, ConnectionSide};
+use ibc_relayer::dry_run::dry_run;
 
-use crate::cli_utils::ChainHandlePair;
+use crate::cli_utils::{is_dry_run, ChainHandlePair};
 use crate::conclude::Output;
 use crate::error::Error;
 use crate::prelude::*;
 
 macro_rules! conn_open_cmd {
-    ($dbg_string:literal, $func:ident, $self:expr, $conn:expr) => {
+    ($dbg_string:literal, $build:ident, $func:ident, $self:expr, $conn:expr) => {
         let config = app_config();
 
         let chains = match ChainHandlePair::spawn(&config, &$self.src_chain_id, &$self.dst_chain_id)
@@ -24,6 +25,18 @@ macro_rules! conn_open_cmd {
 
         debug!("Message {}: {:?}", $dbg_string, connection);
 
+        if is_dry_run() {
+            let res = connection
+                .$build()
+                .map(|msgs| vec![dry_run(&connection.dst_chain(), msgs)])
+                .map_err(Error::connection);
+
+            return match res {
+                Ok(reports) => Output::success(reports).exit(),
+                Err(e) => Output::error(format!("{}", e)).exit(),
+            };
+        }
+
         let res: Result<IbcEvent, Error> = connection.$func().map_err(Error::connection);
 
         match res {
@@ -52,6 +65,7 @@ impl Runnable for TxRawConnInitCmd {
     fn run(&self) {
         conn_open_cmd!(
             "ConnOpenInit",
+            build_conn_init,
             build_conn_init_and_send,
             self,
             |chains: ChainHandlePair| {
@@ -101,6 +115,7 @@ impl Runnable for TxRawConnTryCmd {
     fn run(&self) {
         conn_open_cmd!(
             "ConnOpenTry",
+            build_conn_try,
             build_conn_try_and_send,
             self,
             |chains: ChainHandlePair| {
@@ -159,6 +174,7 @@ impl Runnable for TxRawConnAckCmd {
     fn run(&self) {
         conn_open_cmd!(
             "ConnOpenAck",
+            build_conn_ack,
             build_conn_ack_and_send,
             self,
             |chains: ChainHandlePair| {
@@ -217,6 +233,7 @@ impl Runnable for TxRawConnConfirmCmd {
     fn run(&self) {
         conn_open_cmd!(
             "ConnOpenConfirm",
+            build_conn_confirm,
             build_conn_confirm_and_send,
             self,
             |chains: ChainHandlePair| {
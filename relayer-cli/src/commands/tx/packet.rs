@@ -2,9 +2,11 @@ use abscissa_core::{Clap, Command, Runnable};
 
 use ibc::core::ics24_host::identifier::{ChainId, ChannelId, PortId};
 use ibc::events::IbcEvent;
-use ibc_relayer::link::{Link, LinkParameters};
+use ibc_relayer::chain::handle::ChainHandle;
+use ibc_relayer::dry_run::{dry_run, DryRunReport};
+use ibc_relayer::link::{AssembledMsgs, Link, LinkParameters, PacketSelection};
 
-use crate::cli_utils::ChainHandlePair;
+use crate::cli_utils::{is_dry_run, ChainHandlePair};
 use crate::conclude::Output;
 use crate::error::Error;
 use crate::prelude::*;
@@ -42,6 +44,18 @@ impl Runnable for TxRawPacketRecvCmd {
             Err(e) => return Output::error(format!("{}", e)).exit(),
         };
 
+        if is_dry_run() {
+            let res = link
+                .build_recv_packet_messages(&PacketSelection::default())
+                .map(|msgs| dry_run_link(&link, msgs))
+                .map_err(Error::link);
+
+            return match res {
+                Ok(reports) => Output::success(reports).exit(),
+                Err(e) => Output::error(format!("{}", e)).exit(),
+            };
+        }
+
         let res: Result<Vec<IbcEvent>, Error> = link
             .build_and_send_recv_packet_messages()
             .map_err(Error::link);
@@ -86,6 +100,18 @@ impl Runnable for TxRawPacketAckCmd {
             Err(e) => return Output::error(format!("{}", e)).exit(),
         };
 
+        if is_dry_run() {
+            let res = link
+                .build_ack_packet_messages(&PacketSelection::default())
+                .map(|msgs| dry_run_link(&link, msgs))
+                .map_err(Error::link);
+
+            return match res {
+                Ok(reports) => Output::success(reports).exit(),
+                Err(e) => Output::error(format!("{}", e)).exit(),
+            };
+        }
+
         let res: Result<Vec<IbcEvent>, Error> = link
             .build_and_send_ack_packet_messages()
             .map_err(Error::link);
@@ -96,3 +122,14 @@ impl Runnable for TxRawPacketAckCmd {
         }
     }
 }
+
+/// Simulates the messages assembled for both ends of the link
+pub(crate) fn dry_run_link<ChainA: ChainHandle, ChainB: ChainHandle>(
+    link: &Link<ChainA, ChainB>,
+    msgs: AssembledMsgs,
+) -> Vec<DryRunReport> {
+    vec![
+        dry_run(link.a_to_b.dst_chain(), msgs.dst),
+        dry_run(link.a_to_b.src_chain(), msgs.src),
+    ]
+}
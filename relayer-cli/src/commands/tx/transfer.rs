@@ -12,10 +12,12 @@ use ibc_relayer::chain::handle::ChainHandle;
 use ibc_relayer::transfer::Amount;
 use ibc_relayer::{
     config::Config,
-    transfer::{build_and_send_transfer_messages, TransferOptions},
+    transfer::{build_and_send_transfer_messages, build_transfer_messages, TransferOptions},
 };
 
-use crate::cli_utils::ChainHandlePair;
+use ibc_relayer::dry_run::dry_run;
+
+use crate::cli_utils::{is_dry_run, ChainHandlePair};
 use crate::conclude::{exit_with_unrecoverable_error, Output};
 use crate::error::Error;
 use crate::prelude::*;
@@ -222,6 +224,17 @@ impl Runnable for TxIcs20MsgTransferCmd {
                         src_chain_client_state.chain_id(), self.dst_chain_id)).exit();
         }
 
+        if is_dry_run() {
+            let res = build_transfer_messages(&chains.src, &chains.dst, &opts)
+                .map(|msgs| vec![dry_run(&chains.src, msgs)])
+                .map_err(Error::packet);
+
+            return match res {
+                Ok(reports) => Output::success(reports).exit(),
+                Err(e) => Output::error(format!("{}", e)).exit(),
+            };
+        }
+
         // Checks pass, build and send the tx
         let res: Result<Vec<IbcEvent>, Error> =
             build_and_send_transfer_messages(&chains.src, &chains.dst, &opts)
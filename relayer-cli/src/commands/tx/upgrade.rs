@@ -6,12 +6,16 @@ use tokio::runtime::Runtime as TokioRuntime;
 
 use ibc::core::ics24_host::identifier::{ChainId, ClientId};
 use ibc::events::IbcEvent;
-use ibc_relayer::upgrade_chain::{build_and_send_ibc_upgrade_proposal, UpgradePlanOptions};
+use ibc_relayer::dry_run::DryRunReport;
+use ibc_relayer::upgrade_chain::{
+    build_and_send_ibc_upgrade_proposal, build_ibc_upgrade_proposal, UpgradePlanOptions,
+};
 use ibc_relayer::{
     chain::{ChainEndpoint, CosmosSdkChain},
     config::Config,
 };
 
+use crate::cli_utils::is_dry_run;
 use crate::conclude::Output;
 use crate::error::Error;
 use crate::prelude::*;
@@ -130,11 +134,29 @@ impl Runnable for TxIbcUpgradeChainCmd {
 
         let dst_chain_res =
             CosmosSdkChain::bootstrap(opts.dst_chain_config.clone(), rt).map_err(Error::relayer);
-        let dst_chain = match dst_chain_res {
+        let mut dst_chain = match dst_chain_res {
             Ok(chain) => chain,
             Err(e) => return Output::error(format!("{}", e)).exit(),
         };
 
+        if is_dry_run() {
+            let res = build_ibc_upgrade_proposal(&mut dst_chain, &src_chain, &opts)
+                .map(|msg| {
+                    let simulation = dst_chain.simulate_messages(vec![msg.clone()]);
+                    vec![DryRunReport::new(
+                        dst_chain.id().clone(),
+                        &[msg],
+                        Some(simulation),
+                    )]
+                })
+                .map_err(Error::upgrade_chain);
+
+            return match res {
+                Ok(reports) => Output::success(reports).exit(),
+                Err(e) => Output::error(format!("{}", e)).exit(),
+            };
+        }
+
         let res: Result<Vec<IbcEvent>, Error> =
             build_and_send_ibc_upgrade_proposal(dst_chain, src_chain, &opts)
                 .map_err(Error::upgrade_chain);
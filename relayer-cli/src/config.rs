@@ -9,7 +9,8 @@ use std::path::PathBuf;
 
 use flex_error::{define_error, TraceError};
 use ibc::core::ics24_host::identifier::ChainId;
-use ibc_relayer::config::{Config, ModeConfig};
+use ibc_relayer::config::ics20_filter::AddressFilter;
+use ibc_relayer::config::{Config, Ics20Filter, ModeConfig, PacketFilter};
 use tendermint_light_client::types::TrustThreshold;
 use tracing_subscriber::filter::ParseError;
 
@@ -71,6 +72,45 @@ define_error! {
                 format!("config file specifies an invalid `gas_adjustment` ({0}) for the chain with id {1}, caused by: {2}",
                     e.gas_adjustment, e.chain_id, e.reason)
             },
+
+        InvalidPath
+            {
+                index: usize,
+                reason: String
+            }
+            |e| {
+                format!("config file specifies an invalid path (entry {0} of `paths`), caused by: {1}",
+                    e.index, e.reason)
+            },
+
+        InvalidPacketFilter
+            {
+                chain_id: ChainId,
+                reason: String
+            }
+            |e| {
+                format!("config file specifies an invalid `packet_filter` for the chain with id {0}, caused by: {1}",
+                    e.chain_id, e.reason)
+            },
+
+        InvalidIcs20Filter
+            {
+                chain_id: ChainId,
+                reason: String
+            }
+            |e| {
+                format!("config file specifies an invalid `ics20_filter` for the chain with id {0}, caused by: {1}",
+                    e.chain_id, e.reason)
+            },
+
+        MissingQueryTrustAnchor
+            {
+                chain_id: ChainId,
+            }
+            |e| {
+                format!("config file enables `verify_query_proofs` without a `query_trust_anchor` for the chain with id {0}",
+                    e.chain_id)
+            },
     }
 }
 
@@ -94,11 +134,43 @@ pub fn validate_config(config: &Config) -> Result<(), Diagnostic<Error>> {
 
         // Validate gas-related settings
         validate_gas_settings(&c.id, c.gas_adjustment)?;
+
+        // The proofs of the queries cannot be verified without a trusted block
+        if c.verify_query_proofs && c.query_trust_anchor.is_none() {
+            return Err(Diagnostic::Error(Error::missing_query_trust_anchor(
+                c.id.clone(),
+            )));
+        }
+    }
+
+    // Check that the paths are between distinct configured chains
+    for (index, path) in config.paths.iter().enumerate() {
+        if path.a.chain_id == path.b.chain_id {
+            return Err(Diagnostic::Error(Error::invalid_path(
+                index,
+                format!("both ends of the path are on chain {}", path.a.chain_id),
+            )));
+        }
+
+        for end in [&path.a, &path.b] {
+            if !config.has_chain(&end.chain_id) {
+                return Err(Diagnostic::Error(Error::invalid_path(
+                    index,
+                    format!("chain {} is not present in config", end.chain_id),
+                )));
+            }
+        }
     }
 
     // Check for invalid mode config
     validate_mode(&config.mode)?;
 
+    // Check for packet filters which prevent relaying on any channel
+    for c in config.chains.iter() {
+        validate_packet_filter(&c.id, &c.packet_filter)?;
+        validate_ics20_filter(&c.id, &c.ics20_filter)?;
+    }
+
     Ok(())
 }
 
@@ -109,9 +181,13 @@ fn validate_mode(mode: &ModeConfig) -> Result<(), Diagnostic<Error>> {
         )));
     }
 
-    if mode.clients.enabled && !mode.clients.refresh && !mode.clients.misbehaviour {
+    if mode.clients.enabled
+        && !mode.clients.refresh
+        && !mode.clients.misbehaviour
+        && !mode.clients.upgrade
+    {
         return Err(Diagnostic::Error(Error::invalid_mode(
-            "either `refresh` or `misbehaviour` must be set to true if `clients.enabled` is set to true".to_string(),
+            "either `refresh`, `misbehaviour` or `upgrade` must be set to true if `clients.enabled` is set to true".to_string(),
         )));
     }
 
@@ -154,6 +230,89 @@ fn validate_trust_threshold(
     Ok(())
 }
 
+/// Check that the packet filter lets packets be relayed on some channel.
+/// Invalid identifiers and patterns are rejected when loading the configuration.
+fn validate_packet_filter(
+    id: &ChainId,
+    packet_filter: &PacketFilter,
+) -> Result<(), Diagnostic<Error>> {
+    match packet_filter {
+        PacketFilter::Allow(spec) if spec.iter().next().is_none() => {
+            Err(Diagnostic::Warning(Error::invalid_packet_filter(
+                id.clone(),
+                "the allow list is empty, no packet will be relayed from this chain".to_string(),
+            )))
+        }
+        PacketFilter::Deny(spec)
+            if spec
+                .iter()
+                .any(|(port, channel)| port.matches_all() && channel.matches_all()) =>
+        {
+            Err(Diagnostic::Warning(Error::invalid_packet_filter(
+                id.clone(),
+                "the deny list matches all channels, no packet will be relayed from this chain"
+                    .to_string(),
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Check that the denominations of the ICS20 filter are valid Cosmos SDK denominations,
+/// and that its address filters list valid addresses and let some transfers through.
+fn validate_ics20_filter(
+    id: &ChainId,
+    ics20_filter: &Ics20Filter,
+) -> Result<(), Diagnostic<Error>> {
+    let invalid = |reason: String| Error::invalid_ics20_filter(id.clone(), reason);
+
+    if let Some(denom) = ics20_filter.denoms.iter().find(|d| !is_valid_denom(d)) {
+        return Err(Diagnostic::Error(invalid(format!(
+            "'{}' is not a valid denomination",
+            denom
+        ))));
+    }
+
+    for (field, addresses) in [
+        ("senders", &ics20_filter.senders),
+        ("receivers", &ics20_filter.receivers),
+    ] {
+        let list = match addresses {
+            AddressFilter::Allow(list) | AddressFilter::Deny(list) => list,
+            AddressFilter::AllowAll => continue,
+        };
+
+        if let Some(address) = list
+            .iter()
+            .find(|a| a.is_empty() || a.chars().any(char::is_whitespace))
+        {
+            return Err(Diagnostic::Error(invalid(format!(
+                "'{}' in `{}` is not a valid address",
+                address, field
+            ))));
+        }
+
+        if matches!(addresses, AddressFilter::Allow(list) if list.is_empty()) {
+            return Err(Diagnostic::Warning(invalid(format!(
+                "the allow list of `{}` is empty, no transfer will be relayed from this chain",
+                field
+            ))));
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether the given denomination is valid in the Cosmos SDK, i.e. matches
+/// `[a-zA-Z][a-zA-Z0-9/:._-]{2,127}`
+fn is_valid_denom(denom: &str) -> bool {
+    let mut chars = denom.chars();
+
+    (3..=128).contains(&denom.len())
+        && chars.next().map_or(false, |c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || "/:._-".contains(c))
+}
+
 fn validate_gas_settings(
     id: &ChainId,
     gas_adjustment: Option<f64>,
@@ -169,3 +328,144 @@ fn validate_gas_settings(
         _ => Ok(()),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::str::FromStr;
+
+    use ibc_relayer::config::PathConfig;
+
+    /// The example configuration of the relayer, with chains `chain_A` and `chain_B`
+    fn config() -> Config {
+        ibc_relayer::config::load(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../relayer/tests/config/fixtures/relayer_conf_example.toml"
+        ))
+        .unwrap()
+    }
+
+    fn ics20_filter(filter: &str) -> Result<(), Diagnostic<Error>> {
+        let id = ChainId::from_str("ibc-0").unwrap();
+        validate_ics20_filter(&id, &toml::from_str(filter).unwrap())
+    }
+
+    fn is_error<E>(diagnostic: Result<(), Diagnostic<E>>) -> bool {
+        matches!(diagnostic, Err(Diagnostic::Error(_)))
+    }
+
+    fn is_warning<E>(diagnostic: Result<(), Diagnostic<E>>) -> bool {
+        matches!(diagnostic, Err(Diagnostic::Warning(_)))
+    }
+
+    #[test]
+    fn valid_ics20_filter() {
+        assert!(ics20_filter("").is_ok());
+        assert!(ics20_filter(
+            r#"
+            denoms = ['uatom', 'ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2', 'gamm/pool/1']
+            min_amount = '340282366920938463463374607431768211455'
+            senders = { policy = 'deny', list = ['cosmos1sender'] }
+            receivers = { policy = 'allow', list = ['osmo1receiver'] }
+            "#
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn invalid_ics20_filter() {
+        assert!(is_error(ics20_filter("denoms = ['1atom']")));
+        assert!(is_error(ics20_filter("denoms = ['at']")));
+        assert!(is_error(ics20_filter("denoms = ['u atom']")));
+        assert!(is_error(ics20_filter(
+            "senders = { policy = 'deny', list = ['cosmos1 sender'] }"
+        )));
+        assert!(is_error(ics20_filter(
+            "receivers = { policy = 'allow', list = [''] }"
+        )));
+        assert!(is_warning(ics20_filter(
+            "receivers = { policy = 'allow', list = [] }"
+        )));
+    }
+
+    #[test]
+    fn ics20_filter_is_validated_with_the_config() {
+        let mut config = config();
+        assert!(validate_config(&config).is_ok());
+
+        config.chains[1].ics20_filter = toml::from_str("denoms = ['1atom']").unwrap();
+
+        assert!(matches!(
+            validate_config(&config),
+            Err(Diagnostic::Error(Error(
+                ErrorDetail::InvalidIcs20Filter(_),
+                _
+            )))
+        ));
+    }
+
+    fn path(a: &str, b: &str) -> PathConfig {
+        let end = |chain_id: &str| {
+            format!("{{ chain_id = '{}', client_id = '07-tendermint-0', connection_id = 'connection-0', port_id = 'transfer', channel_id = 'channel-0' }}", chain_id)
+        };
+        toml::from_str(&format!("a = {}\nb = {}", end(a), end(b))).unwrap()
+    }
+
+    fn is_invalid_path(diagnostic: Result<(), Diagnostic<Error>>, index: usize) -> bool {
+        matches!(
+            diagnostic,
+            Err(Diagnostic::Error(Error(ErrorDetail::InvalidPath(e), _))) if e.index == index
+        )
+    }
+
+    #[test]
+    fn valid_paths() {
+        let mut config = config();
+        config.paths = vec![path("chain_A", "chain_B"), path("chain_B", "chain_A")];
+
+        assert!(validate_config(&config).is_ok());
+    }
+
+    #[test]
+    fn path_on_a_single_chain() {
+        let mut config = config();
+        config.paths = vec![path("chain_A", "chain_B"), path("chain_B", "chain_B")];
+
+        assert!(is_invalid_path(validate_config(&config), 1));
+    }
+
+    #[test]
+    fn path_to_unknown_chain() {
+        let mut config = config();
+
+        config.paths = vec![path("chain_C", "chain_B")];
+        assert!(is_invalid_path(validate_config(&config), 0));
+
+        config.paths = vec![path("chain_A", "chain_B"), path("chain_A", "chain_C")];
+        assert!(is_invalid_path(validate_config(&config), 1));
+    }
+
+    #[test]
+    fn query_proofs_require_trust_anchor() {
+        let mut config = config();
+        config.chains[0].verify_query_proofs = true;
+
+        assert!(matches!(
+            validate_config(&config),
+            Err(Diagnostic::Error(Error(
+                ErrorDetail::MissingQueryTrustAnchor(_),
+                _
+            )))
+        ));
+
+        config.chains[0].query_trust_anchor = Some(
+            toml::from_str(
+                "height = 1000\nhash = '44A3BBE7E5A9E3E7BD12FEBE1F48F0E0D8B5CEBF31A1D1C9ADB0B9B4B3D4C1A2'",
+            )
+            .unwrap(),
+        );
+
+        assert!(validate_config(&config).is_ok());
+    }
+}
@@ -6,6 +6,7 @@ use clap::IntoApp;
 use ibc_relayer::config::Config;
 
 use crate::commands::CliCmd;
+use crate::conclude::Output;
 
 /// Entry point for Hermes CLI.
 #[derive(Command, Debug, Clap)]
@@ -19,6 +20,13 @@ pub struct EntryPoint {
     #[clap(short = 'j', long, about = "enable JSON output")]
     pub json: bool,
 
+    /// Toggle dry-run mode, in which transactions are simulated instead of sent
+    #[clap(
+        long,
+        about = "simulate the transactions of tx and create commands instead of sending them"
+    )]
+    pub dry_run: bool,
+
     /// Subcommand to execute.
     ///
     /// The `command` option will delegate option parsing to the command type,
@@ -30,6 +38,11 @@ pub struct EntryPoint {
 impl Runnable for EntryPoint {
     fn run(&self) {
         match &self.command {
+            Some(cmd) if self.dry_run && cmd.rejects_dry_run() => Output::error(
+                "this command sends transactions which cannot be simulated, \
+                 and cannot run with the --dry-run flag",
+            )
+            .exit(),
             Some(cmd) => cmd.run(),
             None => {
                 EntryPoint::into_app().print_help().unwrap();
@@ -65,3 +78,53 @@ impl Configurable<Config> for EntryPoint {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rejects_dry_run(args: &[&str]) -> bool {
+        let entry = EntryPoint::try_parse_from(args).unwrap();
+        entry.command.unwrap().rejects_dry_run()
+    }
+
+    #[test]
+    fn commands_sending_transactions_reject_dry_run() {
+        assert!(rejects_dry_run(&["hermes", "--dry-run", "start"]));
+        assert!(rejects_dry_run(&[
+            "hermes",
+            "--dry-run",
+            "misbehaviour",
+            "ibc-0",
+            "07-tendermint-0",
+        ]));
+        assert!(rejects_dry_run(&[
+            "hermes",
+            "--dry-run",
+            "misbehaviour",
+            "ibc-0",
+            "07-tendermint-0",
+            "--scan-all",
+        ]));
+
+        assert!(!rejects_dry_run(&[
+            "hermes",
+            "--dry-run",
+            "tx",
+            "raw",
+            "packet-recv",
+            "ibc-1",
+            "ibc-0",
+            "transfer",
+            "channel-0",
+        ]));
+        assert!(!rejects_dry_run(&[
+            "hermes",
+            "--dry-run",
+            "update",
+            "client",
+            "ibc-0",
+            "07-tendermint-0",
+        ]));
+    }
+}
@@ -6,6 +6,7 @@ use ibc_relayer::connection::ConnectionError;
 use ibc_relayer::error::Error as RelayerError;
 use ibc_relayer::foreign_client::ForeignClientError;
 use ibc_relayer::link::error::LinkError;
+use ibc_relayer::recover_client::RecoverClientError;
 use ibc_relayer::supervisor::Error as SupervisorError;
 use ibc_relayer::transfer::PacketError;
 use ibc_relayer::upgrade_chain::UpgradeChainError;
@@ -92,5 +93,9 @@ define_error! {
         UpgradeChain
             [ UpgradeChainError ]
             |_| { "upgrade chain error" },
+
+        RecoverClient
+            [ RecoverClientError ]
+            |_| { "recover client error" },
     }
 }
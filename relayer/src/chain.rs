@@ -1,5 +1,6 @@
 use alloc::sync::Arc;
 use prost_types::Any;
+use serde::Serialize;
 use tendermint::block::Height;
 use tokio::runtime::Runtime as TokioRuntime;
 
@@ -15,7 +16,9 @@ use ibc::core::ics02_client::header::Header;
 use ibc::core::ics03_connection::connection::{ConnectionEnd, IdentifiedConnectionEnd, State};
 use ibc::core::ics03_connection::version::{get_compatible_versions, Version};
 use ibc::core::ics04_channel;
-use ibc::core::ics04_channel::channel::{ChannelEnd, IdentifiedChannelEnd};
+use ibc::core::ics04_channel::channel::{
+    ChannelEnd, IdentifiedChannelEnd, QueryPacketEventDataRequest,
+};
 use ibc::core::ics04_channel::packet::{PacketMsgType, Sequence};
 use ibc::core::ics23_commitment::commitment::{CommitmentPrefix, CommitmentProofBytes};
 use ibc::core::ics24_host::identifier::{ChainId, ChannelId, ClientId, ConnectionId, PortId};
@@ -25,6 +28,7 @@ use ibc::query::{QueryBlockRequest, QueryTxRequest};
 use ibc::signer::Signer;
 use ibc::timestamp::Timestamp;
 use ibc::Height as ICSHeight;
+use ibc_proto::cosmos::upgrade::v1beta1::Plan;
 use ibc_proto::ibc::core::channel::v1::{
     PacketState, QueryChannelClientStateRequest, QueryChannelsRequest,
     QueryConnectionChannelsRequest, QueryNextSequenceReceiveRequest,
@@ -51,7 +55,7 @@ pub mod counterparty;
 pub mod handle;
 pub mod runtime;
 
-#[cfg(test)]
+#[cfg(any(test, feature = "mocks"))]
 pub mod mock;
 
 /// The result of a health check.
@@ -68,6 +72,30 @@ pub struct StatusResponse {
     pub timestamp: Timestamp,
 }
 
+/// The outcome of simulating a transaction, without broadcasting it.
+#[derive(Clone, Debug, Serialize)]
+pub struct TxSimulation {
+    /// The amount of gas used by the simulated transaction
+    pub gas_used: u64,
+    /// The gas limit the transaction would be submitted with,
+    /// ie. the gas used adjusted with the configured `gas_adjustment`
+    pub gas_limit: u64,
+    /// The fee that would be paid for the transaction
+    pub fee: String,
+}
+
+/// A packet event, along with the transaction in which it was emitted.
+#[derive(Clone, Debug, Serialize)]
+pub struct PacketTx {
+    pub event: IbcEvent,
+    /// Height of the block that includes the transaction
+    pub height: ICSHeight,
+    /// Hash of the transaction
+    pub tx_hash: String,
+    /// The accounts which submitted the messages of the transaction
+    pub signers: Vec<String>,
+}
+
 /// Generic query response type
 /// TODO - will slowly move to GRPC protobuf specs for queries
 #[derive(Clone, Debug, PartialEq)]
@@ -135,6 +163,10 @@ pub trait ChainEndpoint: Sized {
         proto_msgs: Vec<Any>,
     ) -> Result<Vec<TxResponse>, Error>;
 
+    /// Simulates a single transaction with all the `proto_msgs`, without broadcasting it,
+    /// and reports the gas it would use and the fee it would cost.
+    fn simulate_messages(&mut self, proto_msgs: Vec<Any>) -> Result<TxSimulation, Error>;
+
     fn get_signer(&mut self) -> Result<Signer, Error>;
 
     fn config(&self) -> ChainConfig;
@@ -191,6 +223,9 @@ pub trait ChainEndpoint: Sized {
         height: ICSHeight,
     ) -> Result<(Self::ConsensusState, MerkleProof), Error>;
 
+    /// Performs a query to retrieve the upgrade plan currently scheduled on the chain, if any.
+    fn query_upgrade_plan(&self) -> Result<Option<Plan>, Error>;
+
     /// Performs a query to retrieve the identifiers of all connections.
     fn query_connections(
         &self,
@@ -262,6 +297,13 @@ pub trait ChainEndpoint: Sized {
 
     fn query_txs(&self, request: QueryTxRequest) -> Result<Vec<IbcEvent>, Error>;
 
+    /// Queries the first transaction emitting the packet event requested for each
+    /// of the sequences of the request, together with the hash and signers of the transaction.
+    fn query_packet_txs(
+        &self,
+        request: QueryPacketEventDataRequest,
+    ) -> Result<Vec<PacketTx>, Error>;
+
     fn query_blocks(
         &self,
         request: QueryBlockRequest,
@@ -8,7 +8,7 @@ use core::{
 };
 use num_bigint::BigInt;
 use num_rational::BigRational;
-use std::{fmt, thread, time::Instant};
+use std::{fmt, sync::Mutex, thread, time::Instant};
 
 use bech32::{ToBase32, Variant};
 use bitcoin::hashes::hex::ToHex;
@@ -19,6 +19,7 @@ use tendermint::abci::{Code, Event, Path as TendermintABCIPath};
 use tendermint::account::Id as AccountId;
 use tendermint::block::Height;
 use tendermint::consensus::Params;
+use tendermint_light_client::light_client::Options as TmOptions;
 use tendermint_light_client::types::LightBlock as TMLightBlock;
 use tendermint_proto::Protobuf;
 use tendermint_rpc::endpoint::tx::Response as ResultTx;
@@ -67,6 +68,7 @@ use ibc_proto::cosmos::tx::v1beta1::{
     AuthInfo, Fee, ModeInfo, SignDoc, SignerInfo, SimulateRequest, SimulateResponse, Tx, TxBody,
     TxRaw,
 };
+use ibc_proto::cosmos::upgrade::v1beta1::{Plan, QueryCurrentPlanRequest};
 use ibc_proto::ibc::core::channel::v1::{
     PacketState, QueryChannelClientStateRequest, QueryChannelsRequest,
     QueryConnectionChannelsRequest, QueryNextSequenceReceiveRequest,
@@ -80,12 +82,12 @@ use ibc_proto::ibc::core::connection::v1::{
 };
 use ibc_proto::ibc::core::port::v1::QueryAppVersionRequest;
 
-use crate::event::monitor::{EventMonitor, EventReceiver};
+use crate::event::monitor::{EventMonitor, EventReceiver, NodeAddr};
 use crate::keyring::{KeyEntry, KeyRing};
 use crate::light_client::tendermint::LightClient as TmLightClient;
 use crate::light_client::LightClient;
 use crate::light_client::Verified;
-use crate::util::retry::{retry_with_index, RetryResult};
+use crate::util::retry::{retry_with_index, RetryError, RetryResult};
 use crate::{
     chain::handle::requests::AppVersion, chain::QueryResponse, chain::StatusResponse,
     event::monitor::TxMonitorCmd,
@@ -96,11 +98,16 @@ use crate::{
     sdk_error::sdk_error_from_tx_sync_error_code,
 };
 
-use super::{ChainEndpoint, HealthCheck};
+use super::{ChainEndpoint, HealthCheck, PacketTx, TxSimulation};
 
 mod compatibility;
+pub(crate) mod endpoints;
+mod query_verifier;
 pub mod version;
 
+use endpoints::{Endpoints, Node};
+use query_verifier::{check_trust_anchor, QueryVerifier};
+
 /// Default gas limit when submitting a transaction.
 const DEFAULT_MAX_GAS: u64 = 400_000;
 
@@ -134,16 +141,24 @@ mod retry_strategy {
         let count: usize = (max_total_wait.as_millis() / backoff_millis as u128) as usize;
         Fixed::from_millis(backoff_millis).take(count)
     }
+
+    pub fn wait_for_block(max_total_wait: Duration) -> impl Iterator<Item = Duration> {
+        let backoff_millis = 500; // The periodic backoff
+        let count: usize = (max_total_wait.as_millis() / backoff_millis as u128) as usize;
+        Fixed::from_millis(backoff_millis).take(count)
+    }
 }
 
 pub struct CosmosSdkChain {
     config: ChainConfig,
-    rpc_client: HttpClient,
-    grpc_addr: Uri,
+    /// The full nodes of the chain, and the one currently in use
+    endpoints: Arc<Endpoints>,
     rt: Arc<TokioRuntime>,
     keybase: KeyRing,
     /// A cached copy of the account information
     account: Option<BaseAccount>,
+    /// The verifier of the query proofs, initialized on first use
+    query_verifier: Mutex<Option<QueryVerifier>>,
 }
 
 impl CosmosSdkChain {
@@ -198,11 +213,11 @@ impl CosmosSdkChain {
 
         // Check on the configured max_tx_size against the consensus parameters at latest height
         let result = self
-            .block_on(self.rpc_client.consensus_params(latest_height))
+            .block_on(self.rpc_client().consensus_params(latest_height))
             .map_err(|e| {
                 Error::config_validation_json_rpc(
                     self.id().clone(),
-                    self.config.rpc_addr.to_string(),
+                    self.rpc_addr().to_string(),
                     "/consensus_params".to_string(),
                     e,
                 )
@@ -248,17 +263,17 @@ impl CosmosSdkChain {
         let mut client = self
             .block_on(
                 ibc_proto::cosmos::staking::v1beta1::query_client::QueryClient::connect(
-                    self.grpc_addr.clone(),
+                    self.grpc_addr(),
                 ),
             )
-            .map_err(Error::grpc_transport)?;
+            .map_err(|e| self.grpc_transport_error(e))?;
 
         let request =
             tonic::Request::new(ibc_proto::cosmos::staking::v1beta1::QueryParamsRequest {});
 
         let response = self
             .block_on(client.params(request))
-            .map_err(Error::grpc_status)?;
+            .map_err(|e| self.grpc_status_error(e))?;
 
         let res = response
             .into_inner()
@@ -271,7 +286,27 @@ impl CosmosSdkChain {
     }
 
     fn rpc_client(&self) -> &HttpClient {
-        &self.rpc_client
+        &self.endpoints.current().rpc_client
+    }
+
+    fn rpc_addr(&self) -> Url {
+        self.endpoints.current().rpc_addr.clone()
+    }
+
+    fn grpc_addr(&self) -> Uri {
+        self.endpoints.current().grpc_addr.clone()
+    }
+
+    /// Records the failure to connect to the gRPC endpoint of the node in use.
+    fn grpc_transport_error(&self, e: tonic::transport::Error) -> Error {
+        self.endpoints.record_error();
+        Error::grpc_transport(e)
+    }
+
+    /// Records the error status returned by the gRPC endpoint of the node in use.
+    fn grpc_status_error(&self, status: tonic::Status) -> Error {
+        self.endpoints.record_grpc_status(&status);
+        Error::grpc_status(status)
     }
 
     pub fn config(&self) -> &ChainConfig {
@@ -285,7 +320,7 @@ impl CosmosSdkChain {
 
         Ok(self
             .block_on(self.rpc_client().genesis())
-            .map_err(|e| Error::rpc(self.config.rpc_addr.clone(), e))?
+            .map_err(|e| Error::rpc(self.rpc_addr(), e))?
             .consensus_params)
     }
 
@@ -364,11 +399,17 @@ impl CosmosSdkChain {
         prost::Message::encode(&tx_raw, &mut tx_bytes)
             .map_err(|e| Error::protobuf_encode(String::from("Transaction"), e))?;
 
-        self.block_on(broadcast_tx_sync(
+        let response = self.block_on(broadcast_tx_sync(
             self.rpc_client(),
-            &self.config.rpc_addr,
+            &self.rpc_addr(),
             tx_bytes,
-        ))
+        ));
+
+        if response.is_err() {
+            self.endpoints.record_error();
+        }
+
+        response
     }
 
     /// Try to `send_tx` with retry on account sequence error.
@@ -608,12 +649,96 @@ impl CosmosSdkChain {
 
         let response = self.block_on(abci_query(self, path, data.to_string(), height, prove))?;
 
-        // TODO - Verify response proof, if requested.
-        if prove {}
+        if prove && self.config.verify_query_proofs {
+            self.verify_query_proof(&data, &response)?;
+        }
 
         Ok(response)
     }
 
+    /// Verifies the proof of the response to a query against the app hash
+    /// of the next block, verified by the light client.
+    fn verify_query_proof(&self, data: &Path, response: &QueryResponse) -> Result<(), Error> {
+        crate::time!("verify_query_proof");
+
+        // The block holding the app hash may not be committed yet if the query
+        // was made at the latest height
+        self.wait_for_height(response.height.value() + 1)?;
+
+        let mut query_verifier = self.query_verifier.lock().expect("poisoned lock");
+
+        let mut verifier = match query_verifier.take() {
+            Some(verifier) => verifier,
+            None => self.init_query_verifier()?,
+        };
+
+        let result = verifier.verify(data, response);
+
+        *query_verifier = Some(verifier);
+
+        result
+    }
+
+    /// Waits until the block at the given height is committed,
+    /// for at most the RPC timeout of the chain.
+    fn wait_for_height(&self, height: u64) -> Result<(), Error> {
+        let not_committed = || Error::query_proof_block_not_committed(self.id().clone(), height);
+
+        retry_with_index(
+            retry_strategy::wait_for_block(self.config.rpc_timeout),
+            |_| match self.query_latest_height() {
+                Ok(latest) if latest.revision_height >= height => RetryResult::Ok(()),
+                Ok(_) => RetryResult::Retry(not_committed()),
+                Err(e) => RetryResult::Err(e),
+            },
+        )
+        .map_err(|e| match e {
+            RetryError::Operation { error, .. } => error,
+            RetryError::Internal(_) => not_committed(),
+        })
+    }
+
+    /// Creates the verifier of the query proofs, which trusts the block configured as
+    /// `query_trust_anchor` and the trust parameters of the chain configuration.
+    /// The full node cannot vouch for the block from which its own responses are
+    /// verified, so no proof is accepted if no block is configured.
+    fn init_query_verifier(&self) -> Result<QueryVerifier, Error> {
+        let trust_anchor = self
+            .config
+            .query_trust_anchor
+            .as_ref()
+            .ok_or_else(|| Error::query_trust_anchor_missing(self.id().clone()))?;
+
+        let mut light_client = self.init_light_client()?;
+
+        let unbonding_period = self.unbonding_period()?;
+        let options = TmOptions {
+            trust_threshold: self.config.trust_threshold,
+            trusting_period: self.trusting_period(unbonding_period),
+            clock_drift: self.config.clock_drift,
+        };
+
+        let anchor_height = ICSHeight::new(self.id().version(), trust_anchor.height);
+        let anchor = light_client.fetch(anchor_height)?;
+
+        check_trust_anchor(self.id(), &anchor, trust_anchor)?;
+
+        info!(
+            "[{}] verifying the proofs of queries from the trusted block at height {}",
+            self.id(),
+            anchor.height()
+        );
+
+        Ok(QueryVerifier::new(
+            self.id().clone(),
+            light_client,
+            options,
+            self.query_commitment_prefix()?,
+            self.config.proof_specs.clone(),
+            anchor,
+        ))
+    }
+
     /// Perform an ABCI query against the client upgrade sub-store.
     /// Fetches both the target data, as well as the proof.
     ///
@@ -630,13 +755,13 @@ impl CosmosSdkChain {
         // SAFETY: Creating a Path from a constant; this should never fail
         let path = TendermintABCIPath::from_str(SDK_UPGRADE_QUERY_PATH)
             .expect("Turning SDK upgrade query path constant into a Tendermint ABCI path");
-        let response: QueryResponse = self.block_on(abci_query(
-            self,
-            path,
-            Path::Upgrade(data).to_string(),
-            prev_height,
-            true,
-        ))?;
+        let data = Path::Upgrade(data);
+        let response: QueryResponse =
+            self.block_on(abci_query(self, path, data.to_string(), prev_height, true))?;
+
+        if self.config.verify_query_proofs {
+            self.verify_query_proof(&data, &response)?;
+        }
 
         let proof = response.proof.ok_or_else(Error::empty_response_proof)?;
 
@@ -660,13 +785,13 @@ impl CosmosSdkChain {
         };
 
         let mut client = self
-            .block_on(ServiceClient::connect(self.grpc_addr.clone()))
-            .map_err(Error::grpc_transport)?;
+            .block_on(ServiceClient::connect(self.grpc_addr()))
+            .map_err(|e| self.grpc_transport_error(e))?;
 
         let request = tonic::Request::new(req);
         let response = self
             .block_on(client.simulate(request))
-            .map_err(Error::grpc_status)?
+            .map_err(|e| self.grpc_status_error(e))?
             .into_inner();
 
         Ok(response)
@@ -891,13 +1016,14 @@ impl CosmosSdkChain {
 
     /// Query the chain status via an RPC query
     fn status(&self) -> Result<status::Response, Error> {
-        let status = self
-            .block_on(self.rpc_client().status())
-            .map_err(|e| Error::rpc(self.config.rpc_addr.clone(), e))?;
+        let status = self.block_on(self.rpc_client().status()).map_err(|e| {
+            self.endpoints.record_error();
+            Error::rpc(self.rpc_addr(), e)
+        })?;
 
         if status.sync_info.catching_up {
             return Err(Error::chain_not_caught_up(
-                self.config.rpc_addr.to_string(),
+                self.rpc_addr().to_string(),
                 self.config().id.clone(),
             ));
         }
@@ -915,6 +1041,13 @@ impl CosmosSdkChain {
     }
 }
 
+fn node_addr(node: &Node) -> NodeAddr {
+    NodeAddr {
+        websocket_addr: node.websocket_addr.clone(),
+        rpc_addr: node.rpc_addr.clone(),
+    }
+}
+
 fn empty_event_present(events: &[IbcEvent]) -> bool {
     events.iter().any(|ev| matches!(ev, IbcEvent::Empty(_)))
 }
@@ -933,44 +1066,31 @@ impl ChainEndpoint for CosmosSdkChain {
     type LightClient = TmLightClient;
 
     fn bootstrap(config: ChainConfig, rt: Arc<TokioRuntime>) -> Result<Self, Error> {
-        let rpc_client = HttpClient::new(config.rpc_addr.clone())
-            .map_err(|e| Error::rpc(config.rpc_addr.clone(), e))?;
+        let endpoints = Arc::new(Endpoints::from_config(&config)?);
+        Endpoints::spawn_health_checks(&endpoints, rt.clone());
 
         // Initialize key store and load key
         let keybase = KeyRing::new(config.key_store_type, &config.account_prefix, &config.id)
             .map_err(Error::key_base)?;
 
-        let grpc_addr = Uri::from_str(&config.grpc_addr.to_string())
-            .map_err(|e| Error::invalid_uri(config.grpc_addr.to_string(), e))?;
-
         // Retrieve the version specification of this chain
 
         let chain = Self {
             config,
-            rpc_client,
-            grpc_addr,
+            endpoints,
             rt,
             keybase,
             account: None,
+            query_verifier: Mutex::new(None),
         };
 
         Ok(chain)
     }
 
     fn init_light_client(&self) -> Result<Self::LightClient, Error> {
-        use tendermint_light_client::types::PeerId;
-
         crate::time!("init_light_client");
 
-        let peer_id: PeerId = self
-            .rt
-            .block_on(self.rpc_client.status())
-            .map(|s| s.node_info.id)
-            .map_err(|e| Error::rpc(self.config.rpc_addr.clone(), e))?;
-
-        let light_client = TmLightClient::from_config(&self.config, peer_id)?;
-
-        Ok(light_client)
+        TmLightClient::from_config(&self.config, self.endpoints.clone(), self.rt.clone())
     }
 
     fn init_event_monitor(
@@ -979,14 +1099,29 @@ impl ChainEndpoint for CosmosSdkChain {
     ) -> Result<(EventReceiver, TxMonitorCmd), Error> {
         crate::time!("init_event_monitor");
 
-        let (mut event_monitor, event_receiver, monitor_tx) = EventMonitor::new(
+        let node_addrs = self.endpoints.all_from_current().map(node_addr).collect();
+
+        let (event_monitor, event_receiver, monitor_tx) = EventMonitor::new(
             self.config.id.clone(),
-            self.config.websocket_addr.clone(),
+            node_addrs,
+            self.config.event_source.clone(),
             rt,
         )
         .map_err(Error::event_monitor)?;
 
-        event_monitor.subscribe().map_err(Error::event_monitor)?;
+        // The event monitor fails over to the healthiest node, as of the last health checks
+        let endpoints = self.endpoints.clone();
+        let mut event_monitor = event_monitor
+            .with_node_ranking(move || endpoints.by_health().into_iter().map(node_addr).collect());
+
+        // The event monitor polls the blocks until it manages to subscribe, if the fallback is enabled
+        if let Err(e) = event_monitor.subscribe() {
+            if !self.config.event_source.poll_fallback {
+                return Err(Error::event_monitor(e));
+            }
+
+            warn!("[{}] failed to subscribe to events: {}", self.id(), e);
+        }
 
         thread::spawn(move || event_monitor.run());
 
@@ -1145,6 +1280,43 @@ impl ChainEndpoint for CosmosSdkChain {
         Ok(responses)
     }
 
+    fn simulate_messages(&mut self, proto_msgs: Vec<Any>) -> Result<TxSimulation, Error> {
+        crate::time!("simulate_messages");
+
+        let account_seq = self.account_sequence()?;
+        let signer_info = self.signer(account_seq)?;
+
+        let (body, body_buf) = tx_body_and_bytes(proto_msgs, self.tx_memo())?;
+        let (auth_info, auth_buf) = auth_info_and_bytes(signer_info, self.max_fee())?;
+        let signed_doc = self.signed_doc(body_buf, auth_buf, account_seq)?;
+
+        let simulate_tx = Tx {
+            body: Some(body),
+            auth_info: Some(auth_info),
+            signatures: vec![signed_doc],
+        };
+
+        // Unlike `estimate_gas`, do not fall back on the default gas if the
+        // simulation fails, since the failure is what a dry run should report.
+        let gas_used = self
+            .send_tx_simulate(simulate_tx)?
+            .gas_info
+            .map_or_else(|| self.default_gas(), |gas_info| gas_info.gas_used);
+
+        let fee = self.fee_with_gas(gas_used);
+
+        Ok(TxSimulation {
+            gas_used,
+            gas_limit: fee.gas_limit,
+            fee: fee
+                .amount
+                .iter()
+                .map(|coin| format!("{}{}", coin.amount, coin.denom))
+                .collect::<Vec<_>>()
+                .join(","),
+        })
+    }
+
     /// Get the account for the signer
     fn get_signer(&mut self) -> Result<Signer, Error> {
         crate::time!("get_signer");
@@ -1220,15 +1392,15 @@ impl ChainEndpoint for CosmosSdkChain {
         let mut client = self
             .block_on(
                 ibc_proto::ibc::core::client::v1::query_client::QueryClient::connect(
-                    self.grpc_addr.clone(),
+                    self.grpc_addr(),
                 ),
             )
-            .map_err(Error::grpc_transport)?;
+            .map_err(|e| self.grpc_transport_error(e))?;
 
         let request = tonic::Request::new(request);
         let response = self
             .block_on(client.client_states(request))
-            .map_err(Error::grpc_status)?
+            .map_err(|e| self.grpc_status_error(e))?
             .into_inner();
 
         // Deserialize into domain type
@@ -1308,6 +1480,27 @@ impl ChainEndpoint for CosmosSdkChain {
         Ok((tm_consensus_state, proof))
     }
 
+    fn query_upgrade_plan(&self) -> Result<Option<Plan>, Error> {
+        crate::time!("query_upgrade_plan");
+
+        let mut client = self
+            .block_on(
+                ibc_proto::cosmos::upgrade::v1beta1::query_client::QueryClient::connect(
+                    self.grpc_addr(),
+                ),
+            )
+            .map_err(|e| self.grpc_transport_error(e))?;
+
+        let request = tonic::Request::new(QueryCurrentPlanRequest {});
+
+        let response = self
+            .block_on(client.current_plan(request))
+            .map_err(|e| self.grpc_status_error(e))?
+            .into_inner();
+
+        Ok(response.plan)
+    }
+
     /// Performs a query to retrieve the identifiers of all connections.
     fn query_consensus_states(
         &self,
@@ -1318,15 +1511,15 @@ impl ChainEndpoint for CosmosSdkChain {
         let mut client = self
             .block_on(
                 ibc_proto::ibc::core::client::v1::query_client::QueryClient::connect(
-                    self.grpc_addr.clone(),
+                    self.grpc_addr(),
                 ),
             )
-            .map_err(Error::grpc_transport)?;
+            .map_err(|e| self.grpc_transport_error(e))?;
 
         let request = tonic::Request::new(request);
         let response = self
             .block_on(client.consensus_states(request))
-            .map_err(Error::grpc_status)?
+            .map_err(|e| self.grpc_status_error(e))?
             .into_inner();
 
         let mut consensus_states: Vec<AnyConsensusStateWithHeight> = response
@@ -1362,17 +1555,17 @@ impl ChainEndpoint for CosmosSdkChain {
         let mut client = self
             .block_on(
                 ibc_proto::ibc::core::connection::v1::query_client::QueryClient::connect(
-                    self.grpc_addr.clone(),
+                    self.grpc_addr(),
                 ),
             )
-            .map_err(Error::grpc_transport)?;
+            .map_err(|e| self.grpc_transport_error(e))?;
 
         let request = tonic::Request::new(request);
 
         let response = match self.block_on(client.client_connections(request)) {
             Ok(res) => res.into_inner(),
             Err(e) if e.code() == tonic::Code::NotFound => return Ok(vec![]),
-            Err(e) => return Err(Error::grpc_status(e)),
+            Err(e) => return Err(self.grpc_status_error(e)),
         };
 
         // TODO: add warnings for any identifiers that fail to parse (below).
@@ -1396,16 +1589,16 @@ impl ChainEndpoint for CosmosSdkChain {
         let mut client = self
             .block_on(
                 ibc_proto::ibc::core::connection::v1::query_client::QueryClient::connect(
-                    self.grpc_addr.clone(),
+                    self.grpc_addr(),
                 ),
             )
-            .map_err(Error::grpc_transport)?;
+            .map_err(|e| self.grpc_transport_error(e))?;
 
         let request = tonic::Request::new(request);
 
         let response = self
             .block_on(client.connections(request))
-            .map_err(Error::grpc_status)?
+            .map_err(|e| self.grpc_status_error(e))?
             .into_inner();
 
         // TODO: add warnings for any identifiers that fail to parse (below).
@@ -1433,10 +1626,9 @@ impl ChainEndpoint for CosmosSdkChain {
             use ibc_proto::ibc::core::connection::v1 as connection;
             use tonic::{metadata::MetadataValue, IntoRequest};
 
-            let mut client =
-                connection::query_client::QueryClient::connect(chain.grpc_addr.clone())
-                    .await
-                    .map_err(Error::grpc_transport)?;
+            let mut client = connection::query_client::QueryClient::connect(chain.grpc_addr())
+                .await
+                .map_err(|e| chain.grpc_transport_error(e))?;
 
             let mut request = connection::QueryConnectionRequest {
                 connection_id: connection_id.to_string(),
@@ -1454,7 +1646,7 @@ impl ChainEndpoint for CosmosSdkChain {
                 if e.code() == tonic::Code::NotFound {
                     Error::connection_not_found(connection_id.clone())
                 } else {
-                    Error::grpc_status(e)
+                    chain.grpc_status_error(e)
                 }
             })?;
 
@@ -1486,16 +1678,16 @@ impl ChainEndpoint for CosmosSdkChain {
         let mut client = self
             .block_on(
                 ibc_proto::ibc::core::channel::v1::query_client::QueryClient::connect(
-                    self.grpc_addr.clone(),
+                    self.grpc_addr(),
                 ),
             )
-            .map_err(Error::grpc_transport)?;
+            .map_err(|e| self.grpc_transport_error(e))?;
 
         let request = tonic::Request::new(request);
 
         let response = self
             .block_on(client.connection_channels(request))
-            .map_err(Error::grpc_status)?
+            .map_err(|e| self.grpc_status_error(e))?
             .into_inner();
 
         // TODO: add warnings for any identifiers that fail to parse (below).
@@ -1518,16 +1710,16 @@ impl ChainEndpoint for CosmosSdkChain {
         let mut client = self
             .block_on(
                 ibc_proto::ibc::core::channel::v1::query_client::QueryClient::connect(
-                    self.grpc_addr.clone(),
+                    self.grpc_addr(),
                 ),
             )
-            .map_err(Error::grpc_transport)?;
+            .map_err(|e| self.grpc_transport_error(e))?;
 
         let request = tonic::Request::new(request);
 
         let response = self
             .block_on(client.channels(request))
-            .map_err(Error::grpc_status)?
+            .map_err(|e| self.grpc_status_error(e))?
             .into_inner();
 
         let channels = response
@@ -1563,16 +1755,16 @@ impl ChainEndpoint for CosmosSdkChain {
         let mut client = self
             .block_on(
                 ibc_proto::ibc::core::channel::v1::query_client::QueryClient::connect(
-                    self.grpc_addr.clone(),
+                    self.grpc_addr(),
                 ),
             )
-            .map_err(Error::grpc_transport)?;
+            .map_err(|e| self.grpc_transport_error(e))?;
 
         let request = tonic::Request::new(request);
 
         let response = self
             .block_on(client.channel_client_state(request))
-            .map_err(Error::grpc_status)?
+            .map_err(|e| self.grpc_status_error(e))?
             .into_inner();
 
         let client_state: Option<IdentifiedAnyClientState> = response
@@ -1592,16 +1784,16 @@ impl ChainEndpoint for CosmosSdkChain {
         let mut client = self
             .block_on(
                 ibc_proto::ibc::core::channel::v1::query_client::QueryClient::connect(
-                    self.grpc_addr.clone(),
+                    self.grpc_addr(),
                 ),
             )
-            .map_err(Error::grpc_transport)?;
+            .map_err(|e| self.grpc_transport_error(e))?;
 
         let request = tonic::Request::new(request);
 
         let response = self
             .block_on(client.packet_commitments(request))
-            .map_err(Error::grpc_status)?
+            .map_err(|e| self.grpc_status_error(e))?
             .into_inner();
 
         let mut pc = response.commitments;
@@ -1625,16 +1817,16 @@ impl ChainEndpoint for CosmosSdkChain {
         let mut client = self
             .block_on(
                 ibc_proto::ibc::core::channel::v1::query_client::QueryClient::connect(
-                    self.grpc_addr.clone(),
+                    self.grpc_addr(),
                 ),
             )
-            .map_err(Error::grpc_transport)?;
+            .map_err(|e| self.grpc_transport_error(e))?;
 
         let request = tonic::Request::new(request);
 
         let mut response = self
             .block_on(client.unreceived_packets(request))
-            .map_err(Error::grpc_status)?
+            .map_err(|e| self.grpc_status_error(e))?
             .into_inner();
 
         response.sequences.sort_unstable();
@@ -1651,16 +1843,16 @@ impl ChainEndpoint for CosmosSdkChain {
         let mut client = self
             .block_on(
                 ibc_proto::ibc::core::channel::v1::query_client::QueryClient::connect(
-                    self.grpc_addr.clone(),
+                    self.grpc_addr(),
                 ),
             )
-            .map_err(Error::grpc_transport)?;
+            .map_err(|e| self.grpc_transport_error(e))?;
 
         let request = tonic::Request::new(request);
 
         let response = self
             .block_on(client.packet_acknowledgements(request))
-            .map_err(Error::grpc_status)?
+            .map_err(|e| self.grpc_status_error(e))?
             .into_inner();
 
         let pc = response.acknowledgements;
@@ -1683,16 +1875,16 @@ impl ChainEndpoint for CosmosSdkChain {
         let mut client = self
             .block_on(
                 ibc_proto::ibc::core::channel::v1::query_client::QueryClient::connect(
-                    self.grpc_addr.clone(),
+                    self.grpc_addr(),
                 ),
             )
-            .map_err(Error::grpc_transport)?;
+            .map_err(|e| self.grpc_transport_error(e))?;
 
         let request = tonic::Request::new(request);
 
         let mut response = self
             .block_on(client.unreceived_acks(request))
-            .map_err(Error::grpc_status)?
+            .map_err(|e| self.grpc_status_error(e))?
             .into_inner();
 
         response.sequences.sort_unstable();
@@ -1708,16 +1900,16 @@ impl ChainEndpoint for CosmosSdkChain {
         let mut client = self
             .block_on(
                 ibc_proto::ibc::core::channel::v1::query_client::QueryClient::connect(
-                    self.grpc_addr.clone(),
+                    self.grpc_addr(),
                 ),
             )
-            .map_err(Error::grpc_transport)?;
+            .map_err(|e| self.grpc_transport_error(e))?;
 
         let request = tonic::Request::new(request);
 
         let response = self
             .block_on(client.next_sequence_receive(request))
-            .map_err(Error::grpc_status)?
+            .map_err(|e| self.grpc_status_error(e))?
             .into_inner();
 
         Ok(Sequence::from(response.next_sequence_receive))
@@ -1746,14 +1938,14 @@ impl ChainEndpoint for CosmosSdkChain {
                 for seq in &request.sequences {
                     // query first (and only) Tx that includes the event specified in the query request
                     let response = self
-                        .block_on(self.rpc_client.tx_search(
+                        .block_on(self.rpc_client().tx_search(
                             packet_query(&request, *seq),
                             false,
                             1,
                             1, // get only the first Tx matching the query
                             Order::Ascending,
                         ))
-                        .map_err(|e| Error::rpc(self.config.rpc_addr.clone(), e))?;
+                        .map_err(|e| Error::rpc(self.rpc_addr(), e))?;
 
                     assert!(
                         response.txs.len() <= 1,
@@ -1786,14 +1978,14 @@ impl ChainEndpoint for CosmosSdkChain {
                 // failed on chain. Therefore only one Tx is of interest and current API returns
                 // the first one.
                 let mut response = self
-                    .block_on(self.rpc_client.tx_search(
+                    .block_on(self.rpc_client().tx_search(
                         header_query(&request),
                         false,
                         1,
                         1, // get only the first Tx matching the query
                         Order::Ascending,
                     ))
-                    .map_err(|e| Error::rpc(self.config.rpc_addr.clone(), e))?;
+                    .map_err(|e| Error::rpc(self.rpc_addr(), e))?;
 
                 if response.txs.is_empty() {
                     return Ok(vec![]);
@@ -1813,14 +2005,14 @@ impl ChainEndpoint for CosmosSdkChain {
 
             QueryTxRequest::Transaction(tx) => {
                 let mut response = self
-                    .block_on(self.rpc_client.tx_search(
+                    .block_on(self.rpc_client().tx_search(
                         tx_hash_query(&tx),
                         false,
                         1,
                         1, // get only the first Tx matching the query
                         Order::Ascending,
                     ))
-                    .map_err(|e| Error::rpc(self.config.rpc_addr.clone(), e))?;
+                    .map_err(|e| Error::rpc(self.rpc_addr(), e))?;
 
                 if response.txs.is_empty() {
                     Ok(vec![])
@@ -1832,6 +2024,38 @@ impl ChainEndpoint for CosmosSdkChain {
         }
     }
 
+    fn query_packet_txs(
+        &self,
+        request: QueryPacketEventDataRequest,
+    ) -> Result<Vec<PacketTx>, Error> {
+        crate::time!("query_packet_txs");
+
+        let mut result = vec![];
+
+        for seq in &request.sequences {
+            let response = self
+                .block_on(self.rpc_client().tx_search(
+                    packet_query(&request, *seq),
+                    false,
+                    1,
+                    1, // get only the first Tx matching the query
+                    Order::Ascending,
+                ))
+                .map_err(|e| Error::rpc(self.rpc_addr(), e))?;
+
+            if let Some(tx) = response.txs.into_iter().next() {
+                result.extend(packet_tx_from_tx_search_response(
+                    self.id(),
+                    &request,
+                    *seq,
+                    tx,
+                ));
+            }
+        }
+
+        Ok(result)
+    }
+
     fn query_blocks(
         &self,
         request: QueryBlockRequest,
@@ -1847,13 +2071,13 @@ impl ChainEndpoint for CosmosSdkChain {
 
                 for seq in &request.sequences {
                     let response = self
-                        .block_on(self.rpc_client.block_search(
+                        .block_on(self.rpc_client().block_search(
                             packet_query(&request, *seq),
                             1,
                             1, // there should only be a single match for this query
                             Order::Ascending,
                         ))
-                        .map_err(|e| Error::rpc(self.config.rpc_addr.clone(), e))?;
+                        .map_err(|e| Error::rpc(self.rpc_addr(), e))?;
 
                     assert!(
                         response.blocks.len() <= 1,
@@ -1869,8 +2093,8 @@ impl ChainEndpoint for CosmosSdkChain {
                         }
 
                         let response = self
-                            .block_on(self.rpc_client.block_results(block.header.height))
-                            .map_err(|e| Error::rpc(self.config.rpc_addr.clone(), e))?;
+                            .block_on(self.rpc_client().block_results(block.header.height))
+                            .map_err(|e| Error::rpc(self.rpc_addr(), e))?;
 
                         begin_block_events.append(
                             &mut response
@@ -1878,6 +2102,10 @@ impl ChainEndpoint for CosmosSdkChain {
                                 .unwrap_or_default()
                                 .into_iter()
                                 .filter_map(|ev| filter_matching_event(ev, &request, *seq))
+                                .map(|mut event| {
+                                    event.set_height(response_height);
+                                    event
+                                })
                                 .collect(),
                         );
 
@@ -1887,6 +2115,10 @@ impl ChainEndpoint for CosmosSdkChain {
                                 .unwrap_or_default()
                                 .into_iter()
                                 .filter_map(|ev| filter_matching_event(ev, &request, *seq))
+                                .map(|mut event| {
+                                    event.set_height(response_height);
+                                    event
+                                })
                                 .collect(),
                         );
                     }
@@ -2078,13 +2310,13 @@ impl ChainEndpoint for CosmosSdkChain {
         use ibc_proto::ibc::core::port::v1::query_client::QueryClient;
 
         let mut client = self
-            .block_on(QueryClient::connect(self.grpc_addr.clone()))
-            .map_err(Error::grpc_transport)?;
+            .block_on(QueryClient::connect(self.grpc_addr()))
+            .map_err(|e| self.grpc_transport_error(e))?;
 
         let tonic_req: QueryAppVersionRequest = request.into();
         let response = self.block_on(client.app_version(tonic_req));
         let resp_version = response
-            .map_err(Error::grpc_status)?
+            .map_err(|e| self.grpc_status_error(e))?
             .into_inner()
             .version
             .into();
@@ -2157,6 +2389,50 @@ fn packet_from_tx_search_response(
         .events
         .into_iter()
         .find_map(|ev| filter_matching_event(ev, request, seq))
+        .map(|mut event| {
+            event.set_height(height);
+            event
+        })
+}
+
+// Extracts the packet event from the Tx, like `packet_from_tx_search_response`,
+// along with the hash of the Tx and the senders of its messages.
+fn packet_tx_from_tx_search_response(
+    chain_id: &ChainId,
+    request: &QueryPacketEventDataRequest,
+    seq: Sequence,
+    response: ResultTx,
+) -> Option<PacketTx> {
+    let height = ICSHeight::new(chain_id.version(), u64::from(response.height));
+    if request.height != ICSHeight::zero() && height > request.height {
+        return None;
+    }
+
+    let signers = response
+        .tx_result
+        .events
+        .iter()
+        .filter(|event| event.type_str == "message")
+        .flat_map(|event| event.attributes.iter())
+        .filter(|tag| tag.key.as_ref() == "sender")
+        .map(|tag| tag.value.as_ref().to_string())
+        .unique()
+        .collect();
+
+    let mut event = response
+        .tx_result
+        .events
+        .into_iter()
+        .find_map(|ev| filter_matching_event(ev, request, seq))?;
+
+    event.set_height(height);
+
+    Some(PacketTx {
+        event,
+        height,
+        tx_hash: response.hash.to_string(),
+        signers,
+    })
 }
 
 // Extracts from the Tx the update client event for the requested client and height.
@@ -2247,6 +2523,17 @@ fn filter_matching_event(
         {
             Some(ibc_event)
         }
+        IbcEvent::ReceivePacket(ref recv_ev) if matches_packet(request, seq, &recv_ev.packet) => {
+            Some(ibc_event)
+        }
+        IbcEvent::AcknowledgePacket(ref ack_ev) if matches_packet(request, seq, &ack_ev.packet) => {
+            Some(ibc_event)
+        }
+        IbcEvent::TimeoutPacket(ref timeout_ev)
+            if matches_packet(request, seq, &timeout_ev.packet) =>
+        {
+            Some(ibc_event)
+        }
         _ => None,
     }
 }
@@ -2270,7 +2557,10 @@ async fn abci_query(
         .rpc_client()
         .abci_query(Some(path), data.into_bytes(), height, prove)
         .await
-        .map_err(|e| Error::rpc(chain.config.rpc_addr.clone(), e))?;
+        .map_err(|e| {
+            chain.endpoints.record_error();
+            Error::rpc(chain.rpc_addr(), e)
+        })?;
 
     if !response.code.is_ok() {
         // Fail with response log.
@@ -2313,11 +2603,10 @@ pub async fn broadcast_tx_sync(
 
 /// Uses the GRPC client to retrieve the account sequence
 async fn query_account(chain: &CosmosSdkChain, address: String) -> Result<BaseAccount, Error> {
-    let mut client = ibc_proto::cosmos::auth::v1beta1::query_client::QueryClient::connect(
-        chain.grpc_addr.clone(),
-    )
-    .await
-    .map_err(Error::grpc_transport)?;
+    let mut client =
+        ibc_proto::cosmos::auth::v1beta1::query_client::QueryClient::connect(chain.grpc_addr())
+            .await
+            .map_err(|e| chain.grpc_transport_error(e))?;
 
     let request = tonic::Request::new(QueryAccountRequest {
         address: address.clone(),
@@ -2326,7 +2615,11 @@ async fn query_account(chain: &CosmosSdkChain, address: String) -> Result<BaseAc
     let response = client.account(request).await;
 
     // Querying for an account might fail, i.e. if the account doesn't actually exist
-    let resp_account = match response.map_err(Error::grpc_status)?.into_inner().account {
+    let resp_account = match response
+        .map_err(|e| chain.grpc_status_error(e))?
+        .into_inner()
+        .account
+    {
         Some(account) => account,
         None => return Err(Error::empty_query_account(address)),
     };
@@ -2408,11 +2701,11 @@ pub fn tx_body_and_bytes(proto_msgs: Vec<Any>, memo: &Memo) -> Result<(TxBody, V
 
 async fn do_health_check(chain: &CosmosSdkChain) -> Result<(), Error> {
     let chain_id = chain.id();
-    let grpc_address = chain.grpc_addr.to_string();
-    let rpc_address = chain.config.rpc_addr.to_string();
+    let grpc_address = chain.grpc_addr().to_string();
+    let rpc_address = chain.rpc_addr().to_string();
 
     // Checkup on the self-reported health endpoint
-    chain.rpc_client.health().await.map_err(|e| {
+    chain.rpc_client().health().await.map_err(|e| {
         Error::health_check_json_rpc(
             chain_id.clone(),
             rpc_address.clone(),
@@ -2423,7 +2716,7 @@ async fn do_health_check(chain: &CosmosSdkChain) -> Result<(), Error> {
 
     // Checkup on transaction indexing
     chain
-        .rpc_client
+        .rpc_client()
         .tx_search(
             Query::from(EventType::NewBlock),
             false,
@@ -2441,7 +2734,7 @@ async fn do_health_check(chain: &CosmosSdkChain) -> Result<(), Error> {
             )
         })?;
 
-    let version_specs = fetch_version_specs(&chain.config.id, &chain.grpc_addr).await?;
+    let version_specs = fetch_version_specs(&chain.config.id, &chain.grpc_addr()).await?;
 
     // Checkup on the underlying SDK & IBC-go versions
     if let Err(diagnostic) = compatibility::run_diagnostic(&version_specs) {
@@ -0,0 +1,483 @@
+//! The full nodes of a chain, and the selection of the node to which the
+//! queries and transactions are sent.
+//!
+//! The primary node of the chain is the one configured by `rpc_addr`, `grpc_addr`
+//! and `websocket_addr`, followed by the configured `backup_nodes`. The health of
+//! the nodes is checked periodically in the background, and the relayer switches
+//! to the healthiest node, the one with the lowest latency amongst those which
+//! respond, are not catching up and do not lag behind the others, when the node
+//! it is using becomes unhealthy or returns too many errors.
+//!
+//! The light client fetches the light blocks from the node in use, and the event
+//! monitor reconnects to the healthiest node when its WebSocket connection fails.
+
+use alloc::sync::{Arc, Weak};
+use core::str::FromStr;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use core::time::Duration;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Instant;
+
+use futures::future::join_all;
+use tendermint_rpc::{Client, HttpClient, Url};
+use tokio::runtime::Runtime as TokioRuntime;
+use tokio::time::timeout;
+use tonic::codegen::http::Uri;
+use tonic::Code;
+use tracing::{debug, info, warn};
+
+use ibc::core::ics24_host::identifier::ChainId;
+use ibc_proto::cosmos::base::tendermint::v1beta1::service_client::ServiceClient;
+
+use crate::config::{ChainConfig, Failover};
+use crate::error::Error;
+
+pub struct Node {
+    pub rpc_addr: Url,
+    pub rpc_client: HttpClient,
+    pub grpc_addr: Uri,
+    pub websocket_addr: Url,
+}
+
+impl Node {
+    fn new(rpc_addr: &Url, grpc_addr: &Url, websocket_addr: &Url) -> Result<Self, Error> {
+        let rpc_client =
+            HttpClient::new(rpc_addr.clone()).map_err(|e| Error::rpc(rpc_addr.clone(), e))?;
+
+        let grpc_addr = Uri::from_str(&grpc_addr.to_string())
+            .map_err(|e| Error::invalid_uri(grpc_addr.to_string(), e))?;
+
+        Ok(Self {
+            rpc_addr: rpc_addr.clone(),
+            rpc_client,
+            grpc_addr,
+            websocket_addr: websocket_addr.clone(),
+        })
+    }
+}
+
+/// The health of a node, as of its last health check
+#[derive(Clone, Debug, Default)]
+struct Health {
+    /// Latency of the status query, `None` if it failed
+    latency: Option<Duration>,
+    latest_height: u64,
+    catching_up: bool,
+    grpc_reachable: bool,
+    /// Number of errors returned by the node since the last health check
+    errors: u32,
+}
+
+impl Health {
+    fn responds(&self) -> bool {
+        self.latency.is_some() && self.grpc_reachable && !self.catching_up
+    }
+}
+
+pub struct Endpoints {
+    chain_id: ChainId,
+    config: Failover,
+    rpc_timeout: Duration,
+    nodes: Vec<Node>,
+    /// Index of the node currently in use
+    current: AtomicUsize,
+    health: Mutex<Vec<Health>>,
+}
+
+impl Endpoints {
+    pub fn from_config(config: &ChainConfig) -> Result<Self, Error> {
+        let mut nodes = vec![Node::new(
+            &config.rpc_addr,
+            &config.grpc_addr,
+            &config.websocket_addr,
+        )?];
+
+        for backup in &config.backup_nodes {
+            nodes.push(Node::new(
+                &backup.rpc_addr,
+                &backup.grpc_addr,
+                &backup.websocket_addr,
+            )?);
+        }
+
+        let health = vec![Health::default(); nodes.len()];
+
+        Ok(Self {
+            chain_id: config.id.clone(),
+            config: config.failover.clone(),
+            rpc_timeout: config.rpc_timeout,
+            nodes,
+            current: AtomicUsize::new(0),
+            health: Mutex::new(health),
+        })
+    }
+
+    /// The node currently in use
+    pub fn current(&self) -> &Node {
+        &self.nodes[self.current.load(Ordering::SeqCst)]
+    }
+
+    /// All the nodes, starting with the one currently in use
+    pub fn all_from_current(&self) -> impl Iterator<Item = &Node> {
+        let current = self.current.load(Ordering::SeqCst);
+
+        self.nodes[current..]
+            .iter()
+            .chain(self.nodes[..current].iter())
+    }
+
+    /// All the nodes, from the healthiest to the least healthy as of their last health check:
+    /// the healthy nodes by ascending latency, followed by the others in configuration order.
+    pub fn by_health(&self) -> Vec<&Node> {
+        let health = self.health.lock().expect("poisoned lock");
+
+        self.ranking(&health)
+            .into_iter()
+            .map(|i| &self.nodes[i])
+            .collect()
+    }
+
+    /// Records an error returned by the node currently in use, and fails over
+    /// to another node once it has returned `max_errors` errors since its last health check.
+    pub fn record_error(&self) {
+        if self.nodes.len() < 2 {
+            return;
+        }
+
+        let mut health = self.health.lock().expect("poisoned lock");
+
+        let current = self.current.load(Ordering::SeqCst);
+        health[current].errors += 1;
+
+        if health[current].errors >= self.config.max_errors {
+            self.select(&health);
+        }
+    }
+
+    /// Records the error status of a gRPC query made to the node currently in use, unless
+    /// the status is about the query itself, e.g. if the queried state does not exist.
+    pub fn record_grpc_status(&self, status: &tonic::Status) {
+        if is_node_error(status) {
+            self.record_error();
+        }
+    }
+
+    /// Spawns a thread checking the health of the nodes periodically,
+    /// until the given endpoints are dropped. Does nothing if there is no backup node.
+    pub fn spawn_health_checks(endpoints: &Arc<Self>, rt: Arc<TokioRuntime>) {
+        if endpoints.nodes.len() < 2 {
+            return;
+        }
+
+        let endpoints: Weak<Self> = Arc::downgrade(endpoints);
+
+        thread::spawn(move || loop {
+            let interval = match endpoints.upgrade() {
+                Some(endpoints) => {
+                    rt.block_on(endpoints.check_health());
+                    endpoints.config.health_check_interval
+                }
+                None => break,
+            };
+
+            thread::sleep(interval);
+        });
+    }
+
+    /// Checks the health of all the nodes, and switches to the healthiest one
+    /// if the node currently in use is unhealthy.
+    pub async fn check_health(&self) {
+        let checks = join_all(self.nodes.iter().map(|node| self.check_node(node))).await;
+
+        let mut health = self.health.lock().expect("poisoned lock");
+        *health = checks;
+
+        let current = self.current.load(Ordering::SeqCst);
+
+        if !self.is_healthy(&health, current) {
+            self.select(&health);
+        }
+    }
+
+    async fn check_node(&self, node: &Node) -> Health {
+        let start = Instant::now();
+
+        let status = match timeout(self.rpc_timeout, node.rpc_client.status()).await {
+            Ok(Ok(status)) => status,
+            Ok(Err(e)) => {
+                debug!(
+                    "[{}] health check of node {} failed: {}",
+                    self.chain_id, node.rpc_addr, e
+                );
+
+                return Health::default();
+            }
+            Err(_) => {
+                debug!(
+                    "[{}] health check of node {} timed out",
+                    self.chain_id, node.rpc_addr
+                );
+
+                return Health::default();
+            }
+        };
+
+        let latency = start.elapsed();
+
+        let grpc_reachable = matches!(
+            timeout(
+                self.rpc_timeout,
+                ServiceClient::connect(node.grpc_addr.clone())
+            )
+            .await,
+            Ok(Ok(_))
+        );
+
+        Health {
+            latency: Some(latency),
+            latest_height: status.sync_info.latest_block_height.value(),
+            catching_up: status.sync_info.catching_up,
+            grpc_reachable,
+            errors: 0,
+        }
+    }
+
+    fn is_healthy(&self, health: &[Health], index: usize) -> bool {
+        let node = &health[index];
+
+        let lagging = self.config.cross_check_heights && {
+            let max_height = health
+                .iter()
+                .filter(|h| h.responds())
+                .map(|h| h.latest_height)
+                .max()
+                .unwrap_or_default();
+
+            max_height.saturating_sub(node.latest_height) > self.config.max_height_lag
+        };
+
+        node.responds() && !lagging && node.errors < self.config.max_errors
+    }
+
+    /// The indices of the nodes, from the healthiest to the least healthy.
+    fn ranking(&self, health: &[Health]) -> Vec<usize> {
+        let (mut healthy, unhealthy): (Vec<_>, Vec<_>) =
+            (0..self.nodes.len()).partition(|&i| self.is_healthy(health, i));
+
+        healthy.sort_by_key(|&i| health[i].latency);
+        healthy.extend(unhealthy);
+
+        healthy
+    }
+
+    /// Switches to the healthy node with the lowest latency,
+    /// or stays on the current node if no other node is healthy.
+    fn select(&self, health: &[Health]) {
+        let current = self.current.load(Ordering::SeqCst);
+
+        let best = (0..self.nodes.len())
+            .filter(|&i| i != current && self.is_healthy(health, i))
+            .min_by_key(|&i| health[i].latency);
+
+        match best {
+            Some(best) => {
+                self.current.store(best, Ordering::SeqCst);
+
+                info!(
+                    "[{}] node {} is unhealthy, failing over to node {}",
+                    self.chain_id, self.nodes[current].rpc_addr, self.nodes[best].rpc_addr
+                );
+            }
+            None => warn!(
+                "[{}] node {} is unhealthy, but no other node is healthy",
+                self.chain_id, self.nodes[current].rpc_addr
+            ),
+        }
+    }
+}
+
+/// Returns `true` if the given gRPC status reports a failure of the node,
+/// rather than a query which is invalid or whose state does not exist.
+fn is_node_error(status: &tonic::Status) -> bool {
+    !matches!(
+        status.code(),
+        Code::NotFound
+            | Code::InvalidArgument
+            | Code::AlreadyExists
+            | Code::FailedPrecondition
+            | Code::OutOfRange
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::chain::mock::test_utils::get_basic_chain_config;
+    use crate::config::BackupNode;
+
+    fn endpoints(backups: u16) -> Endpoints {
+        let mut config = get_basic_chain_config("chain_a");
+
+        config.failover = Failover {
+            health_check_interval: Duration::from_secs(60),
+            max_errors: 3,
+            cross_check_heights: true,
+            max_height_lag: 5,
+        };
+
+        config.backup_nodes = (1..=backups)
+            .map(|i| BackupNode {
+                rpc_addr: format!("http://127.0.0.{}:26657", i + 1).parse().unwrap(),
+                grpc_addr: format!("http://127.0.0.{}:9090", i + 1).parse().unwrap(),
+                websocket_addr: format!("ws://127.0.0.{}:26657/websocket", i + 1)
+                    .parse()
+                    .unwrap(),
+            })
+            .collect();
+
+        Endpoints::from_config(&config).unwrap()
+    }
+
+    fn healthy(latency_millis: u64, latest_height: u64) -> Health {
+        Health {
+            latency: Some(Duration::from_millis(latency_millis)),
+            latest_height,
+            catching_up: false,
+            grpc_reachable: true,
+            errors: 0,
+        }
+    }
+
+    fn current(endpoints: &Endpoints) -> usize {
+        endpoints.current.load(Ordering::SeqCst)
+    }
+
+    #[test]
+    fn node_health() {
+        let endpoints = endpoints(1);
+
+        let health = vec![healthy(10, 100), healthy(10, 100)];
+        assert!(endpoints.is_healthy(&health, 0));
+
+        let unresponsive = vec![Health::default(), healthy(10, 100)];
+        assert!(!endpoints.is_healthy(&unresponsive, 0));
+
+        let catching_up = vec![
+            Health {
+                catching_up: true,
+                ..healthy(10, 100)
+            },
+            healthy(10, 100),
+        ];
+        assert!(!endpoints.is_healthy(&catching_up, 0));
+
+        let no_grpc = vec![
+            Health {
+                grpc_reachable: false,
+                ..healthy(10, 100)
+            },
+            healthy(10, 100),
+        ];
+        assert!(!endpoints.is_healthy(&no_grpc, 0));
+
+        let erroring = vec![
+            Health {
+                errors: 3,
+                ..healthy(10, 100)
+            },
+            healthy(10, 100),
+        ];
+        assert!(!endpoints.is_healthy(&erroring, 0));
+
+        // Lagging behind by more than `max_height_lag` blocks
+        let lagging = vec![healthy(10, 94), healthy(10, 100)];
+        assert!(!endpoints.is_healthy(&lagging, 0));
+
+        let behind = vec![healthy(10, 95), healthy(10, 100)];
+        assert!(endpoints.is_healthy(&behind, 0));
+
+        // The heights of the nodes which do not respond are not compared
+        let ahead_unresponsive = vec![
+            healthy(10, 94),
+            Health {
+                latency: None,
+                ..healthy(10, 100)
+            },
+        ];
+        assert!(endpoints.is_healthy(&ahead_unresponsive, 0));
+    }
+
+    #[test]
+    fn select_healthy_node_with_lowest_latency() {
+        let endpoints = endpoints(3);
+
+        let health = vec![
+            Health::default(),
+            healthy(30, 100),
+            healthy(20, 100),
+            Health {
+                catching_up: true,
+                ..healthy(10, 100)
+            },
+        ];
+
+        assert_eq!(endpoints.ranking(&health), vec![2, 1, 0, 3]);
+
+        endpoints.select(&health);
+        assert_eq!(current(&endpoints), 2);
+
+        // Stays on the current node if no other node is healthy
+        let health = vec![
+            Health::default(),
+            Health::default(),
+            healthy(10, 100),
+            Health::default(),
+        ];
+
+        endpoints.select(&health);
+        assert_eq!(current(&endpoints), 2);
+    }
+
+    #[test]
+    fn fail_over_after_max_errors() {
+        let endpoints = endpoints(1);
+
+        *endpoints.health.lock().unwrap() = vec![healthy(10, 100), healthy(20, 100)];
+
+        endpoints.record_error();
+        endpoints.record_error();
+        assert_eq!(current(&endpoints), 0);
+
+        endpoints.record_error();
+        assert_eq!(current(&endpoints), 1);
+
+        // The node which returned the errors is ranked last
+        assert_eq!(
+            endpoints.by_health()[0].rpc_addr.to_string(),
+            endpoints.nodes[1].rpc_addr.to_string()
+        );
+    }
+
+    #[test]
+    fn fail_over_after_grpc_errors() {
+        let endpoints = endpoints(1);
+
+        *endpoints.health.lock().unwrap() = vec![healthy(10, 100), healthy(20, 100)];
+
+        // A state which does not exist is not an error of the node
+        for _ in 0..3 {
+            endpoints.record_grpc_status(&tonic::Status::not_found("connection not found"));
+            endpoints.record_grpc_status(&tonic::Status::invalid_argument("invalid height"));
+        }
+        assert_eq!(current(&endpoints), 0);
+
+        endpoints.record_grpc_status(&tonic::Status::unavailable("connection refused"));
+        endpoints.record_grpc_status(&tonic::Status::deadline_exceeded("timed out"));
+        assert_eq!(current(&endpoints), 0);
+
+        endpoints.record_grpc_status(&tonic::Status::internal("node is syncing"));
+        assert_eq!(current(&endpoints), 1);
+    }
+}
@@ -0,0 +1,454 @@
+//! Verification of the proofs of the ABCI queries made to a chain.
+//!
+//! The proof of a query at height `H` is checked against the app hash of the block
+//! at height `H + 1`, which is verified by the light client of the relayer.
+//! The light client verifies the blocks starting from a trust anchor, which is the block
+//! configured as `query_trust_anchor` for the chain, since the full node whose responses
+//! are verified cannot be trusted to provide it, and which is moved forward to a newly
+//! verified block every half trusting period.
+//! The blocks below the earliest anchor are verified backwards from it, by following
+//! the hashes of the previous blocks, one block at a time.
+//!
+//! The upgraded client and consensus states are proven in the `upgrade` store of the chain,
+//! and all the other paths in its IBC store.
+//!
+//! The caller must ensure that the block at `H + 1` was committed before verifying
+//! the proof of a query at height `H`.
+
+use core::time::Duration;
+
+use tendermint::Time;
+use tendermint_light_client::light_client::Options as TmOptions;
+use tendermint_light_client::types::LightBlock;
+use tracing::debug;
+
+use ibc::core::ics23_commitment::commitment::CommitmentPrefix;
+use ibc::core::ics23_commitment::error::Error as CommitmentError;
+use ibc::core::ics23_commitment::merkle::{apply_prefix, MerkleProof};
+use ibc::core::ics23_commitment::specs::ProofSpecs;
+use ibc::core::ics24_host::identifier::ChainId;
+use ibc::core::ics24_host::Path;
+use ibc_proto::ibc::core::commitment::v1::MerkleRoot;
+
+use crate::chain::QueryResponse;
+use crate::config::TrustAnchor;
+use crate::error::Error;
+use crate::light_client::tendermint::LightClient as TmLightClient;
+
+/// The prefix of the store holding the upgraded client and consensus states
+const UPGRADE_STORE_PREFIX: &[u8] = b"upgrade";
+
+pub struct QueryVerifier {
+    chain_id: ChainId,
+    light_client: TmLightClient,
+    options: TmOptions,
+    store_prefix: CommitmentPrefix,
+    proof_specs: ProofSpecs,
+    /// The light blocks from which the others are verified, by ascending height
+    anchors: Vec<LightBlock>,
+}
+
+impl QueryVerifier {
+    /// Creates a verifier trusting the given `anchor` block.
+    pub fn new(
+        chain_id: ChainId,
+        light_client: TmLightClient,
+        options: TmOptions,
+        store_prefix: CommitmentPrefix,
+        proof_specs: ProofSpecs,
+        anchor: LightBlock,
+    ) -> Self {
+        Self {
+            chain_id,
+            light_client,
+            options,
+            store_prefix,
+            proof_specs,
+            anchors: vec![anchor],
+        }
+    }
+
+    /// Verifies the proof of the response to the query of `data`,
+    /// either of membership if a value was returned, or of non-membership otherwise.
+    pub fn verify(&mut self, data: &Path, response: &QueryResponse) -> Result<(), Error> {
+        let height = response.height.value();
+
+        let proof = response
+            .proof
+            .clone()
+            .ok_or_else(Error::empty_response_proof)?;
+        let proof = MerkleProof::from(proof);
+
+        // The app hash resulting from the state at `height` is in the next block
+        let block = self.verified_block(height + 1)?;
+
+        verify_proof(
+            &self.proof_specs,
+            &store_prefix(&self.store_prefix, data),
+            block.signed_header.header.app_hash.as_ref(),
+            data,
+            &response.value,
+            proof,
+        )
+        .map_err(|e| {
+            Error::query_proof_verification(self.chain_id.clone(), data.to_string(), height, e)
+        })
+    }
+
+    /// Returns the light block at the given height, verified from the highest anchor
+    /// below it, or backwards from the earliest anchor if there is none.
+    fn verified_block(&mut self, height: u64) -> Result<LightBlock, Error> {
+        if let Some(anchor) = self.anchors.iter().find(|b| b.height().value() == height) {
+            return Ok(anchor.clone());
+        }
+
+        let anchor = self
+            .anchors
+            .iter()
+            .rev()
+            .find(|b| b.height().value() < height)
+            .unwrap_or(&self.anchors[0])
+            .clone();
+
+        let target = ibc::Height::new(self.chain_id.version(), height);
+        let block = self
+            .light_client
+            .verify_from(anchor, target, self.options)?;
+
+        self.update_anchors(&block);
+
+        Ok(block)
+    }
+
+    /// Adds the given verified block to the anchors if the latest anchor is older
+    /// than half the trusting period, and drops the anchors which are no longer trusted.
+    fn update_anchors(&mut self, block: &LightBlock) {
+        let half_trusting_period = self.options.trusting_period / 2;
+
+        let latest = match self.anchors.last() {
+            Some(latest) => latest,
+            None => return,
+        };
+
+        if block.height() <= latest.height() || age(latest) < half_trusting_period {
+            return;
+        }
+
+        debug!(
+            chain = %self.chain_id,
+            "moving the trust anchor of the query proofs to height {}",
+            block.height()
+        );
+
+        let trusting_period = self.options.trusting_period;
+        self.anchors.retain(|b| age(b) < trusting_period);
+
+        self.anchors.push(block.clone());
+    }
+}
+
+/// Checks that the given block is the configured trust `anchor`, and that its validator sets
+/// are the ones its header commits to, since the next blocks are verified against them.
+pub fn check_trust_anchor(
+    chain_id: &ChainId,
+    block: &LightBlock,
+    anchor: &TrustAnchor,
+) -> Result<(), Error> {
+    let header = &block.signed_header.header;
+
+    let mismatch = |reason: &str| {
+        Error::query_trust_anchor_mismatch(chain_id.clone(), anchor.height, reason.to_string())
+    };
+
+    if header.height.value() != anchor.height {
+        return Err(mismatch("the block is at another height"));
+    }
+
+    if header.hash() != anchor.hash {
+        return Err(mismatch(&format!("the block has hash {}", header.hash())));
+    }
+
+    if block.validators.hash() != header.validators_hash
+        || block.next_validators.hash() != header.next_validators_hash
+    {
+        return Err(mismatch(
+            "the validator sets differ from the ones of the header",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Returns the prefix of the store holding `data`, given the prefix of the IBC store.
+fn store_prefix(ibc_store_prefix: &CommitmentPrefix, data: &Path) -> CommitmentPrefix {
+    match data {
+        Path::Upgrade(_) => CommitmentPrefix::from(UPGRADE_STORE_PREFIX.to_vec()),
+        _ => ibc_store_prefix.clone(),
+    }
+}
+
+/// Verifies the proof of `data` against the given app hash, either of membership
+/// of `value`, or of non-membership if `value` is empty.
+fn verify_proof(
+    proof_specs: &ProofSpecs,
+    store_prefix: &CommitmentPrefix,
+    app_hash: &[u8],
+    data: &Path,
+    value: &[u8],
+    proof: MerkleProof,
+) -> Result<(), CommitmentError> {
+    let root = MerkleRoot {
+        hash: app_hash.to_vec(),
+    };
+
+    let path = apply_prefix(store_prefix, vec![data.to_string()])?;
+
+    if value.is_empty() {
+        proof.verify_non_membership(proof_specs, root, path)
+    } else {
+        proof.verify_membership(proof_specs, root, path, value.to_vec(), 0)
+    }
+}
+
+fn age(block: &LightBlock) -> Duration {
+    Time::now()
+        .duration_since(block.signed_header.header.time)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ibc::core::ics02_client::client_type::ClientType;
+    use ibc::core::ics24_host::identifier::{ChannelId, ClientId, PortId};
+    use ibc::core::ics24_host::ClientUpgradePath;
+    use ics23::commitment_proof::Proof;
+    use ics23::{
+        calculate_existence_root, CommitmentProof, ExistenceProof, HashOp, LeafOp, LengthOp,
+        NonExistenceProof,
+    };
+    use tendermint::validator::Set as ValidatorSet;
+    use tendermint_testgen::{
+        Generator, LightBlock as TestgenLightBlock, Validator as TestgenValidator,
+    };
+
+    /// The app hash of a chain whose `ibc` store only holds the client state
+    /// of `07-tendermint-0`, set to `client state`
+    const APP_HASH: &str = "b4b0e8c5529740a120b6ab49061ccdc319f78d0f14ac35cc04c0aaa2f9e3a09d";
+
+    /// The root of the `ibc` store
+    const STORE_ROOT: &str = "0897d4e7737e9bfd9a3518002cd270e9e5b033e114348291328b0e5575d36d7f";
+
+    const CLIENT_STATE: &[u8] = b"client state";
+
+    const UPGRADED_CLIENT_STATE: &[u8] = b"upgraded client state";
+
+    fn leaf(prefix: Vec<u8>) -> LeafOp {
+        LeafOp {
+            hash: HashOp::Sha256 as i32,
+            prehash_key: HashOp::NoHash as i32,
+            prehash_value: HashOp::Sha256 as i32,
+            length: LengthOp::VarProto as i32,
+            prefix,
+        }
+    }
+
+    /// The proof of the client state in the IAVL tree of the `ibc` store,
+    /// whose leaves are prefixed with their height, size and version
+    fn client_state_proof() -> ExistenceProof {
+        ExistenceProof {
+            key: Path::ClientState(ClientId::default())
+                .to_string()
+                .into_bytes(),
+            value: CLIENT_STATE.to_vec(),
+            leaf: Some(leaf(vec![0, 2, 2])),
+            path: vec![],
+        }
+    }
+
+    /// The proof of the `ibc` store in the multistore
+    fn store_proof() -> CommitmentProof {
+        CommitmentProof {
+            proof: Some(Proof::Exist(ExistenceProof {
+                key: b"ibc".to_vec(),
+                value: hex::decode(STORE_ROOT).unwrap(),
+                leaf: Some(leaf(vec![0])),
+                path: vec![],
+            })),
+        }
+    }
+
+    fn verify(data: &Path, value: &[u8], proof: CommitmentProof) -> Result<(), CommitmentError> {
+        verify_proof(
+            &ProofSpecs::default(),
+            &CommitmentPrefix::from(b"ibc".to_vec()),
+            &hex::decode(APP_HASH).unwrap(),
+            data,
+            value,
+            MerkleProof {
+                proofs: vec![proof, store_proof()],
+            },
+        )
+    }
+
+    #[test]
+    fn verify_membership() {
+        let data = Path::ClientState(ClientId::default());
+        let proof = CommitmentProof {
+            proof: Some(Proof::Exist(client_state_proof())),
+        };
+
+        assert!(verify(&data, CLIENT_STATE, proof.clone()).is_ok());
+
+        assert!(verify(&data, b"other client state", proof.clone()).is_err());
+        assert!(verify(
+            &Path::ClientState(ClientId::new(ClientType::Tendermint, 1).unwrap()),
+            CLIENT_STATE,
+            proof
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn verify_non_membership() {
+        let data = Path::ClientState(ClientId::new(ClientType::Tendermint, 1).unwrap());
+
+        // The client state of `07-tendermint-0` is the rightmost leaf below the key
+        let proof = CommitmentProof {
+            proof: Some(Proof::Nonexist(NonExistenceProof {
+                key: data.to_string().into_bytes(),
+                left: Some(client_state_proof()),
+                right: None,
+            })),
+        };
+
+        assert!(verify(&data, &[], proof.clone()).is_ok());
+
+        // The proof does not hold for a key on the left of its leaf
+        let before = Path::ChannelEnds(PortId::transfer(), ChannelId::default());
+        assert!(verify(&before, &[], proof).is_err());
+
+        // A proof of membership does not prove non-membership
+        let proof = CommitmentProof {
+            proof: Some(Proof::Exist(client_state_proof())),
+        };
+        assert!(verify(&Path::ClientState(ClientId::default()), &[], proof).is_err());
+    }
+
+    /// The proof of the upgraded client state at height 10, in a chain whose `upgrade`
+    /// store only holds it, along with the app hash of that chain
+    fn upgraded_client_state_proof() -> (MerkleProof, Vec<u8>) {
+        let leaf_proof = ExistenceProof {
+            key: Path::Upgrade(ClientUpgradePath::UpgradedClientState(10))
+                .to_string()
+                .into_bytes(),
+            value: UPGRADED_CLIENT_STATE.to_vec(),
+            leaf: Some(leaf(vec![0, 2, 2])),
+            path: vec![],
+        };
+
+        let store_proof = ExistenceProof {
+            key: UPGRADE_STORE_PREFIX.to_vec(),
+            value: calculate_existence_root(&leaf_proof).unwrap(),
+            leaf: Some(leaf(vec![0])),
+            path: vec![],
+        };
+
+        let app_hash = calculate_existence_root(&store_proof).unwrap();
+
+        let proofs = [leaf_proof, store_proof]
+            .into_iter()
+            .map(|proof| CommitmentProof {
+                proof: Some(Proof::Exist(proof)),
+            })
+            .collect();
+
+        (MerkleProof { proofs }, app_hash)
+    }
+
+    #[test]
+    fn verify_upgrade_store_membership() {
+        let data = Path::Upgrade(ClientUpgradePath::UpgradedClientState(10));
+        let ibc_store = CommitmentPrefix::from(b"ibc".to_vec());
+        let (proof, app_hash) = upgraded_client_state_proof();
+
+        let verify = |value: &[u8], proof: MerkleProof| {
+            verify_proof(
+                &ProofSpecs::default(),
+                &store_prefix(&ibc_store, &data),
+                &app_hash,
+                &data,
+                value,
+                proof,
+            )
+        };
+
+        assert!(verify(UPGRADED_CLIENT_STATE, proof.clone()).is_ok());
+
+        // A tampered value or proof is rejected
+        assert!(verify(b"other upgraded client state", proof.clone()).is_err());
+
+        let mut tampered = proof.clone();
+        if let Some(Proof::Exist(leaf_proof)) = &mut tampered.proofs[0].proof {
+            leaf_proof.value = b"other upgraded client state".to_vec();
+        }
+        assert!(verify(b"other upgraded client state", tampered).is_err());
+
+        // The upgraded client state is not proven in the IBC store
+        assert!(verify_proof(
+            &ProofSpecs::default(),
+            &ibc_store,
+            &app_hash,
+            &data,
+            UPGRADED_CLIENT_STATE,
+            proof,
+        )
+        .is_err());
+    }
+
+    fn light_block(height: u64) -> LightBlock {
+        let block = TestgenLightBlock::new_default(height).generate().unwrap();
+
+        LightBlock::new(
+            block.signed_header,
+            block.validators,
+            block.next_validators,
+            block.provider,
+        )
+    }
+
+    #[test]
+    fn check_configured_trust_anchor() {
+        let chain_id = ChainId::new("chain_a".to_string(), 0);
+        let block = light_block(10);
+        let anchor = TrustAnchor {
+            height: 10,
+            hash: block.signed_header.header.hash(),
+        };
+
+        assert!(check_trust_anchor(&chain_id, &block, &anchor).is_ok());
+
+        // A block at another height, or with another hash
+        assert!(check_trust_anchor(&chain_id, &light_block(11), &anchor).is_err());
+
+        let other = TrustAnchor {
+            height: 10,
+            hash: light_block(11).signed_header.header.hash(),
+        };
+        assert!(check_trust_anchor(&chain_id, &block, &other).is_err());
+
+        // The block of the anchor, with validator sets other than the ones of its header
+        let validators = ValidatorSet::without_proposer(vec![TestgenValidator::new("mallory")
+            .generate()
+            .unwrap()]);
+
+        let mut tampered = block.clone();
+        tampered.next_validators = validators.clone();
+        assert!(check_trust_anchor(&chain_id, &tampered, &anchor).is_err());
+
+        let mut tampered = block;
+        tampered.validators = validators;
+        assert!(check_trust_anchor(&chain_id, &tampered, &anchor).is_err());
+    }
+}
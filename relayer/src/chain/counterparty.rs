@@ -505,3 +505,26 @@ pub fn unreceived_acknowledgements(
 
     Ok(sequences)
 }
+
+/// Returns true if there are packets or acknowledgements sent from `chain` on the
+/// counterparty of `counterparty_channel` which have not been relayed to it yet.
+/// Failed queries are reported as nothing pending.
+pub fn has_pending_packets(
+    chain: &impl ChainHandle,
+    counterparty_chain: &impl ChainHandle,
+    counterparty_channel: &IdentifiedChannelEnd,
+) -> bool {
+    let has_packets = || -> bool {
+        !unreceived_packets(counterparty_chain, chain, counterparty_channel)
+            .unwrap_or_default()
+            .is_empty()
+    };
+
+    let has_acks = || -> bool {
+        !unreceived_acknowledgements(counterparty_chain, chain, counterparty_channel)
+            .unwrap_or_default()
+            .is_empty()
+    };
+
+    has_packets() || has_acks()
+}
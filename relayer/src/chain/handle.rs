@@ -19,7 +19,7 @@ use ibc::{
         },
         ics04_channel::{
             self,
-            channel::{ChannelEnd, IdentifiedChannelEnd},
+            channel::{ChannelEnd, IdentifiedChannelEnd, QueryPacketEventDataRequest},
             packet::{PacketMsgType, Sequence},
         },
         ics23_commitment::commitment::CommitmentPrefix,
@@ -31,6 +31,7 @@ use ibc::{
     signer::Signer,
     Height,
 };
+use ibc_proto::cosmos::upgrade::v1beta1::Plan;
 use ibc_proto::ibc::core::{
     channel::v1::{
         PacketState, QueryChannelClientStateRequest, QueryChannelsRequest,
@@ -54,7 +55,10 @@ use crate::{
     keyring::KeyEntry,
 };
 
-use super::HealthCheck;
+use super::{HealthCheck, PacketTx, TxSimulation};
+
+mod cache;
+pub use cache::CachingChainHandle;
 
 mod prod;
 pub mod requests;
@@ -120,6 +124,11 @@ pub enum ChainRequest {
         reply_to: ReplyTo<Vec<tendermint_rpc::endpoint::broadcast::tx_sync::Response>>,
     },
 
+    SimulateMessages {
+        proto_msgs: Vec<prost_types::Any>,
+        reply_to: ReplyTo<TxSimulation>,
+    },
+
     Config {
         reply_to: ReplyTo<ChainConfig>,
     },
@@ -219,6 +228,10 @@ pub enum ChainRequest {
         reply_to: ReplyTo<(AnyConsensusState, MerkleProof)>,
     },
 
+    QueryUpgradePlan {
+        reply_to: ReplyTo<Option<Plan>>,
+    },
+
     QueryCommitmentPrefix {
         reply_to: ReplyTo<CommitmentPrefix>,
     },
@@ -329,6 +342,11 @@ pub enum ChainRequest {
         request: QueryBlockRequest,
         reply_to: ReplyTo<(Vec<IbcEvent>, Vec<IbcEvent>)>,
     },
+
+    QueryPacketTxs {
+        request: QueryPacketEventDataRequest,
+        reply_to: ReplyTo<Vec<PacketTx>>,
+    },
 }
 
 pub trait ChainHandle: Clone + Send + Sync + Serialize + Debug + 'static {
@@ -362,6 +380,9 @@ pub trait ChainHandle: Clone + Send + Sync + Serialize + Debug + 'static {
         proto_msgs: Vec<prost_types::Any>,
     ) -> Result<Vec<tendermint_rpc::endpoint::broadcast::tx_sync::Response>, Error>;
 
+    /// Simulates a single transaction with all the `proto_msgs`, without broadcasting it.
+    fn simulate_messages(&self, proto_msgs: Vec<prost_types::Any>) -> Result<TxSimulation, Error>;
+
     fn get_signer(&self) -> Result<Signer, Error>;
 
     fn config(&self) -> Result<ChainConfig, Error>;
@@ -416,6 +437,9 @@ pub trait ChainHandle: Clone + Send + Sync + Serialize + Debug + 'static {
         height: Height,
     ) -> Result<(AnyConsensusState, MerkleProof), Error>;
 
+    /// Query the upgrade plan currently scheduled on the chain, if any.
+    fn query_upgrade_plan(&self) -> Result<Option<Plan>, Error>;
+
     fn query_commitment_prefix(&self) -> Result<CommitmentPrefix, Error>;
 
     fn query_compatible_versions(&self) -> Result<Vec<Version>, Error>;
@@ -551,6 +575,11 @@ pub trait ChainHandle: Clone + Send + Sync + Serialize + Debug + 'static {
 
     fn query_txs(&self, request: QueryTxRequest) -> Result<Vec<IbcEvent>, Error>;
 
+    fn query_packet_txs(
+        &self,
+        request: QueryPacketEventDataRequest,
+    ) -> Result<Vec<PacketTx>, Error>;
+
     fn query_blocks(
         &self,
         request: QueryBlockRequest,
@@ -0,0 +1,751 @@
+use alloc::collections::btree_map::BTreeMap as HashMap;
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::time::Duration;
+use std::sync::RwLock;
+use std::thread;
+use std::time::Instant;
+
+use crossbeam_channel as channel;
+use serde::{Serialize, Serializer};
+use tracing::{debug, warn};
+
+use ibc::{
+    core::{
+        ics02_client::client_consensus::{AnyConsensusState, AnyConsensusStateWithHeight},
+        ics02_client::client_state::{AnyClientState, IdentifiedAnyClientState},
+        ics02_client::events::UpdateClient,
+        ics02_client::header::AnyHeader,
+        ics02_client::misbehaviour::MisbehaviourEvidence,
+        ics03_connection::connection::{ConnectionEnd, IdentifiedConnectionEnd},
+        ics03_connection::version::Version,
+        ics04_channel,
+        ics04_channel::channel::{ChannelEnd, IdentifiedChannelEnd, QueryPacketEventDataRequest},
+        ics04_channel::packet::{PacketMsgType, Sequence},
+        ics23_commitment::commitment::CommitmentPrefix,
+        ics24_host::identifier::ChainId,
+        ics24_host::identifier::ChannelId,
+        ics24_host::identifier::{ClientId, ConnectionId, PortId},
+    },
+    events::IbcEvent,
+    proofs::Proofs,
+    query::{QueryBlockRequest, QueryTxRequest},
+    signer::Signer,
+    Height,
+};
+use ibc_proto::cosmos::upgrade::v1beta1::Plan;
+use ibc_proto::ibc::core::channel::v1::{
+    PacketState, QueryChannelClientStateRequest, QueryChannelsRequest,
+    QueryConnectionChannelsRequest, QueryNextSequenceReceiveRequest,
+    QueryPacketAcknowledgementsRequest, QueryPacketCommitmentsRequest, QueryUnreceivedAcksRequest,
+    QueryUnreceivedPacketsRequest,
+};
+use ibc_proto::ibc::core::client::v1::{QueryClientStatesRequest, QueryConsensusStatesRequest};
+use ibc_proto::ibc::core::commitment::v1::MerkleProof;
+use ibc_proto::ibc::core::connection::v1::QueryClientConnectionsRequest;
+use ibc_proto::ibc::core::connection::v1::QueryConnectionsRequest;
+
+use crate::{
+    chain::handle::requests::AppVersion, chain::StatusResponse, config::ChainConfig,
+    config::QueryCache, connection::ConnectionMsgType, error::Error, keyring::KeyEntry, telemetry,
+};
+
+use super::{
+    ChainHandle, ChainRequest, HealthCheck, PacketTx, ProdChainHandle, Subscription, TxSimulation,
+};
+
+/// A [`ChainHandle`] which caches the latest state of the clients, connections
+/// and channels of the chain, as configured in the `query_cache` section of the chain.
+///
+/// Only the queries at the latest height are cached, and connections and channels
+/// are only cached once they are open, after which they seldom change.
+/// The entries are evicted once their TTL expires, or as soon as an event
+/// affecting them is seen, either in the events of the transactions submitted
+/// through the handle, or in the events of the chain once the handle is subscribed to them.
+///
+/// The cache is shared by all the clones of the handle.
+#[derive(Debug, Clone)]
+pub struct CachingChainHandle {
+    inner: ProdChainHandle,
+    cache: Arc<Cache>,
+}
+
+impl CachingChainHandle {
+    pub fn new(chain_id: ChainId, sender: channel::Sender<ChainRequest>) -> Self {
+        Self {
+            inner: ProdChainHandle::new(chain_id, sender),
+            cache: Arc::new(Cache::default()),
+        }
+    }
+
+    /// Returns the configuration of the cache, loaded from the chain configuration
+    /// on first use. Returns `None` if caching is disabled.
+    ///
+    /// The configuration is never reloaded afterwards: when the configuration of a chain
+    /// is updated, the supervisor spawns a new runtime for the chain, along with new
+    /// handles, each with a fresh cache.
+    fn settings(&self) -> Option<QueryCache> {
+        if let Some(settings) = self.cache.settings.read().expect("poisoned lock").as_ref() {
+            return Some(settings.clone()).filter(|s| s.enabled);
+        }
+
+        let settings = match self.inner.config() {
+            Ok(config) => config.query_cache,
+            Err(e) => {
+                warn!(
+                    "[{}] query cache disabled until the chain configuration can be fetched: {}",
+                    self.id(),
+                    e
+                );
+
+                return None;
+            }
+        };
+
+        *self.cache.settings.write().expect("poisoned lock") = Some(settings.clone());
+
+        Some(settings).filter(|s| s.enabled)
+    }
+
+    /// Serves the query from the given cache if possible, and otherwise performs it,
+    /// and caches the response if `cacheable` holds for it.
+    fn cached<K: Ord, V: Clone>(
+        &self,
+        query_type: &'static str,
+        entries: &TtlMap<K, V>,
+        key: K,
+        ttl: impl FnOnce(&QueryCache) -> Duration,
+        cacheable: impl FnOnce(&V) -> bool,
+        query: impl FnOnce() -> Result<V, Error>,
+    ) -> Result<V, Error> {
+        let settings = match self.settings() {
+            Some(settings) => settings,
+            None => return query(),
+        };
+
+        if let Some(value) = entries.get(&key) {
+            telemetry!(query_cache_hit, &self.id(), query_type);
+            return Ok(value);
+        }
+
+        telemetry!(query_cache_miss, &self.id(), query_type);
+
+        let value = query()?;
+
+        if cacheable(&value) {
+            entries.insert(key, value.clone(), ttl(&settings));
+        }
+
+        Ok(value)
+    }
+
+    /// Spawns a thread evicting the entries affected by the events of the chain,
+    /// unless one is already running.
+    fn spawn_invalidation(&self) {
+        if self.cache.invalidating.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let subscription = match self.inner.subscribe() {
+            Ok(subscription) => subscription,
+            Err(e) => {
+                warn!(
+                    "[{}] failed to subscribe to the events evicting the query cache: {}",
+                    self.id(),
+                    e
+                );
+
+                self.cache.invalidating.store(false, Ordering::SeqCst);
+                return;
+            }
+        };
+
+        let cache = self.cache.clone();
+
+        // The subscription is closed when the chain runtime shuts down
+        thread::spawn(move || {
+            for batch in subscription.iter() {
+                if let Ok(batch) = batch.as_ref() {
+                    cache.invalidate(&batch.events);
+                }
+            }
+
+            cache.invalidating.store(false, Ordering::SeqCst);
+        });
+    }
+}
+
+#[derive(Debug, Default)]
+struct Cache {
+    settings: RwLock<Option<QueryCache>>,
+    client_states: TtlMap<ClientId, AnyClientState>,
+    connections: TtlMap<ConnectionId, ConnectionEnd>,
+    channels: TtlMap<(PortId, ChannelId), ChannelEnd>,
+    /// Whether a thread evicting the entries affected by the events of the chain is running
+    invalidating: AtomicBool,
+}
+
+impl Cache {
+    /// Evicts the entries whose state is changed by the given events.
+    fn invalidate(&self, events: &[IbcEvent]) {
+        for event in events {
+            match event {
+                IbcEvent::UpdateClient(ev) => self.client_states.remove(ev.client_id()),
+                IbcEvent::UpgradeClient(ev) => self.client_states.remove(ev.client_id()),
+                IbcEvent::ClientMisbehaviour(ev) => self.client_states.remove(ev.client_id()),
+
+                IbcEvent::OpenAckConnection(ev) => {
+                    if let Some(connection_id) = ev.connection_id() {
+                        self.connections.remove(connection_id);
+                    }
+                }
+                IbcEvent::OpenConfirmConnection(ev) => {
+                    if let Some(connection_id) = ev.connection_id() {
+                        self.connections.remove(connection_id);
+                    }
+                }
+
+                IbcEvent::CloseInitChannel(ev) => {
+                    self.remove_channel(ev.port_id(), Some(ev.channel_id()))
+                }
+                IbcEvent::CloseConfirmChannel(ev) => {
+                    self.remove_channel(&ev.0.port_id, ev.channel_id())
+                }
+
+                // A timeout closes an ordered channel
+                IbcEvent::TimeoutPacket(ev) => {
+                    self.remove_channel(&ev.packet.source_port, Some(&ev.packet.source_channel))
+                }
+                IbcEvent::TimeoutOnClosePacket(ev) => {
+                    self.remove_channel(&ev.packet.source_port, Some(&ev.packet.source_channel))
+                }
+
+                _ => {}
+            }
+        }
+    }
+
+    fn remove_channel(&self, port_id: &PortId, channel_id: Option<&ChannelId>) {
+        if let Some(channel_id) = channel_id {
+            debug!(
+                "evicting channel {}/{} from the query cache",
+                port_id, channel_id
+            );
+
+            self.channels.remove(&(port_id.clone(), channel_id.clone()));
+        }
+    }
+}
+
+/// A map whose entries expire after a given time.
+#[derive(Debug)]
+struct TtlMap<K, V> {
+    entries: RwLock<HashMap<K, (V, Instant)>>,
+}
+
+impl<K, V> Default for TtlMap<K, V> {
+    fn default() -> Self {
+        Self {
+            entries: RwLock::new(HashMap::new()),
+        }
+    }
+}
+
+impl<K: Ord, V: Clone> TtlMap<K, V> {
+    fn get(&self, key: &K) -> Option<V> {
+        let entries = self.entries.read().expect("poisoned lock");
+
+        entries
+            .get(key)
+            .filter(|(_, expiry)| *expiry > Instant::now())
+            .map(|(value, _)| value.clone())
+    }
+
+    fn insert(&self, key: K, value: V, ttl: Duration) {
+        let mut entries = self.entries.write().expect("poisoned lock");
+
+        // Drop the expired entries, so that the map does not grow unbounded
+        let now = Instant::now();
+        entries.retain(|_, (_, expiry)| *expiry > now);
+
+        entries.insert(key, (value, now + ttl));
+    }
+
+    fn remove(&self, key: &K) {
+        self.entries.write().expect("poisoned lock").remove(key);
+    }
+}
+
+impl ChainHandle for CachingChainHandle {
+    fn new(chain_id: ChainId, sender: channel::Sender<ChainRequest>) -> Self {
+        Self::new(chain_id, sender)
+    }
+
+    fn id(&self) -> ChainId {
+        self.inner.id()
+    }
+
+    fn health_check(&self) -> Result<HealthCheck, Error> {
+        self.inner.health_check()
+    }
+
+    fn shutdown(&self) -> Result<(), Error> {
+        self.inner.shutdown()
+    }
+
+    fn subscribe(&self) -> Result<Subscription, Error> {
+        let subscription = self.inner.subscribe()?;
+
+        if self.settings().is_some() {
+            self.spawn_invalidation();
+        }
+
+        Ok(subscription)
+    }
+
+    fn send_messages_and_wait_commit(
+        &self,
+        proto_msgs: Vec<prost_types::Any>,
+    ) -> Result<Vec<IbcEvent>, Error> {
+        let events = self.inner.send_messages_and_wait_commit(proto_msgs)?;
+
+        self.cache.invalidate(&events);
+
+        Ok(events)
+    }
+
+    fn send_messages_and_wait_check_tx(
+        &self,
+        proto_msgs: Vec<prost_types::Any>,
+    ) -> Result<Vec<tendermint_rpc::endpoint::broadcast::tx_sync::Response>, Error> {
+        self.inner.send_messages_and_wait_check_tx(proto_msgs)
+    }
+
+    fn simulate_messages(&self, proto_msgs: Vec<prost_types::Any>) -> Result<TxSimulation, Error> {
+        self.inner.simulate_messages(proto_msgs)
+    }
+
+    fn get_signer(&self) -> Result<Signer, Error> {
+        self.inner.get_signer()
+    }
+
+    fn config(&self) -> Result<ChainConfig, Error> {
+        self.inner.config()
+    }
+
+    fn get_key(&self) -> Result<KeyEntry, Error> {
+        self.inner.get_key()
+    }
+
+    fn add_key(&self, key_name: String, key: KeyEntry) -> Result<(), Error> {
+        self.inner.add_key(key_name, key)
+    }
+
+    fn app_version(&self, request: AppVersion) -> Result<ics04_channel::Version, Error> {
+        self.inner.app_version(request)
+    }
+
+    fn query_status(&self) -> Result<StatusResponse, Error> {
+        self.inner.query_status()
+    }
+
+    fn query_clients(
+        &self,
+        request: QueryClientStatesRequest,
+    ) -> Result<Vec<IdentifiedAnyClientState>, Error> {
+        self.inner.query_clients(request)
+    }
+
+    fn query_client_state(
+        &self,
+        client_id: &ClientId,
+        height: Height,
+    ) -> Result<AnyClientState, Error> {
+        if !height.is_zero() {
+            return self.inner.query_client_state(client_id, height);
+        }
+
+        self.cached(
+            "client_state",
+            &self.cache.client_states,
+            client_id.clone(),
+            |settings| settings.client_state_ttl,
+            |_| true,
+            || self.inner.query_client_state(client_id, height),
+        )
+    }
+
+    fn query_client_connections(
+        &self,
+        request: QueryClientConnectionsRequest,
+    ) -> Result<Vec<ConnectionId>, Error> {
+        self.inner.query_client_connections(request)
+    }
+
+    fn query_consensus_states(
+        &self,
+        request: QueryConsensusStatesRequest,
+    ) -> Result<Vec<AnyConsensusStateWithHeight>, Error> {
+        self.inner.query_consensus_states(request)
+    }
+
+    fn query_consensus_state(
+        &self,
+        client_id: ClientId,
+        consensus_height: Height,
+        query_height: Height,
+    ) -> Result<AnyConsensusState, Error> {
+        self.inner
+            .query_consensus_state(client_id, consensus_height, query_height)
+    }
+
+    fn query_upgraded_client_state(
+        &self,
+        height: Height,
+    ) -> Result<(AnyClientState, MerkleProof), Error> {
+        self.inner.query_upgraded_client_state(height)
+    }
+
+    fn query_upgraded_consensus_state(
+        &self,
+        height: Height,
+    ) -> Result<(AnyConsensusState, MerkleProof), Error> {
+        self.inner.query_upgraded_consensus_state(height)
+    }
+
+    fn query_upgrade_plan(&self) -> Result<Option<Plan>, Error> {
+        self.inner.query_upgrade_plan()
+    }
+
+    fn query_commitment_prefix(&self) -> Result<CommitmentPrefix, Error> {
+        self.inner.query_commitment_prefix()
+    }
+
+    fn query_compatible_versions(&self) -> Result<Vec<Version>, Error> {
+        self.inner.query_compatible_versions()
+    }
+
+    fn query_connection(
+        &self,
+        connection_id: &ConnectionId,
+        height: Height,
+    ) -> Result<ConnectionEnd, Error> {
+        if !height.is_zero() {
+            return self.inner.query_connection(connection_id, height);
+        }
+
+        self.cached(
+            "connection",
+            &self.cache.connections,
+            connection_id.clone(),
+            |settings| settings.connection_ttl,
+            |connection| connection.is_open(),
+            || self.inner.query_connection(connection_id, height),
+        )
+    }
+
+    fn query_connections(
+        &self,
+        request: QueryConnectionsRequest,
+    ) -> Result<Vec<IdentifiedConnectionEnd>, Error> {
+        self.inner.query_connections(request)
+    }
+
+    fn query_connection_channels(
+        &self,
+        request: QueryConnectionChannelsRequest,
+    ) -> Result<Vec<IdentifiedChannelEnd>, Error> {
+        self.inner.query_connection_channels(request)
+    }
+
+    fn query_next_sequence_receive(
+        &self,
+        request: QueryNextSequenceReceiveRequest,
+    ) -> Result<Sequence, Error> {
+        self.inner.query_next_sequence_receive(request)
+    }
+
+    fn query_channels(
+        &self,
+        request: QueryChannelsRequest,
+    ) -> Result<Vec<IdentifiedChannelEnd>, Error> {
+        self.inner.query_channels(request)
+    }
+
+    fn query_channel(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        height: Height,
+    ) -> Result<ChannelEnd, Error> {
+        if !height.is_zero() {
+            return self.inner.query_channel(port_id, channel_id, height);
+        }
+
+        self.cached(
+            "channel",
+            &self.cache.channels,
+            (port_id.clone(), channel_id.clone()),
+            |settings| settings.channel_ttl,
+            |channel| channel.is_open(),
+            || self.inner.query_channel(port_id, channel_id, height),
+        )
+    }
+
+    fn query_channel_client_state(
+        &self,
+        request: QueryChannelClientStateRequest,
+    ) -> Result<Option<IdentifiedAnyClientState>, Error> {
+        self.inner.query_channel_client_state(request)
+    }
+
+    fn proven_client_state(
+        &self,
+        client_id: &ClientId,
+        height: Height,
+    ) -> Result<(AnyClientState, MerkleProof), Error> {
+        self.inner.proven_client_state(client_id, height)
+    }
+
+    fn proven_connection(
+        &self,
+        connection_id: &ConnectionId,
+        height: Height,
+    ) -> Result<(ConnectionEnd, MerkleProof), Error> {
+        self.inner.proven_connection(connection_id, height)
+    }
+
+    fn proven_client_consensus(
+        &self,
+        client_id: &ClientId,
+        consensus_height: Height,
+        height: Height,
+    ) -> Result<(AnyConsensusState, MerkleProof), Error> {
+        self.inner
+            .proven_client_consensus(client_id, consensus_height, height)
+    }
+
+    fn build_header(
+        &self,
+        trusted_height: Height,
+        target_height: Height,
+        client_state: AnyClientState,
+    ) -> Result<(AnyHeader, Vec<AnyHeader>), Error> {
+        self.inner
+            .build_header(trusted_height, target_height, client_state)
+    }
+
+    fn build_client_state(
+        &self,
+        height: Height,
+        dst_config: ChainConfig,
+    ) -> Result<AnyClientState, Error> {
+        self.inner.build_client_state(height, dst_config)
+    }
+
+    fn build_consensus_state(
+        &self,
+        trusted: Height,
+        target: Height,
+        client_state: AnyClientState,
+    ) -> Result<AnyConsensusState, Error> {
+        self.inner
+            .build_consensus_state(trusted, target, client_state)
+    }
+
+    fn check_misbehaviour(
+        &self,
+        update_event: UpdateClient,
+        client_state: AnyClientState,
+    ) -> Result<Option<MisbehaviourEvidence>, Error> {
+        self.inner.check_misbehaviour(update_event, client_state)
+    }
+
+    fn build_connection_proofs_and_client_state(
+        &self,
+        message_type: ConnectionMsgType,
+        connection_id: &ConnectionId,
+        client_id: &ClientId,
+        height: Height,
+    ) -> Result<(Option<AnyClientState>, Proofs), Error> {
+        self.inner.build_connection_proofs_and_client_state(
+            message_type,
+            connection_id,
+            client_id,
+            height,
+        )
+    }
+
+    fn build_channel_proofs(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        height: Height,
+    ) -> Result<Proofs, Error> {
+        self.inner.build_channel_proofs(port_id, channel_id, height)
+    }
+
+    fn build_packet_proofs(
+        &self,
+        packet_type: PacketMsgType,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        sequence: Sequence,
+        height: Height,
+    ) -> Result<(Vec<u8>, Proofs), Error> {
+        self.inner
+            .build_packet_proofs(packet_type, port_id, channel_id, sequence, height)
+    }
+
+    fn query_packet_commitments(
+        &self,
+        request: QueryPacketCommitmentsRequest,
+    ) -> Result<(Vec<PacketState>, Height), Error> {
+        self.inner.query_packet_commitments(request)
+    }
+
+    fn query_unreceived_packets(
+        &self,
+        request: QueryUnreceivedPacketsRequest,
+    ) -> Result<Vec<u64>, Error> {
+        self.inner.query_unreceived_packets(request)
+    }
+
+    fn query_packet_acknowledgements(
+        &self,
+        request: QueryPacketAcknowledgementsRequest,
+    ) -> Result<(Vec<PacketState>, Height), Error> {
+        self.inner.query_packet_acknowledgements(request)
+    }
+
+    fn query_unreceived_acknowledgement(
+        &self,
+        request: QueryUnreceivedAcksRequest,
+    ) -> Result<Vec<u64>, Error> {
+        self.inner.query_unreceived_acknowledgement(request)
+    }
+
+    fn query_txs(&self, request: QueryTxRequest) -> Result<Vec<IbcEvent>, Error> {
+        self.inner.query_txs(request)
+    }
+
+    fn query_packet_txs(
+        &self,
+        request: QueryPacketEventDataRequest,
+    ) -> Result<Vec<PacketTx>, Error> {
+        self.inner.query_packet_txs(request)
+    }
+
+    fn query_blocks(
+        &self,
+        request: QueryBlockRequest,
+    ) -> Result<(Vec<IbcEvent>, Vec<IbcEvent>), Error> {
+        self.inner.query_blocks(request)
+    }
+}
+
+impl Serialize for CachingChainHandle {
+    fn serialize<S>(&self, serializer: S) -> Result<<S as Serializer>::Ok, <S as Serializer>::Error>
+    where
+        S: Serializer,
+    {
+        self.id().serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ibc::core::ics02_client::events::Attributes as ClientAttributes;
+    use ibc::core::ics04_channel::events::{
+        Attributes as ChannelAttributes, CloseInit, TimeoutPacket,
+    };
+    use ibc::core::ics04_channel::packet::Packet;
+    use ibc::mock::client_state::MockClientState;
+    use ibc::mock::header::MockHeader;
+
+    #[test]
+    fn ttl_map_expiry() {
+        let map = TtlMap::default();
+
+        map.insert(1, "a", Duration::from_secs(60));
+        map.insert(2, "b", Duration::from_secs(0));
+
+        assert_eq!(map.get(&1), Some("a"));
+        assert_eq!(map.get(&2), None);
+
+        map.remove(&1);
+        assert_eq!(map.get(&1), None);
+    }
+
+    #[test]
+    fn invalidate_evicts_affected_entries() {
+        let ttl = Duration::from_secs(60);
+        let cache = Cache::default();
+
+        let client_id = ClientId::default();
+        let connection_id = ConnectionId::new(0);
+        let port_id = PortId::transfer();
+        let closed_channel_id = ChannelId::new(0);
+        let timed_out_channel_id = ChannelId::new(1);
+        let other_channel_id = ChannelId::new(2);
+
+        let client_state =
+            AnyClientState::Mock(MockClientState::new(MockHeader::new(Height::new(0, 10))));
+        cache
+            .client_states
+            .insert(client_id.clone(), client_state, ttl);
+        cache
+            .connections
+            .insert(connection_id.clone(), ConnectionEnd::default(), ttl);
+
+        for channel_id in [&closed_channel_id, &timed_out_channel_id, &other_channel_id] {
+            cache.channels.insert(
+                (port_id.clone(), channel_id.clone()),
+                ChannelEnd::default(),
+                ttl,
+            );
+        }
+
+        let update_client = UpdateClient::from(ClientAttributes {
+            client_id: client_id.clone(),
+            ..Default::default()
+        });
+
+        let close_init = CloseInit(ChannelAttributes {
+            port_id: port_id.clone(),
+            channel_id: Some(closed_channel_id.clone()),
+            ..Default::default()
+        });
+
+        let timeout = TimeoutPacket {
+            height: Height::new(0, 10),
+            packet: Packet {
+                source_port: port_id.clone(),
+                source_channel: timed_out_channel_id.clone(),
+                ..Default::default()
+            },
+        };
+
+        cache.invalidate(&[
+            IbcEvent::UpdateClient(update_client),
+            IbcEvent::CloseInitChannel(close_init),
+            IbcEvent::TimeoutPacket(timeout),
+        ]);
+
+        assert!(cache.client_states.get(&client_id).is_none());
+        assert!(cache
+            .channels
+            .get(&(port_id.clone(), closed_channel_id))
+            .is_none());
+        assert!(cache
+            .channels
+            .get(&(port_id.clone(), timed_out_channel_id))
+            .is_none());
+
+        // The entries unaffected by the events are kept
+        assert!(cache.connections.get(&connection_id).is_some());
+        assert!(cache.channels.get(&(port_id, other_channel_id)).is_some());
+    }
+}
@@ -13,7 +13,7 @@ use ibc::{
         ics03_connection::connection::{ConnectionEnd, IdentifiedConnectionEnd},
         ics03_connection::version::Version,
         ics04_channel,
-        ics04_channel::channel::{ChannelEnd, IdentifiedChannelEnd},
+        ics04_channel::channel::{ChannelEnd, IdentifiedChannelEnd, QueryPacketEventDataRequest},
         ics04_channel::packet::{PacketMsgType, Sequence},
         ics23_commitment::commitment::CommitmentPrefix,
         ics24_host::identifier::ChainId,
@@ -26,6 +26,7 @@ use ibc::{
     signer::Signer,
     Height,
 };
+use ibc_proto::cosmos::upgrade::v1beta1::Plan;
 use ibc_proto::ibc::core::channel::v1::{
     PacketState, QueryChannelClientStateRequest, QueryChannelsRequest,
     QueryConnectionChannelsRequest, QueryNextSequenceReceiveRequest,
@@ -42,7 +43,10 @@ use crate::{
     connection::ConnectionMsgType, error::Error, keyring::KeyEntry,
 };
 
-use super::{reply_channel, ChainHandle, ChainRequest, HealthCheck, ReplyTo, Subscription};
+use super::{
+    reply_channel, ChainHandle, ChainRequest, HealthCheck, PacketTx, ReplyTo, Subscription,
+    TxSimulation,
+};
 
 #[derive(Debug, Clone)]
 pub struct ProdChainHandle {
@@ -116,6 +120,13 @@ impl ChainHandle for ProdChainHandle {
         })
     }
 
+    fn simulate_messages(&self, proto_msgs: Vec<prost_types::Any>) -> Result<TxSimulation, Error> {
+        self.send(|reply_to| ChainRequest::SimulateMessages {
+            proto_msgs,
+            reply_to,
+        })
+    }
+
     fn get_signer(&self) -> Result<Signer, Error> {
         self.send(|reply_to| ChainRequest::Signer { reply_to })
     }
@@ -205,6 +216,10 @@ impl ChainHandle for ProdChainHandle {
         self.send(|reply_to| ChainRequest::QueryUpgradedConsensusState { height, reply_to })
     }
 
+    fn query_upgrade_plan(&self) -> Result<Option<Plan>, Error> {
+        self.send(|reply_to| ChainRequest::QueryUpgradePlan { reply_to })
+    }
+
     fn query_commitment_prefix(&self) -> Result<CommitmentPrefix, Error> {
         self.send(|reply_to| ChainRequest::QueryCommitmentPrefix { reply_to })
     }
@@ -446,6 +461,13 @@ impl ChainHandle for ProdChainHandle {
         self.send(|reply_to| ChainRequest::QueryPacketEventDataFromTxs { request, reply_to })
     }
 
+    fn query_packet_txs(
+        &self,
+        request: QueryPacketEventDataRequest,
+    ) -> Result<Vec<PacketTx>, Error> {
+        self.send(|reply_to| ChainRequest::QueryPacketTxs { request, reply_to })
+    }
+
     fn query_blocks(
         &self,
         request: QueryBlockRequest,
@@ -1,6 +1,7 @@
 use alloc::sync::Arc;
 use core::ops::Add;
 use core::time::Duration;
+use std::sync::RwLock;
 
 use crossbeam_channel as channel;
 use prost_types::Any;
@@ -15,7 +16,9 @@ use ibc::clients::ics07_tendermint::header::Header as TendermintHeader;
 use ibc::core::ics02_client::client_consensus::{AnyConsensusState, AnyConsensusStateWithHeight};
 use ibc::core::ics02_client::client_state::{AnyClientState, IdentifiedAnyClientState};
 use ibc::core::ics03_connection::connection::{ConnectionEnd, IdentifiedConnectionEnd};
-use ibc::core::ics04_channel::channel::{ChannelEnd, IdentifiedChannelEnd};
+use ibc::core::ics04_channel::channel::{
+    ChannelEnd, IdentifiedChannelEnd, QueryPacketEventDataRequest,
+};
 use ibc::core::ics04_channel::context::ChannelReader;
 use ibc::core::ics04_channel::packet::{PacketMsgType, Sequence};
 use ibc::core::ics04_channel::Version;
@@ -30,6 +33,7 @@ use ibc::relayer::ics18_relayer::context::Ics18Context;
 use ibc::signer::Signer;
 use ibc::test_utils::get_dummy_account_id;
 use ibc::Height;
+use ibc_proto::cosmos::upgrade::v1beta1::Plan;
 use ibc_proto::ibc::core::channel::v1::{
     PacketState, QueryChannelClientStateRequest, QueryChannelsRequest,
     QueryConnectionChannelsRequest, QueryNextSequenceReceiveRequest,
@@ -41,24 +45,31 @@ use ibc_proto::ibc::core::commitment::v1::MerkleProof;
 use ibc_proto::ibc::core::connection::v1::{
     QueryClientConnectionsRequest, QueryConnectionsRequest,
 };
+use ibc_proto::ics23::CommitmentProof;
 
-use crate::chain::{handle::requests::AppVersion, ChainEndpoint, StatusResponse};
+use crate::chain::{
+    handle::requests::AppVersion, ChainEndpoint, PacketTx, StatusResponse, TxSimulation,
+};
 use crate::config::ChainConfig;
 use crate::error::Error;
 use crate::event::monitor::{EventReceiver, EventSender, TxMonitorCmd};
 use crate::keyring::{KeyEntry, KeyRing};
 use crate::light_client::Verified;
 use crate::light_client::{mock::LightClient as MockLightClient, LightClient};
+use crate::util::lock::LockExt;
 
 use super::HealthCheck;
 
+/// The amount of gas used by every message in a simulated transaction.
+const MOCK_GAS_PER_MSG: u64 = 100_000;
+
 /// The representation of a mocked chain as the relayer sees it.
 /// The relayer runtime and the light client will engage with the MockChain to query/send tx; the
 /// primary interface for doing so is captured by `ICS18Context` which this struct can access via
 /// the `context` field.
 pub struct MockChain {
     config: ChainConfig,
-    context: MockContext,
+    context: Arc<RwLock<MockContext>>,
 
     // keep a reference to event sender to prevent it from being dropped
     _event_sender: EventSender,
@@ -66,6 +77,19 @@ pub struct MockChain {
 }
 
 impl MockChain {
+    /// Creates a mock chain backed by the given context, which may be shared so that
+    /// the state of the chain can be changed while the chain runtime is running.
+    pub fn new(config: ChainConfig, context: Arc<RwLock<MockContext>>) -> Self {
+        let (sender, receiver) = channel::unbounded();
+
+        MockChain {
+            config,
+            context,
+            _event_sender: sender,
+            event_receiver: receiver,
+        }
+    }
+
     fn trusting_period(&self) -> Duration {
         self.config
             .trusting_period
@@ -81,18 +105,14 @@ impl ChainEndpoint for MockChain {
     type LightClient = MockLightClient;
 
     fn bootstrap(config: ChainConfig, _rt: Arc<Runtime>) -> Result<Self, Error> {
-        let (sender, receiver) = channel::unbounded();
-        Ok(MockChain {
-            config: config.clone(),
-            context: MockContext::new(
-                config.id.clone(),
-                HostType::SyntheticTendermint,
-                50,
-                Height::new(config.id.version(), 20),
-            ),
-            _event_sender: sender,
-            event_receiver: receiver,
-        })
+        let context = MockContext::new(
+            config.id.clone(),
+            HostType::SyntheticTendermint,
+            50,
+            Height::new(config.id.version(), 20),
+        );
+
+        Ok(MockChain::new(config, Arc::new(RwLock::new(context))))
     }
 
     fn init_light_client(&self) -> Result<Self::LightClient, Error> {
@@ -132,7 +152,11 @@ impl ChainEndpoint for MockChain {
         proto_msgs: Vec<Any>,
     ) -> Result<Vec<IbcEvent>, Error> {
         // Use the ICS18Context interface to submit the set of messages.
-        let events = self.context.send(proto_msgs).map_err(Error::ics18)?;
+        let events = self
+            .context
+            .acquire_write()
+            .send(proto_msgs)
+            .map_err(Error::ics18)?;
 
         Ok(events)
     }
@@ -141,7 +165,22 @@ impl ChainEndpoint for MockChain {
         &mut self,
         _proto_msgs: Vec<Any>,
     ) -> Result<Vec<tendermint_rpc::endpoint::broadcast::tx_sync::Response>, Error> {
-        todo!()
+        Err(unsupported("send_messages_and_wait_check_tx"))
+    }
+
+    fn simulate_messages(&mut self, proto_msgs: Vec<Any>) -> Result<TxSimulation, Error> {
+        // The mock chain does not execute the messages, so every message
+        // is assumed to use the same amount of gas.
+        let gas_used = MOCK_GAS_PER_MSG * proto_msgs.len() as u64;
+        let gas_adjustment = self.config.gas_adjustment.unwrap_or(0.0);
+        let gas_limit = (gas_used as f64 * (1.0 + gas_adjustment)).ceil() as u64;
+        let fee_amount = (gas_limit as f64 * self.config.gas_price.price).ceil() as u64;
+
+        Ok(TxSimulation {
+            gas_used,
+            gas_limit,
+            fee: format!("{}{}", fee_amount, self.config.gas_price.denom),
+        })
     }
 
     fn get_signer(&mut self) -> Result<Signer, Error> {
@@ -153,21 +192,23 @@ impl ChainEndpoint for MockChain {
     }
 
     fn get_key(&mut self) -> Result<KeyEntry, Error> {
-        unimplemented!()
+        Err(unsupported("get_key"))
     }
 
     fn add_key(&mut self, _key_name: &str, _key: KeyEntry) -> Result<(), Error> {
-        unimplemented!()
+        Err(unsupported("add_key"))
     }
 
     fn query_commitment_prefix(&self) -> Result<CommitmentPrefix, Error> {
-        unimplemented!()
+        Err(unsupported("query_commitment_prefix"))
     }
 
     fn query_status(&self) -> Result<StatusResponse, Error> {
+        let context = self.context.acquire_read();
+
         Ok(StatusResponse {
-            height: self.context.host_height(),
-            timestamp: self.context.host_timestamp(),
+            height: context.host_height(),
+            timestamp: context.host_timestamp(),
         })
     }
 
@@ -175,7 +216,7 @@ impl ChainEndpoint for MockChain {
         &self,
         _request: QueryClientStatesRequest,
     ) -> Result<Vec<IdentifiedAnyClientState>, Error> {
-        unimplemented!()
+        Err(unsupported("query_clients"))
     }
 
     fn query_client_state(
@@ -186,6 +227,7 @@ impl ChainEndpoint for MockChain {
         // TODO: unclear what are the scenarios where we need to take height into account.
         let any_state = self
             .context
+            .acquire_read()
             .query_client_full_state(client_id)
             .ok_or_else(Error::empty_response_value)?;
         let client_state = downcast!(any_state.clone() => AnyClientState::Tendermint)
@@ -197,105 +239,157 @@ impl ChainEndpoint for MockChain {
         &self,
         _height: Height,
     ) -> Result<(Self::ClientState, MerkleProof), Error> {
-        unimplemented!()
+        Err(unsupported("query_upgraded_client_state"))
+    }
+
+    fn query_upgrade_plan(&self) -> Result<Option<Plan>, Error> {
+        Ok(None)
     }
 
     fn query_connection(
         &self,
-        _connection_id: &ConnectionId,
+        connection_id: &ConnectionId,
         _height: Height,
     ) -> Result<ConnectionEnd, Error> {
-        unimplemented!()
+        self.context
+            .acquire_read()
+            .connection_end(connection_id)
+            .map_err(|_| Error::query(format!("connection {}", connection_id)))
     }
 
     fn query_client_connections(
         &self,
         _request: QueryClientConnectionsRequest,
     ) -> Result<Vec<ConnectionId>, Error> {
-        unimplemented!()
+        Err(unsupported("query_client_connections"))
     }
 
     fn query_connections(
         &self,
         _request: QueryConnectionsRequest,
     ) -> Result<Vec<IdentifiedConnectionEnd>, Error> {
-        unimplemented!()
+        Err(unsupported("query_connections"))
     }
 
     fn query_connection_channels(
         &self,
         _request: QueryConnectionChannelsRequest,
     ) -> Result<Vec<IdentifiedChannelEnd>, Error> {
-        unimplemented!()
+        Err(unsupported("query_connection_channels"))
     }
 
     fn query_channels(
         &self,
         _request: QueryChannelsRequest,
     ) -> Result<Vec<IdentifiedChannelEnd>, Error> {
-        unimplemented!()
+        Err(unsupported("query_channels"))
     }
 
     fn query_channel(
         &self,
-        _port_id: &PortId,
-        _channel_id: &ChannelId,
+        port_id: &PortId,
+        channel_id: &ChannelId,
         _height: Height,
     ) -> Result<ChannelEnd, Error> {
-        unimplemented!()
+        self.context
+            .acquire_read()
+            .channel_end(&(port_id.clone(), channel_id.clone()))
+            .map_err(|_| Error::query(format!("channel {}/{}", port_id, channel_id)))
     }
 
     fn query_channel_client_state(
         &self,
         _request: QueryChannelClientStateRequest,
     ) -> Result<Option<IdentifiedAnyClientState>, Error> {
-        unimplemented!()
+        Err(unsupported("query_channel_client_state"))
     }
 
     fn query_packet_commitments(
         &self,
         _request: QueryPacketCommitmentsRequest,
     ) -> Result<(Vec<PacketState>, Height), Error> {
-        unimplemented!()
+        Err(unsupported("query_packet_commitments"))
     }
 
     fn query_unreceived_packets(
         &self,
-        _request: QueryUnreceivedPacketsRequest,
+        request: QueryUnreceivedPacketsRequest,
     ) -> Result<Vec<u64>, Error> {
-        unimplemented!()
+        let context = self.context.acquire_read();
+        let port_id: PortId = request.port_id.parse().unwrap();
+        let channel_id: ChannelId = request.channel_id.parse().unwrap();
+
+        Ok(request
+            .packet_commitment_sequences
+            .into_iter()
+            .filter(|&seq| {
+                context
+                    .get_packet_receipt(&(port_id.clone(), channel_id.clone(), seq.into()))
+                    .is_err()
+            })
+            .collect())
     }
 
     fn query_packet_acknowledgements(
         &self,
         _request: QueryPacketAcknowledgementsRequest,
     ) -> Result<(Vec<PacketState>, Height), Error> {
-        unimplemented!()
+        Err(unsupported("query_packet_acknowledgements"))
     }
 
     fn query_unreceived_acknowledgements(
         &self,
-        _request: QueryUnreceivedAcksRequest,
+        request: QueryUnreceivedAcksRequest,
     ) -> Result<Vec<u64>, Error> {
-        unimplemented!()
+        let context = self.context.acquire_read();
+        let port_id: PortId = request.port_id.parse().unwrap();
+        let channel_id: ChannelId = request.channel_id.parse().unwrap();
+
+        Ok(request
+            .packet_ack_sequences
+            .into_iter()
+            .filter(|&seq| {
+                context
+                    .get_packet_commitment(&(port_id.clone(), channel_id.clone(), seq.into()))
+                    .is_ok()
+            })
+            .collect())
     }
 
     fn query_next_sequence_receive(
         &self,
-        _request: QueryNextSequenceReceiveRequest,
+        request: QueryNextSequenceReceiveRequest,
     ) -> Result<Sequence, Error> {
-        unimplemented!()
+        let port_id: PortId = request.port_id.parse().unwrap();
+        let channel_id: ChannelId = request.channel_id.parse().unwrap();
+
+        self.context
+            .acquire_read()
+            .get_next_sequence_recv(&(port_id.clone(), channel_id.clone()))
+            .map_err(|_| {
+                Error::query(format!(
+                    "next sequence receive of channel {}/{}",
+                    port_id, channel_id
+                ))
+            })
     }
 
     fn query_txs(&self, _request: QueryTxRequest) -> Result<Vec<IbcEvent>, Error> {
-        unimplemented!()
+        Err(unsupported("query_txs"))
+    }
+
+    fn query_packet_txs(
+        &self,
+        _request: QueryPacketEventDataRequest,
+    ) -> Result<Vec<PacketTx>, Error> {
+        Err(unsupported("query_packet_txs"))
     }
 
     fn query_blocks(
         &self,
         _request: QueryBlockRequest,
     ) -> Result<(Vec<IbcEvent>, Vec<IbcEvent>), Error> {
-        unimplemented!()
+        Err(unsupported("query_blocks"))
     }
 
     fn proven_client_state(
@@ -303,7 +397,7 @@ impl ChainEndpoint for MockChain {
         _client_id: &ClientId,
         _height: Height,
     ) -> Result<(Self::ClientState, MerkleProof), Error> {
-        unimplemented!()
+        Err(unsupported("proven_client_state"))
     }
 
     fn proven_connection(
@@ -311,7 +405,7 @@ impl ChainEndpoint for MockChain {
         _connection_id: &ConnectionId,
         _height: Height,
     ) -> Result<(ConnectionEnd, MerkleProof), Error> {
-        unimplemented!()
+        Err(unsupported("proven_connection"))
     }
 
     fn proven_client_consensus(
@@ -320,27 +414,52 @@ impl ChainEndpoint for MockChain {
         _consensus_height: Height,
         _height: Height,
     ) -> Result<(Self::ConsensusState, MerkleProof), Error> {
-        unimplemented!()
+        Err(unsupported("proven_client_consensus"))
     }
 
     fn proven_channel(
         &self,
-        _port_id: &PortId,
-        _channel_id: &ChannelId,
-        _height: Height,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        height: Height,
     ) -> Result<(ChannelEnd, MerkleProof), Error> {
-        unimplemented!()
+        let channel_end = self.query_channel(port_id, channel_id, height)?;
+
+        Ok((channel_end, mock_proof()))
     }
 
+    /// The value of the packet commitment, acknowledgement or receipt is empty if it does not
+    /// exist, as with a full node. The proof cannot be verified, but it is not empty.
     fn proven_packet(
         &self,
-        _packet_type: PacketMsgType,
-        _port_id: PortId,
-        _channel_id: ChannelId,
-        _sequence: Sequence,
+        packet_type: PacketMsgType,
+        port_id: PortId,
+        channel_id: ChannelId,
+        sequence: Sequence,
         _height: Height,
     ) -> Result<(Vec<u8>, MerkleProof), Error> {
-        unimplemented!()
+        let context = self.context.acquire_read();
+
+        let value = match packet_type {
+            PacketMsgType::Recv => context
+                .get_packet_commitment(&(port_id, channel_id, sequence))
+                .map(String::into_bytes)
+                .unwrap_or_default(),
+            PacketMsgType::Ack => context
+                .get_packet_acknowledgement(&(port_id, channel_id, sequence))
+                .map(String::into_bytes)
+                .unwrap_or_default(),
+            PacketMsgType::TimeoutUnordered | PacketMsgType::TimeoutOnClose => context
+                .get_packet_receipt(&(port_id, channel_id, sequence))
+                .map(|_| vec![1])
+                .unwrap_or_default(),
+            PacketMsgType::TimeoutOrdered => context
+                .get_next_sequence_recv(&(port_id, channel_id))
+                .map(|seq| u64::from(seq).to_be_bytes().to_vec())
+                .unwrap_or_default(),
+        };
+
+        Ok((value, mock_proof()))
     }
 
     fn build_client_state(
@@ -413,6 +532,7 @@ impl ChainEndpoint for MockChain {
     ) -> Result<Vec<AnyConsensusStateWithHeight>, Error> {
         Ok(self
             .context
+            .acquire_read()
             .consensus_states(&request.client_id.parse().unwrap()))
     }
 
@@ -423,23 +543,40 @@ impl ChainEndpoint for MockChain {
         consensus_height: Height,
         _query_height: Height,
     ) -> Result<AnyConsensusState, Error> {
-        let consensus_states = self.context.consensus_states(&client_id);
-        Ok(consensus_states
+        let consensus_states = self.context.acquire_read().consensus_states(&client_id);
+        consensus_states
             .into_iter()
             .find(|s| s.height == consensus_height)
-            .unwrap()
-            .consensus_state)
+            .map(|s| s.consensus_state)
+            .ok_or_else(|| {
+                Error::query(format!(
+                    "consensus state of client {} at height {}",
+                    client_id, consensus_height
+                ))
+            })
     }
 
     fn query_upgraded_consensus_state(
         &self,
         _height: Height,
     ) -> Result<(Self::ConsensusState, MerkleProof), Error> {
-        unimplemented!()
+        Err(unsupported("query_upgraded_consensus_state"))
     }
 
     fn query_app_version(&self, _request: AppVersion) -> Result<Version, Error> {
-        todo!()
+        Err(unsupported("query_app_version"))
+    }
+}
+
+/// The error returned by the operations which the mock chain does not support
+fn unsupported(operation: &str) -> Error {
+    Error::mock_unsupported(operation.to_string())
+}
+
+/// A proof which is not empty, as required to build messages, but cannot be verified
+fn mock_proof() -> MerkleProof {
+    MerkleProof {
+        proofs: vec![CommitmentProof::default()],
     }
 }
 
@@ -475,9 +612,18 @@ pub mod test_utils {
             clock_drift: Duration::from_secs(5),
             max_block_time: Duration::from_secs(10),
             trusting_period: Some(Duration::from_secs(14 * 24 * 60 * 60)), // 14 days
+            verify_query_proofs: false,
             trust_threshold: Default::default(),
             packet_filter: PacketFilter::default(),
+            ics20_filter: Default::default(),
+            packet_scheduler: Default::default(),
+            query_cache: Default::default(),
+            light_store: Default::default(),
+            query_trust_anchor: None,
+            failover: Default::default(),
+            event_source: Default::default(),
             address_type: AddressType::default(),
+            backup_nodes: vec![],
             memo_prefix: Default::default(),
             proof_specs: Default::default(),
         }
@@ -20,7 +20,7 @@ use ibc::{
         },
         ics04_channel::{
             self,
-            channel::{ChannelEnd, IdentifiedChannelEnd},
+            channel::{ChannelEnd, IdentifiedChannelEnd, QueryPacketEventDataRequest},
             packet::{PacketMsgType, Sequence},
         },
         ics23_commitment::commitment::CommitmentPrefix,
@@ -32,6 +32,7 @@ use ibc::{
     signer::Signer,
     Height,
 };
+use ibc_proto::cosmos::upgrade::v1beta1::Plan;
 use ibc_proto::ibc::core::{
     channel::v1::{
         PacketState, QueryChannelClientStateRequest, QueryChannelsRequest,
@@ -60,7 +61,7 @@ use crate::{
 
 use super::{
     handle::{ChainHandle, ChainRequest, ReplyTo, Subscription},
-    ChainEndpoint, HealthCheck,
+    ChainEndpoint, HealthCheck, PacketTx, TxSimulation,
 };
 
 pub struct Threads {
@@ -163,6 +164,15 @@ where
         // Similar to `from_config`.
         let chain = Endpoint::bootstrap(config, rt.clone())?;
 
+        Self::spawn_with_endpoint(chain, rt)
+    }
+
+    /// Spawns a new runtime for an already bootstrapped chain,
+    /// e.g. a mock chain whose state was set up beforehand.
+    pub fn spawn_with_endpoint<Handle: ChainHandle>(
+        chain: Endpoint,
+        rt: Arc<TokioRuntime>,
+    ) -> Result<Handle, Error> {
         // Start the light client
         let light_client = chain.init_light_client()?;
 
@@ -259,6 +269,10 @@ where
                             self.send_messages_and_wait_check_tx(proto_msgs, reply_to)?
                         },
 
+                        Ok(ChainRequest::SimulateMessages { proto_msgs, reply_to }) => {
+                            self.simulate_messages(proto_msgs, reply_to)?
+                        },
+
                         Ok(ChainRequest::Signer { reply_to }) => {
                             self.get_signer(reply_to)?
                         }
@@ -336,6 +350,10 @@ where
                             self.query_upgraded_consensus_state(height, reply_to)?
                         }
 
+                        Ok(ChainRequest::QueryUpgradePlan { reply_to }) => {
+                            self.query_upgrade_plan(reply_to)?
+                        }
+
                         Ok(ChainRequest::QueryCommitmentPrefix { reply_to }) => {
                             self.query_commitment_prefix(reply_to)?
                         },
@@ -412,6 +430,10 @@ where
                             self.query_blocks(request, reply_to)?
                         },
 
+                        Ok(ChainRequest::QueryPacketTxs { request, reply_to }) => {
+                            self.query_packet_txs(request, reply_to)?
+                        },
+
                         Err(e) => error!("received error via chain request channel: {}", e),
                     }
                 },
@@ -462,6 +484,15 @@ where
         reply_to.send(result).map_err(Error::send)
     }
 
+    fn simulate_messages(
+        &mut self,
+        proto_msgs: Vec<prost_types::Any>,
+        reply_to: ReplyTo<TxSimulation>,
+    ) -> Result<(), Error> {
+        let result = self.chain.simulate_messages(proto_msgs);
+        reply_to.send(result).map_err(Error::send)
+    }
+
     fn query_status(&self, reply_to: ReplyTo<StatusResponse>) -> Result<(), Error> {
         let latest_timestamp = self.chain.query_status();
         reply_to.send(latest_timestamp).map_err(Error::send)
@@ -674,6 +705,11 @@ where
         reply_to.send(result).map_err(Error::send)
     }
 
+    fn query_upgrade_plan(&self, reply_to: ReplyTo<Option<Plan>>) -> Result<(), Error> {
+        let plan = self.chain.query_upgrade_plan();
+        reply_to.send(plan).map_err(Error::send)
+    }
+
     fn query_commitment_prefix(&self, reply_to: ReplyTo<CommitmentPrefix>) -> Result<(), Error> {
         let prefix = self.chain.query_commitment_prefix();
         reply_to.send(prefix).map_err(Error::send)
@@ -864,6 +900,15 @@ where
         reply_to.send(result).map_err(Error::send)
     }
 
+    fn query_packet_txs(
+        &self,
+        request: QueryPacketEventDataRequest,
+        reply_to: ReplyTo<Vec<PacketTx>>,
+    ) -> Result<(), Error> {
+        let result = self.chain.query_packet_txs(request);
+        reply_to.send(result).map_err(Error::send)
+    }
+
     fn query_blocks(
         &self,
         request: QueryBlockRequest,
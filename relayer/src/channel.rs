@@ -152,6 +152,35 @@ impl<ChainA: ChainHandle, ChainB: ChainHandle> Channel<ChainA, ChainB> {
         a_port: PortId,
         b_port: PortId,
         version: Option<String>,
+    ) -> Result<Self, ChannelError> {
+        let mut channel = Self::uninitialized(connection, ordering, a_port, b_port, version)?;
+
+        channel.handshake()?;
+
+        Ok(channel)
+    }
+
+    /// Builds the messages which initialize the handshake of a new channel
+    /// on top of the given connection, without sending them.
+    /// The messages are meant for the source chain of the connection.
+    pub fn build_handshake_init(
+        connection: Connection<ChainA, ChainB>,
+        ordering: Order,
+        a_port: PortId,
+        b_port: PortId,
+        version: Option<String>,
+    ) -> Result<Vec<Any>, ChannelError> {
+        Self::uninitialized(connection, ordering, a_port, b_port, version)?
+            .flipped()
+            .build_chan_open_init()
+    }
+
+    fn uninitialized(
+        connection: Connection<ChainA, ChainB>,
+        ordering: Order,
+        a_port: PortId,
+        b_port: PortId,
+        version: Option<String>,
     ) -> Result<Self, ChannelError> {
         let src_connection_id = connection
             .src_connection_id()
@@ -163,7 +192,7 @@ impl<ChainA: ChainHandle, ChainB: ChainHandle> Channel<ChainA, ChainB> {
         // Convert the raw version into our domain type.
         let domain_version = version.map(Into::into);
 
-        let mut channel = Self {
+        Ok(Self {
             ordering,
             a_side: ChannelSide::new(
                 connection.src_chain(),
@@ -182,11 +211,7 @@ impl<ChainA: ChainHandle, ChainB: ChainHandle> Channel<ChainA, ChainB> {
                 domain_version,
             ),
             connection_delay: connection.delay_period,
-        };
-
-        channel.handshake()?;
-
-        Ok(channel)
+        })
     }
 
     pub fn restore_from_event(
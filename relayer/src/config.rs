@@ -1,27 +1,30 @@
 //! Relayer configuration
 
 mod error;
+pub mod filter;
+pub mod ics20_filter;
 mod proof_specs;
 pub mod reload;
 pub mod types;
 
 use alloc::collections::BTreeMap as HashMap;
-use alloc::collections::BTreeSet as HashSet;
 use core::{fmt, time::Duration};
 use std::sync::{Arc, RwLock};
-use std::{fs, fs::File, io::Write, path::Path};
+use std::{fs, fs::File, io::Write, path::Path, path::PathBuf};
 
 use serde_derive::{Deserialize, Serialize};
 use tendermint_light_client::types::TrustThreshold;
 
 use ibc::core::ics23_commitment::specs::ProofSpecs;
-use ibc::core::ics24_host::identifier::{ChainId, ChannelId, PortId};
+use ibc::core::ics24_host::identifier::{ChainId, ChannelId, ClientId, ConnectionId, PortId};
 use ibc::timestamp::ZERO_DURATION;
 
+use crate::config::filter::{ChannelFilterMatch, PortFilterMatch};
 use crate::config::types::{MaxMsgNum, MaxTxSize, Memo};
 use crate::keyring::Store;
 
 pub use error::Error;
+pub use ics20_filter::Ics20Filter;
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct GasPrice {
@@ -66,23 +69,236 @@ impl PacketFilter {
     /// false otherwise.
     pub fn is_allowed(&self, port_id: &PortId, channel_id: &ChannelId) -> bool {
         match self {
-            PacketFilter::Allow(spec) => spec.contains(&(port_id.clone(), channel_id.clone())),
-            PacketFilter::Deny(spec) => !spec.contains(&(port_id.clone(), channel_id.clone())),
+            PacketFilter::Allow(spec) => spec.matches(port_id, channel_id),
+            PacketFilter::Deny(spec) => !spec.matches(port_id, channel_id),
             PacketFilter::AllowAll => true,
         }
     }
 }
 
+/// A list of channels, each specified by a port identifier and a channel identifier,
+/// either of which may be a wildcard pattern, e.g. `['icacontroller-*', '*']`.
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
-pub struct ChannelsSpec(HashSet<(PortId, ChannelId)>);
+pub struct ChannelsSpec(Vec<(PortFilterMatch, ChannelFilterMatch)>);
 
 impl ChannelsSpec {
-    pub fn contains(&self, channel_port: &(PortId, ChannelId)) -> bool {
-        self.0.contains(channel_port)
+    /// Returns true if the channel matches any of the entries of the list.
+    pub fn matches(&self, port_id: &PortId, channel_id: &ChannelId) -> bool {
+        self.0
+            .iter()
+            .any(|(port, channel)| port.matches(port_id) && channel.matches(channel_id))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(PortFilterMatch, ChannelFilterMatch)> {
+        self.0.iter()
+    }
+}
+
+/// Caching of the queries made to a chain for the latest state of its clients,
+/// connections and channels, which are served from the cache until their TTL expires,
+/// or until an event invalidates them.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QueryCache {
+    #[serde(default = "default::query_cache_enabled")]
+    pub enabled: bool,
+    /// Time during which the latest state of a client is served from the cache
+    #[serde(
+        default = "default::query_cache_client_state_ttl",
+        with = "humantime_serde"
+    )]
+    pub client_state_ttl: Duration,
+    /// Time during which a connection is served from the cache, once it is open
+    #[serde(
+        default = "default::query_cache_connection_ttl",
+        with = "humantime_serde"
+    )]
+    pub connection_ttl: Duration,
+    /// Time during which a channel is served from the cache, once it is open
+    #[serde(default = "default::query_cache_channel_ttl", with = "humantime_serde")]
+    pub channel_ttl: Duration,
+}
+
+impl Default for QueryCache {
+    fn default() -> Self {
+        Self {
+            enabled: default::query_cache_enabled(),
+            client_state_ttl: default::query_cache_client_state_ttl(),
+            connection_ttl: default::query_cache_connection_ttl(),
+            channel_ttl: default::query_cache_channel_ttl(),
+        }
+    }
+}
+
+/// Persistence on disk of the light blocks fetched by the light client of the relayer,
+/// which are reused across client updates and restarts.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LightStoreConfig {
+    #[serde(default = "default::light_store_persistent")]
+    pub persistent: bool,
+    /// Directory in which the light blocks of each chain are stored,
+    /// by default `$HOME/.hermes/light_store`
+    #[serde(default)]
+    pub dir: Option<PathBuf>,
+}
+
+impl Default for LightStoreConfig {
+    fn default() -> Self {
+        Self {
+            persistent: default::light_store_persistent(),
+            dir: None,
+        }
+    }
+}
+
+/// The block from which the light client verifies the proofs of the queries made to a chain,
+/// given by its height and hash as obtained from a source independent of the full nodes
+/// of the chain, e.g. a block explorer or the operator of a validator.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TrustAnchor {
+    pub height: u64,
+    pub hash: tendermint::Hash,
+}
+
+/// The source of the events of a chain: the WebSocket subscriptions to the given queries,
+/// and the polling of the `/block_results` of each height when the WebSocket is unavailable or lagging.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EventSource {
+    /// Queries to subscribe to, by default the `NewBlock` events and the transactions
+    /// of the IBC client, connection and channel modules
+    #[serde(default = "default::event_source_queries")]
+    pub queries: Vec<String>,
+    /// Whether to poll the blocks when the WebSocket is unavailable or lagging
+    #[serde(default = "default::event_source_poll_fallback")]
+    pub poll_fallback: bool,
+    /// Time without any event from the WebSocket after which it is considered lagging
+    #[serde(
+        default = "default::event_source_lag_timeout",
+        with = "humantime_serde"
+    )]
+    pub lag_timeout: Duration,
+    /// Interval between two polls of the blocks
+    #[serde(
+        default = "default::event_source_poll_interval",
+        with = "humantime_serde"
+    )]
+    pub poll_interval: Duration,
+    /// Maximum number of heights whose events are backfilled after the WebSocket reconnects,
+    /// above which the pending packets are cleared instead
+    #[serde(default = "default::event_source_max_backfill_blocks")]
+    pub max_backfill_blocks: u64,
+}
+
+impl Default for EventSource {
+    fn default() -> Self {
+        Self {
+            queries: default::event_source_queries(),
+            poll_fallback: default::event_source_poll_fallback(),
+            lag_timeout: default::event_source_lag_timeout(),
+            poll_interval: default::event_source_poll_interval(),
+            max_backfill_blocks: default::event_source_max_backfill_blocks(),
+        }
+    }
+}
+
+/// A full node of a chain, to which the relayer fails over when
+/// the node it is using is unhealthy.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BackupNode {
+    pub rpc_addr: tendermint_rpc::Url,
+    pub websocket_addr: tendermint_rpc::Url,
+    pub grpc_addr: tendermint_rpc::Url,
+}
+
+/// Health checking of the full nodes of a chain, and failover between them.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Failover {
+    /// Interval between the health checks of the nodes
+    #[serde(
+        default = "default::failover_health_check_interval",
+        with = "humantime_serde"
+    )]
+    pub health_check_interval: Duration,
+    /// Number of errors returned by a node between two health checks
+    /// after which the relayer fails over to another node
+    #[serde(default = "default::failover_max_errors")]
+    pub max_errors: u32,
+    /// Whether to compare the latest heights of the nodes, and consider unhealthy
+    /// the nodes lagging behind the others by more than `max_height_lag` blocks
+    #[serde(default = "default::failover_cross_check_heights")]
+    pub cross_check_heights: bool,
+    #[serde(default = "default::failover_max_height_lag")]
+    pub max_height_lag: u64,
+}
+
+impl Default for Failover {
+    fn default() -> Self {
+        Self {
+            health_check_interval: default::failover_health_check_interval(),
+            max_errors: default::failover_max_errors(),
+            cross_check_heights: default::failover_cross_check_heights(),
+            max_height_lag: default::failover_max_height_lag(),
+        }
     }
 }
 
+/// Scheduling of the transactions submitted to a chain by the packet workers
+/// relaying to it, which compete for the same account.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PacketScheduler {
+    /// Maximum number of transactions which are submitted but not yet confirmed,
+    /// across all the channels relaying to the chain. Scheduling is disabled if `0`.
+    #[serde(default)]
+    pub max_inflight_txs: usize,
+    /// Time after which a channel waiting for a transaction slot is served
+    /// ahead of all the others, regardless of its weight
+    #[serde(default = "default::starvation_timeout", with = "humantime_serde")]
+    pub starvation_timeout: Duration,
+    /// Share of the transaction slots of the channels on the chain,
+    /// relative to the default weight of `1`
+    #[serde(default)]
+    pub weights: Vec<ChannelWeight>,
+}
+
+impl Default for PacketScheduler {
+    fn default() -> Self {
+        Self {
+            max_inflight_txs: 0,
+            starvation_timeout: default::starvation_timeout(),
+            weights: Vec::new(),
+        }
+    }
+}
+
+impl PacketScheduler {
+    pub fn is_enabled(&self) -> bool {
+        self.max_inflight_txs > 0
+    }
+
+    /// Returns the weight of the channel with [`PortId`] and [`ChannelId`], at least `1`.
+    pub fn weight(&self, port_id: &PortId, channel_id: &ChannelId) -> u64 {
+        self.weights
+            .iter()
+            .find(|w| w.port_id == *port_id && w.channel_id == *channel_id)
+            .map_or(1, |w| w.weight.max(1))
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChannelWeight {
+    pub port_id: PortId,
+    pub channel_id: ChannelId,
+    pub weight: u64,
+}
+
 /// Defaults for various fields
 pub mod default {
     use super::*;
@@ -114,6 +330,77 @@ pub mod default {
     pub fn connection_delay() -> Duration {
         ZERO_DURATION
     }
+
+    pub fn starvation_timeout() -> Duration {
+        Duration::from_secs(30)
+    }
+
+    pub fn query_cache_enabled() -> bool {
+        true
+    }
+
+    pub fn query_cache_client_state_ttl() -> Duration {
+        Duration::from_millis(500)
+    }
+
+    pub fn query_cache_connection_ttl() -> Duration {
+        Duration::from_secs(10 * 60)
+    }
+
+    pub fn query_cache_channel_ttl() -> Duration {
+        Duration::from_secs(60)
+    }
+
+    pub fn light_store_persistent() -> bool {
+        true
+    }
+
+    pub fn event_source_queries() -> Vec<String> {
+        crate::event::monitor::queries::all()
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    pub fn event_source_poll_fallback() -> bool {
+        true
+    }
+
+    pub fn event_source_lag_timeout() -> Duration {
+        Duration::from_secs(30)
+    }
+
+    pub fn event_source_poll_interval() -> Duration {
+        Duration::from_secs(1)
+    }
+
+    pub fn event_source_max_backfill_blocks() -> u64 {
+        100
+    }
+
+    pub fn failover_health_check_interval() -> Duration {
+        Duration::from_secs(30)
+    }
+
+    pub fn failover_max_errors() -> u32 {
+        3
+    }
+
+    pub fn failover_cross_check_heights() -> bool {
+        true
+    }
+
+    pub fn failover_max_height_lag() -> u64 {
+        5
+    }
+
+    pub fn scan_parallelism() -> usize {
+        4
+    }
+
+    pub fn scan_cache() -> bool {
+        true
+    }
 }
 
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
@@ -127,8 +414,14 @@ pub struct Config {
     pub rest: RestConfig,
     #[serde(default)]
     pub telemetry: TelemetryConfig,
+    #[serde(default)]
+    pub scan: ScanConfig,
     #[serde(default = "Vec::new", skip_serializing_if = "Vec::is_empty")]
     pub chains: Vec<ChainConfig>,
+    /// The paths to relay on. If any, the relayer only relays on these paths,
+    /// instead of scanning the chains for clients, connections and channels.
+    #[serde(default = "Vec::new", skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<PathConfig>,
 }
 
 pub type SharedConfig = Arc<RwLock<Config>>;
@@ -168,6 +461,53 @@ impl Config {
     pub fn chains_map(&self) -> HashMap<&ChainId, &ChainConfig> {
         self.chains.iter().map(|c| (&c.id, c)).collect()
     }
+
+    /// Returns true if the relayer is restricted to the paths listed in the configuration.
+    pub fn has_paths(&self) -> bool {
+        !self.paths.is_empty()
+    }
+
+    /// Returns the paths with an end on the given chain.
+    pub fn paths_of_chain<'a>(
+        &'a self,
+        chain_id: &'a ChainId,
+    ) -> impl Iterator<Item = &'a PathConfig> + 'a {
+        self.paths
+            .iter()
+            .filter(move |path| &path.a.chain_id == chain_id || &path.b.chain_id == chain_id)
+    }
+}
+
+/// A path between two chains, given by the identifiers of the client, connection
+/// and channel at each of its ends.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PathConfig {
+    pub a: PathEndConfig,
+    pub b: PathEndConfig,
+}
+
+impl PathConfig {
+    /// Returns the end of the path on the given chain, and the end on its counterparty.
+    pub fn ends_from(&self, chain_id: &ChainId) -> Option<(&PathEndConfig, &PathEndConfig)> {
+        if &self.a.chain_id == chain_id {
+            Some((&self.a, &self.b))
+        } else if &self.b.chain_id == chain_id {
+            Some((&self.b, &self.a))
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PathEndConfig {
+    pub chain_id: ChainId,
+    pub client_id: ClientId,
+    pub connection_id: ConnectionId,
+    pub port_id: PortId,
+    pub channel_id: ChannelId,
 }
 
 #[derive(Copy, Clone, Debug, Deserialize, Serialize)]
@@ -195,6 +535,7 @@ impl Default for ModeConfig {
                 enabled: true,
                 refresh: true,
                 misbehaviour: true,
+                upgrade: false,
             },
             connections: Connections { enabled: false },
             channels: Channels { enabled: false },
@@ -217,6 +558,8 @@ pub struct Clients {
     pub refresh: bool,
     #[serde(default)]
     pub misbehaviour: bool,
+    #[serde(default)]
+    pub upgrade: bool,
 }
 
 #[derive(Copy, Clone, Debug, Default, Deserialize, Serialize)]
@@ -294,6 +637,33 @@ pub struct GlobalConfig {
     pub log_level: LogLevel,
 }
 
+/// Configuration of the scan of the chains performed on startup,
+/// to find out which clients, connections and channels to relay on.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScanConfig {
+    /// Maximum number of chains scanned concurrently
+    #[serde(default = "default::scan_parallelism")]
+    pub parallelism: usize,
+    /// Whether to cache the results of the scan on disk, so that the connections
+    /// which are open need not be queried again on the next startup
+    #[serde(default = "default::scan_cache")]
+    pub cache: bool,
+    /// The directory of the cache, `$HOME/.hermes/scan` if unset
+    #[serde(default)]
+    pub cache_dir: Option<PathBuf>,
+}
+
+impl Default for ScanConfig {
+    fn default() -> Self {
+        Self {
+            parallelism: default::scan_parallelism(),
+            cache: default::scan_cache(),
+            cache_dir: None,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct TelemetryConfig {
@@ -389,6 +759,10 @@ pub struct ChainConfig {
     pub max_block_time: Duration,
     #[serde(default, with = "humantime_serde")]
     pub trusting_period: Option<Duration>,
+    /// Whether to verify the proofs of the queries made to the chain against
+    /// the app hashes verified by the light client of the relayer
+    #[serde(default)]
+    pub verify_query_proofs: bool,
     #[serde(default)]
     pub memo_prefix: Memo,
     #[serde(default, with = "self::proof_specs")]
@@ -401,7 +775,26 @@ pub struct ChainConfig {
     #[serde(default)]
     pub packet_filter: PacketFilter,
     #[serde(default)]
+    pub ics20_filter: Ics20Filter,
+    #[serde(default)]
+    pub packet_scheduler: PacketScheduler,
+    #[serde(default)]
+    pub query_cache: QueryCache,
+    #[serde(default)]
+    pub light_store: LightStoreConfig,
+    /// The block trusted by the verifier of the query proofs, which is required
+    /// if `verify_query_proofs` is enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query_trust_anchor: Option<TrustAnchor>,
+    #[serde(default)]
+    pub failover: Failover,
+    #[serde(default)]
+    pub event_source: EventSource,
+    #[serde(default)]
     pub address_type: AddressType,
+    // an empty list would be serialized as a value after the tables above
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub backup_nodes: Vec<BackupNode>,
 }
 
 /// Attempt to load and parse the TOML config file as a `Config`.
@@ -436,7 +829,7 @@ pub(crate) fn store_writer(config: &Config, mut writer: impl Write) -> Result<()
 
 #[cfg(test)]
 mod tests {
-    use super::{load, store_writer};
+    use super::{load, store_writer, Config, PathConfig};
     use test_log::test;
 
     #[test]
@@ -463,4 +856,50 @@ mod tests {
         let mut buffer = Vec::new();
         store_writer(&config, &mut buffer).unwrap();
     }
+
+    #[test]
+    fn parse_paths() {
+        let config: Config = toml::from_str(
+            r#"
+            [[paths]]
+            a = { chain_id = 'ibc-0', client_id = '07-tendermint-0', connection_id = 'connection-0', port_id = 'transfer', channel_id = 'channel-0' }
+            b = { chain_id = 'ibc-1', client_id = '07-tendermint-1', connection_id = 'connection-1', port_id = 'transfer', channel_id = 'channel-1' }
+            "#,
+        )
+        .unwrap();
+
+        assert!(config.has_paths());
+
+        let path = &config.paths[0];
+        assert_eq!(path.a.chain_id.as_str(), "ibc-0");
+        assert_eq!(path.a.client_id.as_str(), "07-tendermint-0");
+        assert_eq!(path.b.connection_id.as_str(), "connection-1");
+        assert_eq!(path.b.port_id.as_str(), "transfer");
+        assert_eq!(path.b.channel_id.as_str(), "channel-1");
+
+        let (end, counterparty) = path.ends_from(&path.b.chain_id).unwrap();
+        assert_eq!(end.chain_id, path.b.chain_id);
+        assert_eq!(counterparty.chain_id, path.a.chain_id);
+
+        assert_eq!(config.paths_of_chain(&path.a.chain_id).count(), 1);
+    }
+
+    #[test]
+    fn parse_invalid_paths() {
+        let end = "{ chain_id = 'ibc-0', client_id = '07-tendermint-0', connection_id = 'connection-0', port_id = 'transfer', channel_id = 'channel-0' }";
+
+        // Unknown end of the path
+        assert!(
+            toml::from_str::<PathConfig>(&format!("a = {}\nb = {}\nc = {}", end, end, end))
+                .is_err()
+        );
+
+        // Unknown field of a path end
+        let unknown = end.replace(" }", ", version = 'ics20-1' }");
+        assert!(toml::from_str::<PathConfig>(&format!("a = {}\nb = {}", end, unknown)).is_err());
+
+        // Missing field of a path end
+        let missing = end.replace(", channel_id = 'channel-0'", "");
+        assert!(toml::from_str::<PathConfig>(&format!("a = {}\nb = {}", end, missing)).is_err());
+    }
 }
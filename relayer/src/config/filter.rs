@@ -0,0 +1,214 @@
+//! Patterns matching the port and channel identifiers of the packet filters.
+
+use core::fmt;
+use core::marker::PhantomData;
+use core::str::FromStr;
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::ser::Serializer;
+use serde::{Deserialize, Serialize};
+
+use ibc::core::ics24_host::identifier::{ChannelId, PortId};
+
+/// The characters which may appear in an identifier, besides alphanumeric characters.
+/// Mirrors the validation of identifiers in `ics24_host::validate`.
+const VALID_SPECIAL_CHARS: &str = "._+-#[]<>";
+
+/// A pattern matching identifiers, either exactly, or by means of a wildcard
+/// such as `icacontroller-*`.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FilterPattern<T> {
+    Exact(T),
+    Wildcard(Wildcard),
+}
+
+impl<T: PartialEq + fmt::Display> FilterPattern<T> {
+    pub fn matches(&self, id: &T) -> bool {
+        match self {
+            FilterPattern::Exact(exact) => exact == id,
+            FilterPattern::Wildcard(wildcard) => wildcard.matches(&id.to_string()),
+        }
+    }
+
+    /// Returns true if the pattern matches any identifier, i.e. is the `*` wildcard.
+    pub fn matches_all(&self) -> bool {
+        matches!(self, FilterPattern::Wildcard(w) if w.0.chars().all(|c| c == '*'))
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for FilterPattern<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FilterPattern::Exact(exact) => write!(f, "{}", exact),
+            FilterPattern::Wildcard(wildcard) => write!(f, "{}", wildcard),
+        }
+    }
+}
+
+impl<T> FromStr for FilterPattern<T>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if Wildcard::is_wildcard(s) {
+            s.parse().map(FilterPattern::Wildcard)
+        } else {
+            s.parse()
+                .map(FilterPattern::Exact)
+                .map_err(|e| format!("invalid identifier '{}': {}", s, e))
+        }
+    }
+}
+
+impl<T: fmt::Display> Serialize for FilterPattern<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for FilterPattern<T>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct PatternVisitor<T>(PhantomData<T>);
+
+        impl<'de, T> Visitor<'de> for PatternVisitor<T>
+        where
+            T: FromStr,
+            T::Err: fmt::Display,
+        {
+            type Value = FilterPattern<T>;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "an identifier or a wildcard pattern")
+            }
+
+            fn visit_str<E: de::Error>(self, s: &str) -> Result<Self::Value, E> {
+                s.parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_str(PatternVisitor(PhantomData))
+    }
+}
+
+pub type PortFilterMatch = FilterPattern<PortId>;
+pub type ChannelFilterMatch = FilterPattern<ChannelId>;
+
+/// A glob pattern, where `*` matches any sequence of characters,
+/// and `?` matches any single character.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Wildcard(String);
+
+impl Wildcard {
+    fn is_wildcard(s: &str) -> bool {
+        s.contains(|c| c == '*' || c == '?')
+    }
+
+    pub fn matches(&self, id: &str) -> bool {
+        let pattern = self.0.as_bytes();
+        let id = id.as_bytes();
+
+        let (mut p, mut i) = (0, 0);
+
+        // The position of the latest `*` in the pattern, and the position
+        // in the identifier from which it is currently matching
+        let mut backtrack = None;
+
+        while i < id.len() {
+            match pattern.get(p) {
+                Some(b'*') => {
+                    backtrack = Some((p, i));
+                    p += 1;
+                }
+                Some(&c) if c == b'?' || c == id[i] => {
+                    p += 1;
+                    i += 1;
+                }
+                _ => match backtrack {
+                    // Let the `*` match one more character
+                    Some((star, start)) => {
+                        backtrack = Some((star, start + 1));
+                        p = star + 1;
+                        i = start + 1;
+                    }
+                    None => return false,
+                },
+            }
+        }
+
+        pattern[p..].iter().all(|&c| c == b'*')
+    }
+}
+
+impl FromStr for Wildcard {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let valid = s.chars().all(|c| {
+            c == '*' || c == '?' || c.is_ascii_alphanumeric() || VALID_SPECIAL_CHARS.contains(c)
+        });
+
+        if valid {
+            Ok(Self(s.to_string()))
+        } else {
+            Err(format!(
+                "invalid wildcard pattern '{}': only alphanumeric characters, `{}`, `*` and `?` are allowed",
+                s, VALID_SPECIAL_CHARS
+            ))
+        }
+    }
+}
+
+impl fmt::Display for Wildcard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wildcard(s: &str) -> Wildcard {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn wildcard_matching() {
+        assert!(wildcard("icacontroller-*").matches("icacontroller-cosmos1abc"));
+        assert!(wildcard("icacontroller-*").matches("icacontroller-"));
+        assert!(!wildcard("icacontroller-*").matches("icahost"));
+
+        assert!(wildcard("*").matches("transfer"));
+        assert!(wildcard("channel-?").matches("channel-7"));
+        assert!(!wildcard("channel-?").matches("channel-17"));
+
+        assert!(wildcard("*-1*").matches("channel-12"));
+        assert!(wildcard("a*b*c").matches("aXbYbZc"));
+        assert!(!wildcard("a*b*c").matches("aXbYbZ"));
+    }
+
+    #[test]
+    fn pattern_parsing() {
+        let exact: PortFilterMatch = "transfer".parse().unwrap();
+        assert_eq!(exact, FilterPattern::Exact(PortId::transfer()));
+        assert!(exact.matches(&PortId::transfer()));
+
+        let prefix: PortFilterMatch = "icacontroller-*".parse().unwrap();
+        assert!(matches!(prefix, FilterPattern::Wildcard(_)));
+        assert!(!prefix.matches_all());
+
+        let all: ChannelFilterMatch = "*".parse().unwrap();
+        assert!(all.matches_all());
+        assert!(all.matches(&ChannelId::new(42)));
+
+        assert!("trans/fer".parse::<PortFilterMatch>().is_err());
+        assert!("ica/*".parse::<PortFilterMatch>().is_err());
+    }
+}
@@ -0,0 +1,279 @@
+//! Content-aware filtering of ICS20 fungible token transfer packets.
+
+use serde_derive::{Deserialize, Serialize};
+
+use ibc::applications::ics20_fungible_token_transfer::{
+    derive_ibc_denom, derive_ibc_denom_with_path,
+};
+use ibc::core::ics04_channel::packet::Packet;
+
+/// Rules on the content of the ICS20 packets sent from a chain. Packets which are
+/// not ICS20 packets, i.e. whose data does not decode as a `FungibleTokenPacketData`,
+/// are not affected by these rules.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Ics20Filter {
+    /// The denominations of the tokens whose transfers are relayed, or any denomination
+    /// if empty. A denomination matches the denomination of the transferred tokens,
+    /// either as it appears on the sending chain, or as it will appear on the receiving
+    /// chain, in which case tokens received as vouchers are matched by their `ibc/{hash}`
+    /// denomination.
+    #[serde(default)]
+    pub denoms: Vec<String>,
+    /// The minimum amount of tokens transferred in a packet, written as a string
+    /// since it may not fit in a TOML integer
+    #[serde(default, with = "amount", skip_serializing_if = "Option::is_none")]
+    pub min_amount: Option<u128>,
+    #[serde(default)]
+    pub senders: AddressFilter,
+    #[serde(default)]
+    pub receivers: AddressFilter,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(
+    rename_all = "lowercase",
+    tag = "policy",
+    content = "list",
+    deny_unknown_fields
+)]
+pub enum AddressFilter {
+    Allow(Vec<String>),
+    Deny(Vec<String>),
+    AllowAll,
+}
+
+impl Default for AddressFilter {
+    /// By default, allows all addresses.
+    fn default() -> Self {
+        Self::AllowAll
+    }
+}
+
+impl AddressFilter {
+    pub fn is_allowed(&self, address: &str) -> bool {
+        match self {
+            AddressFilter::Allow(list) => list.iter().any(|a| a == address),
+            AddressFilter::Deny(list) => !list.iter().any(|a| a == address),
+            AddressFilter::AllowAll => true,
+        }
+    }
+}
+
+/// Serialization of the minimum amount as a string. Amounts written as
+/// TOML integers are accepted as well.
+mod amount {
+    use serde::de::Error as _;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    #[derive(serde_derive::Deserialize)]
+    #[serde(untagged)]
+    enum Amount {
+        Integer(u64),
+        String(String),
+    }
+
+    pub fn serialize<S: Serializer>(
+        amount: &Option<u128>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match amount {
+            Some(amount) => serializer.serialize_some(&amount.to_string()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<u128>, D::Error> {
+        match Option::<Amount>::deserialize(deserializer)? {
+            None => Ok(None),
+            Some(Amount::Integer(amount)) => Ok(Some(amount.into())),
+            Some(Amount::String(amount)) => amount
+                .parse()
+                .map(Some)
+                .map_err(|e| D::Error::custom(format!("invalid amount '{}': {}", amount, e))),
+        }
+    }
+}
+
+/// The JSON encoding of `FungibleTokenPacketData`, as found in the data of ICS20 packets
+#[derive(Debug, Deserialize)]
+struct FungibleTokenPacketData {
+    denom: String,
+    amount: String,
+    sender: String,
+    receiver: String,
+}
+
+impl Ics20Filter {
+    /// Returns true if the rules allow relaying the given packet, false otherwise.
+    pub fn is_allowed(&self, packet: &Packet) -> bool {
+        let data = match serde_json::from_slice::<FungibleTokenPacketData>(&packet.data) {
+            Ok(data) => data,
+            // Not an ICS20 packet
+            Err(_) => return true,
+        };
+
+        let min_amount_reached = match self.min_amount {
+            None => true,
+            Some(min_amount) => data
+                .amount
+                .parse::<u128>()
+                .map_or(false, |amount| amount >= min_amount),
+        };
+
+        min_amount_reached
+            && self.senders.is_allowed(&data.sender)
+            && self.receivers.is_allowed(&data.receiver)
+            && (self.denoms.is_empty() || self.denom_allowed(packet, &data.denom))
+    }
+
+    fn denom_allowed(&self, packet: &Packet, denom: &str) -> bool {
+        let candidates = denom_candidates(packet, denom);
+        self.denoms
+            .iter()
+            .any(|allowed| candidates.iter().any(|c| c == allowed))
+    }
+}
+
+/// The denominations under which the tokens of an ICS20 packet with denomination trace
+/// `denom` are known, on the sending chain and on the receiving chain.
+fn denom_candidates(packet: &Packet, denom: &str) -> Vec<String> {
+    let mut candidates = vec![denom.to_string()];
+
+    // The tokens are vouchers on the sending chain
+    if denom.contains('/') {
+        candidates.extend(derive_ibc_denom_with_path(denom).ok());
+    }
+
+    let source_prefix = format!("{}/{}/", packet.source_port, packet.source_channel);
+
+    match denom.strip_prefix(&source_prefix) {
+        // The tokens return to the receiving chain, where they are unescrowed,
+        // or keep being vouchers if they originate from yet another chain
+        Some(unwound) => {
+            candidates.push(unwound.to_string());
+            if unwound.contains('/') {
+                candidates.extend(derive_ibc_denom_with_path(unwound).ok());
+            }
+        }
+        // The tokens are minted as vouchers on the receiving chain
+        None => {
+            candidates.extend(
+                derive_ibc_denom(&packet.destination_port, &packet.destination_channel, denom).ok(),
+            );
+        }
+    }
+
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ibc::core::ics24_host::identifier::{ChannelId, PortId};
+
+    fn packet(denom: &str, amount: &str) -> Packet {
+        let data = format!(
+            r#"{{"amount":"{}","denom":"{}","receiver":"cosmos1receiver","sender":"cosmos1sender"}}"#,
+            amount, denom
+        );
+
+        Packet {
+            source_port: PortId::transfer(),
+            source_channel: ChannelId::new(0),
+            destination_port: PortId::transfer(),
+            destination_channel: ChannelId::new(1),
+            data: data.into_bytes(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn non_ics20_packets_are_allowed() {
+        let filter = Ics20Filter {
+            min_amount: Some(1000),
+            ..Default::default()
+        };
+
+        let packet = Packet {
+            data: b"not a transfer".to_vec(),
+            ..Default::default()
+        };
+
+        assert!(filter.is_allowed(&packet));
+    }
+
+    #[test]
+    fn min_amount() {
+        let filter = Ics20Filter {
+            min_amount: Some(1000),
+            ..Default::default()
+        };
+
+        assert!(!filter.is_allowed(&packet("stake", "999")));
+        assert!(filter.is_allowed(&packet("stake", "1000")));
+        assert!(!filter.is_allowed(&packet("stake", "not a number")));
+    }
+
+    #[test]
+    fn min_amount_above_u64() {
+        let filter: Ics20Filter =
+            toml::from_str("min_amount = '100000000000000000000000'").unwrap();
+        assert_eq!(filter.min_amount, Some(100_000_000_000_000_000_000_000));
+
+        assert!(!filter.is_allowed(&packet("wei", "99999999999999999999999")));
+        assert!(filter.is_allowed(&packet("wei", "100000000000000000000000")));
+
+        // The amount is written back as a string
+        let written = toml::to_string(&filter).unwrap();
+        assert!(written.contains("min_amount = \"100000000000000000000000\""));
+
+        // Integers are accepted as well
+        let filter: Ics20Filter = toml::from_str("min_amount = 1000").unwrap();
+        assert_eq!(filter.min_amount, Some(1000));
+
+        assert!(toml::from_str::<Ics20Filter>("min_amount = '-1'").is_err());
+        assert!(toml::from_str::<Ics20Filter>("min_amount = 'lots'").is_err());
+    }
+
+    #[test]
+    fn senders_and_receivers() {
+        let filter = Ics20Filter {
+            senders: AddressFilter::Allow(vec!["cosmos1sender".to_string()]),
+            ..Default::default()
+        };
+        assert!(filter.is_allowed(&packet("stake", "1")));
+
+        let filter = Ics20Filter {
+            receivers: AddressFilter::Deny(vec!["cosmos1receiver".to_string()]),
+            ..Default::default()
+        };
+        assert!(!filter.is_allowed(&packet("stake", "1")));
+    }
+
+    #[test]
+    fn denoms_on_both_chains() {
+        // Native tokens of the sending chain, minted as vouchers on the receiving chain
+        let voucher = derive_ibc_denom(&PortId::transfer(), &ChannelId::new(1), "stake").unwrap();
+
+        for denom in ["stake".to_string(), voucher] {
+            let filter = Ics20Filter {
+                denoms: vec![denom],
+                ..Default::default()
+            };
+            assert!(filter.is_allowed(&packet("stake", "1")));
+            assert!(!filter.is_allowed(&packet("uatom", "1")));
+        }
+
+        // Vouchers on the sending chain, returning to the receiving chain
+        let filter = Ics20Filter {
+            denoms: vec!["uatom".to_string()],
+            ..Default::default()
+        };
+        assert!(filter.is_allowed(&packet("transfer/channel-0/uatom", "1")));
+        assert!(!filter.is_allowed(&packet("transfer/channel-5/uatom", "1")));
+    }
+}
@@ -271,6 +271,31 @@ impl<ChainA: ChainHandle, ChainB: ChainHandle> Connection<ChainA, ChainB> {
         b_to_a_client: ForeignClient<ChainA, ChainB>,
         a_to_b_client: ForeignClient<ChainB, ChainA>,
         delay_period: Duration,
+    ) -> Result<Self, ConnectionError> {
+        let mut c = Self::uninitialized(b_to_a_client, a_to_b_client, delay_period)?;
+
+        c.handshake()?;
+
+        Ok(c)
+    }
+
+    /// Builds the messages which initialize the handshake of a new connection
+    /// between the two given clients, without sending them.
+    /// The messages are meant for the chain hosting `b_to_a_client`.
+    pub fn build_handshake_init(
+        b_to_a_client: ForeignClient<ChainA, ChainB>,
+        a_to_b_client: ForeignClient<ChainB, ChainA>,
+        delay_period: Duration,
+    ) -> Result<Vec<Any>, ConnectionError> {
+        Self::uninitialized(b_to_a_client, a_to_b_client, delay_period)?
+            .flipped()
+            .build_conn_init()
+    }
+
+    fn uninitialized(
+        b_to_a_client: ForeignClient<ChainA, ChainB>,
+        a_to_b_client: ForeignClient<ChainB, ChainA>,
+        delay_period: Duration,
     ) -> Result<Self, ConnectionError> {
         Self::validate_clients(&b_to_a_client, &a_to_b_client)?;
 
@@ -279,7 +304,7 @@ impl<ChainA: ChainHandle, ChainB: ChainHandle> Connection<ChainA, ChainB> {
             return Err(ConnectionError::max_delay_period(delay_period));
         }
 
-        let mut c = Self {
+        Ok(Self {
             delay_period,
             a_side: ConnectionSide::new(
                 b_to_a_client.dst_chain(),
@@ -291,11 +316,7 @@ impl<ChainA: ChainHandle, ChainB: ChainHandle> Connection<ChainA, ChainB> {
                 a_to_b_client.id().clone(),
                 Default::default(),
             ),
-        };
-
-        c.handshake()?;
-
-        Ok(c)
+        })
     }
 
     pub fn restore_from_event(
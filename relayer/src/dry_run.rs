@@ -0,0 +1,551 @@
+//! Dry runs of IBC transactions, which simulate the transactions instead of
+//! broadcasting them, so that their messages and fees can be reviewed first.
+
+use prost::Message;
+use prost_types::Any;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use ibc::core::ics04_channel::channel::{Order, State};
+use ibc::core::ics24_host::identifier::ChainId;
+use ibc_proto::cosmos::base::v1beta1::Coin;
+use ibc_proto::cosmos::gov::v1beta1::MsgSubmitProposal;
+use ibc_proto::ibc::apps::transfer::v1::MsgTransfer;
+use ibc_proto::ibc::core::channel::v1::{
+    Channel as RawChannel, MsgAcknowledgement, MsgChannelCloseConfirm, MsgChannelCloseInit,
+    MsgChannelOpenAck, MsgChannelOpenConfirm, MsgChannelOpenInit, MsgChannelOpenTry, MsgRecvPacket,
+    MsgTimeout, MsgTimeoutOnClose, Packet as RawPacket,
+};
+use ibc_proto::ibc::core::client::v1::{
+    ClientUpdateProposal, Height as RawHeight, MsgCreateClient, MsgSubmitMisbehaviour,
+    MsgUpdateClient, MsgUpgradeClient,
+};
+use ibc_proto::ibc::core::connection::v1::{
+    Counterparty as RawConnectionCounterparty, MsgConnectionOpenAck, MsgConnectionOpenConfirm,
+    MsgConnectionOpenInit, MsgConnectionOpenTry, Version as RawConnectionVersion,
+};
+
+use crate::chain::handle::ChainHandle;
+use crate::chain::TxSimulation;
+use crate::error::Error;
+
+/// The report of a dry run: the messages that would have been submitted
+/// to a chain, and the outcome of simulating them in a single transaction.
+#[derive(Clone, Debug, Serialize)]
+pub struct DryRunReport {
+    pub chain_id: ChainId,
+    pub messages: Vec<DecodedMsg>,
+    /// The gas and fee of the transaction, if the simulation succeeded
+    pub simulation: Option<TxSimulation>,
+    /// The reason why the simulation failed, in which case submitting
+    /// the transaction would most likely fail as well
+    pub simulation_error: Option<String>,
+}
+
+impl DryRunReport {
+    pub fn new(
+        chain_id: ChainId,
+        msgs: &[Any],
+        simulation: Option<Result<TxSimulation, Error>>,
+    ) -> Self {
+        let (simulation, simulation_error) = match simulation {
+            Some(Ok(simulation)) => (Some(simulation), None),
+            Some(Err(e)) => (None, Some(e.to_string())),
+            None => (None, None),
+        };
+
+        Self {
+            chain_id,
+            messages: msgs.iter().map(DecodedMsg::from).collect(),
+            simulation,
+            simulation_error,
+        }
+    }
+}
+
+/// A message in a human readable form. Messages of known types are decoded
+/// into JSON, the value of any other message is hex-encoded.
+#[derive(Clone, Debug, Serialize)]
+pub struct DecodedMsg {
+    pub type_url: String,
+    pub value: Value,
+}
+
+impl From<&Any> for DecodedMsg {
+    fn from(msg: &Any) -> Self {
+        Self {
+            type_url: msg.type_url.clone(),
+            value: decode_value(msg).unwrap_or_else(|| bytes(&msg.value)),
+        }
+    }
+}
+
+/// Simulates the given messages on `chain` instead of submitting them.
+/// Does nothing but decode the messages if there are none to simulate.
+pub fn dry_run<Chain: ChainHandle>(chain: &Chain, msgs: Vec<Any>) -> DryRunReport {
+    let simulation = if msgs.is_empty() {
+        None
+    } else {
+        Some(chain.simulate_messages(msgs.clone()))
+    };
+
+    DryRunReport::new(chain.id(), &msgs, simulation)
+}
+
+fn decode_value(msg: &Any) -> Option<Value> {
+    fn decode<M: Message + Default + ToJson>(value: &[u8]) -> Option<Value> {
+        M::decode(value).ok().map(|m| m.to_json())
+    }
+
+    let value = msg.value.as_slice();
+
+    match msg.type_url.as_str() {
+        "/ibc.core.client.v1.MsgCreateClient" => decode::<MsgCreateClient>(value),
+        "/ibc.core.client.v1.MsgUpdateClient" => decode::<MsgUpdateClient>(value),
+        "/ibc.core.client.v1.MsgUpgradeClient" => decode::<MsgUpgradeClient>(value),
+        "/ibc.core.client.v1.MsgSubmitMisbehaviour" => decode::<MsgSubmitMisbehaviour>(value),
+        "/ibc.core.client.v1.ClientUpdateProposal" => decode::<ClientUpdateProposal>(value),
+        "/ibc.core.connection.v1.MsgConnectionOpenInit" => decode::<MsgConnectionOpenInit>(value),
+        "/ibc.core.connection.v1.MsgConnectionOpenTry" => decode::<MsgConnectionOpenTry>(value),
+        "/ibc.core.connection.v1.MsgConnectionOpenAck" => decode::<MsgConnectionOpenAck>(value),
+        "/ibc.core.connection.v1.MsgConnectionOpenConfirm" => {
+            decode::<MsgConnectionOpenConfirm>(value)
+        }
+        "/ibc.core.channel.v1.MsgChannelOpenInit" => decode::<MsgChannelOpenInit>(value),
+        "/ibc.core.channel.v1.MsgChannelOpenTry" => decode::<MsgChannelOpenTry>(value),
+        "/ibc.core.channel.v1.MsgChannelOpenAck" => decode::<MsgChannelOpenAck>(value),
+        "/ibc.core.channel.v1.MsgChannelOpenConfirm" => decode::<MsgChannelOpenConfirm>(value),
+        "/ibc.core.channel.v1.MsgChannelCloseInit" => decode::<MsgChannelCloseInit>(value),
+        "/ibc.core.channel.v1.MsgChannelCloseConfirm" => decode::<MsgChannelCloseConfirm>(value),
+        "/ibc.core.channel.v1.MsgRecvPacket" => decode::<MsgRecvPacket>(value),
+        "/ibc.core.channel.v1.MsgAcknowledgement" => decode::<MsgAcknowledgement>(value),
+        "/ibc.core.channel.v1.MsgTimeout" => decode::<MsgTimeout>(value),
+        "/ibc.core.channel.v1.MsgTimeoutOnClose" => decode::<MsgTimeoutOnClose>(value),
+        "/ibc.applications.transfer.v1.MsgTransfer" => decode::<MsgTransfer>(value),
+        "/cosmos.gov.v1beta1.MsgSubmitProposal" => decode::<MsgSubmitProposal>(value),
+        _ => None,
+    }
+}
+
+/// JSON view of a decoded protobuf message.
+///
+/// The protobuf types do not implement `Serialize`, so the views are written by hand.
+/// Byte fields (e.g. proofs) are hex-encoded, and nested `Any` values are decoded
+/// when their type is known, as in [`DecodedMsg`].
+trait ToJson {
+    fn to_json(&self) -> Value;
+}
+
+fn bytes(value: &[u8]) -> Value {
+    Value::String(hex::encode(value))
+}
+
+fn any(value: &Option<Any>) -> Value {
+    value
+        .as_ref()
+        .map_or(Value::Null, |value| json!(DecodedMsg::from(value)))
+}
+
+fn height(value: &Option<RawHeight>) -> Value {
+    value.as_ref().map_or(Value::Null, |height| {
+        json!({
+            "revision_number": height.revision_number,
+            "revision_height": height.revision_height,
+        })
+    })
+}
+
+fn coin(value: &Coin) -> Value {
+    json!({
+        "denom": value.denom,
+        "amount": value.amount,
+    })
+}
+
+fn connection_version(value: &RawConnectionVersion) -> Value {
+    json!({
+        "identifier": value.identifier,
+        "features": value.features,
+    })
+}
+
+fn connection_counterparty(value: &Option<RawConnectionCounterparty>) -> Value {
+    value.as_ref().map_or(Value::Null, |counterparty| {
+        json!({
+            "client_id": counterparty.client_id,
+            "connection_id": counterparty.connection_id,
+            "prefix": counterparty
+                .prefix
+                .as_ref()
+                .map_or(Value::Null, |prefix| bytes(&prefix.key_prefix)),
+        })
+    })
+}
+
+fn channel(value: &Option<RawChannel>) -> Value {
+    value.as_ref().map_or(Value::Null, |channel| {
+        json!({
+            "state": State::from_i32(channel.state)
+                .map_or_else(|_| json!(channel.state), |state| json!(state.as_string())),
+            "ordering": Order::from_i32(channel.ordering)
+                .map_or_else(|_| json!(channel.ordering), |order| json!(order.as_str())),
+            "counterparty": channel.counterparty.as_ref().map(|counterparty| json!({
+                "port_id": counterparty.port_id,
+                "channel_id": counterparty.channel_id,
+            })),
+            "connection_hops": channel.connection_hops,
+            "version": channel.version,
+        })
+    })
+}
+
+fn packet(value: &Option<RawPacket>) -> Value {
+    value.as_ref().map_or(Value::Null, |packet| {
+        json!({
+            "sequence": packet.sequence,
+            "source_port": packet.source_port,
+            "source_channel": packet.source_channel,
+            "destination_port": packet.destination_port,
+            "destination_channel": packet.destination_channel,
+            "data": String::from_utf8(packet.data.clone())
+                .map_or_else(|_| bytes(&packet.data), Value::String),
+            "timeout_height": height(&packet.timeout_height),
+            "timeout_timestamp": packet.timeout_timestamp,
+        })
+    })
+}
+
+impl ToJson for MsgCreateClient {
+    fn to_json(&self) -> Value {
+        json!({
+            "client_state": any(&self.client_state),
+            "consensus_state": any(&self.consensus_state),
+            "signer": self.signer,
+        })
+    }
+}
+
+impl ToJson for MsgUpdateClient {
+    fn to_json(&self) -> Value {
+        json!({
+            "client_id": self.client_id,
+            "header": any(&self.header),
+            "signer": self.signer,
+        })
+    }
+}
+
+impl ToJson for MsgUpgradeClient {
+    fn to_json(&self) -> Value {
+        json!({
+            "client_id": self.client_id,
+            "client_state": any(&self.client_state),
+            "consensus_state": any(&self.consensus_state),
+            "proof_upgrade_client": bytes(&self.proof_upgrade_client),
+            "proof_upgrade_consensus_state": bytes(&self.proof_upgrade_consensus_state),
+            "signer": self.signer,
+        })
+    }
+}
+
+impl ToJson for MsgSubmitMisbehaviour {
+    fn to_json(&self) -> Value {
+        json!({
+            "client_id": self.client_id,
+            "misbehaviour": any(&self.misbehaviour),
+            "signer": self.signer,
+        })
+    }
+}
+
+impl ToJson for ClientUpdateProposal {
+    fn to_json(&self) -> Value {
+        json!({
+            "title": self.title,
+            "description": self.description,
+            "subject_client_id": self.subject_client_id,
+            "substitute_client_id": self.substitute_client_id,
+        })
+    }
+}
+
+impl ToJson for MsgConnectionOpenInit {
+    fn to_json(&self) -> Value {
+        json!({
+            "client_id": self.client_id,
+            "counterparty": connection_counterparty(&self.counterparty),
+            "version": self.version.as_ref().map(connection_version),
+            "delay_period": self.delay_period,
+            "signer": self.signer,
+        })
+    }
+}
+
+impl ToJson for MsgConnectionOpenTry {
+    fn to_json(&self) -> Value {
+        json!({
+            "client_id": self.client_id,
+            "previous_connection_id": self.previous_connection_id,
+            "client_state": any(&self.client_state),
+            "counterparty": connection_counterparty(&self.counterparty),
+            "delay_period": self.delay_period,
+            "counterparty_versions": self
+                .counterparty_versions
+                .iter()
+                .map(connection_version)
+                .collect::<Vec<_>>(),
+            "proof_height": height(&self.proof_height),
+            "proof_init": bytes(&self.proof_init),
+            "proof_client": bytes(&self.proof_client),
+            "proof_consensus": bytes(&self.proof_consensus),
+            "consensus_height": height(&self.consensus_height),
+            "signer": self.signer,
+        })
+    }
+}
+
+impl ToJson for MsgConnectionOpenAck {
+    fn to_json(&self) -> Value {
+        json!({
+            "connection_id": self.connection_id,
+            "counterparty_connection_id": self.counterparty_connection_id,
+            "version": self.version.as_ref().map(connection_version),
+            "client_state": any(&self.client_state),
+            "proof_height": height(&self.proof_height),
+            "proof_try": bytes(&self.proof_try),
+            "proof_client": bytes(&self.proof_client),
+            "proof_consensus": bytes(&self.proof_consensus),
+            "consensus_height": height(&self.consensus_height),
+            "signer": self.signer,
+        })
+    }
+}
+
+impl ToJson for MsgConnectionOpenConfirm {
+    fn to_json(&self) -> Value {
+        json!({
+            "connection_id": self.connection_id,
+            "proof_ack": bytes(&self.proof_ack),
+            "proof_height": height(&self.proof_height),
+            "signer": self.signer,
+        })
+    }
+}
+
+impl ToJson for MsgChannelOpenInit {
+    fn to_json(&self) -> Value {
+        json!({
+            "port_id": self.port_id,
+            "channel": channel(&self.channel),
+            "signer": self.signer,
+        })
+    }
+}
+
+impl ToJson for MsgChannelOpenTry {
+    fn to_json(&self) -> Value {
+        json!({
+            "port_id": self.port_id,
+            "previous_channel_id": self.previous_channel_id,
+            "channel": channel(&self.channel),
+            "counterparty_version": self.counterparty_version,
+            "proof_init": bytes(&self.proof_init),
+            "proof_height": height(&self.proof_height),
+            "signer": self.signer,
+        })
+    }
+}
+
+impl ToJson for MsgChannelOpenAck {
+    fn to_json(&self) -> Value {
+        json!({
+            "port_id": self.port_id,
+            "channel_id": self.channel_id,
+            "counterparty_channel_id": self.counterparty_channel_id,
+            "counterparty_version": self.counterparty_version,
+            "proof_try": bytes(&self.proof_try),
+            "proof_height": height(&self.proof_height),
+            "signer": self.signer,
+        })
+    }
+}
+
+impl ToJson for MsgChannelOpenConfirm {
+    fn to_json(&self) -> Value {
+        json!({
+            "port_id": self.port_id,
+            "channel_id": self.channel_id,
+            "proof_ack": bytes(&self.proof_ack),
+            "proof_height": height(&self.proof_height),
+            "signer": self.signer,
+        })
+    }
+}
+
+impl ToJson for MsgChannelCloseInit {
+    fn to_json(&self) -> Value {
+        json!({
+            "port_id": self.port_id,
+            "channel_id": self.channel_id,
+            "signer": self.signer,
+        })
+    }
+}
+
+impl ToJson for MsgChannelCloseConfirm {
+    fn to_json(&self) -> Value {
+        json!({
+            "port_id": self.port_id,
+            "channel_id": self.channel_id,
+            "proof_init": bytes(&self.proof_init),
+            "proof_height": height(&self.proof_height),
+            "signer": self.signer,
+        })
+    }
+}
+
+impl ToJson for MsgRecvPacket {
+    fn to_json(&self) -> Value {
+        json!({
+            "packet": packet(&self.packet),
+            "proof_commitment": bytes(&self.proof_commitment),
+            "proof_height": height(&self.proof_height),
+            "signer": self.signer,
+        })
+    }
+}
+
+impl ToJson for MsgAcknowledgement {
+    fn to_json(&self) -> Value {
+        json!({
+            "packet": packet(&self.packet),
+            "acknowledgement": String::from_utf8(self.acknowledgement.clone())
+                .map_or_else(|_| bytes(&self.acknowledgement), Value::String),
+            "proof_acked": bytes(&self.proof_acked),
+            "proof_height": height(&self.proof_height),
+            "signer": self.signer,
+        })
+    }
+}
+
+impl ToJson for MsgTimeout {
+    fn to_json(&self) -> Value {
+        json!({
+            "packet": packet(&self.packet),
+            "proof_unreceived": bytes(&self.proof_unreceived),
+            "proof_height": height(&self.proof_height),
+            "next_sequence_recv": self.next_sequence_recv,
+            "signer": self.signer,
+        })
+    }
+}
+
+impl ToJson for MsgTimeoutOnClose {
+    fn to_json(&self) -> Value {
+        json!({
+            "packet": packet(&self.packet),
+            "proof_unreceived": bytes(&self.proof_unreceived),
+            "proof_close": bytes(&self.proof_close),
+            "proof_height": height(&self.proof_height),
+            "next_sequence_recv": self.next_sequence_recv,
+            "signer": self.signer,
+        })
+    }
+}
+
+impl ToJson for MsgTransfer {
+    fn to_json(&self) -> Value {
+        json!({
+            "source_port": self.source_port,
+            "source_channel": self.source_channel,
+            "token": self.token.as_ref().map(coin),
+            "sender": self.sender,
+            "receiver": self.receiver,
+            "timeout_height": height(&self.timeout_height),
+            "timeout_timestamp": self.timeout_timestamp,
+        })
+    }
+}
+
+impl ToJson for MsgSubmitProposal {
+    fn to_json(&self) -> Value {
+        json!({
+            "content": any(&self.content),
+            "initial_deposit": self.initial_deposit.iter().map(coin).collect::<Vec<_>>(),
+            "proposer": self.proposer,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::sync::Arc;
+
+    use prost_types::Any;
+    use serde_json::json;
+    use test_log::test;
+    use tokio::runtime::Runtime as TokioRuntime;
+
+    use ibc_proto::ibc::core::channel::v1::MsgChannelCloseInit;
+
+    use super::dry_run;
+    use crate::chain::handle::ProdChainHandle;
+    use crate::chain::mock::test_utils::get_basic_chain_config;
+    use crate::chain::mock::MockChain;
+    use crate::chain::runtime::ChainRuntime;
+
+    #[test]
+    fn dry_run_on_mock_chain() {
+        let cfg = get_basic_chain_config("chain_a");
+        let rt = Arc::new(TokioRuntime::new().unwrap());
+        let chain = ChainRuntime::<MockChain>::spawn::<ProdChainHandle>(cfg, rt).unwrap();
+
+        let close_init = MsgChannelCloseInit {
+            port_id: "transfer".to_string(),
+            channel_id: "channel-0".to_string(),
+            signer: "cosmos1sqnt9lp4en2asa5tu5vj2rfhs6xwhw5c3rglkv".to_string(),
+        };
+
+        let mut value = Vec::new();
+        prost::Message::encode(&close_init, &mut value).unwrap();
+
+        let msgs = vec![
+            Any {
+                type_url: "/ibc.core.channel.v1.MsgChannelCloseInit".to_string(),
+                value,
+            },
+            Any {
+                type_url: "/unknown.v1.Msg".to_string(),
+                value: vec![0xca, 0xfe],
+            },
+        ];
+
+        let report = dry_run(&chain, msgs.clone());
+
+        assert_eq!(report.messages.len(), 2);
+        assert_eq!(
+            report.messages[0].value,
+            json!({
+                "port_id": "transfer",
+                "channel_id": "channel-0",
+                "signer": "cosmos1sqnt9lp4en2asa5tu5vj2rfhs6xwhw5c3rglkv",
+            })
+        );
+        assert_eq!(report.messages[1].value, json!("cafe"));
+
+        assert!(report.simulation_error.is_none());
+        let simulation = report.simulation.expect("mock chain simulates messages");
+        assert!(simulation.gas_used > 0);
+        assert!(simulation.gas_limit >= simulation.gas_used);
+
+        // Simulations on the mock chain are deterministic
+        let again = dry_run(&chain, msgs).simulation.unwrap();
+        assert_eq!(again.gas_used, simulation.gas_used);
+        assert_eq!(again.fee, simulation.fee);
+
+        // Nothing to simulate
+        let empty = dry_run(&chain, vec![]);
+        assert!(empty.messages.is_empty());
+        assert!(empty.simulation.is_none());
+    }
+}
@@ -243,6 +243,10 @@ define_error! {
             { query: String }
             |e| { format!("Query error occurred (failed to query for {0})", e.query) },
 
+        MockUnsupported
+            { operation: String }
+            |e| { format!("Operation not supported by the mock chain: {0}", e.operation) },
+
         KeyBase
             [ KeyringError ]
             |_| { "Keybase error" },
@@ -272,6 +276,35 @@ define_error! {
             [ commitment_error::Error ]
             |_| { "ICS 23 error" },
 
+        QueryProofVerification
+            { chain_id: ChainId, path: String, height: u64 }
+            [ commitment_error::Error ]
+            |e| {
+                format!("failed to verify the proof of the query of '{}' at height {} on chain {}",
+                    e.path, e.height, e.chain_id)
+            },
+
+        QueryProofBlockNotCommitted
+            { chain_id: ChainId, height: u64 }
+            |e| {
+                format!("cannot verify the proof of a query on chain {}: the block at height {}, which commits to it, was not committed in time",
+                    e.chain_id, e.height)
+            },
+
+        QueryTrustAnchorMissing
+            { chain_id: ChainId }
+            |e| {
+                format!("cannot verify the proofs of the queries made to chain {}: no `query_trust_anchor` is configured for it",
+                    e.chain_id)
+            },
+
+        QueryTrustAnchorMismatch
+            { chain_id: ChainId, height: u64, reason: String }
+            |e| {
+                format!("the block at height {} of chain {} is not the configured `query_trust_anchor`: {}",
+                    e.height, e.chain_id, e.reason)
+            },
+
         InvalidUri
             { uri: String }
             [ TraceError<InvalidUri> ]
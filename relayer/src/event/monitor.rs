@@ -1,15 +1,20 @@
 use alloc::sync::Arc;
 use core::cmp::Ordering;
+use core::str::FromStr;
+use core::time::Duration;
+use std::thread;
+use std::time::Instant;
 
 use crossbeam_channel as channel;
 use futures::{
-    pin_mut,
+    future, pin_mut,
     stream::{self, select_all, StreamExt},
     Stream, TryStreamExt,
 };
+use serde::{Deserialize, Serialize};
 use tokio::task::JoinHandle;
 use tokio::{runtime::Runtime as TokioRuntime, sync::mpsc};
-use tracing::{debug, error, info, trace};
+use tracing::{debug, error, info, trace, warn};
 
 use tendermint_rpc::{
     event::Event as RpcEvent, query::Query, Error as RpcError, SubscriptionClient, Url,
@@ -20,14 +25,25 @@ use ibc::{
     core::ics02_client::height::Height, core::ics24_host::identifier::ChainId, events::IbcEvent,
 };
 
-use crate::util::{
-    retry::{retry_count, retry_with_index, RetryResult},
-    stream::try_group_while,
+use crate::{
+    config::EventSource,
+    event::rpc::SeenTxs,
+    telemetry,
+    util::{
+        retry::{retry_count, retry_with_index, RetryResult},
+        stream::try_group_while,
+    },
 };
 
 mod error;
 pub use error::*;
 
+mod batches;
+use batches::BatchSender;
+
+mod poll;
+use poll::NodeBlocks;
+
 pub type Result<T> = core::result::Result<T, Error>;
 
 mod retry_strategy {
@@ -45,8 +61,18 @@ mod retry_strategy {
     }
 }
 
-/// A batch of events from a chain at a specific height
+/// Interval between the attempts to reconnect to the WebSocket endpoint while polling the blocks
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The WebSocket and RPC addresses of a full node
 #[derive(Clone, Debug)]
+pub struct NodeAddr {
+    pub websocket_addr: Url,
+    pub rpc_addr: Url,
+}
+
+/// A batch of events from a chain at a specific height
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct EventBatch {
     pub chain_id: ChainId,
     pub height: Height,
@@ -72,26 +98,44 @@ pub enum MonitorCmd {
 /// The default events that are queried are:
 /// - [`EventType::NewBlock`](tendermint_rpc::query::EventType::NewBlock)
 /// - [`EventType::Tx`](tendermint_rpc::query::EventType::Tx)
+///
+/// When the WebSocket is unavailable, or when it does not deliver any event for a while,
+/// the monitor polls the `/block_results` of each height of the chain instead, until the
+/// WebSocket delivers events again.
 pub struct EventMonitor {
     chain_id: ChainId,
     /// WebSocket to collect events from
     client: WebSocketClient,
     /// Async task handle for the WebSocket client's driver
     driver_handle: JoinHandle<()>,
-    /// Channel to handler where the monitor for this chain sends the events
-    tx_batch: channel::Sender<Result<EventBatch>>,
+    /// Sends the events to the handler, backfilling the heights missed by the subscriptions
+    batches: BatchSender,
     /// Channel where to receive client driver errors
     rx_err: mpsc::UnboundedReceiver<tendermint_rpc::Error>,
     /// Channel where to send client driver errors
     tx_err: mpsc::UnboundedSender<tendermint_rpc::Error>,
     /// Channel where to receive commands
     rx_cmd: channel::Receiver<MonitorCmd>,
-    /// Node Address
+    /// Address of the node currently in use
     node_addr: Url,
+    /// Addresses of all the nodes, to which the monitor fails over in turn when reconnecting,
+    /// unless they are ranked by `node_ranking`
+    node_addrs: Vec<NodeAddr>,
+    /// Ranks the nodes from the healthiest to the least healthy, to pick the node
+    /// to fail over to when reconnecting
+    node_ranking: Option<Box<dyn Fn() -> Vec<NodeAddr> + Send>>,
     /// Queries
     event_queries: Vec<Query>,
     /// All subscriptions combined in a single stream
     subscriptions: Box<SubscriptionStream>,
+    /// Whether the subscriptions to the queries are active
+    subscribed: bool,
+    /// Configuration of the polling of the blocks
+    event_source: EventSource,
+    /// Blocks of the node currently in use
+    blocks: NodeBlocks,
+    /// The error which caused the WebSocket client to disconnect, if it was not yet handled
+    disconnect_error: Option<Error>,
     /// Tokio runtime
     rt: Arc<TokioRuntime>,
 }
@@ -112,6 +156,11 @@ pub mod queries {
         ]
     }
 
+    /// The queries for the transactions of the IBC client, connection and channel modules
+    pub fn ibc_modules() -> Vec<Query> {
+        vec![ibc_client(), ibc_connection(), ibc_channel()]
+    }
+
     pub fn new_block() -> Query {
         Query::from(EventType::NewBlock)
     }
@@ -130,43 +179,87 @@ pub mod queries {
 }
 
 impl EventMonitor {
-    /// Create an event monitor, and connect to a node
+    /// Create an event monitor, and connect to the first reachable node amongst `node_addrs`
     pub fn new(
         chain_id: ChainId,
-        node_addr: Url,
+        node_addrs: Vec<NodeAddr>,
+        event_source: EventSource,
         rt: Arc<TokioRuntime>,
     ) -> Result<(Self, EventReceiver, TxMonitorCmd)> {
         let (tx_batch, rx_batch) = channel::unbounded();
         let (tx_cmd, rx_cmd) = channel::unbounded();
 
-        let ws_addr = node_addr.clone();
-        let (client, driver) = rt
-            .block_on(async move { WebSocketClient::new(ws_addr).await })
-            .map_err(|_| Error::client_creation_failed(chain_id.clone(), node_addr.clone()))?;
+        let event_queries = event_source
+            .queries
+            .iter()
+            .map(|query| Query::from_str(query).map_err(|e| Error::invalid_query(query.clone(), e)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let (node_addr, client, driver) = node_addrs
+            .iter()
+            .map(|node| &node.websocket_addr)
+            .find_map(|node_addr| {
+                let ws_addr = node_addr.clone();
+                let (client, driver) = rt
+                    .block_on(async move { WebSocketClient::new(ws_addr).await })
+                    .map_err(|e| {
+                        debug!(
+                            "[{}] failed to connect to WebSocket endpoint {}: {}",
+                            chain_id, node_addr, e
+                        );
+                    })
+                    .ok()?;
+
+                Some((node_addr.clone(), client, driver))
+            })
+            .ok_or_else(|| {
+                Error::client_creation_failed(
+                    chain_id.clone(),
+                    node_addrs[0].websocket_addr.clone(),
+                )
+            })?;
 
         let (tx_err, rx_err) = mpsc::unbounded_channel();
         let websocket_driver_handle = rt.spawn(run_driver(driver, tx_err.clone()));
 
-        // TODO: move them to config file(?)
-        let event_queries = queries::all();
+        let rpc_addr = node_addrs
+            .iter()
+            .find(|node| node.websocket_addr == node_addr)
+            .map(|node| node.rpc_addr.clone());
 
         let monitor = Self {
+            batches: BatchSender::new(chain_id.clone(), tx_batch),
+            blocks: NodeBlocks::new(chain_id.clone(), rpc_addr, rt.clone()),
             rt,
             chain_id,
             client,
             driver_handle: websocket_driver_handle,
             event_queries,
-            tx_batch,
             rx_err,
             tx_err,
             rx_cmd,
             node_addr,
+            node_addrs,
+            node_ranking: None,
             subscriptions: Box::new(futures::stream::empty()),
+            subscribed: false,
+            event_source,
+            disconnect_error: None,
         };
 
         Ok((monitor, rx_batch, tx_cmd))
     }
 
+    /// Fail over to the healthiest node returned by `node_ranking` when reconnecting,
+    /// rather than to the next node in turn.
+    pub fn with_node_ranking(
+        mut self,
+        node_ranking: impl Fn() -> Vec<NodeAddr> + Send + 'static,
+    ) -> Self {
+        self.node_ranking = Some(Box::new(node_ranking));
+        self
+    }
+
     /// The list of [`Query`] that this event monitor is subscribing for.
     pub fn queries(&self) -> &[Query] {
         &self.event_queries
@@ -188,6 +281,7 @@ impl EventMonitor {
         }
 
         self.subscriptions = Box::new(select_all(subscriptions));
+        self.subscribed = true;
 
         trace!("[{}] subscribed to all queries", self.chain_id);
 
@@ -239,6 +333,44 @@ impl EventMonitor {
         Ok(())
     }
 
+    /// Switch to the healthiest other node if the nodes are ranked,
+    /// or else to the next node, if there are more than one
+    fn next_node(&mut self) {
+        let next = match &self.node_ranking {
+            Some(node_ranking) => node_ranking()
+                .into_iter()
+                .map(|node| node.websocket_addr)
+                .find(|addr| addr != &self.node_addr),
+            None => self.current_node().map(|index| {
+                self.node_addrs[(index + 1) % self.node_addrs.len()]
+                    .websocket_addr
+                    .clone()
+            }),
+        };
+
+        if let Some(next) = next {
+            if next != self.node_addr {
+                debug!(
+                    "[{}] failing over from WebSocket endpoint {} to {}",
+                    self.chain_id, self.node_addr, next
+                );
+
+                self.node_addr = next;
+
+                let rpc_addr = self
+                    .current_node()
+                    .map(|index| self.node_addrs[index].rpc_addr.clone());
+                self.blocks.switch_node(rpc_addr);
+            }
+        }
+    }
+
+    fn current_node(&self) -> Option<usize> {
+        self.node_addrs
+            .iter()
+            .position(|node| node.websocket_addr == self.node_addr)
+    }
+
     /// Try to resubscribe to events
     fn try_resubscribe(&mut self) -> Result<()> {
         trace!("[{}] trying to resubscribe to events", self.chain_id);
@@ -254,6 +386,7 @@ impl EventMonitor {
             // Try to reconnect
             if let Err(e) = self.try_reconnect() {
                 trace!("[{}] error when reconnecting: {}", self.chain_id, e);
+                self.next_node();
                 return RetryResult::Retry(());
             }
 
@@ -267,16 +400,45 @@ impl EventMonitor {
         });
 
         match result {
-            Ok(()) => info!(
-                "[{}] successfully reconnected to WebSocket endpoint {}",
-                self.chain_id, self.node_addr
-            ),
-            Err(retries) => error!(
-                "[{}] failed to reconnect to {} after {} retries",
-                self.chain_id,
-                self.node_addr,
-                retry_count(&retries)
-            ),
+            Ok(()) => {
+                info!(
+                    "[{}] successfully reconnected to WebSocket endpoint {}",
+                    self.chain_id, self.node_addr
+                );
+
+                self.resume();
+            }
+            Err(retries) => {
+                error!(
+                    "[{}] failed to reconnect to {} after {} retries",
+                    self.chain_id,
+                    self.node_addr,
+                    retry_count(&retries)
+                );
+
+                self.propagate_disconnect_error();
+            }
+        }
+    }
+
+    /// Backfills the events missed while the WebSocket client was disconnected, before the
+    /// live events are processed again. If they cannot be backfilled, the error which caused
+    /// the disconnection is propagated, so that the pending packets get cleared instead.
+    fn resume(&mut self) {
+        let max_backfill_blocks = self.event_source.max_backfill_blocks;
+
+        if self.batches.backfill(&mut self.blocks, max_backfill_blocks) {
+            self.disconnect_error = None;
+        } else {
+            self.propagate_disconnect_error();
+        }
+    }
+
+    fn propagate_disconnect_error(&mut self) {
+        if let Some(e) = self.disconnect_error.take() {
+            self.propagate_error(e).unwrap_or_else(|e| {
+                error!("[{}] {}", self.chain_id, e);
+            });
         }
     }
 
@@ -288,7 +450,13 @@ impl EventMonitor {
         // Continuously run the event loop, so that when it aborts
         // because of WebSocket client restart, we pick up the work again.
         loop {
-            match self.run_loop() {
+            let next = if self.subscribed {
+                self.run_loop()
+            } else {
+                self.reconnect_or_poll()
+            };
+
+            match next {
                 Next::Continue => continue,
                 Next::Abort => break,
             }
@@ -322,15 +490,25 @@ impl EventMonitor {
         // Work around double borrow
         let rt = self.rt.clone();
 
+        let poll_fallback = self.event_source.poll_fallback;
+
         loop {
             if let Ok(MonitorCmd::Shutdown) = self.rx_cmd.try_recv() {
                 return Next::Abort;
             }
 
+            // Poll the blocks if the WebSocket does not deliver any event for a while
+            let timeout = if self.batches.is_polling() {
+                self.event_source.poll_interval
+            } else {
+                self.event_source.lag_timeout
+            };
+
             let result = rt.block_on(async {
                 tokio::select! {
-                    Some(batch) = batches.next() => batch,
-                    Some(e) = self.rx_err.recv() => Err(Error::web_socket_driver(e)),
+                    Some(batch) = batches.next() => Some(batch),
+                    Some(e) = self.rx_err.recv() => Some(Err(Error::web_socket_driver(e))),
+                    _ = tokio::time::sleep(timeout), if poll_fallback => None,
                 }
             });
 
@@ -340,6 +518,21 @@ impl EventMonitor {
                 return Next::Abort;
             }
 
+            let result = match result {
+                Some(result) => result,
+                None => {
+                    if !self.batches.is_polling() {
+                        warn!(
+                            "[{}] no event received from WebSocket endpoint {} for {:?}, polling the blocks",
+                            self.chain_id, self.node_addr, self.event_source.lag_timeout
+                        );
+                    }
+
+                    self.poll_blocks();
+                    continue;
+                }
+            };
+
             match result {
                 Ok(batch) => self.process_batch(batch).unwrap_or_else(|e| {
                     error!("[{}] {}", self.chain_id, e);
@@ -352,12 +545,16 @@ impl EventMonitor {
                                 self.chain_id, reason
                             );
 
-                            self.propagate_error(e).unwrap_or_else(|e| {
-                                error!("[{}] {}", self.chain_id, e);
-                            });
+                            // Only propagated if the missed events cannot be backfilled after reconnecting
+                            self.disconnect_error = Some(e);
 
-                            // Reconnect to the WebSocket endpoint, and subscribe again to the queries.
-                            self.reconnect();
+                            // Reconnect to the WebSocket endpoint, and subscribe again to the queries,
+                            // or poll the blocks until then if the fallback is enabled.
+                            if poll_fallback {
+                                self.subscribed = false;
+                            } else {
+                                self.reconnect();
+                            }
 
                             // Abort this event loop, the `run` method will start a new one.
                             // We can't just write `return self.run()` here because Rust
@@ -368,8 +565,13 @@ impl EventMonitor {
                         _ => {
                             error!("[{}] failed to collect events: {}", self.chain_id, e);
 
-                            // Reconnect to the WebSocket endpoint, and subscribe again to the queries.
-                            self.reconnect();
+                            // Reconnect to the WebSocket endpoint, and subscribe again to the queries,
+                            // or poll the blocks until then if the fallback is enabled.
+                            if poll_fallback {
+                                self.subscribed = false;
+                            } else {
+                                self.reconnect();
+                            }
 
                             // Abort this event loop, the `run` method will start a new one.
                             // We can't just write `return self.run()` here because Rust
@@ -388,23 +590,82 @@ impl EventMonitor {
     /// The main use case for propagating RPC errors is for the [`Supervisor`]
     /// to notice that the WebSocket connection or subscription has been closed,
     /// and to trigger a clearing of packets, as this typically means that we have
-    /// missed a bunch of events which were emitted after the subscription was closed.
+    /// missed a bunch of events which were emitted after the subscription was closed,
+    /// and which could not be backfilled after reconnecting.
     /// In that case, this error will be handled in [`Supervisor::handle_batch`].
     fn propagate_error(&self, error: Error) -> Result<()> {
-        self.tx_batch
-            .send(Err(error))
-            .map_err(|_| Error::channel_send_failed())?;
+        self.batches.send_error(error)
+    }
 
-        Ok(())
+    /// Polls the blocks until the WebSocket client is reconnected and subscribed
+    /// again to the queries, trying to reconnect every [`RECONNECT_INTERVAL`].
+    fn reconnect_or_poll(&mut self) -> Next {
+        warn!(
+            "[{}] WebSocket endpoint {} is unavailable, polling the blocks",
+            self.chain_id, self.node_addr
+        );
+
+        let mut last_attempt: Option<Instant> = None;
+
+        loop {
+            if let Ok(MonitorCmd::Shutdown) = self.rx_cmd.try_recv() {
+                return Next::Abort;
+            }
+
+            if last_attempt.map_or(true, |t| t.elapsed() >= RECONNECT_INTERVAL) {
+                last_attempt = Some(Instant::now());
+
+                match self.try_reconnect().and_then(|_| self.try_resubscribe()) {
+                    Ok(()) => {
+                        info!(
+                            "[{}] successfully reconnected to WebSocket endpoint {}",
+                            self.chain_id, self.node_addr
+                        );
+
+                        self.resume();
+
+                        return Next::Continue;
+                    }
+                    Err(e) => {
+                        trace!("[{}] error when reconnecting: {}", self.chain_id, e);
+                        self.next_node();
+                    }
+                }
+            }
+
+            self.poll_blocks();
+
+            thread::sleep(self.event_source.poll_interval);
+        }
+    }
+
+    /// Collects the events of the blocks following the last height whose events were sent.
+    fn poll_blocks(&mut self) {
+        self.batches.poll(&mut self.blocks);
     }
 
     /// Collect the IBC events from the subscriptions
-    fn process_batch(&self, batch: EventBatch) -> Result<()> {
-        self.tx_batch
-            .send(Ok(batch))
-            .map_err(|_| Error::channel_send_failed())?;
+    fn process_batch(&mut self, batch: EventBatch) -> Result<()> {
+        self.batches.process_batch(batch, &mut self.blocks)
+    }
+}
 
-        Ok(())
+/// Record the time at which a `NewBlock` event was last seen for the chain
+#[cfg(feature = "telemetry")]
+fn new_block_metrics(batch: &EventBatch) {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    let has_new_block = batch
+        .events
+        .iter()
+        .any(|e| matches!(e, IbcEvent::NewBlock(_)));
+
+    if has_new_block {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+
+        telemetry!(chain_last_new_block, &batch.chain_id, now);
     }
 }
 
@@ -423,9 +684,11 @@ fn stream_batches(
     chain_id: ChainId,
 ) -> impl Stream<Item = Result<EventBatch>> {
     let id = chain_id.clone();
+    let mut seen_txs = SeenTxs::default();
 
-    // Collect IBC events from each RPC event
+    // Collect IBC events from each RPC event, once per transaction
     let events = subscriptions
+        .try_filter(move |rpc_event| future::ready(seen_txs.is_new(rpc_event)))
         .map_ok(move |rpc_event| collect_events(&id, rpc_event))
         .map_err(Error::canceled_or_generic)
         .try_flatten();
@@ -0,0 +1,345 @@
+//! Sending of the event batches of a chain in order of height, at most once per height,
+//! collecting the events of the heights missed by the subscriptions from the blocks.
+
+use crossbeam_channel as channel;
+use tracing::{error, info, warn};
+
+use ibc::core::ics24_host::identifier::ChainId;
+
+use super::poll::{BlockSource, MAX_BLOCKS_PER_POLL};
+use super::{Error, EventBatch, Result};
+use crate::telemetry;
+
+pub struct BatchSender {
+    chain_id: ChainId,
+    /// Channel to handler where the monitor for this chain sends the events
+    tx_batch: channel::Sender<Result<EventBatch>>,
+    /// Whether the last events were collected from the blocks rather than from the WebSocket,
+    /// in which case the events of the WebSocket at the heights already collected are skipped
+    polling: bool,
+    /// The last height whose events were sent
+    last_height: Option<u64>,
+}
+
+impl BatchSender {
+    pub fn new(chain_id: ChainId, tx_batch: channel::Sender<Result<EventBatch>>) -> Self {
+        Self {
+            chain_id,
+            tx_batch,
+            polling: false,
+            last_height: None,
+        }
+    }
+
+    pub fn is_polling(&self) -> bool {
+        self.polling
+    }
+
+    /// Collects the events of the heights following the last height whose events were sent,
+    /// up to the latest height, sending a single batch per height in order.
+    /// Returns whether all the missed heights were collected, which is not attempted
+    /// if there are more than `max_backfill_blocks` of them.
+    pub fn backfill(&mut self, blocks: &mut impl BlockSource, max_backfill_blocks: u64) -> bool {
+        let last_height = match self.last_height {
+            Some(last_height) => last_height,
+            None => return true,
+        };
+
+        let result = blocks.latest_height().and_then(|latest_height| {
+            let missed = latest_height.saturating_sub(last_height);
+
+            if missed > max_backfill_blocks {
+                return Ok(Err(missed));
+            }
+
+            blocks
+                .fetch_range(last_height + 1, latest_height + 1)
+                .map(Ok)
+        });
+
+        match result {
+            Ok(Ok(batches)) => {
+                if !batches.is_empty() {
+                    info!(
+                        "[{}] backfilling the events of the {} heights missed since height {}",
+                        self.chain_id,
+                        batches.len(),
+                        last_height
+                    );
+                }
+
+                for batch in batches {
+                    self.send_batch(batch).unwrap_or_else(|e| {
+                        error!("[{}] {}", self.chain_id, e);
+                    });
+                }
+
+                // Skip the live events at the heights which were just backfilled
+                self.polling = true;
+
+                true
+            }
+            Ok(Err(missed)) => {
+                warn!(
+                    "[{}] not backfilling the events of the {} heights missed since height {}, more than the maximum of {}",
+                    self.chain_id, missed, last_height, max_backfill_blocks
+                );
+
+                false
+            }
+            Err(e) => {
+                error!(
+                    "[{}] failed to backfill the missed events: {}",
+                    self.chain_id, e
+                );
+
+                false
+            }
+        }
+    }
+
+    /// Collects the events of the blocks following the last height whose events were sent,
+    /// up to the latest height or up to [`MAX_BLOCKS_PER_POLL`] heights.
+    /// Only the latest height is collected if no events were sent yet.
+    pub fn poll(&mut self, blocks: &mut impl BlockSource) {
+        self.polling = true;
+
+        let last_height = self.last_height;
+
+        let result = blocks.latest_height().and_then(|latest| {
+            let from = last_height.map_or(latest, |h| h + 1);
+            let to = core::cmp::min(latest + 1, from + MAX_BLOCKS_PER_POLL);
+
+            blocks.fetch_range(from, to)
+        });
+
+        match result {
+            Ok(batches) => {
+                for batch in batches {
+                    self.send_batch(batch).unwrap_or_else(|e| {
+                        error!("[{}] {}", self.chain_id, e);
+                    });
+                }
+            }
+            Err(e) => error!("[{}] failed to poll the blocks: {}", self.chain_id, e),
+        }
+    }
+
+    /// Sends a batch collected from the subscriptions, unless its events were already
+    /// collected from the blocks, collecting first the events of the heights between
+    /// the last polled height and the height of the batch.
+    pub fn process_batch(
+        &mut self,
+        batch: EventBatch,
+        blocks: &mut impl BlockSource,
+    ) -> Result<()> {
+        if self.polling {
+            let height = batch.height.revision_height;
+
+            match self.last_height {
+                // The events at this height were already collected by polling the blocks
+                Some(last_height) if height <= last_height => return Ok(()),
+
+                // Collect the events of the heights between the last polled one and this one
+                Some(last_height) if height > last_height + 1 => {
+                    for batch in blocks.fetch_range(last_height + 1, height)? {
+                        self.send_batch(batch)?;
+                    }
+                }
+
+                _ => {}
+            }
+
+            info!(
+                "[{}] receiving events from the WebSocket endpoint again, stopped polling the blocks",
+                self.chain_id
+            );
+
+            self.polling = false;
+        }
+
+        self.send_batch(batch)
+    }
+
+    /// Propagate error to subscribers.
+    pub fn send_error(&self, error: Error) -> Result<()> {
+        self.tx_batch
+            .send(Err(error))
+            .map_err(|_| Error::channel_send_failed())?;
+
+        Ok(())
+    }
+
+    fn send_batch(&mut self, batch: EventBatch) -> Result<()> {
+        telemetry!(super::new_block_metrics(&batch));
+
+        self.last_height = self.last_height.max(Some(batch.height.revision_height));
+
+        self.tx_batch
+            .send(Ok(batch))
+            .map_err(|_| Error::channel_send_failed())?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ibc::core::ics02_client::height::Height;
+
+    const MAX_BACKFILL_BLOCKS: u64 = 10;
+
+    /// Blocks without events, recording the ranges of heights fetched
+    struct StubBlocks {
+        latest_height: u64,
+        fetched: Vec<(u64, u64)>,
+    }
+
+    impl StubBlocks {
+        fn new(latest_height: u64) -> Self {
+            Self {
+                latest_height,
+                fetched: Vec::new(),
+            }
+        }
+    }
+
+    impl BlockSource for StubBlocks {
+        fn latest_height(&mut self) -> Result<u64> {
+            Ok(self.latest_height)
+        }
+
+        fn fetch_range(&mut self, from: u64, to: u64) -> Result<Vec<EventBatch>> {
+            self.fetched.push((from, to));
+            Ok((from..to).map(batch).collect())
+        }
+    }
+
+    fn chain_id() -> ChainId {
+        ChainId::new("chain_a".to_string(), 0)
+    }
+
+    fn batch(height: u64) -> EventBatch {
+        EventBatch {
+            chain_id: chain_id(),
+            height: Height::new(0, height),
+            events: vec![],
+        }
+    }
+
+    fn received(rx_batch: &channel::Receiver<Result<EventBatch>>) -> Vec<u64> {
+        rx_batch
+            .try_iter()
+            .map(|batch| batch.unwrap().height.revision_height)
+            .collect()
+    }
+
+    /// A sender which already sent the live batch at `height`
+    fn sender_at(height: u64) -> (BatchSender, channel::Receiver<Result<EventBatch>>) {
+        let (tx_batch, rx_batch) = channel::unbounded();
+        let mut sender = BatchSender::new(chain_id(), tx_batch);
+
+        sender
+            .process_batch(batch(height), &mut StubBlocks::new(height))
+            .unwrap();
+        assert_eq!(received(&rx_batch), vec![height]);
+
+        (sender, rx_batch)
+    }
+
+    #[test]
+    fn backfill_sends_missed_heights_in_order() {
+        let (mut sender, rx_batch) = sender_at(5);
+        let mut blocks = StubBlocks::new(8);
+
+        assert!(sender.backfill(&mut blocks, MAX_BACKFILL_BLOCKS));
+
+        assert_eq!(blocks.fetched, vec![(6, 9)]);
+        assert_eq!(received(&rx_batch), vec![6, 7, 8]);
+        assert!(sender.is_polling());
+    }
+
+    #[test]
+    fn backfill_before_any_batch_is_not_needed() {
+        let (tx_batch, rx_batch) = channel::unbounded();
+        let mut sender = BatchSender::new(chain_id(), tx_batch);
+        let mut blocks = StubBlocks::new(8);
+
+        assert!(sender.backfill(&mut blocks, MAX_BACKFILL_BLOCKS));
+
+        assert!(blocks.fetched.is_empty());
+        assert!(received(&rx_batch).is_empty());
+    }
+
+    #[test]
+    fn backfill_is_refused_above_max_backfill_blocks() {
+        let (mut sender, rx_batch) = sender_at(5);
+        let mut blocks = StubBlocks::new(5 + MAX_BACKFILL_BLOCKS + 1);
+
+        assert!(!sender.backfill(&mut blocks, MAX_BACKFILL_BLOCKS));
+
+        assert!(blocks.fetched.is_empty());
+        assert!(received(&rx_batch).is_empty());
+        assert!(!sender.is_polling());
+    }
+
+    #[test]
+    fn live_batches_already_backfilled_are_skipped() {
+        let (mut sender, rx_batch) = sender_at(5);
+        let mut blocks = StubBlocks::new(8);
+
+        assert!(sender.backfill(&mut blocks, MAX_BACKFILL_BLOCKS));
+        assert_eq!(received(&rx_batch), vec![6, 7, 8]);
+
+        sender.process_batch(batch(7), &mut blocks).unwrap();
+        sender.process_batch(batch(8), &mut blocks).unwrap();
+        assert!(received(&rx_batch).is_empty());
+        assert!(sender.is_polling());
+
+        sender.process_batch(batch(9), &mut blocks).unwrap();
+        assert_eq!(received(&rx_batch), vec![9]);
+        assert!(!sender.is_polling());
+
+        assert_eq!(blocks.fetched, vec![(6, 9)]);
+    }
+
+    #[test]
+    fn gap_before_live_batch_is_fetched() {
+        let (mut sender, rx_batch) = sender_at(5);
+        let mut blocks = StubBlocks::new(8);
+
+        assert!(sender.backfill(&mut blocks, MAX_BACKFILL_BLOCKS));
+        assert_eq!(received(&rx_batch), vec![6, 7, 8]);
+
+        sender.process_batch(batch(11), &mut blocks).unwrap();
+
+        assert_eq!(blocks.fetched, vec![(6, 9), (9, 11)]);
+        assert_eq!(received(&rx_batch), vec![9, 10, 11]);
+        assert!(!sender.is_polling());
+
+        // Live batches are sent as they come once the gap is filled
+        sender.process_batch(batch(12), &mut blocks).unwrap();
+        assert_eq!(received(&rx_batch), vec![12]);
+        assert_eq!(blocks.fetched.len(), 2);
+    }
+
+    #[test]
+    fn poll_catches_up_by_bounded_ranges() {
+        let (mut sender, rx_batch) = sender_at(5);
+        let mut blocks = StubBlocks::new(5 + MAX_BLOCKS_PER_POLL + 10);
+
+        sender.poll(&mut blocks);
+        assert!(sender.is_polling());
+        assert_eq!(blocks.fetched, vec![(6, 6 + MAX_BLOCKS_PER_POLL)]);
+        assert_eq!(received(&rx_batch).len() as u64, MAX_BLOCKS_PER_POLL);
+
+        sender.poll(&mut blocks);
+        assert_eq!(
+            blocks.fetched[1],
+            (6 + MAX_BLOCKS_PER_POLL, 16 + MAX_BLOCKS_PER_POLL)
+        );
+        assert_eq!(received(&rx_batch).len(), 10);
+    }
+}
@@ -11,6 +11,11 @@ define_error! {
             [ TraceError<RpcError> ]
             |_| { "WebSocket driver failed" },
 
+        InvalidQuery
+            { query: String }
+            [ TraceError<RpcError> ]
+            |e| { format!("invalid event query '{0}'", e.query) },
+
         ClientCreationFailed
             { chain_id: ChainId, address: Url }
             |e| { format!("failed to create WebSocket driver for chain {0} with address {1}", e.chain_id, e.address) },
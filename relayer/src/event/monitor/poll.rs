@@ -0,0 +1,230 @@
+//! Collection of the events of a chain by polling the `/block_results` of each height,
+//! used when the WebSocket subscriptions are unavailable or lagging.
+
+use alloc::sync::Arc;
+
+use tendermint::block::Height as BlockHeight;
+use tendermint_rpc::{Client, HttpClient, Url};
+use tokio::runtime::Runtime as TokioRuntime;
+use tracing::trace;
+
+use ibc::core::{ics02_client::height::Height, ics24_host::identifier::ChainId};
+
+use super::{sort_events, Error, EventBatch, Result};
+use crate::event::rpc::get_block_results_events;
+
+/// Maximum number of blocks fetched by a single poll, so that the
+/// monitor does not stop checking for commands while catching up
+pub const MAX_BLOCKS_PER_POLL: u64 = 100;
+
+/// The blocks of a chain, from which the events of the heights missed
+/// by the subscriptions are collected
+pub trait BlockSource {
+    fn latest_height(&mut self) -> Result<u64>;
+
+    /// Fetches the batches of the heights in the range `[from, to)`, in order
+    fn fetch_range(&mut self, from: u64, to: u64) -> Result<Vec<EventBatch>>;
+}
+
+/// The blocks of the node currently in use, polled through a [`BlockPoller`] created on first use
+pub struct NodeBlocks {
+    chain_id: ChainId,
+    /// RPC address of the node currently in use, if known
+    rpc_addr: Option<Url>,
+    poller: Option<BlockPoller>,
+    rt: Arc<TokioRuntime>,
+}
+
+impl NodeBlocks {
+    pub fn new(chain_id: ChainId, rpc_addr: Option<Url>, rt: Arc<TokioRuntime>) -> Self {
+        Self {
+            chain_id,
+            rpc_addr,
+            poller: None,
+            rt,
+        }
+    }
+
+    /// Switch to the node at `rpc_addr`, dropping the poller of the previous node
+    pub fn switch_node(&mut self, rpc_addr: Option<Url>) {
+        self.rpc_addr = rpc_addr;
+        self.poller = None;
+    }
+
+    fn poller(&mut self) -> Result<&BlockPoller> {
+        if self.poller.is_none() {
+            let rpc_addr = self
+                .rpc_addr
+                .clone()
+                .ok_or_else(|| Error::collect_events_failed("unknown node".to_string()))?;
+
+            self.poller = Some(BlockPoller::new(self.chain_id.clone(), rpc_addr)?);
+        }
+
+        Ok(self.poller.as_ref().expect("poller was just created"))
+    }
+}
+
+impl BlockSource for NodeBlocks {
+    fn latest_height(&mut self) -> Result<u64> {
+        let rt = self.rt.clone();
+        rt.block_on(self.poller()?.latest_height())
+    }
+
+    fn fetch_range(&mut self, from: u64, to: u64) -> Result<Vec<EventBatch>> {
+        let rt = self.rt.clone();
+        rt.block_on(self.poller()?.fetch_range(from, to))
+    }
+}
+
+pub struct BlockPoller {
+    chain_id: ChainId,
+    rpc_addr: Url,
+    client: HttpClient,
+}
+
+impl BlockPoller {
+    pub fn new(chain_id: ChainId, rpc_addr: Url) -> Result<Self> {
+        let client = HttpClient::new(rpc_addr.clone()).map_err(Error::rpc)?;
+
+        Ok(Self {
+            chain_id,
+            rpc_addr,
+            client,
+        })
+    }
+
+    /// Fetches the batches of the heights in the range `[from, to)`, in order
+    fn fetch_range(&mut self, from: u64, to: u64) -> Result<Vec<EventBatch>>;
+}
+
+/// The blocks of the node currently in use, polled through a [`BlockPoller`] created on first use
+pub struct NodeBlocks {
+    chain_id: ChainId,
+    /// RPC address of the node currently in use, if known
+    rpc_addr: Option<Url>,
+    poller: Option<BlockPoller>,
+    rt: Arc<TokioRuntime>,
+}
+
+impl NodeBlocks {
+    pub fn new(chain_id: ChainId, rpc_addr: Option<Url>, rt: Arc<TokioRuntime>) -> Self {
+        Self {
+            chain_id,
+            rpc_addr,
+            poller: None,
+            rt,
+        }
+    }
+
+    /// Switch to the node at `rpc_addr`, dropping the poller of the previous node
+    pub fn switch_node(&mut self, rpc_addr: Option<Url>) {
+        self.rpc_addr = rpc_addr;
+        self.poller = None;
+    }
+
+    fn poller(&mut self) -> Result<&BlockPoller> {
+        if self.poller.is_none() {
+            let rpc_addr = self
+                .rpc_addr
+                .clone()
+                .ok_or_else(|| Error::collect_events_failed("unknown node".to_string()))?;
+
+            self.poller = Some(BlockPoller::new(self.chain_id.clone(), rpc_addr)?);
+        }
+
+        Ok(self.poller.as_ref().expect("poller was just created"))
+    }
+}
+
+impl BlockSource for NodeBlocks {
+    fn latest_height(&mut self) -> Result<u64> {
+        let rt = self.rt.clone();
+        rt.block_on(self.poller()?.latest_height())
+    }
+
+    fn fetch_range(&mut self, from: u64, to: u64) -> Result<Vec<EventBatch>> {
+        let rt = self.rt.clone();
+        rt.block_on(self.poller()?.fetch_range(from, to))
+    }
+}
+
+pub struct BlockPoller {
+    chain_id: ChainId,
+    rpc_addr: Url,
+    client: HttpClient,
+}
+
+impl BlockPoller {
+    pub fn new(chain_id: ChainId, rpc_addr: Url) -> Result<Self> {
+        let client = HttpClient::new(rpc_addr.clone()).map_err(Error::rpc)?;
+
+        Ok(Self {
+            chain_id,
+            rpc_addr,
+            client,
+        })
+    }
+
+    /// Fetches the batches of the heights following `last_height`, the last height whose
+    /// events were collected, up to the latest height of the chain or up to
+    /// [`MAX_BLOCKS_PER_POLL`] heights. Only the latest height is fetched if `last_height` is `None`.
+    pub async fn poll(&self, last_height: Option<u64>) -> Result<Vec<EventBatch>> {
+        let latest = self.latest_height().await?;
+        let from = last_height.map_or(latest, |h| h + 1);
+        let to = core::cmp::min(latest + 1, from + MAX_BLOCKS_PER_POLL);
+
+        self.fetch_range(from, to).await
+    }
+
+    /// Fetches the batches of the heights in the range `[from, to)`, in order,
+    /// failing if any of them cannot be fetched so that no height is skipped.
+    pub async fn fetch_range(&self, from: u64, to: u64) -> Result<Vec<EventBatch>> {
+        let mut batches = Vec::new();
+
+        for height in from..to {
+            batches.push(self.fetch(height).await?);
+        }
+
+        Ok(batches)
+    }
+
+    async fn fetch(&self, height: u64) -> Result<EventBatch> {
+        trace!(
+            "[{}] fetching the block results at height {} from {}",
+            self.chain_id,
+            height,
+            self.rpc_addr
+        );
+
+        let block_height = BlockHeight::try_from(height)
+            .map_err(|_| Error::collect_events_failed(format!("invalid height {}", height)))?;
+
+        let results = self
+            .client
+            .block_results(block_height)
+            .await
+            .map_err(Error::rpc)?;
+
+        let height = Height::new(self.chain_id.version(), height);
+
+        let mut events = get_block_results_events(height, &results)
+            .into_iter()
+            .map(|(_, e)| e)
+            .collect();
+
+        sort_events(&mut events);
+
+        Ok(EventBatch {
+            chain_id: self.chain_id.clone(),
+            height,
+            events,
+        })
+    }
+
+    pub async fn latest_height(&self) -> Result<u64> {
+        let status = self.client.status().await.map_err(Error::rpc)?;
+
+        Ok(status.sync_info.latest_block_height.value())
+    }
+}
@@ -0,0 +1,197 @@
+//! Recording of the event batches received from the chains to a file, one JSON
+//! object per line, so that the decisions of the relayer can be replayed offline.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use flex_error::{define_error, TraceError};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use ibc::timestamp::Timestamp;
+
+use crate::event::monitor::EventBatch;
+
+define_error! {
+    RecordingError {
+        Io
+            { path: PathBuf }
+            [ TraceError<io::Error> ]
+            |e| { format!("I/O error on the recording at {}", e.path.display()) },
+
+        Decode
+            { path: PathBuf, line: usize }
+            [ TraceError<serde_json::Error> ]
+            |e| {
+                format!("invalid event batch at line {} of the recording at {}",
+                    e.line, e.path.display())
+            },
+    }
+}
+
+/// An event batch, as recorded when it was received
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RecordedBatch {
+    /// When the batch was received
+    pub recorded_at: Timestamp,
+    pub batch: EventBatch,
+}
+
+/// Appends the event batches it is given to a recording file
+pub struct EventRecorder {
+    path: PathBuf,
+    file: File,
+}
+
+impl EventRecorder {
+    /// Opens the recording at `path` for appending, creating it if it does not exist.
+    pub fn create(path: &Path) -> Result<Self, RecordingError> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| RecordingError::io(path.to_path_buf(), e))?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+        })
+    }
+
+    /// Records the given batch. Failures are only logged, so that
+    /// the recording never gets in the way of relaying.
+    pub fn record(&mut self, batch: &EventBatch) {
+        let recorded = RecordedBatch {
+            recorded_at: Timestamp::now(),
+            batch: batch.clone(),
+        };
+
+        let res = serde_json::to_string(&recorded)
+            .map_err(|e| e.to_string())
+            .and_then(|json| writeln!(self.file, "{}", json).map_err(|e| e.to_string()));
+
+        if let Err(e) = res {
+            warn!(
+                "[{}] failed to record the event batch at height {} to {}, reason: {}",
+                batch.chain_id,
+                batch.height,
+                self.path.display(),
+                e
+            );
+        }
+    }
+}
+
+/// Reads all the batches of the recording at `path`, in the order they were recorded.
+pub fn read_recording(path: &Path) -> Result<Vec<RecordedBatch>, RecordingError> {
+    let file = File::open(path).map_err(|e| RecordingError::io(path.to_path_buf(), e))?;
+
+    let mut batches = Vec::new();
+
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| RecordingError::io(path.to_path_buf(), e))?;
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let batch = serde_json::from_str(&line)
+            .map_err(|e| RecordingError::decode(path.to_path_buf(), i + 1, e))?;
+
+        batches.push(batch);
+    }
+
+    Ok(batches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+
+    use ibc::core::ics02_client::events::NewBlock;
+    use ibc::core::ics04_channel::events::SendPacket;
+    use ibc::core::ics04_channel::packet::Packet;
+    use ibc::core::ics24_host::identifier::{ChainId, ChannelId, PortId};
+    use ibc::events::IbcEvent;
+    use ibc::Height;
+
+    fn batch(height: u64, events: Vec<IbcEvent>) -> EventBatch {
+        EventBatch {
+            chain_id: ChainId::new("chain_a".to_string(), 0),
+            height: Height::new(0, height),
+            events,
+        }
+    }
+
+    fn send_packet(height: u64, sequence: u64) -> IbcEvent {
+        IbcEvent::SendPacket(SendPacket {
+            height: Height::new(0, height),
+            packet: Packet {
+                sequence: sequence.into(),
+                source_port: PortId::transfer(),
+                source_channel: ChannelId::new(0),
+                destination_port: PortId::transfer(),
+                destination_channel: ChannelId::new(1),
+                data: b"packet data".to_vec(),
+                timeout_height: Height::new(0, 100),
+                timeout_timestamp: Timestamp::none(),
+            },
+        })
+    }
+
+    #[test]
+    fn recorded_batches_are_read_back_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("recording.json");
+
+        let batches = vec![
+            batch(
+                1,
+                vec![IbcEvent::NewBlock(NewBlock::new(Height::new(0, 1)))],
+            ),
+            batch(2, vec![send_packet(2, 1), send_packet(2, 2)]),
+            batch(3, vec![]),
+        ];
+
+        let mut recorder = EventRecorder::create(&path).unwrap();
+        recorder.record(&batches[0]);
+        recorder.record(&batches[1]);
+        drop(recorder);
+
+        // A recording is appended to when opened again
+        let mut recorder = EventRecorder::create(&path).unwrap();
+        recorder.record(&batches[2]);
+        drop(recorder);
+
+        let recorded = read_recording(&path).unwrap();
+        assert_eq!(recorded.len(), batches.len());
+
+        for (recorded, batch) in recorded.iter().zip(&batches) {
+            assert_eq!(recorded.batch.chain_id, batch.chain_id);
+            assert_eq!(recorded.batch.height, batch.height);
+            assert_eq!(recorded.batch.events, batch.events);
+        }
+    }
+
+    #[test]
+    fn invalid_batches_are_reported_by_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("recording.json");
+
+        let mut recorder = EventRecorder::create(&path).unwrap();
+        recorder.record(&batch(1, vec![]));
+        drop(recorder);
+
+        let mut contents = fs::read_to_string(&path).unwrap();
+        contents.push_str("\n{\"not\": \"a batch\"}\n");
+        fs::write(&path, contents).unwrap();
+
+        match read_recording(&path) {
+            Err(RecordingError(RecordingErrorDetail::Decode(e), _)) => assert_eq!(e.line, 3),
+            other => panic!("unexpected result: {:?}", other.map(|r| r.len())),
+        }
+    }
+}
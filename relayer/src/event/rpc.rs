@@ -1,14 +1,13 @@
-use alloc::collections::BTreeMap as HashMap;
+use alloc::collections::{BTreeMap as HashMap, BTreeSet};
 
+use tendermint::abci::Event as AbciEvent;
+use tendermint_rpc::endpoint::block_results;
 use tendermint_rpc::{event::Event as RpcEvent, event::EventData as RpcEventData};
 
 use ibc::core::ics02_client::{events as ClientEvents, height::Height};
-use ibc::core::ics03_connection::events as ConnectionEvents;
 use ibc::core::ics04_channel::events as ChannelEvents;
 use ibc::core::ics24_host::identifier::ChainId;
-use ibc::events::{IbcEvent, RawObject};
-
-use crate::event::monitor::queries;
+use ibc::events::{from_tx_response_event, IbcEvent, RawObject};
 
 pub fn get_all_events(
     chain_id: &ChainId,
@@ -39,30 +38,10 @@ pub fn get_all_events(
                 tx_result.height as u64,
             );
 
-            for abci_event in &tx_result.result.events {
-                let query = &result.query;
-                if *query == queries::ibc_client().to_string() {
-                    if let Some(mut client_event) = ClientEvents::try_from_tx(abci_event) {
-                        client_event.set_height(height);
-                        tracing::trace!("extracted ibc_client event {:?}", client_event);
-                        vals.push((height, client_event));
-                    }
-                }
-                if *query == queries::ibc_connection().to_string() {
-                    if let Some(mut conn_event) = ConnectionEvents::try_from_tx(abci_event) {
-                        conn_event.set_height(height);
-                        tracing::trace!("extracted ibc_connection event {:?}", conn_event);
-                        vals.push((height, conn_event));
-                    }
-                }
-                if *query == queries::ibc_channel().to_string() {
-                    if let Some(mut chan_event) = ChannelEvents::try_from_tx(abci_event) {
-                        chan_event.set_height(height);
-                        tracing::trace!("extracted ibc_channel event {:?}", chan_event);
-                        vals.push((height, chan_event));
-                    }
-                }
-            }
+            // A transaction is delivered once for each of the subscribed queries it matches,
+            // all its IBC events are extracted each time, and the duplicates are skipped
+            // by the event monitor with `SeenTxs`.
+            vals.extend(tx_events(height, &tx_result.result.events));
         }
         _ => {}
     }
@@ -70,6 +49,102 @@ pub fn get_all_events(
     Ok(vals)
 }
 
+/// Collects the IBC events of the block at the given height from its `/block_results`,
+/// in the same way as they are collected from the `NewBlock` and `Tx` events of the subscriptions.
+pub fn get_block_results_events(
+    height: Height,
+    results: &block_results::Response,
+) -> Vec<(Height, IbcEvent)> {
+    let mut vals: Vec<(Height, IbcEvent)> =
+        vec![(height, ClientEvents::NewBlock::new(height).into())];
+
+    let block_events = results
+        .begin_block_events
+        .iter()
+        .chain(results.end_block_events.iter())
+        .flatten();
+
+    vals.extend(send_packet_from_block_events(
+        height,
+        flatten_block_events(block_events),
+    ));
+
+    for tx_result in results.txs_results.iter().flatten() {
+        vals.extend(tx_events(height, &tx_result.events));
+    }
+
+    vals
+}
+
+/// Extracts the IBC events of a transaction.
+fn tx_events(height: Height, events: &[AbciEvent]) -> Vec<(Height, IbcEvent)> {
+    events
+        .iter()
+        .filter_map(|abci_event| from_tx_response_event(height, abci_event))
+        .inspect(|event| tracing::trace!("extracted ibc event {:?}", event))
+        .map(|event| (height, event))
+        .collect()
+}
+
+/// Number of heights for which the hashes of the transactions already delivered are kept,
+/// as the subscriptions may deliver a transaction after the next block
+const SEEN_TXS_HEIGHTS: u64 = 3;
+
+/// The transactions whose events were already collected at the latest heights.
+///
+/// A transaction is delivered by each of the subscriptions whose query it matches, e.g. by
+/// both a user query and the default `ibc_client` query, but its events are collected once.
+#[derive(Debug, Default)]
+pub struct SeenTxs {
+    hashes: HashMap<u64, BTreeSet<String>>,
+}
+
+impl SeenTxs {
+    /// Returns whether the events of the given RPC event must be collected,
+    /// i.e. unless it is a transaction which was already delivered.
+    pub fn is_new(&mut self, event: &RpcEvent) -> bool {
+        let tx_result = match &event.data {
+            RpcEventData::Tx { tx_result } => tx_result,
+            _ => return true,
+        };
+
+        let hash = match event
+            .events
+            .as_ref()
+            .and_then(|events| events.get("tx.hash"))
+            .and_then(|hashes| hashes.first())
+        {
+            Some(hash) => hash.clone(),
+            None => return true,
+        };
+
+        let height = tx_result.height as u64;
+
+        self.hashes.retain(|h, _| h + SEEN_TXS_HEIGHTS > height);
+
+        self.hashes.entry(height).or_default().insert(hash)
+    }
+}
+
+/// Indexes the attributes of the given events by `{type}.{key}`,
+/// as done for the events of a `NewBlock` event.
+fn flatten_block_events<'a>(
+    events: impl Iterator<Item = &'a AbciEvent>,
+) -> HashMap<String, Vec<String>> {
+    let mut flattened: HashMap<String, Vec<String>> = HashMap::new();
+
+    for event in events {
+        for tag in &event.attributes {
+            flattened
+                .entry(format!("{}.{}", event.type_str, tag.key.as_ref()))
+                .or_default()
+                .push(tag.value.as_ref().to_string());
+        }
+    }
+
+    flattened
+}
+
 fn send_packet_from_block_events(
     height: Height,
     events: HashMap<String, Vec<String>>,
@@ -86,3 +161,170 @@ fn send_packet_from_block_events(
     }
     vals
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap as StdHashMap;
+
+    use ibc::core::ics02_client::events::{Attributes as ClientAttributes, UpdateClient};
+    use ibc::core::ics04_channel::events::SendPacket;
+    use ibc::core::ics04_channel::packet::{Packet, Sequence};
+    use ibc::core::ics24_host::identifier::{ChannelId, ClientId, PortId};
+    use tendermint_rpc::event::{TxInfo, TxResult};
+
+    fn height() -> Height {
+        Height::new(0, 10)
+    }
+
+    fn update_client() -> AbciEvent {
+        let event = UpdateClient::from(ClientAttributes {
+            height: height(),
+            client_id: ClientId::default(),
+            ..Default::default()
+        });
+
+        AbciEvent::try_from(IbcEvent::UpdateClient(event)).unwrap()
+    }
+
+    fn send_packet(sequence: u64) -> AbciEvent {
+        let event = SendPacket {
+            height: height(),
+            packet: Packet {
+                sequence: Sequence::from(sequence),
+                source_port: PortId::transfer(),
+                source_channel: ChannelId::default(),
+                destination_port: PortId::transfer(),
+                destination_channel: ChannelId::default(),
+                data: b"data".to_vec(),
+                timeout_height: Height::new(0, 100),
+                ..Default::default()
+            },
+        };
+
+        AbciEvent::try_from(IbcEvent::SendPacket(event)).unwrap()
+    }
+
+    fn transfer() -> AbciEvent {
+        AbciEvent {
+            type_str: "transfer".to_string(),
+            attributes: vec![],
+        }
+    }
+
+    fn tx(query: &str, hash: &str, height: i64, events: Vec<AbciEvent>) -> RpcEvent {
+        let mut rpc_events = StdHashMap::new();
+        rpc_events.insert("tx.hash".to_string(), vec![hash.to_string()]);
+
+        RpcEvent {
+            query: query.to_string(),
+            data: RpcEventData::Tx {
+                tx_result: TxInfo {
+                    height,
+                    index: None,
+                    tx: vec![],
+                    result: TxResult {
+                        log: None,
+                        gas_wanted: None,
+                        gas_used: None,
+                        events,
+                    },
+                },
+            },
+            events: Some(rpc_events),
+        }
+    }
+
+    fn sequences(events: &[(Height, IbcEvent)]) -> Vec<u64> {
+        events
+            .iter()
+            .filter_map(|(_, e)| match e {
+                IbcEvent::SendPacket(ev) => Some(ev.packet.sequence.into()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn block_results_events() {
+        let mut results: block_results::Response = serde_json::from_str(
+            r#"{
+                "height": "10",
+                "txs_results": [
+                    {
+                        "code": 0,
+                        "data": null,
+                        "log": "",
+                        "info": "",
+                        "gas_wanted": "0",
+                        "gas_used": "0",
+                        "events": [],
+                        "codespace": ""
+                    }
+                ],
+                "begin_block_events": null,
+                "end_block_events": null,
+                "validator_updates": null,
+                "consensus_param_updates": null
+            }"#,
+        )
+        .unwrap();
+
+        results.txs_results.as_mut().unwrap()[0].events =
+            vec![transfer(), update_client(), send_packet(2)];
+        results.end_block_events = Some(vec![send_packet(1)]);
+
+        let events = get_block_results_events(height(), &results);
+
+        assert!(events.iter().all(|(h, _)| *h == height()));
+        assert!(matches!(events[0].1, IbcEvent::NewBlock(_)));
+        assert_eq!(sequences(&events), vec![1, 2]);
+        assert_eq!(
+            events
+                .iter()
+                .filter(|(_, e)| matches!(e, IbcEvent::UpdateClient(_)))
+                .count(),
+            1
+        );
+        assert_eq!(events.len(), 4);
+    }
+
+    #[test]
+    fn all_events_of_a_tx_are_extracted_for_any_query() {
+        let chain_id = ChainId::new("chain_a".to_string(), 0);
+        let events = vec![transfer(), update_client(), send_packet(1)];
+
+        for query in [
+            "tm.event = 'Tx' AND update_client.client_id EXISTS",
+            "tm.event = 'Tx' AND message.module = 'transfer'",
+        ] {
+            let all = get_all_events(&chain_id, tx(query, "A", 10, events.clone())).unwrap();
+
+            assert_eq!(all.len(), 2);
+            assert!(all.iter().all(|(h, _)| *h == height()));
+            assert!(matches!(all[0].1, IbcEvent::UpdateClient(_)));
+            assert_eq!(sequences(&all), vec![1]);
+        }
+    }
+
+    #[test]
+    fn txs_are_collected_once() {
+        let mut seen = SeenTxs::default();
+
+        let client_query = "tm.event = 'Tx' AND update_client.client_id EXISTS";
+        let user_query = "tm.event = 'Tx' AND message.module = 'transfer'";
+
+        assert!(seen.is_new(&tx(client_query, "A", 10, vec![])));
+        assert!(!seen.is_new(&tx(user_query, "A", 10, vec![])));
+        assert!(seen.is_new(&tx(user_query, "B", 10, vec![])));
+
+        // A late delivery of a transaction of a previous height is still skipped
+        assert!(seen.is_new(&tx(client_query, "C", 11, vec![])));
+        assert!(!seen.is_new(&tx(user_query, "B", 10, vec![])));
+
+        // The hashes of older heights are eventually dropped
+        assert!(seen.is_new(&tx(client_query, "D", 10 + SEEN_TXS_HEIGHTS as i64, vec![])));
+        assert!(seen.is_new(&tx(user_query, "A", 10, vec![])));
+    }
+}
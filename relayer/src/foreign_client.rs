@@ -1,4 +1,5 @@
 use core::{fmt, time::Duration};
+use std::path::{Path, PathBuf};
 use std::thread;
 use std::time::Instant;
 
@@ -7,16 +8,19 @@ use prost_types::Any;
 use tracing::{debug, error, info, trace, warn};
 
 use crate::error::Error as RelayerError;
-use flex_error::define_error;
+use flex_error::{define_error, TraceError};
+use ibc::clients::ics07_tendermint::client_state::ClientState as TmClientState;
+use ibc::clients::ics07_tendermint::misbehaviour::Misbehaviour as TmMisbehaviour;
 use ibc::core::ics02_client::client_consensus::{
     AnyConsensusState, AnyConsensusStateWithHeight, ConsensusState, QueryClientEventRequest,
 };
 use ibc::core::ics02_client::client_state::AnyClientState;
 use ibc::core::ics02_client::client_state::ClientState;
+use ibc::core::ics02_client::client_type::ClientType;
 use ibc::core::ics02_client::error::Error as ClientError;
 use ibc::core::ics02_client::events::UpdateClient;
 use ibc::core::ics02_client::header::{AnyHeader, Header};
-use ibc::core::ics02_client::misbehaviour::MisbehaviourEvidence;
+use ibc::core::ics02_client::misbehaviour::{AnyMisbehaviour, Misbehaviour, MisbehaviourEvidence};
 use ibc::core::ics02_client::msgs::create_client::MsgCreateAnyClient;
 use ibc::core::ics02_client::msgs::misbehavior::MsgSubmitAnyMisbehaviour;
 use ibc::core::ics02_client::msgs::update_client::MsgUpdateAnyClient;
@@ -28,10 +32,14 @@ use ibc::query::QueryTxRequest;
 use ibc::timestamp::{Timestamp, TimestampOverflowError};
 use ibc::tx_msg::Msg;
 use ibc::Height;
+use ibc_proto::cosmos::upgrade::v1beta1::Plan;
 use ibc_proto::ibc::core::client::v1::QueryConsensusStatesRequest;
 
 use crate::chain::handle::ChainHandle;
 
+mod evidence;
+pub use evidence::{EncodedAny, EvidenceBundle};
+
 const MAX_MISBEHAVIOUR_CHECK_DURATION: Duration = Duration::from_secs(120);
 
 const MAX_RETRIES: usize = 5;
@@ -188,6 +196,16 @@ define_error! {
                     e.client_id, e.expected_chain_id, e.actual_chain_id)
             },
 
+        SubstituteClientUnsupported
+            {
+                client_id: ClientId,
+                client_type: ClientType,
+            }
+            |e| {
+                format_args!("cannot build a substitute for client {0} of unsupported type {1}",
+                    e.client_id, e.client_type)
+            },
+
         ExpiredOrFrozen
             {
                 client_id: ClientId,
@@ -207,6 +225,13 @@ define_error! {
                 format_args!("error raised while checking for misbehaviour evidence: {0}", e.description)
             },
 
+        EvidenceArchive
+            { path: PathBuf }
+            [ TraceError<std::io::Error> ]
+            |e| {
+                format_args!("failed to archive misbehaviour evidence to {}", e.path.display())
+            },
+
         MisbehaviourExit
             { reason: String }
             |e| {
@@ -363,6 +388,9 @@ impl<DstChain: ChainHandle, SrcChain: ChainHandle> ForeignClient<DstChain, SrcCh
         }
     }
 
+    /// Upgrades this client using the upgraded client and consensus states
+    /// stored on the source chain at its latest height. The source chain is
+    /// expected to have halted at the upgrade height.
     pub fn upgrade(&self) -> Result<Vec<IbcEvent>, ForeignClientError> {
         // Fetch the latest height of the source chain.
         let src_height = self.src_chain.query_latest_height().map_err(|e| {
@@ -374,8 +402,38 @@ impl<DstChain: ChainHandle, SrcChain: ChainHandle> ForeignClient<DstChain, SrcCh
             )
         })?;
 
+        self.upgrade_at(src_height)
+    }
+
+    /// Upgrades this client using the upgraded client and consensus states
+    /// stored on the source chain for the upgrade plan at `src_height`.
+    ///
+    /// Unlike [`ForeignClient::upgrade`], this can also be used after the
+    /// source chain has resumed past the upgrade height.
+    pub fn upgrade_at(&self, src_height: Height) -> Result<Vec<IbcEvent>, ForeignClientError> {
         info!("[{}] upgrade Height: {}", self, src_height);
 
+        let msgs = self.build_upgrade_client(src_height)?;
+
+        let res = self
+            .dst_chain
+            .send_messages_and_wait_commit(msgs)
+            .map_err(|e| {
+                ForeignClientError::client_upgrade(
+                    self.id.clone(),
+                    self.dst_chain.id(),
+                    "failed while sending message to destination chain".to_string(),
+                    e,
+                )
+            })?;
+
+        Ok(res)
+    }
+
+    /// Lower-level interface for preparing the messages to upgrade this client
+    /// for the upgrade plan at `src_height`, namely a client update followed
+    /// by the client upgrade itself.
+    pub fn build_upgrade_client(&self, src_height: Height) -> Result<Vec<Any>, ForeignClientError> {
         let mut msgs = self.build_update_client(src_height)?;
 
         // Query the host chain for the upgraded client state, consensus state & their proofs.
@@ -433,19 +491,53 @@ impl<DstChain: ChainHandle, SrcChain: ChainHandle> ForeignClient<DstChain, SrcCh
 
         msgs.push(msg_upgrade);
 
-        let res = self
+        Ok(msgs)
+    }
+
+    /// Returns the height of the upgrade plan currently scheduled on the source chain, if any.
+    pub fn pending_upgrade_height(&self) -> Result<Option<Height>, ForeignClientError> {
+        let plan = self.src_chain.query_upgrade_plan().map_err(|e| {
+            ForeignClientError::client_upgrade(
+                self.id.clone(),
+                self.src_chain.id(),
+                "failed while querying src chain for the upgrade plan".to_string(),
+                e,
+            )
+        })?;
+
+        let plan = match plan {
+            Some(plan) => plan,
+            None => return Ok(None),
+        };
+
+        let src_latest_height = self.src_chain.query_latest_height().map_err(|e| {
+            ForeignClientError::client_upgrade(
+                self.id.clone(),
+                self.src_chain.id(),
+                "failed while querying src chain for latest height".to_string(),
+                e,
+            )
+        })?;
+
+        Ok(upgrade_plan_height(&plan, src_latest_height))
+    }
+
+    /// Returns true if this client was already upgraded (or updated) past
+    /// the upgrade plan at `upgrade_height`.
+    pub fn is_upgraded(&self, upgrade_height: Height) -> Result<bool, ForeignClientError> {
+        let client_state = self
             .dst_chain
-            .send_messages_and_wait_commit(msgs)
+            .query_client_state(self.id(), Height::zero())
             .map_err(|e| {
                 ForeignClientError::client_upgrade(
                     self.id.clone(),
                     self.dst_chain.id(),
-                    "failed while sending message to destination chain".to_string(),
+                    "failed while querying client state on dst chain".to_string(),
                     e,
                 )
             })?;
 
-        Ok(res)
+        Ok(client_state.latest_height() > upgrade_height)
     }
 
     /// Returns a handle to the chain hosting this client.
@@ -551,6 +643,77 @@ impl<DstChain: ChainHandle, SrcChain: ChainHandle> ForeignClient<DstChain, SrcCh
         Ok(res[0].clone())
     }
 
+    /// Lower-level interface for preparing a message to create a substitute for this
+    /// client, to be used in a `ClientUpdateProposal` which recovers this client once
+    /// it has expired or was frozen.
+    ///
+    /// The substitute copies all the parameters of this client except for the latest
+    /// height, frozen height and chain identifier, since the host chain rejects the
+    /// proposal if the subject and substitute clients differ in any other parameter.
+    pub fn build_create_substitute_client(&self) -> Result<MsgCreateAnyClient, ForeignClientError> {
+        let signer = self.dst_chain.get_signer().map_err(|e| {
+            ForeignClientError::client_create(
+                self.src_chain.id(),
+                format!(
+                    "failed while fetching the dst chain ({}) signer",
+                    self.dst_chain.id()
+                ),
+                e,
+            )
+        })?;
+
+        let subject_state = self
+            .dst_chain
+            .query_client_state(self.id(), Height::zero())
+            .map_err(|e| {
+                ForeignClientError::client_create(
+                    self.src_chain.id(),
+                    format!("failed while querying the state of client {}", self.id),
+                    e,
+                )
+            })?;
+
+        let subject_state = downcast!(subject_state.clone() => AnyClientState::Tendermint)
+            .ok_or_else(|| {
+                ForeignClientError::substitute_client_unsupported(
+                    self.id.clone(),
+                    subject_state.client_type(),
+                )
+            })?;
+
+        let latest_height = self.src_chain.query_latest_height().map_err(|e| {
+            ForeignClientError::client_create(
+                self.src_chain.id(),
+                "failed while querying src chain for latest height".to_string(),
+                e,
+            )
+        })?;
+
+        let client_state = AnyClientState::Tendermint(TmClientState {
+            chain_id: self.src_chain.id(),
+            latest_height,
+            frozen_height: None,
+            ..subject_state
+        });
+
+        let consensus_state = self
+            .src_chain
+            .build_consensus_state(latest_height, latest_height, client_state.clone())
+            .map_err(|e| {
+                ForeignClientError::client_create(
+                    self.src_chain.id(),
+                    "failed while building client consensus state from src chain".to_string(),
+                    e,
+                )
+            })?
+            .wrap_any();
+
+        let msg = MsgCreateAnyClient::new(client_state, consensus_state, signer)
+            .map_err(ForeignClientError::client)?;
+
+        Ok(msg)
+    }
+
     /// Sends the client creation transaction & subsequently sets the id of this ForeignClient
     fn create(&mut self) -> Result<(), ForeignClientError> {
         let event = self.build_create_client_and_send().map_err(|e| {
@@ -602,6 +765,51 @@ impl<DstChain: ChainHandle, SrcChain: ChainHandle> ForeignClient<DstChain, SrcCh
         }
     }
 
+    /// Collects information about the health of this client, namely how long until
+    /// its trusting period expires, and how far behind the source chain it is lagging.
+    ///
+    /// Unlike [`ForeignClient::validated_client_state`], this does not fail if the
+    /// client is expired or frozen, so that the health can still be reported.
+    pub fn health(&self) -> Result<ClientHealth, ForeignClientError> {
+        let client_state = self
+            .dst_chain
+            .query_client_state(self.id(), Height::zero())
+            .map_err(|e| {
+                ForeignClientError::client_refresh(
+                    self.id().clone(),
+                    "failed querying client state on dst chain".to_string(),
+                    e,
+                )
+            })?;
+
+        let client_latest_height = client_state.latest_height();
+
+        let src_latest_height = self.src_chain.query_latest_height().map_err(|e| {
+            ForeignClientError::client_refresh(
+                self.id().clone(),
+                "failed querying src chain for latest height".to_string(),
+                e,
+            )
+        })?;
+
+        let last_update_time = self.consensus_state(client_latest_height)?.timestamp();
+
+        // Compute the duration since the last update of this client
+        let elapsed = Timestamp::now()
+            .duration_since(&last_update_time)
+            .unwrap_or_default();
+
+        let seconds_until_expiry = client_state
+            .trusting_period()
+            .map(|trusting_period| trusting_period.as_secs() as i64 - elapsed.as_secs() as i64);
+
+        Ok(ClientHealth {
+            client_latest_height,
+            src_latest_height,
+            seconds_until_expiry,
+        })
+    }
+
     pub fn refresh(&mut self) -> Result<Option<Vec<IbcEvent>>, ForeignClientError> {
         let (client_state, elapsed) = self.validated_client_state()?;
 
@@ -1216,6 +1424,209 @@ impl<DstChain: ChainHandle, SrcChain: ChainHandle> ForeignClient<DstChain, SrcCh
         Ok(None)
     }
 
+    /// Checks all the consensus states of this client for misbehaviour.
+    ///
+    /// Unlike [`ForeignClient::detect_misbehaviour`], this is not bounded in time and does
+    /// not stop at the first consensus state without an update event, so that every
+    /// consensus state of the client is accounted for. The following checks are performed:
+    ///
+    /// 1 - BFT time violation for existing headers:
+    /// The consensus state timestamps must be strictly increasing with height. If two
+    /// consecutive consensus states violate this, the headers from their update events
+    /// are submitted as evidence.
+    ///
+    /// 2 - fork:
+    /// For every consensus state that was installed by an update, the header in the update
+    /// event is compared against the header fetched from the source chain at the same height,
+    /// as described in [`ForeignClient::detect_misbehaviour`].
+    ///
+    /// Consensus states whose update header is trusting a consensus state which no longer
+    /// exists on the destination chain (e.g. because it was pruned) cannot be used as evidence,
+    /// and are reported as skipped.
+    pub fn detect_misbehaviour_in_all_consensus_states(
+        &self,
+    ) -> Result<MisbehaviourScan, ForeignClientError> {
+        let client_state = self
+            .dst_chain()
+            .query_client_state(&self.id, Height::zero())
+            .map_err(|e| {
+                ForeignClientError::misbehaviour(
+                    format!("failed querying client state on dst chain {}", self.id),
+                    e,
+                )
+            })?;
+
+        // Consensus states in ascending height order
+        let mut consensus_states = self.consensus_states()?;
+        consensus_states.reverse();
+
+        let consensus_heights = consensus_states.iter().map(|cs| cs.height).collect_vec();
+
+        info!(
+            "[{}] scanning {} consensus states for misbehaviour",
+            self,
+            consensus_heights.len()
+        );
+
+        let mut scan = MisbehaviourScan::default();
+
+        // Fetch the update headers once, they are needed by both checks below.
+        let mut update_events = Vec::with_capacity(consensus_heights.len());
+        for height in &consensus_heights {
+            let update_event = self
+                .update_client_event(*height)?
+                .filter(|event| event.header.is_some());
+
+            update_events.push(update_event);
+        }
+
+        // BFT time violation: timestamps must increase monotonically with height.
+        for i in time_violations(&consensus_states) {
+            let (lower, higher) = (&consensus_states[i], &consensus_states[i + 1]);
+
+            warn!(
+                "[{}] consensus state at height {} has a timestamp ({}) which is not after the one at lower height {} ({})",
+                self,
+                higher.height,
+                higher.consensus_state.timestamp(),
+                lower.height,
+                lower.consensus_state.timestamp(),
+            );
+
+            let headers = update_events[i + 1]
+                .as_ref()
+                .and_then(|e| e.header.clone())
+                .zip(update_events[i].as_ref().and_then(|e| e.header.clone()));
+
+            match headers {
+                Some((header1, header2))
+                    if trusts_existing_consensus_state(&header1, &consensus_heights)
+                        && trusts_existing_consensus_state(&header2, &consensus_heights) =>
+                {
+                    if let Some(misbehaviour) =
+                        time_violation_misbehaviour(self.id.clone(), header1, header2)
+                    {
+                        scan.evidence = Some(MisbehaviourEvidence {
+                            misbehaviour,
+                            supporting_headers: vec![],
+                        });
+
+                        return Ok(scan);
+                    }
+                }
+                _ => {
+                    warn!(
+                        "[{}] cannot build evidence for time violation at height {}: update headers are missing or no longer trusted",
+                        self, higher.height
+                    );
+                }
+            }
+        }
+
+        // Fork: compare the update headers against the source chain, highest height first.
+        for (height, update_event) in consensus_heights
+            .iter()
+            .zip(update_events.into_iter())
+            .rev()
+        {
+            let update_event = match checkable_update(update_event, &consensus_heights) {
+                Some(update_event) => update_event,
+                None => {
+                    debug!(
+                        "[{}] skipping consensus state at height {}: no update header, or the consensus state it trusts is missing",
+                        self, height
+                    );
+                    scan.skipped_heights.push(*height);
+                    continue;
+                }
+            };
+
+            let misbehaviour = self
+                .src_chain
+                .check_misbehaviour(update_event.clone(), client_state.clone())
+                .map_err(|e| {
+                    ForeignClientError::misbehaviour(
+                        format!(
+                            "failed to check misbehaviour for {} at consensus height {}",
+                            update_event.client_id(),
+                            update_event.consensus_height(),
+                        ),
+                        e,
+                    )
+                })?;
+
+            scan.checked_heights.push(*height);
+
+            if misbehaviour.is_some() {
+                scan.evidence = misbehaviour;
+                return Ok(scan);
+            }
+
+            // slight backoff
+            thread::sleep(Duration::from_millis(100));
+        }
+
+        info!(
+            "[{}] finished misbehaviour scan: {} consensus states checked, {} skipped",
+            self,
+            scan.checked_heights.len(),
+            scan.skipped_heights.len()
+        );
+
+        Ok(scan)
+    }
+
+    /// Scans all the consensus states of this client for misbehaviour with
+    /// [`ForeignClient::detect_misbehaviour_in_all_consensus_states`], and submits the
+    /// evidence if any is found.
+    ///
+    /// If `evidence_dir` is specified, the evidence is written to a file in that
+    /// directory before being submitted, and the submission is aborted if that fails.
+    pub fn scan_misbehaviour_and_submit_evidence(
+        &self,
+        evidence_dir: Option<&Path>,
+    ) -> Result<MisbehaviourScan, ForeignClientError> {
+        let mut scan = self.detect_misbehaviour_in_all_consensus_states()?;
+
+        if let Some(evidence) = scan.evidence.clone() {
+            error!(
+                "[{}] MISBEHAVIOUR DETECTED {}, sending evidence",
+                self, evidence.misbehaviour
+            );
+
+            if let Some(dir) = evidence_dir {
+                scan.evidence_file = Some(self.archive_evidence(&evidence, dir)?);
+            }
+
+            scan.submitted = self.submit_evidence(evidence)?;
+
+            info!("[{}] evidence submission result {:?}", self, scan.submitted);
+        }
+
+        Ok(scan)
+    }
+
+    /// Writes the given evidence to a new file in `dir`, see [`EvidenceBundle`].
+    pub fn archive_evidence(
+        &self,
+        evidence: &MisbehaviourEvidence,
+        dir: &Path,
+    ) -> Result<PathBuf, ForeignClientError> {
+        let bundle = EvidenceBundle::new(self.dst_chain.id(), self.src_chain.id(), evidence);
+
+        let path = bundle
+            .write_to(dir)
+            .map_err(|e| ForeignClientError::evidence_archive(dir.join(bundle.file_name()), e))?;
+
+        info!(
+            "[{}] misbehaviour evidence archived to {}",
+            self,
+            path.display()
+        );
+
+        Ok(path)
+    }
+
     fn submit_evidence(
         &self,
         evidence: MisbehaviourEvidence,
@@ -1336,6 +1747,124 @@ impl<DstChain: ChainHandle, SrcChain: ChainHandle> ForeignClient<DstChain, SrcCh
     }
 }
 
+/// The outcome of [`ForeignClient::scan_misbehaviour_and_submit_evidence`].
+#[derive(Clone, Debug, Default)]
+pub struct MisbehaviourScan {
+    /// Consensus state heights which were checked against the source chain
+    pub checked_heights: Vec<Height>,
+    /// Consensus state heights which could not be checked, either because they were
+    /// not installed by an update with a header, or because the consensus state
+    /// trusted by that header no longer exists
+    pub skipped_heights: Vec<Height>,
+    /// The evidence of misbehaviour, if any was found
+    pub evidence: Option<MisbehaviourEvidence>,
+    /// The file the evidence was archived to, if any
+    pub evidence_file: Option<PathBuf>,
+    /// The events resulting from the submission of the evidence, if any
+    pub submitted: Vec<IbcEvent>,
+}
+
+/// Returns true if the consensus state trusted by the given header still
+/// exists on the destination chain, i.e. if it is in `consensus_heights`.
+fn trusts_existing_consensus_state(header: &AnyHeader, consensus_heights: &[Height]) -> bool {
+    match header {
+        AnyHeader::Tendermint(header) => consensus_heights.contains(&header.trusted_height),
+
+        #[cfg(any(test, feature = "mocks"))]
+        AnyHeader::Mock(_) => true,
+    }
+}
+
+/// Returns the indices `i` such that the consensus state at `i + 1` does not have a
+/// timestamp after the one at `i`, i.e. the BFT time violations, given consensus
+/// states sorted in ascending height order.
+fn time_violations(consensus_states: &[AnyConsensusStateWithHeight]) -> Vec<usize> {
+    consensus_states
+        .windows(2)
+        .enumerate()
+        .filter(|(_, pair)| {
+            !pair[1]
+                .consensus_state
+                .timestamp()
+                .after(&pair[0].consensus_state.timestamp())
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Returns the update event which installed a consensus state if it can be checked
+/// against the source chain, namely if it includes a header (i.e. the consensus state
+/// was not installed by `CreateClient`) which trusts a consensus state that still exists.
+fn checkable_update(
+    update_event: Option<UpdateClient>,
+    consensus_heights: &[Height],
+) -> Option<UpdateClient> {
+    update_event.filter(|event| match &event.header {
+        Some(header) => trusts_existing_consensus_state(header, consensus_heights),
+        None => false,
+    })
+}
+
+/// Builds the misbehaviour for a BFT time violation, where `header1` is at a greater
+/// height than `header2` but does not have a greater timestamp.
+fn time_violation_misbehaviour(
+    client_id: ClientId,
+    header1: AnyHeader,
+    header2: AnyHeader,
+) -> Option<AnyMisbehaviour> {
+    match (header1, header2) {
+        (AnyHeader::Tendermint(header1), AnyHeader::Tendermint(header2)) => Some(
+            TmMisbehaviour {
+                client_id,
+                header1,
+                header2,
+            }
+            .wrap_any(),
+        ),
+
+        #[cfg(any(test, feature = "mocks"))]
+        _ => None,
+    }
+}
+
+/// Returns the height of the given upgrade plan.
+///
+/// The plan height is the last height of the revision the source chain is currently
+/// at, which is not necessarily the revision number of its chain identifier, so the
+/// revision is taken from the latest height of the source chain instead.
+/// Plans without a height, i.e. deprecated time-based plans, are ignored.
+fn upgrade_plan_height(plan: &Plan, src_latest_height: Height) -> Option<Height> {
+    u64::try_from(plan.height)
+        .ok()
+        .filter(|height| *height > 0)
+        .map(|height| Height::new(src_latest_height.revision_number, height))
+}
+
+/// Health information about a [`ForeignClient`], as returned by [`ForeignClient::health`].
+#[derive(Clone, Debug)]
+pub struct ClientHealth {
+    /// The latest height of the client, as stored on the destination chain
+    pub client_latest_height: Height,
+    /// The latest height of the source chain
+    pub src_latest_height: Height,
+    /// Seconds until the trusting period of the client expires, negative if it
+    /// has already expired, or `None` if the client type has no trusting period.
+    pub seconds_until_expiry: Option<i64>,
+}
+
+impl ClientHealth {
+    /// The number of blocks the client is lagging behind the source chain.
+    pub fn height_lag(&self) -> u64 {
+        if self.src_latest_height.revision_number != self.client_latest_height.revision_number {
+            return self.src_latest_height.revision_height;
+        }
+
+        self.src_latest_height
+            .revision_height
+            .saturating_sub(self.client_latest_height.revision_height)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum MisbehaviourResults {
     CannotExecute,
@@ -1362,18 +1891,36 @@ mod test {
     use alloc::sync::Arc;
     use core::str::FromStr;
 
+    use tendermint::Hash;
     use test_log::test;
     use tokio::runtime::Runtime as TokioRuntime;
 
-    use ibc::core::ics24_host::identifier::ClientId;
+    use ibc::clients::ics07_tendermint::client_state::ClientState as TmClientState;
+    use ibc::clients::ics07_tendermint::consensus_state::ConsensusState as TmConsensusState;
+    use ibc::clients::ics07_tendermint::header::Header as TmHeader;
+    use ibc::core::ics02_client::client_consensus::{AnyConsensusStateWithHeight, ConsensusState};
+    use ibc::core::ics02_client::client_state::AnyClientState;
+    use ibc::core::ics02_client::client_type::ClientType;
+    use ibc::core::ics02_client::events::{Attributes, UpdateClient};
+    use ibc::core::ics02_client::header::{AnyHeader, Header};
+    use ibc::core::ics02_client::misbehaviour::AnyMisbehaviour;
+    use ibc::core::ics23_commitment::commitment::CommitmentRoot;
+    use ibc::core::ics24_host::identifier::{ChainId, ClientId};
+    use ibc::downcast;
     use ibc::events::IbcEvent;
+    use ibc::mock::host::HostBlock;
+    use ibc::timestamp::Timestamp;
     use ibc::Height;
+    use ibc_proto::cosmos::upgrade::v1beta1::Plan;
 
     use crate::chain::handle::{ChainHandle, ProdChainHandle};
     use crate::chain::mock::test_utils::get_basic_chain_config;
     use crate::chain::mock::MockChain;
     use crate::chain::runtime::ChainRuntime;
-    use crate::foreign_client::ForeignClient;
+    use crate::foreign_client::{
+        checkable_update, time_violation_misbehaviour, time_violations, upgrade_plan_height,
+        ClientHealth, ForeignClient,
+    };
 
     /// Basic test for the `build_create_client_and_send` method.
     #[test]
@@ -1567,6 +2114,248 @@ mod test {
         );
     }
 
+    /// Tests for `ForeignClient::health()`.
+    #[test]
+    fn foreign_client_health() {
+        let a_cfg = get_basic_chain_config("chain_a");
+        let b_cfg = get_basic_chain_config("chain_b");
+
+        let rt = Arc::new(TokioRuntime::new().unwrap());
+        let a_chain =
+            ChainRuntime::<MockChain>::spawn::<ProdChainHandle>(a_cfg, rt.clone()).unwrap();
+        let b_chain = ChainRuntime::<MockChain>::spawn::<ProdChainHandle>(b_cfg, rt).unwrap();
+
+        let client_on_a = ForeignClient::new(a_chain, b_chain.clone()).unwrap();
+
+        let health = client_on_a.health().unwrap();
+        let b_height = b_chain.query_latest_height().unwrap();
+
+        assert_eq!(health.src_latest_height, b_height);
+        assert_eq!(
+            health.height_lag(),
+            b_height.revision_height - health.client_latest_height.revision_height
+        );
+
+        // The client was just created, so it is far from expiring.
+        let seconds_until_expiry = health
+            .seconds_until_expiry
+            .expect("Tendermint clients have a trusting period");
+        assert!(seconds_until_expiry > 0);
+    }
+
+    #[test]
+    fn client_health_height_lag() {
+        let lag = |client: (u64, u64), src: (u64, u64)| {
+            ClientHealth {
+                client_latest_height: Height::new(client.0, client.1),
+                src_latest_height: Height::new(src.0, src.1),
+                seconds_until_expiry: None,
+            }
+            .height_lag()
+        };
+
+        assert_eq!(lag((1, 10), (1, 15)), 5);
+        assert_eq!(lag((1, 15), (1, 15)), 0);
+
+        // The client can be ahead of the node it queries, which is not a lag.
+        assert_eq!(lag((1, 16), (1, 15)), 0);
+
+        // After an upgrade, the whole new revision counts as lag.
+        assert_eq!(lag((1, 100), (2, 7)), 7);
+    }
+
+    /// Tests for `ForeignClient::build_create_substitute_client()`.
+    #[test]
+    fn foreign_client_substitute() {
+        let a_cfg = get_basic_chain_config("chain_a");
+        let b_cfg = get_basic_chain_config("chain_b");
+
+        let rt = Arc::new(TokioRuntime::new().unwrap());
+        let a_chain =
+            ChainRuntime::<MockChain>::spawn::<ProdChainHandle>(a_cfg, rt.clone()).unwrap();
+        let b_chain = ChainRuntime::<MockChain>::spawn::<ProdChainHandle>(b_cfg, rt).unwrap();
+
+        let subject = ForeignClient::new(a_chain.clone(), b_chain.clone()).unwrap();
+
+        // Creating a client on chain b makes it advance past the height of the subject client
+        let _client_on_b = ForeignClient::new(b_chain.clone(), a_chain.clone()).unwrap();
+
+        let subject_state = a_chain
+            .query_client_state(subject.id(), Height::zero())
+            .unwrap();
+        let subject_state = downcast!(subject_state => AnyClientState::Tendermint).unwrap();
+
+        let msg = subject.build_create_substitute_client().unwrap();
+
+        let b_height = b_chain.query_latest_height().unwrap();
+        assert!(b_height > subject_state.latest_height);
+
+        // The substitute only differs from the subject client in its latest height
+        let substitute_state = downcast!(msg.client_state => AnyClientState::Tendermint).unwrap();
+        assert_eq!(
+            substitute_state,
+            TmClientState {
+                latest_height: b_height,
+                ..subject_state
+            }
+        );
+    }
+
+    /// Tests for `ForeignClient::pending_upgrade_height()` and `ForeignClient::is_upgraded()`.
+    #[test]
+    fn foreign_client_upgrade_plan() {
+        let a_cfg = get_basic_chain_config("chain_a");
+        let b_cfg = get_basic_chain_config("chain_b");
+
+        let rt = Arc::new(TokioRuntime::new().unwrap());
+        let a_chain =
+            ChainRuntime::<MockChain>::spawn::<ProdChainHandle>(a_cfg, rt.clone()).unwrap();
+        let b_chain = ChainRuntime::<MockChain>::spawn::<ProdChainHandle>(b_cfg, rt).unwrap();
+
+        let client_on_a = ForeignClient::new(a_chain.clone(), b_chain).unwrap();
+
+        // Mock chains never schedule upgrades
+        assert_eq!(client_on_a.pending_upgrade_height().unwrap(), None);
+
+        let client_height = a_chain
+            .query_client_state(client_on_a.id(), Height::zero())
+            .unwrap()
+            .latest_height();
+
+        let below = Height::new(
+            client_height.revision_number,
+            client_height.revision_height - 1,
+        );
+        assert!(client_on_a.is_upgraded(below).unwrap());
+        assert!(!client_on_a.is_upgraded(client_height).unwrap());
+        assert!(!client_on_a.is_upgraded(client_height.increment()).unwrap());
+    }
+
+    #[test]
+    fn upgrade_plan_height_in_current_revision() {
+        let plan = |height| Plan {
+            height,
+            ..Default::default()
+        };
+
+        // The revision is taken from the latest height of the source chain,
+        // regardless of the revision number of its chain identifier.
+        assert_eq!(
+            upgrade_plan_height(&plan(1000), Height::new(4, 900)),
+            Some(Height::new(4, 1000))
+        );
+
+        // Time-based plans have no height
+        assert_eq!(upgrade_plan_height(&plan(0), Height::new(4, 900)), None);
+        assert_eq!(upgrade_plan_height(&plan(-1), Height::new(4, 900)), None);
+    }
+
+    /// A Tendermint consensus state at the given height whose timestamp is
+    /// `seconds` after the UNIX epoch.
+    fn tm_consensus_state(height: u64, seconds: u64) -> AnyConsensusStateWithHeight {
+        let timestamp = Timestamp::from_nanoseconds(seconds * 1_000_000_000)
+            .unwrap()
+            .into_datetime()
+            .unwrap()
+            .try_into()
+            .unwrap();
+
+        let consensus_state =
+            TmConsensusState::new(CommitmentRoot::from_bytes(&[]), timestamp, Hash::None);
+
+        AnyConsensusStateWithHeight {
+            height: Height::new(1, height),
+            consensus_state: consensus_state.wrap_any(),
+        }
+    }
+
+    /// A Tendermint header at the given height, trusting the consensus state at `trusted_height`.
+    fn tm_header(height: u64, trusted_height: u64) -> AnyHeader {
+        let block = HostBlock::generate_tm_block(ChainId::new("chain_b".to_string(), 1), height);
+
+        TmHeader {
+            signed_header: block.signed_header,
+            validator_set: block.validators.clone(),
+            trusted_height: Height::new(1, trusted_height),
+            trusted_validator_set: block.validators,
+        }
+        .wrap_any()
+    }
+
+    fn update_event(height: u64, header: Option<AnyHeader>) -> UpdateClient {
+        UpdateClient {
+            common: Attributes {
+                height: Height::new(1, 100),
+                client_id: ClientId::default(),
+                client_type: ClientType::Tendermint,
+                consensus_height: Height::new(1, height),
+            },
+            header,
+        }
+    }
+
+    #[test]
+    fn time_violations_in_consensus_states() {
+        // Strictly increasing timestamps
+        let consensus_states = vec![
+            tm_consensus_state(1, 10),
+            tm_consensus_state(2, 20),
+            tm_consensus_state(3, 30),
+        ];
+        assert!(time_violations(&consensus_states).is_empty());
+
+        // The timestamp of height 3 is before the one of height 2, and the
+        // timestamp of height 5 is equal to the one of height 4.
+        let consensus_states = vec![
+            tm_consensus_state(1, 10),
+            tm_consensus_state(2, 20),
+            tm_consensus_state(3, 15),
+            tm_consensus_state(4, 30),
+            tm_consensus_state(5, 30),
+        ];
+        assert_eq!(time_violations(&consensus_states), vec![1, 3]);
+    }
+
+    #[test]
+    fn time_violation_misbehaviour_from_headers() {
+        let client_id = ClientId::from_str("07-tendermint-0").unwrap();
+
+        let misbehaviour =
+            time_violation_misbehaviour(client_id.clone(), tm_header(3, 1), tm_header(2, 1))
+                .expect("Tendermint headers make up a misbehaviour");
+
+        match misbehaviour {
+            AnyMisbehaviour::Tendermint(misbehaviour) => {
+                assert_eq!(misbehaviour.client_id, client_id);
+                assert_eq!(misbehaviour.header1.height(), Height::new(1, 3));
+                assert_eq!(misbehaviour.header2.height(), Height::new(1, 2));
+            }
+            _ => panic!("expected a Tendermint misbehaviour"),
+        }
+    }
+
+    #[test]
+    fn checkable_updates_in_consensus_scan() {
+        let consensus_heights = vec![Height::new(1, 1), Height::new(1, 5)];
+
+        // Installed by `CreateClient`, there is no update event
+        assert!(checkable_update(None, &consensus_heights).is_none());
+
+        // The update event does not include the header
+        assert!(checkable_update(Some(update_event(5, None)), &consensus_heights).is_none());
+
+        // The header trusts the consensus state at height 3, which was pruned
+        let pruned = update_event(5, Some(tm_header(5, 3)));
+        assert!(checkable_update(Some(pruned), &consensus_heights).is_none());
+
+        // The header trusts the consensus state at height 1, which still exists
+        let update = update_event(5, Some(tm_header(5, 1)));
+        assert_eq!(
+            checkable_update(Some(update.clone()), &consensus_heights),
+            Some(update)
+        );
+    }
+
     /// Tests for `ForeignClient::update()`.
     #[test]
     fn foreign_client_update() {
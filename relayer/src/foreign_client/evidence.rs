@@ -0,0 +1,96 @@
+//! Archiving of misbehaviour evidence to disk, prior to its submission.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use prost_types::Any;
+use serde::Serialize;
+
+use ibc::core::ics02_client::header::AnyHeader;
+use ibc::core::ics02_client::misbehaviour::{Misbehaviour, MisbehaviourEvidence};
+use ibc::core::ics24_host::identifier::{ChainId, ClientId};
+use ibc::timestamp::Timestamp;
+use ibc::Height;
+
+/// A self-contained record of a misbehaviour detected by the relayer,
+/// as written to disk before the evidence is submitted.
+#[derive(Clone, Debug, Serialize)]
+pub struct EvidenceBundle {
+    /// The chain hosting the misbehaving client
+    pub host_chain_id: ChainId,
+    /// The chain whose headers the client is verifying
+    pub src_chain_id: ChainId,
+    /// The identifier of the misbehaving client
+    pub client_id: ClientId,
+    /// The height at which the misbehaviour was detected
+    pub height: Height,
+    /// The time at which the misbehaviour was detected
+    pub detected_at: Timestamp,
+    /// Human readable description of the misbehaviour
+    pub description: String,
+    /// The protobuf-encoded misbehaviour, as it is submitted to the host chain
+    pub misbehaviour: EncodedAny,
+    /// The headers needed to verify the misbehaviour on the host chain
+    pub supporting_headers: Vec<AnyHeader>,
+}
+
+/// A protobuf `Any` message with its value hex-encoded
+#[derive(Clone, Debug, Serialize)]
+pub struct EncodedAny {
+    pub type_url: String,
+    pub value: String,
+}
+
+impl From<Any> for EncodedAny {
+    fn from(any: Any) -> Self {
+        Self {
+            type_url: any.type_url,
+            value: hex::encode(any.value),
+        }
+    }
+}
+
+impl EvidenceBundle {
+    pub fn new(
+        host_chain_id: ChainId,
+        src_chain_id: ChainId,
+        evidence: &MisbehaviourEvidence,
+    ) -> Self {
+        let misbehaviour = &evidence.misbehaviour;
+
+        Self {
+            host_chain_id,
+            src_chain_id,
+            client_id: misbehaviour.client_id().clone(),
+            height: misbehaviour.height(),
+            detected_at: Timestamp::now(),
+            description: misbehaviour.to_string(),
+            misbehaviour: Any::from(misbehaviour.clone()).into(),
+            supporting_headers: evidence.supporting_headers.clone(),
+        }
+    }
+
+    /// The name of the file this bundle is written to, unique per client, height and
+    /// detection time so that repeated detections do not overwrite each other.
+    pub fn file_name(&self) -> String {
+        format!(
+            "{}_{}_{}_{}.json",
+            self.host_chain_id,
+            self.client_id,
+            self.height,
+            self.detected_at.nanoseconds()
+        )
+    }
+
+    /// Writes this bundle as JSON to a new file in `dir`, creating the
+    /// directory if needed, and returns the path of the written file.
+    pub fn write_to(&self, dir: &Path) -> Result<PathBuf, std::io::Error> {
+        fs::create_dir_all(dir)?;
+
+        let path = dir.join(self.file_name());
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(&path, json)?;
+
+        Ok(path)
+    }
+}
@@ -22,6 +22,7 @@ pub mod chain;
 pub mod channel;
 pub mod config;
 pub mod connection;
+pub mod dry_run;
 pub mod error;
 pub mod event;
 pub mod foreign_client;
@@ -30,6 +31,7 @@ pub mod light_client;
 pub mod link;
 pub mod macros;
 pub mod object;
+pub mod recover_client;
 pub mod registry;
 pub mod rest;
 pub mod sdk_error;
@@ -7,7 +7,7 @@ use ibc::core::ics02_client::events::UpdateClient;
 
 pub mod tendermint;
 
-#[cfg(test)]
+#[cfg(any(test, feature = "mocks"))]
 pub mod mock;
 
 /// Defines a light block from the point of view of the relayer.
@@ -1,4 +1,8 @@
+use alloc::sync::Arc;
+use core::time::Duration;
+
 use itertools::Itertools;
+use tokio::runtime::Runtime as TokioRuntime;
 
 use tendermint_light_client::{
     components::{self, io::AtHeight},
@@ -28,16 +32,28 @@ use ibc::{
     },
     downcast,
 };
-use tracing::trace;
+use tracing::{debug, trace, warn};
 
-use crate::{chain::CosmosSdkChain, config::ChainConfig, error::Error};
+use crate::{
+    chain::cosmos::endpoints::Endpoints, chain::CosmosSdkChain, config::ChainConfig, error::Error,
+};
 
 use super::Verified;
 
+mod store;
+use store::DiskStore;
+
 pub struct LightClient {
     chain_id: ChainId,
+    rpc_timeout: Duration,
+    /// The full nodes of the chain, the light blocks are fetched from the one in use
+    endpoints: Arc<Endpoints>,
+    rt: Arc<TokioRuntime>,
+    /// The address of the node from which the light blocks are fetched
+    rpc_addr: rpc::Url,
     peer_id: PeerId,
     io: components::io::ProdIo,
+    store: Option<DiskStore>,
 }
 
 impl super::LightClient<CosmosSdkChain> for LightClient {
@@ -60,17 +76,21 @@ impl super::LightClient<CosmosSdkChain> for LightClient {
     ) -> Result<Verified<LightBlock>, Error> {
         trace!(%trusted, %target, "light client verification");
 
+        self.follow_current_node()?;
+
         let target_height =
             TMHeight::try_from(target.revision_height).map_err(Error::invalid_height)?;
 
         let client = self.prepare_client(client_state)?;
-        let mut state = self.prepare_state(trusted)?;
+        let mut state = self.prepare_state(trusted, target_height)?;
 
         // Verify the target header
         let target = client
             .verify_to_target(target_height, &mut state)
             .map_err(|e| Error::light_client(self.chain_id.to_string(), e))?;
 
+        self.persist_state(&state, client_state);
+
         // Collect the verification trace for the target block
         let target_trace = state.get_trace(target.height());
 
@@ -88,6 +108,8 @@ impl super::LightClient<CosmosSdkChain> for LightClient {
     fn fetch(&mut self, height: ibc::Height) -> Result<LightBlock, Error> {
         trace!(%height, "fetching header");
 
+        self.follow_current_node()?;
+
         let height = TMHeight::try_from(height.revision_height).map_err(Error::invalid_height)?;
 
         self.fetch_light_block(AtHeight::At(height))
@@ -105,6 +127,8 @@ impl super::LightClient<CosmosSdkChain> for LightClient {
     ) -> Result<Option<MisbehaviourEvidence>, Error> {
         crate::time!("light client check_misbehaviour");
 
+        self.follow_current_node()?;
+
         let update_header = update.header.clone().ok_or_else(|| {
             Error::misbehaviour(format!(
                 "missing header in update client event {}",
@@ -127,8 +151,9 @@ impl super::LightClient<CosmosSdkChain> for LightClient {
         let target_height = core::cmp::min(update.consensus_height(), latest_chain_height);
         let trusted_height = update_header.trusted_height;
 
-        // TODO - check that a consensus state at trusted_height still exists on-chain,
-        // currently we don't have access to Cosmos chain query from here
+        // NOTE: We don't have access to the chain hosting the client from here, so it is up
+        // to the caller to check that a consensus state at `trusted_height` still exists
+        // on-chain, see `ForeignClient::detect_misbehaviour_in_all_consensus_states`.
 
         if trusted_height >= latest_chain_height {
             // Can happen with multiple FLA attacks, we return no evidence and hope to catch this in
@@ -164,25 +189,75 @@ impl super::LightClient<CosmosSdkChain> for LightClient {
 }
 
 impl LightClient {
-    pub fn from_config(config: &ChainConfig, peer_id: PeerId) -> Result<Self, Error> {
-        let rpc_client = rpc::HttpClient::new(config.rpc_addr.clone())
-            .map_err(|e| Error::rpc(config.rpc_addr.clone(), e))?;
-
-        let io = components::io::ProdIo::new(peer_id, rpc_client, Some(config.rpc_timeout));
+    /// Creates a light client fetching the light blocks from the full node
+    /// of the chain currently in use.
+    pub fn from_config(
+        config: &ChainConfig,
+        endpoints: Arc<Endpoints>,
+        rt: Arc<TokioRuntime>,
+    ) -> Result<Self, Error> {
+        let (rpc_addr, peer_id, io) = connect(&endpoints, &rt, config.rpc_timeout)?;
 
         Ok(Self {
             chain_id: config.id.clone(),
+            rpc_timeout: config.rpc_timeout,
+            endpoints,
+            rt,
+            rpc_addr,
             peer_id,
             io,
+            store: DiskStore::from_config(config),
         })
     }
 
-    fn prepare_client(&self, client_state: &AnyClientState) -> Result<TmLightClient, Error> {
-        let clock = components::clock::SystemClock;
-        let hasher = operations::hasher::ProdHasher;
-        let verifier = components::verifier::ProdVerifier::default();
-        let scheduler = components::scheduler::basic_bisecting_schedule;
+    /// Switches to the full node currently in use, if the chain failed over
+    /// to another node since the light blocks were last fetched.
+    fn follow_current_node(&mut self) -> Result<(), Error> {
+        if self.endpoints.current().rpc_addr == self.rpc_addr {
+            return Ok(());
+        }
+
+        let (rpc_addr, peer_id, io) = connect(&self.endpoints, &self.rt, self.rpc_timeout)?;
+
+        debug!(
+            "[{}] fetching the light blocks from node {} instead of {}",
+            self.chain_id, rpc_addr, self.rpc_addr
+        );
+
+        self.rpc_addr = rpc_addr;
+        self.peer_id = peer_id;
+        self.io = io;
+
+        Ok(())
+    }
+
+    /// Verifies the light block at the `target` height, starting from the given
+    /// `trusted` light block rather than from a block fetched from the chain.
+    pub fn verify_from(
+        &mut self,
+        trusted: LightBlock,
+        target: ibc::Height,
+        options: TmOptions,
+    ) -> Result<LightBlock, Error> {
+        trace!(trusted = %trusted.height(), %target, "light client verification from trusted block");
+
+        self.follow_current_node()?;
+
+        let target_height =
+            TMHeight::try_from(target.revision_height).map_err(Error::invalid_height)?;
+
+        let client = self.new_client(options);
+
+        let mut store = MemoryStore::new();
+        store.insert(trusted, Status::Trusted);
+        let mut state = LightClientState::new(store);
+
+        client
+            .verify_to_target(target_height, &mut state)
+            .map_err(|e| Error::light_client(self.chain_id.to_string(), e))
+    }
 
+    fn prepare_client(&self, client_state: &AnyClientState) -> Result<TmLightClient, Error> {
         let client_state =
             downcast!(client_state => AnyClientState::Tendermint).ok_or_else(|| {
                 Error::client_type_mismatch(ClientType::Tendermint, client_state.client_type())
@@ -197,7 +272,16 @@ impl LightClient {
             clock_drift: client_state.max_clock_drift,
         };
 
-        Ok(TmLightClient::new(
+        Ok(self.new_client(params))
+    }
+
+    fn new_client(&self, params: TmOptions) -> TmLightClient {
+        let clock = components::clock::SystemClock;
+        let hasher = operations::hasher::ProdHasher;
+        let verifier = components::verifier::ProdVerifier::default();
+        let scheduler = components::scheduler::basic_bisecting_schedule;
+
+        TmLightClient::new(
             self.peer_id,
             params,
             clock,
@@ -205,10 +289,14 @@ impl LightClient {
             verifier,
             hasher,
             self.io.clone(),
-        ))
+        )
     }
 
-    fn prepare_state(&self, trusted: ibc::Height) -> Result<LightClientState, Error> {
+    fn prepare_state(
+        &self,
+        trusted: ibc::Height,
+        target: TMHeight,
+    ) -> Result<LightClientState, Error> {
         let trusted_height =
             TMHeight::try_from(trusted.revision_height).map_err(Error::invalid_height)?;
 
@@ -217,15 +305,74 @@ impl LightClient {
         let mut store = MemoryStore::new();
         store.insert(trusted_block, Status::Trusted);
 
+        // The blocks stored on disk between the trusted and target heights are
+        // still verified by the light client, but do not need to be fetched again.
+        if let Some(disk_store) = &self.store {
+            for block in disk_store.range(trusted_height, target) {
+                store.insert(block, Status::Unverified);
+            }
+        }
+
         Ok(LightClientState::new(store))
     }
 
+    /// Stores on disk the blocks fetched during the verification, and removes
+    /// the blocks which are older than the trusting period of the client.
+    fn persist_state(&self, state: &LightClientState, client_state: &AnyClientState) {
+        let disk_store = match &self.store {
+            Some(disk_store) => disk_store,
+            None => return,
+        };
+
+        for status in [Status::Trusted, Status::Verified, Status::Unverified] {
+            for block in state.light_store.all(status) {
+                disk_store.insert(&block);
+            }
+        }
+
+        if let Some(client_state) = downcast!(client_state => AnyClientState::Tendermint) {
+            disk_store.prune(client_state.trusting_period);
+        }
+    }
+
     fn fetch_light_block(&self, height: AtHeight) -> Result<LightBlock, Error> {
         use tendermint_light_client::components::io::Io;
 
-        self.io
-            .fetch_light_block(height)
-            .map_err(|e| Error::light_client_io(self.chain_id.to_string(), e))
+        if let (Some(disk_store), AtHeight::At(height)) = (&self.store, height) {
+            if let Some(block) = disk_store.get(height) {
+                // Only the header is fetched to check that the stored block is the block
+                // of the chain, rather than of the chain before it was reset
+                let signed_header =
+                    self.io
+                        .fetch_signed_header(AtHeight::At(height))
+                        .map_err(|e| {
+                            self.endpoints.record_error();
+                            Error::light_client_io(self.chain_id.to_string(), e)
+                        })?;
+
+                if signed_header.header.hash() == block.signed_header.header.hash() {
+                    return Ok(block);
+                }
+
+                warn!(
+                    "[{}] the stored light block at height {} differs from the chain, removing all the stored light blocks",
+                    self.chain_id, height
+                );
+
+                disk_store.clear();
+            }
+        }
+
+        let block = self.io.fetch_light_block(height).map_err(|e| {
+            self.endpoints.record_error();
+            Error::light_client_io(self.chain_id.to_string(), e)
+        })?;
+
+        if let Some(disk_store) = &self.store {
+            disk_store.insert(&block);
+        }
+
+        Ok(block)
     }
 
     fn adjust_headers(
@@ -293,3 +440,27 @@ impl LightClient {
         Ok((target_header, supporting_headers))
     }
 }
+
+/// Connects to the full node of the chain currently in use, returning
+/// its address, its peer id and the component fetching the light blocks from it.
+fn connect(
+    endpoints: &Endpoints,
+    rt: &TokioRuntime,
+    rpc_timeout: Duration,
+) -> Result<(rpc::Url, PeerId, components::io::ProdIo), Error> {
+    use rpc::Client;
+
+    let node = endpoints.current();
+
+    let peer_id: PeerId = rt
+        .block_on(node.rpc_client.status())
+        .map(|s| s.node_info.id)
+        .map_err(|e| {
+            endpoints.record_error();
+            Error::rpc(node.rpc_addr.clone(), e)
+        })?;
+
+    let io = components::io::ProdIo::new(peer_id, node.rpc_client.clone(), Some(rpc_timeout));
+
+    Ok((node.rpc_addr.clone(), peer_id, io))
+}
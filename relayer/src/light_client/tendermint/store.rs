@@ -0,0 +1,337 @@
+//! A store of the light blocks of a chain on disk, kept across client updates
+//! and restarts so that the light client does not fetch the same blocks again.
+//!
+//! The blocks are stored as one JSON file per height. They are not trusted:
+//! apart from the trusted block of a verification, which the light client
+//! would otherwise fetch from the full node without verifying it, and whose
+//! hash is checked against the header of the full node at its height, they are
+//! only used to avoid fetching blocks which the light client still verifies.
+//!
+//! The blocks of a chain which was reset are discarded as soon as a block which
+//! differs from the stored one at the same height is fetched.
+
+use core::time::Duration;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use tendermint::Time;
+use tendermint_light_client::types::{Height as TMHeight, LightBlock};
+use tracing::{debug, warn};
+
+use ibc::core::ics24_host::identifier::ChainId;
+
+use crate::config::ChainConfig;
+
+/// How often the blocks older than the trusting period are removed from the store
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug)]
+pub struct DiskStore {
+    chain_id: ChainId,
+    dir: PathBuf,
+    last_pruned: Mutex<Option<Instant>>,
+}
+
+impl DiskStore {
+    /// Returns the store of the chain, if the persistent light store is enabled
+    /// and its directory is known.
+    pub fn from_config(config: &ChainConfig) -> Option<Self> {
+        if !config.light_store.persistent {
+            return None;
+        }
+
+        let dir = match &config.light_store.dir {
+            Some(dir) => dir.clone(),
+            None => dirs_next::home_dir()?.join(".hermes").join("light_store"),
+        };
+
+        Some(Self {
+            chain_id: config.id.clone(),
+            dir: dir.join(config.id.as_str()),
+            last_pruned: Mutex::new(None),
+        })
+    }
+
+    fn path(&self, height: u64) -> PathBuf {
+        self.dir.join(format!("{}.json", height))
+    }
+
+    pub fn get(&self, height: TMHeight) -> Option<LightBlock> {
+        let path = self.path(height.value());
+
+        let contents = fs::read_to_string(&path).ok()?;
+
+        match serde_json::from_str::<LightBlock>(&contents) {
+            Ok(block) if block.signed_header.header.chain_id.as_str() == self.chain_id.as_str() => {
+                Some(block)
+            }
+            Ok(block) => {
+                warn!(
+                    "[{}] ignoring the light block at {} of chain {}",
+                    self.chain_id,
+                    path.display(),
+                    block.signed_header.header.chain_id
+                );
+
+                None
+            }
+            Err(e) => {
+                warn!(
+                    "[{}] ignoring the light block at {}: {}",
+                    self.chain_id,
+                    path.display(),
+                    e
+                );
+
+                None
+            }
+        }
+    }
+
+    /// Returns the blocks in the store above the height `from` and up to the height `to`.
+    pub fn range(&self, from: TMHeight, to: TMHeight) -> Vec<LightBlock> {
+        self.heights()
+            .into_iter()
+            .filter(|h| *h > from.value() && *h <= to.value())
+            .filter_map(|h| TMHeight::try_from(h).ok())
+            .filter_map(|h| self.get(h))
+            .collect()
+    }
+
+    /// Stores the given block, unless it is already stored. If another block is stored
+    /// at the same height, the chain was reset and all the stored blocks are removed.
+    pub fn insert(&self, block: &LightBlock) {
+        let path = self.path(block.height().value());
+
+        if let Some(stored) = self.get(block.height()) {
+            if stored.signed_header.header.hash() == block.signed_header.header.hash() {
+                return;
+            }
+
+            warn!(
+                "[{}] the stored light block at height {} differs from the chain, removing all the stored light blocks",
+                self.chain_id,
+                block.height()
+            );
+
+            self.clear();
+        }
+
+        // The block is written to a temporary file first, so that a block is never
+        // read while it is partially written
+        let tmp_path = path.with_extension("json.tmp");
+
+        let res = fs::create_dir_all(&self.dir)
+            .map_err(|e| e.to_string())
+            .and_then(|_| serde_json::to_string(block).map_err(|e| e.to_string()))
+            .and_then(|json| fs::write(&tmp_path, json).map_err(|e| e.to_string()))
+            .and_then(|_| fs::rename(&tmp_path, &path).map_err(|e| e.to_string()));
+
+        if let Err(e) = res {
+            warn!(
+                "[{}] failed to store the light block at {}: {}",
+                self.chain_id,
+                path.display(),
+                e
+            );
+        }
+    }
+
+    /// Removes all the stored blocks.
+    pub fn clear(&self) {
+        if let Err(e) = fs::remove_dir_all(&self.dir) {
+            warn!(
+                "[{}] failed to remove the light blocks at {}: {}",
+                self.chain_id,
+                self.dir.display(),
+                e
+            );
+        }
+    }
+
+    /// Removes the blocks older than the trusting period, at most once per [`PRUNE_INTERVAL`].
+    pub fn prune(&self, trusting_period: Duration) {
+        {
+            let mut last_pruned = self.last_pruned.lock().expect("poisoned lock");
+
+            if matches!(*last_pruned, Some(t) if t.elapsed() < PRUNE_INTERVAL) {
+                return;
+            }
+
+            *last_pruned = Some(Instant::now());
+        }
+
+        let mut pruned = 0;
+
+        for height in self.heights() {
+            let expired = TMHeight::try_from(height)
+                .ok()
+                .and_then(|h| self.get(h))
+                .map_or(true, |block| {
+                    Time::now()
+                        .duration_since(block.signed_header.header.time)
+                        .map_or(false, |age| age > trusting_period)
+                });
+
+            if expired && fs::remove_file(self.path(height)).is_ok() {
+                pruned += 1;
+            }
+        }
+
+        debug!(
+            "[{}] pruned {} light blocks older than the trusting period",
+            self.chain_id, pruned
+        );
+    }
+
+    fn heights(&self) -> Vec<u64> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(_) => return vec![],
+        };
+
+        entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                entry
+                    .file_name()
+                    .to_str()?
+                    .strip_suffix(".json")?
+                    .parse()
+                    .ok()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tendermint_testgen::{Generator, LightBlock as TestgenLightBlock};
+
+    fn store(dir: &tempfile::TempDir) -> DiskStore {
+        DiskStore {
+            chain_id: ChainId::new("chain_a".to_string(), 0),
+            dir: dir.path().join("chain_a-0"),
+            last_pruned: Mutex::new(None),
+        }
+    }
+
+    /// Generates a block of the given chain, produced `age` ago.
+    fn block(chain_id: &str, height: u64, age: Duration) -> LightBlock {
+        let time = Time::now()
+            .duration_since(Time::unix_epoch())
+            .unwrap()
+            .saturating_sub(age)
+            .as_secs();
+
+        let block = TestgenLightBlock::new_default_with_time_and_chain_id(
+            chain_id.to_string(),
+            time,
+            height,
+        )
+        .generate()
+        .unwrap();
+
+        LightBlock::new(
+            block.signed_header,
+            block.validators,
+            block.next_validators,
+            block.provider,
+        )
+    }
+
+    fn height(h: u64) -> TMHeight {
+        TMHeight::try_from(h).unwrap()
+    }
+
+    #[test]
+    fn insert_get_and_range() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store(&dir);
+
+        assert!(store.get(height(1)).is_none());
+        assert!(store.range(height(0), height(10)).is_empty());
+
+        for h in 1..=3 {
+            store.insert(&block("chain_a-0", h, Duration::ZERO));
+        }
+
+        assert_eq!(store.get(height(2)).unwrap().height(), height(2));
+
+        let mut heights: Vec<_> = store
+            .range(height(1), height(3))
+            .iter()
+            .map(|b| b.height().value())
+            .collect();
+        heights.sort_unstable();
+        assert_eq!(heights, vec![2, 3]);
+
+        // The blocks are written through temporary files, which are renamed once written
+        let files: Vec<_> = fs::read_dir(&store.dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        assert_eq!(files.len(), 3);
+        assert!(files.iter().all(|f| f.ends_with(".json")));
+    }
+
+    #[test]
+    fn blocks_of_other_chains_are_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store(&dir);
+
+        let other = block("chain_b-0", 1, Duration::ZERO);
+        fs::create_dir_all(&store.dir).unwrap();
+        fs::write(store.path(1), serde_json::to_string(&other).unwrap()).unwrap();
+
+        assert!(store.get(height(1)).is_none());
+
+        // Nor is a corrupted block
+        fs::write(store.path(2), "{").unwrap();
+        assert!(store.get(height(2)).is_none());
+    }
+
+    #[test]
+    fn blocks_are_removed_on_chain_reset() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store(&dir);
+
+        let stale = block("chain_a-0", 1, Duration::from_secs(60));
+        store.insert(&stale);
+        store.insert(&block("chain_a-0", 2, Duration::from_secs(60)));
+
+        // Inserting the same block again is a no-op
+        store.insert(&stale);
+        assert_eq!(store.heights().len(), 2);
+
+        // A different block at the same height means that the chain was reset
+        let fresh = block("chain_a-0", 1, Duration::ZERO);
+        store.insert(&fresh);
+
+        assert!(store.get(height(2)).is_none());
+        assert_eq!(
+            store.get(height(1)).unwrap().signed_header.header.hash(),
+            fresh.signed_header.header.hash()
+        );
+    }
+
+    #[test]
+    fn prune_expired_blocks() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store(&dir);
+
+        let trusting_period = Duration::from_secs(60 * 60);
+
+        store.insert(&block("chain_a-0", 1, 2 * trusting_period));
+        store.insert(&block("chain_a-0", 2, Duration::ZERO));
+
+        store.prune(trusting_period);
+
+        assert!(store.get(height(1)).is_none());
+        assert!(store.get(height(2)).is_some());
+    }
+}
@@ -1,3 +1,5 @@
+use prost_types::Any;
+
 use ibc::{
     core::{
         ics03_connection::connection::State as ConnectionState,
@@ -12,17 +14,23 @@ use crate::chain::counterparty::check_channel_counterparty;
 use crate::chain::handle::ChainHandle;
 use crate::channel::{Channel, ChannelSide};
 use crate::link::error::LinkError;
+use crate::link::operational_data::OperationalDataTarget;
 use crate::link::relay_path::RelayPath;
 
 pub mod error;
 mod operational_data;
+mod packet_selection;
 mod pending;
+mod pending_timeouts;
 mod relay_path;
 mod relay_sender;
 mod relay_summary;
+#[cfg(any(test, feature = "mocks"))]
+pub mod replay;
 mod tx_hashes;
 use tx_hashes::TxHashes;
 
+pub use packet_selection::{PacketSelection, SequenceRange, SequenceRangeError};
 // Re-export the telemetries summary
 pub use relay_summary::RelaySummary;
 
@@ -32,6 +40,16 @@ pub struct LinkParameters {
     pub src_channel_id: ChannelId,
 }
 
+/// Messages assembled for relaying packets over a link, without being sent,
+/// split by the chain they target.
+#[derive(Clone, Debug, Default)]
+pub struct AssembledMsgs {
+    /// Messages targeting the source chain, e.g. timeouts
+    pub src: Vec<Any>,
+    /// Messages targeting the destination chain, e.g. packet receipts
+    pub dst: Vec<Any>,
+}
+
 pub struct Link<ChainA: ChainHandle, ChainB: ChainHandle> {
     pub a_to_b: RelayPath<ChainA, ChainB>,
 }
@@ -163,35 +181,97 @@ impl<ChainA: ChainHandle, ChainB: ChainHandle> Link<ChainA, ChainB> {
 
     /// Implements the `packet-recv` CLI
     pub fn build_and_send_recv_packet_messages(&mut self) -> Result<Vec<IbcEvent>, LinkError> {
-        self.a_to_b.build_recv_packet_and_timeout_msgs(None)?;
+        self.send_recv_packet_messages(&PacketSelection::default())
+            .map(|summary| summary.events)
+    }
+
+    /// Implements the `packet-ack` CLI
+    pub fn build_and_send_ack_packet_messages(&mut self) -> Result<Vec<IbcEvent>, LinkError> {
+        self.send_ack_packet_messages(&PacketSelection::default())
+            .map(|summary| summary.events)
+    }
+
+    /// Relays the RecvPacket and Timeout messages for the pending packets picked by `selection`.
+    /// Implements the recv step of the `clear packets` CLI.
+    ///
+    /// Packets which get relayed by another relayer in the meantime, e.g. by a running
+    /// supervisor, are dropped from the messages when they are regenerated after a failure.
+    ///
+    /// If the supervisor submits its transactions with the same key, either may fail with an
+    /// account sequence mismatch. The chain refreshes the account sequence it caches and
+    /// retries the broadcast, or, if the mismatch is detected while estimating the gas,
+    /// drops the transaction, whose packets are then regenerated on the next attempt.
+    /// Using a different key than the supervisor avoids these failures altogether.
+    pub fn send_recv_packet_messages(
+        &mut self,
+        selection: &PacketSelection,
+    ) -> Result<RelaySummary, LinkError> {
+        self.a_to_b
+            .build_recv_packet_and_timeout_msgs(selection.max_height, selection)?;
+
+        self.send_scheduled_msgs()
+    }
+
+    /// Relays the acknowledgment messages for the pending acknowledgments picked by `selection`.
+    /// Implements the ack step of the `clear packets` CLI.
+    pub fn send_ack_packet_messages(
+        &mut self,
+        selection: &PacketSelection,
+    ) -> Result<RelaySummary, LinkError> {
+        self.a_to_b
+            .build_packet_ack_msgs(selection.max_height, selection)?;
 
-        let mut results = vec![];
+        self.send_scheduled_msgs()
+    }
+
+    /// Assembles the messages that [`Link::send_recv_packet_messages`] would send,
+    /// without sending them
+    pub fn build_recv_packet_messages(
+        &mut self,
+        selection: &PacketSelection,
+    ) -> Result<AssembledMsgs, LinkError> {
+        self.a_to_b
+            .build_recv_packet_and_timeout_msgs(selection.max_height, selection)?;
+        self.assemble_scheduled_msgs()
+    }
+
+    /// Assembles the messages that [`Link::send_ack_packet_messages`] would send,
+    /// without sending them
+    pub fn build_ack_packet_messages(
+        &mut self,
+        selection: &PacketSelection,
+    ) -> Result<AssembledMsgs, LinkError> {
+        self.a_to_b
+            .build_packet_ack_msgs(selection.max_height, selection)?;
+        self.assemble_scheduled_msgs()
+    }
+
+    fn send_scheduled_msgs(&self) -> Result<RelaySummary, LinkError> {
+        let mut summary = RelaySummary::empty();
 
         // Block waiting for all of the scheduled data (until `None` is returned)
         while let Some(odata) = self.a_to_b.fetch_scheduled_operational_data() {
-            let mut last_res = self
+            let last_res = self
                 .a_to_b
                 .relay_from_operational_data::<relay_sender::SyncSender>(odata)?;
-            results.append(&mut last_res.events);
+            summary.extend(last_res);
         }
 
-        Ok(results)
+        Ok(summary)
     }
 
-    /// Implements the `packet-ack` CLI
-    pub fn build_and_send_ack_packet_messages(&mut self) -> Result<Vec<IbcEvent>, LinkError> {
-        self.a_to_b.build_packet_ack_msgs(None)?;
-
-        let mut results = vec![];
+    fn assemble_scheduled_msgs(&self) -> Result<AssembledMsgs, LinkError> {
+        let mut msgs = AssembledMsgs::default();
 
-        // Block waiting for all of the scheduled data
         while let Some(odata) = self.a_to_b.fetch_scheduled_operational_data() {
-            let mut last_res = self
-                .a_to_b
-                .relay_from_operational_data::<relay_sender::SyncSender>(odata)?;
-            results.append(&mut last_res.events);
+            let assembled = odata.assemble_msgs(&self.a_to_b)?;
+
+            match odata.target {
+                OperationalDataTarget::Source => msgs.src.extend(assembled),
+                OperationalDataTarget::Destination => msgs.dst.extend(assembled),
+            }
         }
 
-        Ok(results)
+        Ok(msgs)
     }
 }
@@ -0,0 +1,234 @@
+use core::fmt;
+use core::ops::RangeInclusive;
+use core::str::FromStr;
+
+use flex_error::define_error;
+
+use ibc::core::ics04_channel::packet::Sequence;
+use ibc::events::IbcEvent;
+use ibc::Height;
+
+define_error! {
+    SequenceRangeError {
+        Invalid
+            { range: String }
+            |e| {
+                format!("invalid sequence range '{}', expected a sequence or a range of the form 'start..end', with start <= end",
+                    e.range)
+            },
+    }
+}
+
+/// An inclusive range of packet sequences, parsed from either a
+/// single sequence, e.g. `42`, or a range of the form `10..20`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SequenceRange(RangeInclusive<u64>);
+
+impl SequenceRange {
+    pub fn contains(&self, sequence: Sequence) -> bool {
+        self.0.contains(&u64::from(sequence))
+    }
+}
+
+impl FromStr for SequenceRange {
+    type Err = SequenceRangeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |seq: &str| {
+            seq.trim()
+                .parse::<u64>()
+                .map_err(|_| SequenceRangeError::invalid(s.to_string()))
+        };
+
+        let (start, end) = match s.split_once("..") {
+            Some((start, end)) => (parse(start)?, parse(end)?),
+            None => {
+                let seq = parse(s)?;
+                (seq, seq)
+            }
+        };
+
+        if start > end {
+            return Err(SequenceRangeError::invalid(s.to_string()));
+        }
+
+        Ok(Self(start..=end))
+    }
+}
+
+impl fmt::Display for SequenceRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.start() == self.0.end() {
+            write!(f, "{}", self.0.start())
+        } else {
+            write!(f, "{}..{}", self.0.start(), self.0.end())
+        }
+    }
+}
+
+/// Restricts which of the pending packets of a path are cleared
+#[derive(Clone, Debug, Default)]
+pub struct PacketSelection {
+    /// Only clear the packets with a sequence in one of these ranges,
+    /// or all the pending packets if there are none
+    pub sequences: Vec<SequenceRange>,
+    /// Clear at most this many of the packets within the height window,
+    /// starting with the lowest sequences
+    pub max_packets: Option<usize>,
+    /// Only clear the packets whose events were emitted at or after this height
+    pub min_height: Option<Height>,
+    /// Only clear the packets whose events were emitted at or before this height,
+    /// which is also the height at which the packet proofs are queried
+    pub max_height: Option<Height>,
+}
+
+impl PacketSelection {
+    /// Retains the pending sequences which are in the selected ranges.
+    /// The cap on the number of packets is only applied to the events of these sequences,
+    /// by [`PacketSelection::select_events`], once the events tell which packets
+    /// are within the height window.
+    pub fn select(&self, mut sequences: Vec<Sequence>) -> Vec<Sequence> {
+        if !self.sequences.is_empty() {
+            sequences.retain(|seq| self.sequences.iter().any(|range| range.contains(*seq)));
+        }
+
+        sequences
+    }
+
+    /// Retains the packet events emitted within the height window of the selection,
+    /// keeping those of at most `max_packets` packets, starting with the lowest sequences.
+    /// The order of the events is preserved.
+    pub fn select_events(&self, events: &mut Vec<IbcEvent>) {
+        events.retain(|event| self.in_height_window(event));
+
+        if let Some(max_packets) = self.max_packets {
+            let mut sequences: Vec<Sequence> = events
+                .iter()
+                .filter_map(|event| event.packet().map(|packet| packet.sequence))
+                .collect();
+
+            sequences.sort();
+            sequences.dedup();
+            sequences.truncate(max_packets);
+
+            events.retain(|event| {
+                event
+                    .packet()
+                    .map_or(true, |packet| sequences.contains(&packet.sequence))
+            });
+        }
+    }
+
+    /// Whether the given event was emitted within the height window of the selection.
+    pub fn in_height_window(&self, event: &IbcEvent) -> bool {
+        self.min_height.map_or(true, |min| event.height() >= min)
+            && self.max_height.map_or(true, |max| event.height() <= max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ibc::core::ics04_channel::events::SendPacket;
+    use ibc::core::ics04_channel::packet::Packet;
+
+    fn seqs(seqs: &[u64]) -> Vec<Sequence> {
+        seqs.iter().copied().map(Sequence::from).collect()
+    }
+
+    #[test]
+    fn parse_sequence_ranges() {
+        assert_eq!(
+            "10..20".parse::<SequenceRange>().unwrap(),
+            SequenceRange(10..=20)
+        );
+        assert_eq!(
+            "42".parse::<SequenceRange>().unwrap(),
+            SequenceRange(42..=42)
+        );
+        assert!("20..10".parse::<SequenceRange>().is_err());
+        assert!("1..".parse::<SequenceRange>().is_err());
+        assert!("abc".parse::<SequenceRange>().is_err());
+    }
+
+    #[test]
+    fn select_sequences() {
+        let all = PacketSelection::default();
+        assert_eq!(all.select(seqs(&[1, 2, 3])), seqs(&[1, 2, 3]));
+
+        let ranges = PacketSelection {
+            sequences: vec![SequenceRange(2..=4), SequenceRange(8..=8)],
+            ..Default::default()
+        };
+        assert_eq!(ranges.select(seqs(&[1, 2, 3, 5, 8, 9])), seqs(&[2, 3, 8]));
+
+        // The cap is only applied to the events, within the height window
+        let capped = PacketSelection {
+            max_packets: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(capped.select(seqs(&[5, 3, 4])), seqs(&[5, 3, 4]));
+    }
+
+    fn send_packet(height: u64, sequence: u64) -> IbcEvent {
+        IbcEvent::SendPacket(SendPacket {
+            height: Height::new(0, height),
+            packet: Packet {
+                sequence: sequence.into(),
+                ..Default::default()
+            },
+        })
+    }
+
+    fn event_sequences(events: &[IbcEvent]) -> Vec<Sequence> {
+        events
+            .iter()
+            .filter_map(|event| event.packet().map(|packet| packet.sequence))
+            .collect()
+    }
+
+    #[test]
+    fn select_events_in_height_window() {
+        let selection = PacketSelection {
+            min_height: Some(Height::new(0, 10)),
+            max_height: Some(Height::new(0, 15)),
+            ..Default::default()
+        };
+
+        let mut events = vec![
+            send_packet(5, 1),
+            send_packet(10, 2),
+            send_packet(15, 3),
+            send_packet(20, 4),
+        ];
+        selection.select_events(&mut events);
+
+        assert_eq!(event_sequences(&events), seqs(&[2, 3]));
+    }
+
+    #[test]
+    fn select_max_packets_within_height_window() {
+        let selection = PacketSelection {
+            max_packets: Some(2),
+            min_height: Some(Height::new(0, 10)),
+            ..Default::default()
+        };
+
+        // The lowest sequences are below the height window, and do not count towards the cap
+        let sequences = selection.select(seqs(&[1, 2, 3, 4, 5]));
+        assert_eq!(sequences, seqs(&[1, 2, 3, 4, 5]));
+
+        let mut events = vec![
+            send_packet(5, 1),
+            send_packet(8, 2),
+            send_packet(12, 4),
+            send_packet(10, 3),
+            send_packet(15, 5),
+        ];
+        selection.select_events(&mut events);
+
+        // The order of the events is preserved
+        assert_eq!(event_sequences(&events), seqs(&[4, 3]));
+    }
+}
@@ -59,6 +59,13 @@ impl<Chain> PendingTxs<Chain> {
             pending_queue: Queue::new(),
         }
     }
+
+    /// The number of submitted transactions which are not confirmed yet.
+    /// Transactions rejected by `deliver_tx` are never committed, so they are not counted.
+    pub fn pending_tx_count(&self) -> usize {
+        self.pending_queue
+            .fold(0, |count, pending| count + pending.tx_hashes.0.len())
+    }
 }
 
 impl<Chain: ChainHandle> PendingTxs<Chain> {
@@ -245,3 +252,49 @@ impl<Chain: ChainHandle> fmt::Display for PendingTxs<Chain> {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ibc::Height;
+    use tendermint::abci::transaction;
+
+    use crate::link::operational_data::OperationalDataTarget;
+
+    fn pending_data(tx_count: u8) -> PendingData {
+        PendingData {
+            original_od: OperationalData::new(
+                Height::new(0, 1),
+                OperationalDataTarget::Destination,
+            ),
+            tx_hashes: TxHashes(
+                (0..tx_count)
+                    .map(|i| transaction::Hash::new([i; 32]))
+                    .collect(),
+            ),
+            submit_time: Instant::now(),
+            error_events: vec![],
+        }
+    }
+
+    #[test]
+    fn pending_tx_count_counts_transactions() {
+        let pending = PendingTxs::new(
+            (),
+            ChannelId::default(),
+            PortId::transfer(),
+            ChainId::new("ibc-1".to_string(), 1),
+        );
+        assert_eq!(pending.pending_tx_count(), 0);
+
+        // An operational data may be split into several transactions
+        pending.pending_queue.push_back(pending_data(3));
+        pending.pending_queue.push_back(pending_data(1));
+
+        // All the transactions of this one were rejected
+        pending.pending_queue.push_back(pending_data(0));
+
+        assert_eq!(pending.pending_tx_count(), 4);
+    }
+}
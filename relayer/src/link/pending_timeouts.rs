@@ -0,0 +1,359 @@
+//! Tracking of the timeouts of the packets relayed over a path, so that the packets
+//! are timed out as soon as the destination chain passes their deadline.
+//!
+//! A packet stays tracked while its timeout is in flight, until the timeout or the
+//! acknowledgement of the packet is confirmed by the events of the source chain.
+//! Its timeout is scheduled again if its submission fails, or if it is still not
+//! confirmed after [`RESCHEDULE_AFTER`].
+
+use alloc::collections::BTreeMap;
+use core::time::Duration;
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
+
+use ibc::core::ics04_channel::events::SendPacket;
+use ibc::core::ics04_channel::packet::{Packet, Sequence};
+use ibc::core::ics24_host::identifier::{ChannelId, PortId};
+use ibc::events::IbcEvent;
+use ibc::timestamp::Timestamp;
+use ibc::Height;
+
+use crate::chain::StatusResponse;
+use crate::util::lock::LockExt;
+
+/// The time after which the timeout of a packet is scheduled again, if it was
+/// neither confirmed nor reported as failed since it was scheduled.
+pub const RESCHEDULE_AFTER: Duration = Duration::from_secs(120);
+
+#[derive(Clone, Debug)]
+struct PendingTimeout {
+    event: SendPacket,
+    /// When the timeout of the packet was scheduled, if it is in flight
+    scheduled: Option<Instant>,
+}
+
+impl PendingTimeout {
+    fn in_flight(&self) -> bool {
+        self.scheduled
+            .map_or(false, |scheduled| scheduled.elapsed() < RESCHEDULE_AFTER)
+    }
+}
+
+/// The packets with a timeout which were sent from the source chain of a path and
+/// relayed to its destination chain, by sequence. The clones of a `PendingTimeouts`
+/// share the same packets.
+#[derive(Clone, Debug)]
+pub struct PendingTimeouts {
+    src_port_id: PortId,
+    src_channel_id: ChannelId,
+    dst_port_id: PortId,
+    dst_channel_id: ChannelId,
+    packets: Arc<RwLock<BTreeMap<Sequence, PendingTimeout>>>,
+}
+
+impl PendingTimeouts {
+    pub fn new(
+        src_port_id: PortId,
+        src_channel_id: ChannelId,
+        dst_port_id: PortId,
+        dst_channel_id: ChannelId,
+    ) -> Self {
+        Self {
+            src_port_id,
+            src_channel_id,
+            dst_port_id,
+            dst_channel_id,
+            packets: Arc::new(RwLock::new(BTreeMap::new())),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.packets.acquire_read().is_empty()
+    }
+
+    /// Tracks the timeout of a packet which is relayed to the destination chain,
+    /// unless the packet never times out.
+    pub fn track(&self, event: &SendPacket) {
+        let packet = &event.packet;
+
+        if packet.timeout_height == Height::zero() && packet.timeout_timestamp == Timestamp::none()
+        {
+            return;
+        }
+
+        self.packets.acquire_write().insert(
+            packet.sequence,
+            PendingTimeout {
+                event: event.clone(),
+                scheduled: None,
+            },
+        );
+    }
+
+    /// Stops tracking the packets which no longer need to be timed out, according to the
+    /// given events: the packets acknowledged or timed out on the source chain, if the events
+    /// were emitted by the source chain, and the packets received on the destination chain,
+    /// if they were emitted by the destination chain.
+    pub fn untrack(&self, events: &[IbcEvent], from_src: bool, from_dst: bool) {
+        let done = events.iter().filter_map(|event| match event {
+            IbcEvent::AcknowledgePacket(ev) if from_src => Some(&ev.packet),
+            IbcEvent::TimeoutPacket(ev) if from_src => Some(&ev.packet),
+            IbcEvent::TimeoutOnClosePacket(ev) if from_src => Some(&ev.packet),
+            IbcEvent::ReceivePacket(ev) if from_dst => Some(&ev.packet),
+            IbcEvent::WriteAcknowledgement(ev) if from_dst => Some(&ev.packet),
+            _ => None,
+        });
+
+        let mut packets = self.packets.acquire_write();
+
+        for packet in done.filter(|packet| self.is_on_path(packet)) {
+            packets.remove(&packet.sequence);
+        }
+    }
+
+    /// The tracked packets whose deadline the destination chain has passed, given its status,
+    /// or all of them if the channel was closed on the destination chain, except the packets
+    /// whose timeout is in flight.
+    pub fn expired(&self, dst_status: &StatusResponse, dst_closed: bool) -> Vec<SendPacket> {
+        self.packets
+            .acquire_read()
+            .values()
+            .filter(|pending| !pending.in_flight())
+            .map(|pending| &pending.event)
+            .filter(|ev| {
+                dst_closed
+                    || ev
+                        .packet
+                        .timed_out(&dst_status.timestamp, dst_status.height)
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Marks the timeouts of the packets with the given sequences as in flight, until
+    /// they are confirmed, see [`Self::untrack`], or put back, see [`Self::reschedule`].
+    pub fn schedule(&self, sequences: &[Sequence]) {
+        let mut packets = self.packets.acquire_write();
+        let now = Instant::now();
+
+        for sequence in sequences {
+            if let Some(pending) = packets.get_mut(sequence) {
+                pending.scheduled = Some(now);
+            }
+        }
+    }
+
+    /// Puts back the timeouts of the packets with the given sequences, which failed
+    /// to be submitted, so that they are scheduled again.
+    pub fn reschedule(&self, sequences: &[Sequence]) {
+        let mut packets = self.packets.acquire_write();
+
+        for sequence in sequences {
+            if let Some(pending) = packets.get_mut(sequence) {
+                pending.scheduled = None;
+            }
+        }
+    }
+
+    /// Puts back all the timeouts in flight, when a transaction failed on the source chain
+    /// without telling which packets it was timing out.
+    pub fn reschedule_all(&self) {
+        for pending in self.packets.acquire_write().values_mut() {
+            pending.scheduled = None;
+        }
+    }
+
+    /// Stops tracking the packets with the given sequences.
+    pub fn remove(&self, sequences: &[Sequence]) {
+        let mut packets = self.packets.acquire_write();
+
+        for sequence in sequences {
+            packets.remove(sequence);
+        }
+    }
+
+    fn is_on_path(&self, packet: &Packet) -> bool {
+        packet.source_port == self.src_port_id
+            && packet.source_channel == self.src_channel_id
+            && packet.destination_port == self.dst_port_id
+            && packet.destination_channel == self.dst_channel_id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ibc::core::ics04_channel::events::{
+        AcknowledgePacket, TimeoutPacket, WriteAcknowledgement,
+    };
+
+    fn pending_timeouts() -> PendingTimeouts {
+        PendingTimeouts::new(
+            PortId::transfer(),
+            ChannelId::new(0),
+            PortId::transfer(),
+            ChannelId::new(1),
+        )
+    }
+
+    fn packet(sequence: u64, timeout_height: u64) -> Packet {
+        Packet {
+            sequence: sequence.into(),
+            source_port: PortId::transfer(),
+            source_channel: ChannelId::new(0),
+            destination_port: PortId::transfer(),
+            destination_channel: ChannelId::new(1),
+            data: vec![],
+            timeout_height: Height::new(0, timeout_height),
+            timeout_timestamp: Timestamp::none(),
+        }
+    }
+
+    fn send_packet(packet: Packet) -> SendPacket {
+        SendPacket {
+            height: Height::new(0, 1),
+            packet,
+        }
+    }
+
+    fn status(height: u64) -> StatusResponse {
+        StatusResponse {
+            height: Height::new(0, height),
+            timestamp: Timestamp::none(),
+        }
+    }
+
+    fn sequences(events: &[SendPacket]) -> Vec<u64> {
+        events.iter().map(|ev| ev.packet.sequence.into()).collect()
+    }
+
+    #[test]
+    fn packets_without_timeout_are_not_tracked() {
+        let pending = pending_timeouts();
+
+        pending.track(&send_packet(packet(1, 0)));
+        assert!(pending.is_empty());
+
+        pending.track(&send_packet(packet(2, 10)));
+        assert!(!pending.is_empty());
+    }
+
+    #[test]
+    fn packets_expire_once_destination_passes_deadline() {
+        let pending = pending_timeouts();
+
+        pending.track(&send_packet(packet(1, 10)));
+        pending.track(&send_packet(packet(2, 20)));
+
+        assert!(pending.expired(&status(10), false).is_empty());
+        assert_eq!(sequences(&pending.expired(&status(11), false)), vec![1]);
+        assert_eq!(sequences(&pending.expired(&status(21), false)), vec![1, 2]);
+
+        // All the packets in flight are timed out on close
+        assert_eq!(sequences(&pending.expired(&status(1), true)), vec![1, 2]);
+
+        pending.remove(&[1.into()]);
+        assert_eq!(sequences(&pending.expired(&status(21), false)), vec![2]);
+    }
+
+    #[test]
+    fn packets_are_untracked_once_acknowledged_or_timed_out() {
+        let pending = pending_timeouts();
+
+        for sequence in 1..=3 {
+            pending.track(&send_packet(packet(sequence, 10)));
+        }
+
+        let events = vec![
+            IbcEvent::AcknowledgePacket(AcknowledgePacket {
+                height: Height::new(0, 5),
+                packet: packet(1, 10),
+            }),
+            IbcEvent::TimeoutPacket(TimeoutPacket {
+                height: Height::new(0, 12),
+                packet: packet(2, 10),
+            }),
+        ];
+
+        // The events of the destination chain do not acknowledge or time out the packets
+        pending.untrack(&events, false, true);
+        assert_eq!(
+            sequences(&pending.expired(&status(11), false)),
+            vec![1, 2, 3]
+        );
+
+        pending.untrack(&events, true, false);
+        assert_eq!(sequences(&pending.expired(&status(11), false)), vec![3]);
+    }
+
+    #[test]
+    fn packets_are_untracked_once_received() {
+        let pending = pending_timeouts();
+
+        pending.track(&send_packet(packet(1, 10)));
+        pending.track(&send_packet(packet(2, 10)));
+
+        let mut other_channel = packet(2, 10);
+        other_channel.source_channel = ChannelId::new(5);
+
+        let events = vec![
+            IbcEvent::WriteAcknowledgement(WriteAcknowledgement {
+                height: Height::new(0, 5),
+                packet: packet(1, 10),
+                ack: vec![1],
+            }),
+            IbcEvent::WriteAcknowledgement(WriteAcknowledgement {
+                height: Height::new(0, 5),
+                packet: other_channel,
+                ack: vec![1],
+            }),
+        ];
+
+        pending.untrack(&events, false, true);
+        assert_eq!(sequences(&pending.expired(&status(11), false)), vec![2]);
+    }
+
+    #[test]
+    fn timeouts_in_flight_are_not_scheduled_again() {
+        let pending = pending_timeouts();
+
+        pending.track(&send_packet(packet(1, 10)));
+        pending.track(&send_packet(packet(2, 10)));
+
+        pending.schedule(&[1.into()]);
+        assert_eq!(sequences(&pending.expired(&status(11), false)), vec![2]);
+        assert_eq!(sequences(&pending.expired(&status(11), true)), vec![2]);
+
+        // The timeout of the first packet failed to be submitted
+        pending.reschedule(&[1.into()]);
+        assert_eq!(sequences(&pending.expired(&status(11), false)), vec![1, 2]);
+
+        // A transaction failed on the source chain
+        pending.schedule(&[1.into(), 2.into()]);
+        assert!(pending.expired(&status(11), false).is_empty());
+
+        pending.reschedule_all();
+        assert_eq!(sequences(&pending.expired(&status(11), false)), vec![1, 2]);
+
+        // The timeout of the first packet was not confirmed in time
+        pending.schedule(&[1.into(), 2.into()]);
+        pending
+            .packets
+            .acquire_write()
+            .get_mut(&Sequence::from(1))
+            .unwrap()
+            .scheduled = Instant::now().checked_sub(RESCHEDULE_AFTER);
+        assert_eq!(sequences(&pending.expired(&status(11), false)), vec![1]);
+
+        // The timeout of the second packet is confirmed
+        let events = vec![IbcEvent::TimeoutPacket(TimeoutPacket {
+            height: Height::new(0, 12),
+            packet: packet(2, 10),
+        })];
+        pending.untrack(&events, true, false);
+
+        pending.reschedule_all();
+        assert_eq!(sequences(&pending.expired(&status(11), false)), vec![1]);
+    }
+}
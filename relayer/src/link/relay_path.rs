@@ -7,7 +7,7 @@ use std::time::Instant;
 
 use itertools::Itertools;
 use prost_types::Any;
-use tracing::{debug, error, info, trace};
+use tracing::{debug, error, info, trace, warn};
 
 use ibc::{
     core::{
@@ -15,13 +15,15 @@ use ibc::{
             channel::{ChannelEnd, Order, QueryPacketEventDataRequest, State as ChannelState},
             events::{SendPacket, WriteAcknowledgement},
             msgs::{
-                acknowledgement::MsgAcknowledgement, chan_close_confirm::MsgChannelCloseConfirm,
-                recv_packet::MsgRecvPacket, timeout::MsgTimeout,
+                acknowledgement::MsgAcknowledgement,
+                chan_close_confirm::MsgChannelCloseConfirm,
+                recv_packet::{self, MsgRecvPacket},
+                timeout::MsgTimeout,
                 timeout_on_close::MsgTimeoutOnClose,
             },
             packet::{Packet, PacketMsgType, Sequence},
         },
-        ics24_host::identifier::{ChannelId, ClientId, ConnectionId, PortId},
+        ics24_host::identifier::{ChainId, ChannelId, ClientId, ConnectionId, PortId},
     },
     events::{IbcEvent, PrettyEvents, WithBlockDataType},
     query::{QueryBlockRequest, QueryTxRequest},
@@ -35,17 +37,20 @@ use ibc_proto::ibc::core::channel::v1::{
 };
 
 use crate::chain::counterparty::{
-    unreceived_acknowledgements_sequences, unreceived_packets_sequences,
+    commitments_on_chain, unreceived_acknowledgements_sequences, unreceived_packets_sequences,
 };
 use crate::chain::handle::ChainHandle;
 use crate::chain::StatusResponse;
 use crate::channel::error::ChannelError;
 use crate::channel::Channel;
+use crate::config::Ics20Filter;
 use crate::event::monitor::EventBatch;
 use crate::foreign_client::{ForeignClient, ForeignClientError};
 use crate::link::error::{self, LinkError};
 use crate::link::operational_data::{OperationalData, OperationalDataTarget, TransitMessage};
+use crate::link::packet_selection::PacketSelection;
 use crate::link::pending::PendingTxs;
+use crate::link::pending_timeouts::PendingTimeouts;
 use crate::link::relay_sender::{AsyncReply, SubmitReply};
 use crate::link::relay_summary::RelaySummary;
 use crate::link::{pending, relay_sender};
@@ -68,6 +73,21 @@ pub struct RelayPath<ChainA: ChainHandle, ChainB: ChainHandle> {
     // flag turns to `false`.
     clear_packets: Arc<RwLock<bool>>,
 
+    // For ordered channels, the sequence which the destination chain
+    // expects to receive next once all the transactions submitted so far
+    // are committed, or `None` if there are no such transactions.
+    ordered_next_sequence: Arc<RwLock<Option<Sequence>>>,
+
+    // Rules on the content of the ICS20 packets sent from the source chain.
+    ics20_filter: Ics20Filter,
+
+    // The packets with a timeout which were sent from the source chain and relayed
+    // to the destination chain, by sequence. They are timed out as soon as the
+    // destination chain passes their deadline without having received them, see
+    // [`RelayPath::schedule_expired_timeouts`], and are dropped once they are received,
+    // acknowledged or timed out.
+    pending_timeouts: PendingTimeouts,
+
     // Operational data, targeting both the source and destination chain.
     // These vectors of operational data are ordered decreasingly by
     // their age, with element at position `0` being the oldest.
@@ -112,6 +132,11 @@ impl<ChainA: ChainHandle, ChainB: ChainHandle> RelayPath<ChainA, ChainB> {
         let src_port_id = channel.src_port_id().clone();
         let dst_port_id = channel.dst_port_id().clone();
 
+        let ics20_filter = src_chain
+            .config()
+            .map_err(|e| LinkError::query(src_chain_id.clone(), e))?
+            .ics20_filter;
+
         Ok(Self {
             channel,
 
@@ -121,6 +146,14 @@ impl<ChainA: ChainHandle, ChainB: ChainHandle> RelayPath<ChainA, ChainB> {
             dst_port_id: dst_port_id.clone(),
 
             clear_packets: Arc::new(RwLock::new(true)),
+            ordered_next_sequence: Arc::new(RwLock::new(None)),
+            ics20_filter,
+            pending_timeouts: PendingTimeouts::new(
+                src_port_id.clone(),
+                src_channel_id.clone(),
+                dst_port_id.clone(),
+                dst_channel_id.clone(),
+            ),
             src_operational_data: Queue::new(),
             dst_operational_data: Queue::new(),
 
@@ -247,51 +280,58 @@ impl<ChainA: ChainHandle, ChainB: ChainHandle> RelayPath<ChainA, ChainB> {
     // Determines if the events received are relevant and should be processed.
     // Only events for a port/channel matching one of the channel ends should be processed.
     fn filter_relaying_events(&self, events: Vec<IbcEvent>) -> Vec<IbcEvent> {
-        let src_channel_id = self.src_channel_id();
+        events
+            .into_iter()
+            .filter(|event| self.is_relaying_event(event))
+            .collect()
+    }
 
-        let mut result = vec![];
+    fn is_relaying_event(&self, event: &IbcEvent) -> bool {
+        let src_channel_id = self.src_channel_id();
 
-        for event in events.into_iter() {
-            match &event {
-                IbcEvent::SendPacket(send_packet_ev) => {
-                    if src_channel_id == send_packet_ev.src_channel_id()
-                        && self.src_port_id() == send_packet_ev.src_port_id()
-                    {
-                        result.push(event);
-                    }
-                }
-                IbcEvent::WriteAcknowledgement(write_ack_ev) => {
-                    if src_channel_id == write_ack_ev.dst_channel_id()
-                        && self.src_port_id() == write_ack_ev.dst_port_id()
-                    {
-                        result.push(event);
-                    }
-                }
-                IbcEvent::CloseInitChannel(chan_close_ev) => {
-                    if src_channel_id == chan_close_ev.channel_id()
-                        && self.src_port_id() == chan_close_ev.port_id()
-                    {
-                        result.push(event);
-                    }
-                }
-                IbcEvent::TimeoutPacket(timeout_ev) => {
-                    if src_channel_id == timeout_ev.src_channel_id()
-                        && self.channel.src_port_id() == timeout_ev.src_port_id()
-                    {
-                        result.push(event);
-                    }
-                }
-                _ => {}
+        match event {
+            IbcEvent::SendPacket(send_packet_ev) => {
+                src_channel_id == send_packet_ev.src_channel_id()
+                    && self.src_port_id() == send_packet_ev.src_port_id()
+                    && self.send_packet_allowed(&send_packet_ev.packet)
+            }
+            IbcEvent::WriteAcknowledgement(write_ack_ev) => {
+                src_channel_id == write_ack_ev.dst_channel_id()
+                    && self.src_port_id() == write_ack_ev.dst_port_id()
+            }
+            IbcEvent::CloseInitChannel(chan_close_ev) => {
+                src_channel_id == chan_close_ev.channel_id()
+                    && self.src_port_id() == chan_close_ev.port_id()
             }
+            IbcEvent::TimeoutPacket(timeout_ev) => {
+                src_channel_id == timeout_ev.src_channel_id()
+                    && self.channel.src_port_id() == timeout_ev.src_port_id()
+            }
+            _ => false,
+        }
+    }
+
+    /// Checks the content of a packet sent from the source chain against
+    /// the ICS20 filter of that chain.
+    fn send_packet_allowed(&self, packet: &Packet) -> bool {
+        let allowed = self.ics20_filter.is_allowed(packet);
+
+        if !allowed {
+            debug!(
+                "[{}] packet with sequence {} filtered out by its content",
+                self, packet.sequence
+            );
         }
-        result
+
+        allowed
     }
 
     fn relay_pending_packets(&self, height: Option<Height>) -> Result<(), LinkError> {
         for i in 1..=MAX_RETRIES {
+            let all = PacketSelection::default();
             let cleared = self
-                .build_recv_packet_and_timeout_msgs(height)
-                .and_then(|()| self.build_packet_ack_msgs(height));
+                .build_recv_packet_and_timeout_msgs(height, &all)
+                .and_then(|()| self.build_packet_ack_msgs(height, &all));
 
             match cleared {
                 Ok(()) => return Ok(()),
@@ -335,8 +375,51 @@ impl<ChainA: ChainHandle, ChainB: ChainHandle> RelayPath<ChainA, ChainB> {
         Ok(())
     }
 
+    /// Whether the given event, emitted by the chain `chain_id`, signals that
+    /// the channel end of the destination chain was closed.
+    fn is_dst_channel_close_event(&self, chain_id: &ChainId, event: &IbcEvent) -> bool {
+        let attributes = match event {
+            IbcEvent::CloseInitChannel(close_init_ev) => close_init_ev.attributes(),
+            IbcEvent::CloseConfirmChannel(close_confirm_ev) => close_confirm_ev.attributes(),
+            _ => return false,
+        };
+
+        chain_id == &self.dst_chain().id()
+            && attributes.channel_id() == Some(self.dst_channel_id())
+            && attributes.port_id() == self.dst_port_id()
+    }
+
     /// Generate & schedule operational data from the input `batch` of IBC events.
+    ///
+    /// The batch may also come from the destination chain, when the channel was closed there.
+    /// In that case, the pending packets are cleared, so that the packets which are still
+    /// in flight get timed out on close.
     pub fn update_schedule(&self, batch: EventBatch) -> Result<(), LinkError> {
+        let dst_closed = batch
+            .events
+            .iter()
+            .any(|event| self.is_dst_channel_close_event(&batch.chain_id, event));
+
+        if dst_closed {
+            info!(
+                "[{}] channel closed on {}, timing out the packets in flight",
+                self,
+                self.dst_chain().id()
+            );
+
+            self.schedule_packet_clearing(None, true)?;
+        }
+
+        self.pending_timeouts.untrack(
+            &batch.events,
+            batch.chain_id == self.src_chain().id(),
+            batch.chain_id == self.dst_chain().id(),
+        );
+
+        if batch.chain_id != self.src_chain().id() {
+            return Ok(());
+        }
+
         // Collect relevant events from the incoming batch & adjust their height.
         let events = self.filter_relaying_events(batch.events);
 
@@ -344,6 +427,23 @@ impl<ChainA: ChainHandle, ChainB: ChainHandle> RelayPath<ChainA, ChainB> {
         self.events_to_operational_data(events)
     }
 
+    /// Generates the operational data for the input `batch` of IBC events without scheduling it,
+    /// along with the events of the batch which are not relevant to this path.
+    /// Used to replay recorded batches and review the decisions of the relayer.
+    pub fn simulate_batch(
+        &self,
+        batch: EventBatch,
+    ) -> Result<(Vec<OperationalData>, Vec<IbcEvent>), LinkError> {
+        let (events, filtered_out): (Vec<_>, Vec<_>) = batch
+            .events
+            .into_iter()
+            .partition(|event| self.is_relaying_event(event));
+
+        let (src_od, dst_od) = self.generate_operational_data(events)?;
+
+        Ok((src_od.into_iter().chain(dst_od).collect(), filtered_out))
+    }
+
     /// Produces and schedules operational data for this relaying path based on the input events.
     fn events_to_operational_data(&self, events: Vec<IbcEvent>) -> Result<(), LinkError> {
         // Obtain the operational data for the source chain (mostly timeout packets) and for the
@@ -425,10 +525,16 @@ impl<ChainA: ChainHandle, ChainB: ChainHandle> RelayPath<ChainA, ChainB> {
                         debug!("[{}] {} already handled", self, send_packet_ev);
                         (None, None)
                     } else {
-                        self.build_recv_or_timeout_from_send_packet_event(
+                        let msgs = self.build_recv_or_timeout_from_send_packet_event(
                             send_packet_ev,
                             &dst_latest_info,
-                        )?
+                        )?;
+
+                        if msgs.0.is_some() {
+                            self.pending_timeouts.track(send_packet_ev);
+                        }
+
+                        msgs
                     }
                 }
                 IbcEvent::WriteAcknowledgement(ref write_ack_ev) => {
@@ -503,7 +609,21 @@ impl<ChainA: ChainHandle, ChainB: ChainHandle> RelayPath<ChainA, ChainB> {
         // We will operate on potentially different operational data if the initial one fails.
         let mut odata = initial_od;
 
+        // Whether to submit only the first packet of an ordered channel, after a failure
+        let mut isolate_first = false;
+
         for i in 0..MAX_RETRIES {
+            let (ready, deferred) = self.plan_ordered_recv(odata, isolate_first)?;
+
+            if let Some(deferred) = deferred {
+                self.schedule_operational_data(deferred)?;
+            }
+
+            odata = match ready {
+                Some(ready) => ready,
+                None => return Ok(S::Reply::empty()), // Nothing left to submit
+            };
+
             info!(
                 "[{}] relay op. data of {} msgs(s) to {} (height {}), delayed by: {:?} [try {}/{}]",
                 self,
@@ -521,11 +641,26 @@ impl<ChainA: ChainHandle, ChainB: ChainHandle> RelayPath<ChainA, ChainB> {
                     // Done with this op. data
                     info!("[{}] success", self);
 
+                    if self.ordered_channel() {
+                        let last = odata.batch.iter().filter_map(ordered_recv_sequence).max();
+                        if let Some(last) = last {
+                            *self.ordered_next_sequence.acquire_write() = Some(last.increment());
+                        }
+                    }
+
                     return Ok(reply);
                 }
                 Err(LinkError(error::LinkErrorDetail::Send(e), _)) => {
                     // This error means we could retry
                     error!("[{}] error {}", self, e.event);
+
+                    if self.ordered_channel() {
+                        // None of the packets of the failed transaction were received, so retry
+                        // with the packet that blocks the channel alone, and defer the others.
+                        *self.ordered_next_sequence.acquire_write() = None;
+                        isolate_first = true;
+                    }
+
                     if i + 1 == MAX_RETRIES {
                         error!(
                             "[{}] {}/{} retries exhausted. giving up",
@@ -628,6 +763,100 @@ impl<ChainA: ChainHandle, ChainB: ChainHandle> RelayPath<ChainA, ChainB> {
         None
     }
 
+    /// Plans the submission of an operational data targeting the destination of an
+    /// ordered channel. Any other operational data is returned as is.
+    ///
+    /// The destination of an ordered channel only receives packets strictly in the order
+    /// of their sequences, and a transaction fails altogether as soon as one of its
+    /// packets is out of order. Hence, the `RecvPacket` messages of `odata` are sorted,
+    /// the ones for packets which were already received are dropped, and only the messages
+    /// forming a contiguous run of sequences, starting with the next sequence expected by
+    /// the destination chain, are submitted now. If `isolate_first` is set, only the first
+    /// message of the run is submitted now.
+    ///
+    /// Return value:
+    ///   - the operational data to submit now, if any message is left in it,
+    ///   - the operational data comprising the messages to submit later, if any.
+    fn plan_ordered_recv(
+        &self,
+        mut odata: OperationalData,
+        isolate_first: bool,
+    ) -> Result<(Option<OperationalData>, Option<OperationalData>), LinkError> {
+        if !self.ordered_channel() || odata.target != OperationalDataTarget::Destination {
+            return Ok((Some(odata), None));
+        }
+
+        let (recvs, others): (Vec<_>, Vec<_>) = odata
+            .batch
+            .into_iter()
+            .partition(|tm| ordered_recv_sequence(tm).is_some());
+
+        let sequence = |tm: &TransitMessage| ordered_recv_sequence(tm).unwrap_or_default();
+
+        let next_sequence = self
+            .dst_chain()
+            .query_next_sequence_receive(QueryNextSequenceReceiveRequest {
+                port_id: self.dst_port_id().to_string(),
+                channel_id: self.dst_channel_id().to_string(),
+            })
+            .map_err(|e| LinkError::query(self.dst_chain().id(), e))?;
+
+        let in_flight = *self.ordered_next_sequence.acquire_read();
+
+        let plan = plan_ordered_run(recvs, sequence, next_sequence, in_flight, isolate_first);
+
+        for tm in &plan.received {
+            debug!(
+                "[{}] dropping recv packet with sequence {}, already received",
+                self,
+                sequence(tm)
+            );
+        }
+
+        // The packets before the gap were missed altogether: clear the pending packets
+        // on the next block instead.
+        if !plan.missed.is_empty() {
+            warn!(
+                "[{}] dropping {} recv packet message(s) past a gap, starting with sequence {} \
+                while the next sequence expected on chain is {}",
+                self,
+                plan.missed.len(),
+                sequence(&plan.missed[0]),
+                next_sequence,
+            );
+
+            *self.clear_packets.acquire_write() = true;
+        }
+
+        let deferred_batch = plan.deferred;
+        let recvs = plan.ready;
+
+        let deferred = if deferred_batch.is_empty() {
+            None
+        } else {
+            info!(
+                "[{}] deferring {} recv packet message(s) starting with sequence {}",
+                self,
+                deferred_batch.len(),
+                sequence(&deferred_batch[0]),
+            );
+
+            let mut deferred = OperationalData::new(odata.proofs_height, odata.target);
+            deferred.batch = deferred_batch;
+            Some(deferred)
+        };
+
+        odata.batch = recvs.into_iter().chain(others).collect();
+
+        let ready = if odata.batch.is_empty() {
+            None
+        } else {
+            Some(odata)
+        };
+
+        Ok((ready, deferred))
+    }
+
     /// Sends a transaction based on the [`OperationalData`] to
     /// the corresponding target chain.
     ///
@@ -823,6 +1052,7 @@ impl<ChainA: ChainHandle, ChainB: ChainHandle> RelayPath<ChainA, ChainB> {
     fn target_height_and_send_packet_events(
         &self,
         opt_query_height: Option<Height>,
+        selection: &PacketSelection,
     ) -> Result<(Vec<IbcEvent>, Height), LinkError> {
         let mut events_result = vec![];
 
@@ -841,7 +1071,7 @@ impl<ChainA: ChainHandle, ChainB: ChainHandle> RelayPath<ChainA, ChainB> {
 
         let query_height = opt_query_height.unwrap_or(src_response_height);
 
-        let sequences: Vec<Sequence> = sequences.into_iter().map(From::from).collect();
+        let sequences = selection.select(sequences.into_iter().map(From::from).collect());
         if sequences.is_empty() {
             return Ok((events_result, query_height));
         }
@@ -905,6 +1135,8 @@ impl<ChainA: ChainHandle, ChainB: ChainHandle> RelayPath<ChainA, ChainB> {
         events_result.extend(tx_events);
         events_result.extend(end_block_events);
 
+        selection.select_events(&mut events_result);
+
         if events_result.is_empty() {
             info!(
                 "[{}] found zero unprocessed SendPacket events on source chain, nothing to do",
@@ -940,6 +1172,7 @@ impl<ChainA: ChainHandle, ChainB: ChainHandle> RelayPath<ChainA, ChainB> {
     fn target_height_and_write_ack_events(
         &self,
         opt_query_height: Option<Height>,
+        selection: &PacketSelection,
     ) -> Result<(Vec<IbcEvent>, Height), LinkError> {
         let mut events_result = vec![];
 
@@ -959,7 +1192,8 @@ impl<ChainA: ChainHandle, ChainB: ChainHandle> RelayPath<ChainA, ChainB> {
 
         let query_height = opt_query_height.unwrap_or(src_response_height);
 
-        let sequences: Vec<Sequence> = unreceived_acks_by_dst.into_iter().map(From::from).collect();
+        let sequences =
+            selection.select(unreceived_acks_by_dst.into_iter().map(From::from).collect());
         if sequences.is_empty() {
             return Ok((events_result, query_height));
         }
@@ -994,6 +1228,8 @@ impl<ChainA: ChainHandle, ChainB: ChainHandle> RelayPath<ChainA, ChainB> {
             }))
             .map_err(|e| LinkError::query(self.src_chain().id(), e))?;
 
+        selection.select_events(&mut events_result);
+
         if events_result.is_empty() {
             info!(
                 "[{}] found zero unprocessed WriteAcknowledgement events on source chain, nothing to do",
@@ -1024,14 +1260,23 @@ impl<ChainA: ChainHandle, ChainB: ChainHandle> RelayPath<ChainA, ChainB> {
     /// Schedules the relaying of RecvPacket and Timeout messages.
     /// The `opt_query_height` parameter allows to optionally use a specific height on the source
     /// chain where to query for packet data. If `None`, the latest available height on the source
-    /// chain is used.
+    /// chain is used. Only the pending packets picked by `selection` are relayed.
     pub fn build_recv_packet_and_timeout_msgs(
         &self,
         opt_query_height: Option<Height>,
+        selection: &PacketSelection,
     ) -> Result<(), LinkError> {
         // Get the events for the send packets on source chain that have not been received on
         // destination chain (i.e. ack was not seen on source chain).
-        let (mut events, height) = self.target_height_and_send_packet_events(opt_query_height)?;
+        let (mut events, height) =
+            self.target_height_and_send_packet_events(opt_query_height, selection)?;
+
+        events.retain(|event| match event {
+            IbcEvent::SendPacket(send_packet_ev) => {
+                self.send_packet_allowed(&send_packet_ev.packet)
+            }
+            _ => true,
+        });
 
         // Skip: no relevant events found.
         if events.is_empty() {
@@ -1050,11 +1295,16 @@ impl<ChainA: ChainHandle, ChainB: ChainHandle> RelayPath<ChainA, ChainB> {
     /// Schedules the relaying of packet acknowledgment messages.
     /// The `opt_query_height` parameter allows to optionally use a specific height on the source
     /// chain where to query for packet data. If `None`, the latest available height on the source
-    /// chain is used.
-    pub fn build_packet_ack_msgs(&self, opt_query_height: Option<Height>) -> Result<(), LinkError> {
+    /// chain is used. Only the pending acknowledgments picked by `selection` are relayed.
+    pub fn build_packet_ack_msgs(
+        &self,
+        opt_query_height: Option<Height>,
+        selection: &PacketSelection,
+    ) -> Result<(), LinkError> {
         // Get the sequences of packets that have been acknowledged on destination chain but still
         // have commitments on source chain (i.e. ack was not seen on source chain)
-        let (mut events, height) = self.target_height_and_write_ack_events(opt_query_height)?;
+        let (mut events, height) =
+            self.target_height_and_write_ack_events(opt_query_height, selection)?;
 
         // Skip: no relevant events found.
         if events.is_empty() {
@@ -1239,6 +1489,104 @@ impl<ChainA: ChainHandle, ChainB: ChainHandle> RelayPath<ChainA, ChainB> {
         }
     }
 
+    /// Schedules the Timeout messages for the tracked packets which were not received yet,
+    /// once the destination chain has passed their deadline, or the TimeoutOnClose messages
+    /// if the channel was closed on the destination chain in the meantime.
+    ///
+    /// The timeouts of the packets are otherwise only built when their `SendPacket` event is
+    /// processed, or when the pending packets are cleared, so this allows to time out the
+    /// packets promptly even when no new event occurs on the path.
+    pub fn schedule_expired_timeouts(&self) -> Result<(), LinkError> {
+        if self.pending_timeouts.is_empty() {
+            return Ok(());
+        }
+
+        let dst_status = self
+            .dst_chain()
+            .query_status()
+            .map_err(|e| LinkError::query(self.dst_chain().id(), e))?;
+
+        let dst_closed = self
+            .dst_channel(Height::zero())?
+            .state_matches(&ChannelState::Closed);
+
+        let expired = self.pending_timeouts.expired(&dst_status, dst_closed);
+
+        if expired.is_empty() {
+            return Ok(());
+        }
+
+        // The packets whose commitment was cleared on the source chain in the meantime were
+        // acknowledged or timed out already, and no packet can be timed out once the channel
+        // is closed on the source chain, e.g. after a timeout on an ordered channel
+        let src_commitments = if self
+            .src_channel(Height::zero())?
+            .state_matches(&ChannelState::Closed)
+        {
+            vec![]
+        } else {
+            commitments_on_chain(self.src_chain(), self.src_port_id(), self.src_channel_id())
+                .map_err(LinkError::supervisor)?
+                .0
+        };
+
+        let (expired, done): (Vec<SendPacket>, Vec<SendPacket>) = expired
+            .into_iter()
+            .partition(|ev| src_commitments.contains(&ev.packet.sequence.into()));
+
+        self.pending_timeouts.remove(&sequences(&done));
+
+        if expired.is_empty() {
+            return Ok(());
+        }
+
+        let unreceived_sequences = self
+            .dst_chain()
+            .query_unreceived_packets(QueryUnreceivedPacketsRequest {
+                port_id: self.dst_port_id().to_string(),
+                channel_id: self.dst_channel_id().to_string(),
+                packet_commitment_sequences: expired
+                    .iter()
+                    .map(|ev| ev.packet.sequence.into())
+                    .collect(),
+            })
+            .map_err(LinkError::relayer)?;
+
+        let (unreceived, received): (Vec<SendPacket>, Vec<SendPacket>) = expired
+            .into_iter()
+            .partition(|ev| unreceived_sequences.contains(&ev.packet.sequence.into()));
+
+        // The packets which were received in the meantime will be acknowledged instead
+        self.pending_timeouts.remove(&sequences(&received));
+
+        if unreceived.is_empty() {
+            return Ok(());
+        }
+
+        info!(
+            "[{}] packets expired on {} before being received: {} (first 10 shown here; total={})",
+            self,
+            self.dst_chain().id(),
+            unreceived
+                .iter()
+                .map(|ev| ev.packet.sequence)
+                .take(10)
+                .join(", "),
+            unreceived.len()
+        );
+
+        let scheduled = sequences(&unreceived);
+
+        self.events_to_operational_data(
+            unreceived.into_iter().map(IbcEvent::SendPacket).collect(),
+        )?;
+
+        // The packets stay tracked until their timeouts are confirmed
+        self.pending_timeouts.schedule(&scheduled);
+
+        Ok(())
+    }
+
     /// Checks if there are any operational data items ready,
     /// and if so performs the relaying of corresponding packets
     /// to the target chain.
@@ -1247,24 +1595,52 @@ impl<ChainA: ChainHandle, ChainB: ChainHandle> RelayPath<ChainA, ChainB> {
     /// Retains the operational data as pending, and associates it
     /// with one or more transaction hash(es).
     pub fn execute_schedule(&self) -> Result<(), LinkError> {
-        let (src_ods, dst_ods) = self.try_fetch_scheduled_operational_data();
+        self.execute_schedule_with(|| true)
+    }
+
+    /// Same as [`Self::execute_schedule`], except that each operational data
+    /// targeting the destination chain is only submitted if `admit` returns `true`.
+    /// Once `admit` returns `false`, the remaining operational data targeting the
+    /// destination chain are put back in the schedule, in the same order.
+    pub fn execute_schedule_with(&self, mut admit: impl FnMut() -> bool) -> Result<(), LinkError> {
+        let (src_ods, mut dst_ods) = self.try_fetch_scheduled_operational_data();
+
+        while let Some(od) = dst_ods.pop_front() {
+            if !admit() {
+                dst_ods.push_front(od);
+                break;
+            }
 
-        for od in dst_ods {
             let reply =
                 self.relay_from_operational_data::<relay_sender::AsyncSender>(od.clone())?;
 
             self.enqueue_pending_tx(reply, od);
         }
 
+        // Put back the operational data which were not admitted ahead of the others
+        while let Some(od) = dst_ods.pop_back() {
+            self.dst_operational_data.push_front(od);
+        }
+
         for od in src_ods {
-            let reply =
-                self.relay_from_operational_data::<relay_sender::AsyncSender>(od.clone())?;
-            self.enqueue_pending_tx(reply, od);
+            match self.relay_from_operational_data::<relay_sender::AsyncSender>(od.clone()) {
+                Ok(reply) => self.enqueue_pending_tx(reply, od),
+                Err(e) => {
+                    self.pending_timeouts.reschedule(&timeout_sequences(&od));
+                    return Err(e);
+                }
+            }
         }
 
         Ok(())
     }
 
+    /// The number of transactions submitted to the destination chain
+    /// which are not confirmed yet.
+    pub fn pending_dst_txs_count(&self) -> usize {
+        self.pending_txs_dst.pending_tx_count()
+    }
+
     pub fn process_pending_txs(&self) -> RelaySummary {
         if !self.confirm_txes {
             return RelaySummary::empty();
@@ -1295,6 +1671,18 @@ impl<ChainA: ChainHandle, ChainB: ChainHandle> RelayPath<ChainA, ChainB> {
             })?
             .unwrap_or_else(RelaySummary::empty);
 
+        // The packets timed out by the confirmed transactions are no longer tracked,
+        // and the timeouts in flight are scheduled again if a transaction failed.
+        self.pending_timeouts.untrack(&res.events, true, false);
+
+        if res
+            .events
+            .iter()
+            .any(|event| matches!(event, IbcEvent::ChainError(_)))
+        {
+            self.pending_timeouts.reschedule_all();
+        }
+
         Ok(res)
     }
 
@@ -1306,6 +1694,17 @@ impl<ChainA: ChainHandle, ChainB: ChainHandle> RelayPath<ChainA, ChainB> {
             })?
             .unwrap_or_else(RelaySummary::empty);
 
+        // On ordered channels, the packets submitted after a failed transaction
+        // will fail as well, so stop expecting them to be received.
+        if self.ordered_channel()
+            && res
+                .events
+                .iter()
+                .any(|event| matches!(event, IbcEvent::ChainError(_)))
+        {
+            *self.ordered_next_sequence.acquire_write() = None;
+        }
+
         Ok(res)
     }
 
@@ -1558,6 +1957,118 @@ impl<ChainA: ChainHandle, ChainB: ChainHandle> RelayPath<ChainA, ChainB> {
     }
 }
 
+/// The plan for submitting the `RecvPacket` messages of an ordered channel,
+/// as computed by [`plan_ordered_run`].
+#[derive(Debug, PartialEq)]
+struct OrderedRunPlan<T> {
+    /// The messages forming a contiguous run of sequences, to submit now
+    ready: Vec<T>,
+    /// The messages to submit later, once the ones submitted now are committed
+    deferred: Vec<T>,
+    /// The messages for packets already received by the destination chain
+    received: Vec<T>,
+    /// The messages past a gap of sequences which no pending transaction fills
+    missed: Vec<T>,
+}
+
+/// Plans the submission of the `RecvPacket` messages `recvs` for an ordered channel,
+/// whose destination expects `next_sequence`, while the transactions which are not
+/// committed yet end right before `in_flight`, if any.
+///
+/// The messages are sorted and deduplicated by sequence. The ones for packets which
+/// were already received are dropped, and only the ones forming a contiguous run of
+/// sequences, starting with the next sequence expected by the destination chain, or
+/// by the pending transactions, are ready. The ones past a gap are deferred if the
+/// pending transactions fill the gap, and dropped otherwise. If `isolate_first` is set,
+/// only the first message of the run is ready.
+fn plan_ordered_run<T>(
+    recvs: Vec<T>,
+    sequence: impl Fn(&T) -> Sequence,
+    next_sequence: Sequence,
+    in_flight: Option<Sequence>,
+    isolate_first: bool,
+) -> OrderedRunPlan<T> {
+    let (received, mut recvs): (Vec<_>, Vec<_>) = recvs
+        .into_iter()
+        .partition(|tm| sequence(tm) < next_sequence);
+
+    recvs.sort_by_key(&sequence);
+    recvs.dedup_by_key(|tm| sequence(tm));
+
+    // Unless this batch starts with the next sequence expected on chain, it is meant to
+    // follow the packets submitted in transactions which are not committed yet.
+    let head_of_line = recvs.first().map(&sequence) == Some(next_sequence);
+
+    let mut expected = match in_flight {
+        Some(in_flight) if !head_of_line && in_flight > next_sequence => in_flight,
+        _ => next_sequence,
+    };
+
+    let run_len = recvs
+        .iter()
+        .position(|tm| {
+            let in_order = sequence(tm) == expected;
+            expected = expected.increment();
+            !in_order
+        })
+        .unwrap_or(recvs.len());
+
+    let past_gap = recvs.split_off(run_len);
+
+    // Unless the transactions which are not committed yet fill the gap, the packets
+    // before it were missed altogether.
+    let filling_gap = in_flight.map_or(false, |in_flight| in_flight > next_sequence);
+
+    let (mut past_gap, missed) = if filling_gap {
+        (past_gap, vec![])
+    } else {
+        (vec![], past_gap)
+    };
+
+    let mut deferred = if isolate_first && recvs.len() > 1 {
+        recvs.split_off(1)
+    } else {
+        vec![]
+    };
+    deferred.append(&mut past_gap);
+
+    OrderedRunPlan {
+        ready: recvs,
+        deferred,
+        received,
+        missed,
+    }
+}
+
+/// The sequence of the packet relayed by a `RecvPacket` message, if `tm` is one
+fn ordered_recv_sequence(tm: &TransitMessage) -> Option<Sequence> {
+    match &tm.event {
+        IbcEvent::SendPacket(ev) if tm.msg.type_url == recv_packet::TYPE_URL => {
+            Some(ev.packet.sequence)
+        }
+        _ => None,
+    }
+}
+
+/// The sequences of the packets sent by the given events
+fn sequences(events: &[SendPacket]) -> Vec<Sequence> {
+    events.iter().map(|ev| ev.packet.sequence).collect()
+}
+
+/// The sequences of the packets timed out by the messages of the given operational data,
+/// which are built from the `SendPacket` events of the packets.
+fn timeout_sequences(od: &OperationalData) -> Vec<Sequence> {
+    od.batch
+        .iter()
+        .filter_map(|tm| match &tm.event {
+            IbcEvent::SendPacket(ev) if od.target == OperationalDataTarget::Source => {
+                Some(ev.packet.sequence)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
 impl<ChainA: ChainHandle, ChainB: ChainHandle> fmt::Display for RelayPath<ChainA, ChainB> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
@@ -1570,3 +2081,232 @@ impl<ChainA: ChainHandle, ChainB: ChainHandle> fmt::Display for RelayPath<ChainA
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::str::FromStr;
+    use core::time::Duration;
+
+    use tokio::runtime::Runtime as TokioRuntime;
+
+    use ibc::core::ics04_channel::events::{Attributes, CloseConfirm, CloseInit, OpenConfirm};
+    use ibc::mock::context::MockContext;
+    use ibc::mock::host::HostType;
+    use ibc::timestamp::Timestamp;
+
+    use crate::chain::handle::ProdChainHandle;
+    use crate::chain::mock::test_utils::get_basic_chain_config;
+    use crate::chain::mock::MockChain;
+    use crate::chain::runtime::ChainRuntime;
+    use crate::channel::ChannelSide;
+
+    fn chain(id: &str, rt: &Arc<TokioRuntime>) -> ProdChainHandle {
+        let config = get_basic_chain_config(id);
+        let context = MockContext::new(
+            config.id.clone(),
+            HostType::Mock,
+            1,
+            Height::new(config.id.version(), 5),
+        );
+
+        let chain = MockChain::new(config, Arc::new(RwLock::new(context)));
+        ChainRuntime::<MockChain>::spawn_with_endpoint(chain, rt.clone()).unwrap()
+    }
+
+    /// The path relaying the packets sent over `transfer/channel-0` on `chain_a`
+    /// to `transfer/channel-1` on `chain_b`.
+    fn relay_path(rt: &Arc<TokioRuntime>) -> RelayPath<ProdChainHandle, ProdChainHandle> {
+        let side = |chain, channel_id| {
+            ChannelSide::new(
+                chain,
+                ClientId::default(),
+                ConnectionId::default(),
+                PortId::transfer(),
+                Some(ChannelId::new(channel_id)),
+                None,
+            )
+        };
+
+        let channel = Channel {
+            ordering: Order::Unordered,
+            a_side: side(chain("chain_a", rt), 0),
+            b_side: side(chain("chain_b", rt), 1),
+            connection_delay: Duration::ZERO,
+        };
+
+        RelayPath::new(channel, false).unwrap()
+    }
+
+    fn close_attributes(port_id: PortId, channel_id: u64) -> Attributes {
+        Attributes {
+            height: Height::new(0, 5),
+            port_id,
+            channel_id: Some(ChannelId::new(channel_id)),
+            connection_id: ConnectionId::default(),
+            counterparty_port_id: PortId::transfer(),
+            counterparty_channel_id: None,
+        }
+    }
+
+    #[test]
+    fn dst_channel_close_events() {
+        let rt = Arc::new(TokioRuntime::new().unwrap());
+        let path = relay_path(&rt);
+
+        let chain_a = ChainId::from_str("chain_a").unwrap();
+        let chain_b = ChainId::from_str("chain_b").unwrap();
+
+        let close_init =
+            IbcEvent::CloseInitChannel(CloseInit(close_attributes(PortId::transfer(), 1)));
+        let close_confirm =
+            IbcEvent::CloseConfirmChannel(CloseConfirm(close_attributes(PortId::transfer(), 1)));
+
+        assert!(path.is_dst_channel_close_event(&chain_b, &close_init));
+        assert!(path.is_dst_channel_close_event(&chain_b, &close_confirm));
+
+        // The same channel end identifiers, on the source chain
+        assert!(!path.is_dst_channel_close_event(&chain_a, &close_init));
+
+        // Other channel ends of the destination chain
+        let other_channel =
+            IbcEvent::CloseInitChannel(CloseInit(close_attributes(PortId::transfer(), 2)));
+        let other_port = IbcEvent::CloseInitChannel(CloseInit(close_attributes(
+            PortId::from_str("icahost").unwrap(),
+            1,
+        )));
+
+        assert!(!path.is_dst_channel_close_event(&chain_b, &other_channel));
+        assert!(!path.is_dst_channel_close_event(&chain_b, &other_port));
+
+        // Other events on the channel end of the destination chain
+        let mut attributes = close_attributes(PortId::transfer(), 1);
+        attributes.counterparty_channel_id = Some(ChannelId::new(0));
+        let open_confirm = IbcEvent::OpenConfirmChannel(OpenConfirm(attributes));
+
+        assert!(!path.is_dst_channel_close_event(&chain_b, &open_confirm));
+    }
+
+    #[test]
+    fn timeouts_failing_to_be_submitted_are_scheduled_again() {
+        let rt = Arc::new(TokioRuntime::new().unwrap());
+        let path = relay_path(&rt);
+
+        let send_packet = SendPacket {
+            height: Height::new(0, 1),
+            packet: Packet {
+                sequence: 1.into(),
+                source_port: PortId::transfer(),
+                source_channel: ChannelId::new(0),
+                destination_port: PortId::transfer(),
+                destination_channel: ChannelId::new(1),
+                data: vec![],
+                timeout_height: Height::new(0, 3),
+                timeout_timestamp: Timestamp::none(),
+            },
+        };
+
+        let dst_status = StatusResponse {
+            height: Height::new(0, 5),
+            timestamp: Timestamp::none(),
+        };
+
+        path.pending_timeouts.track(&send_packet);
+        path.pending_timeouts
+            .schedule(&[send_packet.packet.sequence]);
+        assert!(path.pending_timeouts.expired(&dst_status, false).is_empty());
+
+        let mut od = OperationalData::new(Height::new(0, 4), OperationalDataTarget::Source);
+        od.batch.push(TransitMessage {
+            event: IbcEvent::SendPacket(send_packet),
+            msg: Any::default(),
+        });
+        path.schedule_operational_data(od).unwrap();
+
+        // The submission fails, since `chain_a` hosts no client of `chain_b`
+        assert!(path.execute_schedule().is_err());
+
+        assert_eq!(path.pending_timeouts.expired(&dst_status, false).len(), 1);
+    }
+
+    fn seqs(seqs: &[u64]) -> Vec<Sequence> {
+        seqs.iter().copied().map(Sequence::from).collect()
+    }
+
+    fn plan_run(
+        recvs: &[u64],
+        next_sequence: u64,
+        in_flight: Option<u64>,
+        isolate_first: bool,
+    ) -> OrderedRunPlan<Sequence> {
+        plan_ordered_run(
+            seqs(recvs),
+            |s| *s,
+            next_sequence.into(),
+            in_flight.map(Sequence::from),
+            isolate_first,
+        )
+    }
+
+    #[test]
+    fn ordered_run_sorts_and_drops_received() {
+        let plan = plan_run(&[5, 3, 4, 2, 4], 3, None, false);
+
+        assert_eq!(plan.ready, seqs(&[3, 4, 5]));
+        assert_eq!(plan.received, seqs(&[2]));
+        assert!(plan.deferred.is_empty());
+        assert!(plan.missed.is_empty());
+    }
+
+    #[test]
+    fn ordered_run_drops_messages_past_a_gap() {
+        let plan = plan_run(&[1, 2, 4, 5], 1, None, false);
+
+        assert_eq!(plan.ready, seqs(&[1, 2]));
+        assert_eq!(plan.missed, seqs(&[4, 5]));
+        assert!(plan.deferred.is_empty());
+
+        // A batch which does not start with the next sequence is missed altogether
+        let plan = plan_run(&[3, 4], 1, None, false);
+
+        assert!(plan.ready.is_empty());
+        assert_eq!(plan.missed, seqs(&[3, 4]));
+    }
+
+    #[test]
+    fn ordered_run_follows_pending_transactions() {
+        // Sequences 1 to 3 are submitted but not committed yet
+        let plan = plan_run(&[6, 4, 5], 1, Some(4), false);
+
+        assert_eq!(plan.ready, seqs(&[4, 5, 6]));
+        assert!(plan.deferred.is_empty());
+        assert!(plan.missed.is_empty());
+
+        // The gap after them may still be filled, so the messages past it are deferred
+        let plan = plan_run(&[4, 6], 1, Some(4), false);
+
+        assert_eq!(plan.ready, seqs(&[4]));
+        assert_eq!(plan.deferred, seqs(&[6]));
+        assert!(plan.missed.is_empty());
+    }
+
+    #[test]
+    fn ordered_run_restarts_from_head_of_line() {
+        // The batch starts with the next sequence expected on chain, e.g. after a failed
+        // transaction, so the pending transactions are not waited for
+        let plan = plan_run(&[1, 2, 3], 1, Some(4), false);
+
+        assert_eq!(plan.ready, seqs(&[1, 2, 3]));
+        assert!(plan.deferred.is_empty());
+    }
+
+    #[test]
+    fn ordered_run_isolates_first_message() {
+        let plan = plan_run(&[1, 2, 3], 1, None, true);
+
+        assert_eq!(plan.ready, seqs(&[1]));
+        assert_eq!(plan.deferred, seqs(&[2, 3]));
+        assert!(plan.missed.is_empty());
+    }
+}
@@ -1,6 +1,8 @@
+use serde::Serialize;
+
 use ibc::events::IbcEvent;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct RelaySummary {
     pub events: Vec<IbcEvent>,
     // errors: todo!(),
@@ -0,0 +1,540 @@
+//! Replays of recorded event batches against mock chains, reproducing the decisions
+//! of the relayer on a path: which events it drops, and which operational data it builds.
+//!
+//! The state of both ends of the path is rebuilt from the recorded events before each
+//! batch is replayed: the packets sent, received, acknowledged or timed out, and whether
+//! the channel ends are closed. As the state of the chains before the recording started
+//! is unknown, the recording should contain the batches of both chains of the path,
+//! starting before the packets of interest were sent. The proofs of the messages built
+//! during a replay are not valid.
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::sync::Arc;
+use core::time::Duration;
+use std::io;
+use std::sync::RwLock;
+
+use flex_error::{define_error, TraceError};
+use serde::Serialize;
+use tokio::runtime::Runtime as TokioRuntime;
+
+use ibc::core::ics04_channel::channel::{ChannelEnd, Counterparty, Order, State as ChannelState};
+use ibc::core::ics04_channel::context::ChannelKeeper;
+use ibc::core::ics04_channel::error::Error as ChannelError;
+use ibc::core::ics04_channel::events::{CloseConfirm, CloseInit};
+use ibc::core::ics04_channel::packet::{Packet, Receipt, Sequence};
+use ibc::core::ics04_channel::Version;
+use ibc::core::ics24_host::identifier::{ChainId, ChannelId, ClientId, ConnectionId, PortId};
+use ibc::events::IbcEvent;
+use ibc::mock::context::MockContext;
+use ibc::mock::host::HostType;
+use ibc::timestamp::Timestamp;
+use ibc::Height;
+
+use crate::chain::handle::{ChainHandle, ProdChainHandle};
+use crate::chain::mock::MockChain;
+use crate::chain::runtime::ChainRuntime;
+use crate::channel::{Channel, ChannelSide};
+use crate::config::Config;
+use crate::dry_run::DecodedMsg;
+use crate::error::Error;
+use crate::event::monitor::EventBatch;
+use crate::event::recording::RecordedBatch;
+use crate::link::error::LinkError;
+use crate::link::operational_data::{OperationalData, OperationalDataTarget};
+use crate::link::Link;
+use crate::util::lock::LockExt;
+
+define_error! {
+    ReplayError {
+        ChainNotFound
+            { chain_id: ChainId }
+            |e| { format!("chain {} not found in configuration", e.chain_id) },
+
+        Runtime
+            [ TraceError<io::Error> ]
+            |_| { "failed to start the tokio runtime" },
+
+        Spawn
+            { chain_id: ChainId }
+            [ Error ]
+            |e| { format!("failed to spawn the runtime of the mock chain {}", e.chain_id) },
+
+        MockState
+            { chain_id: ChainId }
+            [ ChannelError ]
+            |e| { format!("failed to set up the state of the mock chain {}", e.chain_id) },
+
+        Link
+            [ LinkError ]
+            |_| { "failed to set up the link" },
+    }
+}
+
+/// The path whose relaying is replayed, from the source to the destination chain.
+/// The packets relayed in the opposite direction are replayed by swapping both ends.
+#[derive(Clone, Debug)]
+pub struct ReplayPath {
+    pub src_chain_id: ChainId,
+    pub src_port_id: PortId,
+    pub src_channel_id: ChannelId,
+    pub dst_chain_id: ChainId,
+    pub dst_port_id: PortId,
+    pub dst_channel_id: ChannelId,
+    pub ordering: Order,
+}
+
+/// The decisions of the relayer on a batch of events of the source chain
+#[derive(Clone, Debug, Serialize)]
+pub struct BatchReplay {
+    pub chain_id: ChainId,
+    pub height: Height,
+    pub recorded_at: Timestamp,
+    /// The events which are not relevant to the path, and are dropped
+    pub filtered_out: Vec<IbcEvent>,
+    pub operational_data: Vec<ReplayedOperationalData>,
+    /// The reason why the operational data could not be built, if so
+    pub error: Option<String>,
+}
+
+impl BatchReplay {
+    fn is_empty(&self) -> bool {
+        self.filtered_out.is_empty() && self.operational_data.is_empty() && self.error.is_none()
+    }
+}
+
+/// Operational data built during a replay, with its messages decoded
+#[derive(Clone, Debug, Serialize)]
+pub struct ReplayedOperationalData {
+    pub target_chain_id: ChainId,
+    pub proofs_height: Height,
+    pub events: Vec<IbcEvent>,
+    pub messages: Vec<DecodedMsg>,
+}
+
+impl ReplayedOperationalData {
+    fn new(od: &OperationalData, path: &ReplayPath) -> Self {
+        let target_chain_id = match od.target {
+            OperationalDataTarget::Source => path.src_chain_id.clone(),
+            OperationalDataTarget::Destination => path.dst_chain_id.clone(),
+        };
+
+        Self {
+            target_chain_id,
+            proofs_height: od.proofs_height,
+            events: od.events(),
+            messages: od.batch.iter().map(|m| DecodedMsg::from(&m.msg)).collect(),
+        }
+    }
+}
+
+/// The state of a channel end, as of the last batch of its chain replayed
+struct ChannelEndState {
+    chain_id: ChainId,
+    port_id: PortId,
+    channel_id: ChannelId,
+    channel_end: ChannelEnd,
+    height: Height,
+    /// The packets sent whose commitment was not cleared yet
+    sent: BTreeMap<Sequence, Packet>,
+    receipts: BTreeSet<Sequence>,
+    acks: BTreeMap<Sequence, Vec<u8>>,
+    next_sequence_recv: Sequence,
+}
+
+impl ChannelEndState {
+    fn new(
+        chain_id: ChainId,
+        port_id: PortId,
+        channel_id: ChannelId,
+        counterparty: Counterparty,
+        ordering: Order,
+    ) -> Self {
+        let channel_end = ChannelEnd::new(
+            ChannelState::Open,
+            ordering,
+            counterparty,
+            vec![ConnectionId::default()],
+            Version::empty(),
+        );
+
+        Self {
+            height: Height::new(chain_id.version(), 1),
+            chain_id,
+            port_id,
+            channel_id,
+            channel_end,
+            sent: BTreeMap::new(),
+            receipts: BTreeSet::new(),
+            acks: BTreeMap::new(),
+            next_sequence_recv: Sequence::from(1),
+        }
+    }
+
+    fn is_source(&self, packet: &Packet) -> bool {
+        packet.source_port == self.port_id && packet.source_channel == self.channel_id
+    }
+
+    fn is_destination(&self, packet: &Packet) -> bool {
+        packet.destination_port == self.port_id && packet.destination_channel == self.channel_id
+    }
+
+    fn is_channel(&self, port_id: &PortId, channel_id: Option<&ChannelId>) -> bool {
+        port_id == &self.port_id && channel_id == Some(&self.channel_id)
+    }
+
+    fn ordered(&self) -> bool {
+        self.channel_end.ordering() == &Order::Ordered
+    }
+
+    fn receive(&mut self, sequence: Sequence) {
+        self.receipts.insert(sequence);
+
+        if self.ordered() && sequence >= self.next_sequence_recv {
+            self.next_sequence_recv = sequence.increment();
+        }
+    }
+
+    /// Updates the state with the events of a batch of the chain of this channel end
+    fn apply(&mut self, batch: &EventBatch) {
+        self.height = batch.height;
+
+        for event in &batch.events {
+            match event {
+                IbcEvent::SendPacket(ev) if self.is_source(&ev.packet) => {
+                    self.sent.insert(ev.packet.sequence, ev.packet.clone());
+                }
+                IbcEvent::ReceivePacket(ev) if self.is_destination(&ev.packet) => {
+                    self.receive(ev.packet.sequence);
+                }
+                IbcEvent::WriteAcknowledgement(ev) if self.is_destination(&ev.packet) => {
+                    self.receive(ev.packet.sequence);
+                    self.acks.insert(ev.packet.sequence, ev.ack.clone());
+                }
+                IbcEvent::AcknowledgePacket(ev) if self.is_source(&ev.packet) => {
+                    self.sent.remove(&ev.packet.sequence);
+                }
+                IbcEvent::TimeoutPacket(ev) if self.is_source(&ev.packet) => {
+                    self.sent.remove(&ev.packet.sequence);
+
+                    // A timeout closes an ordered channel
+                    if self.ordered() {
+                        self.channel_end.set_state(ChannelState::Closed);
+                    }
+                }
+                IbcEvent::TimeoutOnClosePacket(ev) if self.is_source(&ev.packet) => {
+                    self.sent.remove(&ev.packet.sequence);
+                }
+                IbcEvent::CloseInitChannel(CloseInit(attrs))
+                | IbcEvent::CloseConfirmChannel(CloseConfirm(attrs))
+                    if self.is_channel(&attrs.port_id, attrs.channel_id.as_ref()) =>
+                {
+                    self.channel_end.set_state(ChannelState::Closed);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Builds a mock context holding the current state, at the given time
+    fn context(&self, timestamp: Timestamp) -> Result<MockContext, ReplayError> {
+        let (port_id, channel_id) = (self.port_id.clone(), self.channel_id.clone());
+        let key = |sequence: Sequence| (port_id.clone(), channel_id.clone(), sequence);
+
+        let mut context = MockContext::new(self.chain_id.clone(), HostType::Mock, 1, self.height)
+            .with_timestamp(timestamp)
+            .with_channel(
+                port_id.clone(),
+                channel_id.clone(),
+                self.channel_end.clone(),
+            )
+            .with_recv_sequence(port_id.clone(), channel_id.clone(), self.next_sequence_recv);
+
+        let mock_state_error = |e: ChannelError| ReplayError::mock_state(self.chain_id.clone(), e);
+
+        for packet in self.sent.values() {
+            context
+                .store_packet_commitment(
+                    key(packet.sequence),
+                    packet.timeout_timestamp,
+                    packet.timeout_height,
+                    packet.data.clone(),
+                )
+                .map_err(mock_state_error)?;
+        }
+
+        for sequence in &self.receipts {
+            context
+                .store_packet_receipt(key(*sequence), Receipt::Ok)
+                .map_err(mock_state_error)?;
+        }
+
+        for (sequence, ack) in &self.acks {
+            context
+                .store_packet_acknowledgement(key(*sequence), ack.clone())
+                .map_err(mock_state_error)?;
+        }
+
+        Ok(context)
+    }
+}
+
+/// Replays the recorded batches of the source chain of the given path, in order,
+/// and returns the decisions taken on each batch with events relevant to the path.
+/// The chains of the path must be in the configuration.
+pub fn replay(
+    config: &Config,
+    path: &ReplayPath,
+    recording: Vec<RecordedBatch>,
+) -> Result<Vec<BatchReplay>, ReplayError> {
+    let rt = Arc::new(TokioRuntime::new().map_err(ReplayError::runtime)?);
+
+    let mut src = ChannelEndState::new(
+        path.src_chain_id.clone(),
+        path.src_port_id.clone(),
+        path.src_channel_id.clone(),
+        Counterparty::new(path.dst_port_id.clone(), Some(path.dst_channel_id.clone())),
+        path.ordering,
+    );
+
+    let mut dst = ChannelEndState::new(
+        path.dst_chain_id.clone(),
+        path.dst_port_id.clone(),
+        path.dst_channel_id.clone(),
+        Counterparty::new(path.src_port_id.clone(), Some(path.src_channel_id.clone())),
+        path.ordering,
+    );
+
+    let src_context = Arc::new(RwLock::new(src.context(Timestamp::none())?));
+    let dst_context = Arc::new(RwLock::new(dst.context(Timestamp::none())?));
+
+    let src_chain = spawn_mock_chain(config, &path.src_chain_id, src_context.clone(), &rt)?;
+    let dst_chain = spawn_mock_chain(config, &path.dst_chain_id, dst_context.clone(), &rt)?;
+
+    let channel = Channel {
+        ordering: path.ordering,
+        a_side: ChannelSide::new(
+            src_chain.clone(),
+            ClientId::default(),
+            ConnectionId::default(),
+            path.src_port_id.clone(),
+            Some(path.src_channel_id.clone()),
+            None,
+        ),
+        b_side: ChannelSide::new(
+            dst_chain.clone(),
+            ClientId::default(),
+            ConnectionId::default(),
+            path.dst_port_id.clone(),
+            Some(path.dst_channel_id.clone()),
+            None,
+        ),
+        connection_delay: Duration::ZERO,
+    };
+
+    let link = Link::new(channel, false).map_err(ReplayError::link)?;
+
+    let mut replays = Vec::new();
+
+    for RecordedBatch { recorded_at, batch } in recording {
+        // The events of a batch have already happened on chain when the relayer receives them
+        if batch.chain_id == src.chain_id {
+            src.apply(&batch);
+        }
+        if batch.chain_id == dst.chain_id {
+            dst.apply(&batch);
+        }
+
+        if batch.chain_id != path.src_chain_id {
+            continue;
+        }
+
+        *src_context.acquire_write() = src.context(recorded_at)?;
+        *dst_context.acquire_write() = dst.context(recorded_at)?;
+
+        let (chain_id, height) = (batch.chain_id.clone(), batch.height);
+
+        let mut replay = match link.a_to_b.simulate_batch(batch) {
+            Ok((operational_data, filtered_out)) => BatchReplay {
+                chain_id,
+                height,
+                recorded_at,
+                filtered_out,
+                operational_data: operational_data
+                    .iter()
+                    .map(|od| ReplayedOperationalData::new(od, path))
+                    .collect(),
+                error: None,
+            },
+            Err(e) => BatchReplay {
+                chain_id,
+                height,
+                recorded_at,
+                filtered_out: vec![],
+                operational_data: vec![],
+                error: Some(e.to_string()),
+            },
+        };
+
+        // New blocks are not relayed, but they are not dropped either
+        replay
+            .filtered_out
+            .retain(|e| !matches!(e, IbcEvent::NewBlock(_)));
+
+        if !replay.is_empty() {
+            replays.push(replay);
+        }
+    }
+
+    let _ = src_chain.shutdown();
+    let _ = dst_chain.shutdown();
+
+    Ok(replays)
+}
+
+fn spawn_mock_chain(
+    config: &Config,
+    chain_id: &ChainId,
+    context: Arc<RwLock<MockContext>>,
+    rt: &Arc<TokioRuntime>,
+) -> Result<ProdChainHandle, ReplayError> {
+    let chain_config = config
+        .find_chain(chain_id)
+        .ok_or_else(|| ReplayError::chain_not_found(chain_id.clone()))?;
+
+    let chain = MockChain::new(chain_config.clone(), context);
+
+    ChainRuntime::<MockChain>::spawn_with_endpoint(chain, rt.clone())
+        .map_err(|e| ReplayError::spawn(chain_id.clone(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::str::FromStr;
+
+    use ibc::core::ics04_channel::events::{ReceivePacket, SendPacket};
+    use ibc::core::ics04_channel::msgs::recv_packet;
+
+    use crate::chain::mock::test_utils::get_basic_chain_config;
+
+    fn chain_a() -> ChainId {
+        ChainId::from_str("chain_a").unwrap()
+    }
+
+    fn chain_b() -> ChainId {
+        ChainId::from_str("chain_b").unwrap()
+    }
+
+    fn config() -> Config {
+        Config {
+            chains: vec![
+                get_basic_chain_config("chain_a"),
+                get_basic_chain_config("chain_b"),
+            ],
+            ..Config::default()
+        }
+    }
+
+    fn path() -> ReplayPath {
+        ReplayPath {
+            src_chain_id: chain_a(),
+            src_port_id: PortId::transfer(),
+            src_channel_id: ChannelId::new(0),
+            dst_chain_id: chain_b(),
+            dst_port_id: PortId::transfer(),
+            dst_channel_id: ChannelId::new(1),
+            ordering: Order::Unordered,
+        }
+    }
+
+    fn packet(src_channel_id: u64, sequence: u64) -> Packet {
+        Packet {
+            sequence: sequence.into(),
+            source_port: PortId::transfer(),
+            source_channel: ChannelId::new(src_channel_id),
+            destination_port: PortId::transfer(),
+            destination_channel: ChannelId::new(1),
+            data: b"packet data".to_vec(),
+            timeout_height: Height::zero(),
+            timeout_timestamp: Timestamp::none(),
+        }
+    }
+
+    fn recorded(chain_id: ChainId, height: u64, events: Vec<IbcEvent>) -> RecordedBatch {
+        RecordedBatch {
+            recorded_at: Timestamp::now(),
+            batch: EventBatch {
+                chain_id,
+                height: Height::new(0, height),
+                events,
+            },
+        }
+    }
+
+    fn send_packet(height: u64, packet: Packet) -> IbcEvent {
+        IbcEvent::SendPacket(SendPacket {
+            height: Height::new(0, height),
+            packet,
+        })
+    }
+
+    #[test]
+    fn packet_sent_on_path_is_received_on_destination() {
+        let other_channel_event = send_packet(2, packet(5, 1));
+
+        let recording = vec![recorded(
+            chain_a(),
+            2,
+            vec![send_packet(2, packet(0, 1)), other_channel_event.clone()],
+        )];
+
+        let replays = replay(&config(), &path(), recording).unwrap();
+        assert_eq!(replays.len(), 1);
+
+        let replay = &replays[0];
+        assert_eq!(replay.chain_id, chain_a());
+        assert_eq!(replay.height, Height::new(0, 2));
+        assert_eq!(replay.error, None);
+        assert_eq!(replay.filtered_out, vec![other_channel_event]);
+
+        assert_eq!(replay.operational_data.len(), 1);
+        let od = &replay.operational_data[0];
+        assert_eq!(od.target_chain_id, chain_b());
+        assert_eq!(od.messages.len(), 1);
+        assert_eq!(od.messages[0].type_url, recv_packet::TYPE_URL);
+    }
+
+    #[test]
+    fn packet_already_received_is_not_relayed() {
+        let recording = vec![
+            recorded(
+                chain_b(),
+                2,
+                vec![IbcEvent::ReceivePacket(ReceivePacket {
+                    height: Height::new(0, 2),
+                    packet: packet(0, 1),
+                })],
+            ),
+            recorded(chain_a(), 3, vec![send_packet(3, packet(0, 1))]),
+        ];
+
+        let replays = replay(&config(), &path(), recording).unwrap();
+        assert!(replays.is_empty());
+    }
+
+    #[test]
+    fn chain_not_in_config_is_reported() {
+        let config = Config {
+            chains: vec![get_basic_chain_config("chain_a")],
+            ..Config::default()
+        };
+
+        let result = replay(&config, &path(), vec![]);
+        assert!(matches!(
+            result,
+            Err(ReplayError(ReplayErrorDetail::ChainNotFound(_), _))
+        ));
+    }
+}
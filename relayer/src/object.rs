@@ -6,7 +6,8 @@ use ibc::{
         ics02_client::{client_state::ClientState, events::UpdateClient},
         ics03_connection::events::Attributes as ConnectionAttributes,
         ics04_channel::events::{
-            Attributes, CloseInit, SendPacket, TimeoutPacket, WriteAcknowledgement,
+            AcknowledgePacket, Attributes, CloseInit, SendPacket, TimeoutPacket,
+            WriteAcknowledgement,
         },
         ics24_host::identifier::{ChainId, ChannelId, ClientId, ConnectionId, PortId},
     },
@@ -177,6 +178,13 @@ define_error! {
                     e.event)
             },
 
+        MissingCounterpartyChannelId
+            { event: Attributes }
+            | e | {
+                format!("counterparty channel_id missing in channel close event '{:?}'",
+                    e.event)
+            },
+
         MissingConnectionId
             { event: ConnectionAttributes }
             | e | {
@@ -467,6 +475,27 @@ impl Object {
         .into())
     }
 
+    /// Build the object associated with the given [`AcknowledgePacket`] event.
+    pub fn for_ack_packet(
+        e: &AcknowledgePacket,
+        src_chain: &impl ChainHandle,
+    ) -> Result<Self, ObjectError> {
+        let dst_chain_id = counterparty_chain_from_channel(
+            src_chain,
+            &e.packet.source_channel,
+            &e.packet.source_port,
+        )
+        .map_err(ObjectError::supervisor)?;
+
+        Ok(Packet {
+            dst_chain_id,
+            src_chain_id: src_chain.id(),
+            src_channel_id: e.packet.source_channel.clone(),
+            src_port_id: e.packet.source_port.clone(),
+        }
+        .into())
+    }
+
     /// Build the object associated with the given [`CloseInit`] event.
     pub fn for_close_init_channel(
         e: &CloseInit,
@@ -483,4 +512,202 @@ impl Object {
         }
         .into())
     }
+
+    /// Build the Packet object relaying the packets sent by the counterparty chain
+    /// over a channel which was closed on `dst_chain`, given the attributes of the
+    /// channel close event emitted by `dst_chain`.
+    pub fn for_closed_counterparty_channel(
+        attributes: &Attributes,
+        dst_chain: &impl ChainHandle,
+    ) -> Result<Self, ObjectError> {
+        let channel_id = attributes
+            .channel_id()
+            .ok_or_else(|| ObjectError::missing_channel_id(attributes.clone()))?;
+
+        let src_channel_id = attributes
+            .counterparty_channel_id
+            .as_ref()
+            .ok_or_else(|| ObjectError::missing_counterparty_channel_id(attributes.clone()))?;
+
+        let src_chain_id =
+            counterparty_chain_from_channel(dst_chain, channel_id, attributes.port_id())
+                .map_err(ObjectError::supervisor)?;
+
+        Ok(Packet {
+            dst_chain_id: dst_chain.id(),
+            src_chain_id,
+            src_channel_id: src_channel_id.clone(),
+            src_port_id: attributes.counterparty_port_id.clone(),
+        }
+        .into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::sync::Arc;
+    use core::str::FromStr;
+    use core::time::Duration;
+    use std::sync::RwLock;
+
+    use tokio::runtime::Runtime as TokioRuntime;
+
+    use ibc::clients::ics07_tendermint::client_state::{
+        AllowUpdate, ClientState as TendermintClientState,
+    };
+    use ibc::core::ics02_client::client_state::AnyClientState;
+    use ibc::core::ics02_client::context::ClientKeeper;
+    use ibc::core::ics02_client::trust_threshold::TrustThreshold;
+    use ibc::core::ics03_connection::connection::{
+        ConnectionEnd, Counterparty as ConnectionCounterparty, State as ConnectionState,
+    };
+    use ibc::core::ics03_connection::version::get_compatible_versions;
+    use ibc::core::ics04_channel::channel::{
+        ChannelEnd, Counterparty as ChannelCounterparty, Order, State as ChannelState,
+    };
+    use ibc::core::ics04_channel::Version;
+    use ibc::core::ics23_commitment::specs::ProofSpecs;
+    use ibc::mock::context::MockContext;
+    use ibc::mock::host::HostType;
+    use ibc::timestamp::ZERO_DURATION;
+
+    use crate::chain::handle::ProdChainHandle;
+    use crate::chain::mock::test_utils::get_basic_chain_config;
+    use crate::chain::mock::MockChain;
+    use crate::chain::runtime::ChainRuntime;
+
+    fn client_state(chain_id: &ChainId) -> AnyClientState {
+        let trusting_period = Duration::from_secs(14 * 24 * 3600);
+
+        let client_state = TendermintClientState::new(
+            chain_id.clone(),
+            TrustThreshold::ONE_THIRD,
+            trusting_period,
+            trusting_period * 2,
+            Duration::from_secs(3),
+            Height::new(chain_id.version(), 10),
+            ProofSpecs::default(),
+            vec![],
+            AllowUpdate {
+                after_expiry: false,
+                after_misbehaviour: false,
+            },
+        )
+        .unwrap();
+
+        AnyClientState::Tendermint(client_state)
+    }
+
+    /// Spawns the mock chain `chain_b`, whose channel `transfer/channel-1` was closed,
+    /// over a connection whose client tracks `chain_a`.
+    fn chain_b(rt: &Arc<TokioRuntime>) -> ProdChainHandle {
+        let config = get_basic_chain_config("chain_b");
+
+        let connection_end = ConnectionEnd::new(
+            ConnectionState::Open,
+            ClientId::default(),
+            ConnectionCounterparty::new(
+                ClientId::default(),
+                Some(ConnectionId::default()),
+                Default::default(),
+            ),
+            get_compatible_versions(),
+            ZERO_DURATION,
+        );
+
+        let channel_end = ChannelEnd::new(
+            ChannelState::Closed,
+            Order::Unordered,
+            ChannelCounterparty::new(PortId::transfer(), Some(ChannelId::new(0))),
+            vec![ConnectionId::default()],
+            Version::ics20(),
+        );
+
+        let mut context = MockContext::new(
+            config.id.clone(),
+            HostType::Mock,
+            1,
+            Height::new(config.id.version(), 5),
+        )
+        .with_connection(ConnectionId::default(), connection_end)
+        .with_channel(PortId::transfer(), ChannelId::new(1), channel_end);
+
+        context
+            .store_client_state(
+                ClientId::default(),
+                client_state(&ChainId::from_str("chain_a").unwrap()),
+            )
+            .unwrap();
+
+        let chain = MockChain::new(config, Arc::new(RwLock::new(context)));
+        ChainRuntime::<MockChain>::spawn_with_endpoint(chain, rt.clone()).unwrap()
+    }
+
+    fn close_attributes(counterparty_channel_id: Option<ChannelId>) -> Attributes {
+        Attributes {
+            height: Height::new(0, 5),
+            port_id: PortId::transfer(),
+            channel_id: Some(ChannelId::new(1)),
+            connection_id: ConnectionId::default(),
+            counterparty_port_id: PortId::transfer(),
+            counterparty_channel_id,
+        }
+    }
+
+    #[test]
+    fn closed_counterparty_channel_relays_from_counterparty() {
+        let rt = Arc::new(TokioRuntime::new().unwrap());
+        let chain = chain_b(&rt);
+
+        let object = Object::for_closed_counterparty_channel(
+            &close_attributes(Some(ChannelId::new(0))),
+            &chain,
+        )
+        .unwrap();
+
+        assert_eq!(
+            object,
+            Object::Packet(Packet {
+                dst_chain_id: ChainId::from_str("chain_b").unwrap(),
+                src_chain_id: ChainId::from_str("chain_a").unwrap(),
+                src_channel_id: ChannelId::new(0),
+                src_port_id: PortId::transfer(),
+            })
+        );
+    }
+
+    #[test]
+    fn closed_counterparty_channel_requires_channel_ids() {
+        let rt = Arc::new(TokioRuntime::new().unwrap());
+        let chain = chain_b(&rt);
+
+        let result = Object::for_closed_counterparty_channel(&close_attributes(None), &chain);
+        assert!(matches!(
+            result,
+            Err(ObjectError(
+                ObjectErrorDetail::MissingCounterpartyChannelId(_),
+                _
+            ))
+        ));
+
+        let mut attributes = close_attributes(Some(ChannelId::new(0)));
+        attributes.channel_id = None;
+
+        let result = Object::for_closed_counterparty_channel(&attributes, &chain);
+        assert!(matches!(
+            result,
+            Err(ObjectError(ObjectErrorDetail::MissingChannelId(_), _))
+        ));
+
+        // The channel is not known to the chain
+        attributes.channel_id = Some(ChannelId::new(7));
+
+        let result = Object::for_closed_counterparty_channel(&attributes, &chain);
+        assert!(matches!(
+            result,
+            Err(ObjectError(ObjectErrorDetail::Supervisor(_), _))
+        ));
+    }
 }
@@ -0,0 +1,231 @@
+//! Governance proposals for recovering expired or frozen IBC clients.
+
+use flex_error::define_error;
+use prost_types::Any;
+use serde::Serialize;
+
+use ibc::core::ics24_host::identifier::{ChainId, ClientId};
+use ibc::events::IbcEvent;
+use ibc::tx_msg::Msg;
+use ibc_proto::cosmos::gov::v1beta1::MsgSubmitProposal;
+use ibc_proto::ibc::core::client::v1::ClientUpdateProposal;
+
+use crate::chain::handle::ChainHandle;
+use crate::error::Error;
+use crate::foreign_client::{extract_client_id, ForeignClient, ForeignClientError};
+
+define_error! {
+    RecoverClientError {
+        ClientActive
+            {
+                client_id: ClientId,
+                chain_id: ChainId,
+            }
+            |e| {
+                format!("client {0} on chain {1} is neither expired nor frozen, refusing to recover it",
+                    e.client_id, e.chain_id)
+            },
+
+        SubstituteClient
+            [ ForeignClientError ]
+            |_| { "failed while creating the substitute client" },
+
+        Key
+            [ Error ]
+            |_| { "key error" },
+
+        Submit
+            { chain_id: ChainId }
+            [ Error ]
+            |e| {
+                format!("failed while submitting the ClientUpdateProposal to chain {0}",
+                    e.chain_id)
+            },
+
+        TxResponse
+            { event: String }
+            |e| {
+                format!("tx response event consists of an error: {}",
+                    e.event)
+            },
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RecoverClientOptions {
+    pub title: String,
+    pub description: String,
+    pub deposit_amount: u64,
+    pub deposit_denom: String,
+}
+
+/// The outcome of a client recovery, namely the substitute client that was
+/// created and the events emitted by the submission of the proposal.
+#[derive(Clone, Debug, Serialize)]
+pub struct ClientRecovery {
+    pub subject_client_id: ClientId,
+    pub substitute_client_id: ClientId,
+    pub events: Vec<IbcEvent>,
+}
+
+/// Creates a substitute for the given expired or frozen `subject` client, and submits
+/// a `ClientUpdateProposal` to the chain hosting it. Once the proposal passes, the
+/// latest consensus state of the substitute client is copied over to the subject
+/// client, which can then resume being updated.
+pub fn build_and_send_recover_client_proposal<DstChain: ChainHandle, SrcChain: ChainHandle>(
+    subject: &ForeignClient<DstChain, SrcChain>,
+    opts: &RecoverClientOptions,
+) -> Result<ClientRecovery, RecoverClientError> {
+    let dst_chain = subject.dst_chain();
+
+    if !subject.is_expired_or_frozen() {
+        return Err(RecoverClientError::client_active(
+            subject.id().clone(),
+            dst_chain.id(),
+        ));
+    }
+
+    // Create the substitute client, with the same parameters as the subject client
+    let create_msg = subject
+        .build_create_substitute_client()
+        .map_err(RecoverClientError::substitute_client)?;
+
+    let create_events = dst_chain
+        .send_messages_and_wait_commit(vec![create_msg.to_any()])
+        .map_err(|e| RecoverClientError::submit(dst_chain.id(), e))?;
+
+    check_tx_response(&create_events)?;
+
+    let substitute_client_id = create_events
+        .first()
+        .ok_or_else(|| RecoverClientError::tx_response("no events in response".to_string()))
+        .and_then(|event| {
+            extract_client_id(event)
+                .cloned()
+                .map_err(RecoverClientError::substitute_client)
+        })?;
+
+    let proposal = ClientUpdateProposal {
+        title: opts.title.clone(),
+        description: opts.description.clone(),
+        subject_client_id: subject.id().to_string(),
+        substitute_client_id: substitute_client_id.to_string(),
+    };
+
+    let mut buf_proposal = Vec::new();
+    prost::Message::encode(&proposal, &mut buf_proposal).unwrap();
+    let any_proposal = Any {
+        type_url: "/ibc.core.client.v1.ClientUpdateProposal".to_string(),
+        value: buf_proposal,
+    };
+
+    // build the msg submit proposal
+    let proposer = dst_chain.get_signer().map_err(RecoverClientError::key)?;
+
+    let coins = ibc_proto::cosmos::base::v1beta1::Coin {
+        denom: opts.deposit_denom.clone(),
+        amount: opts.deposit_amount.to_string(),
+    };
+
+    let msg = MsgSubmitProposal {
+        content: Some(any_proposal),
+        initial_deposit: vec![coins],
+        proposer: proposer.to_string(),
+    };
+
+    let mut buf_msg = Vec::new();
+    prost::Message::encode(&msg, &mut buf_msg).unwrap();
+    let any_msg = Any {
+        type_url: "/cosmos.gov.v1beta1.MsgSubmitProposal".to_string(),
+        value: buf_msg,
+    };
+
+    let events = dst_chain
+        .send_messages_and_wait_commit(vec![any_msg])
+        .map_err(|e| RecoverClientError::submit(dst_chain.id(), e))?;
+
+    check_tx_response(&events)?;
+
+    Ok(ClientRecovery {
+        subject_client_id: subject.id().clone(),
+        substitute_client_id,
+        events,
+    })
+}
+
+/// Check if the chain rejected the transaction
+fn check_tx_response(events: &[IbcEvent]) -> Result<(), RecoverClientError> {
+    let result = events.iter().find_map(|event| match event {
+        IbcEvent::ChainError(reason) => Some(reason.clone()),
+        _ => None,
+    });
+
+    match result {
+        None => Ok(()),
+        Some(reason) => Err(RecoverClientError::tx_response(reason)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::sync::Arc;
+
+    use test_log::test;
+    use tokio::runtime::Runtime as TokioRuntime;
+
+    use ibc::events::IbcEvent;
+
+    use super::{
+        build_and_send_recover_client_proposal, check_tx_response, RecoverClientError,
+        RecoverClientErrorDetail, RecoverClientOptions,
+    };
+    use crate::chain::handle::ProdChainHandle;
+    use crate::chain::mock::test_utils::get_basic_chain_config;
+    use crate::chain::mock::MockChain;
+    use crate::chain::runtime::ChainRuntime;
+    use crate::foreign_client::ForeignClient;
+
+    #[test]
+    fn refuses_to_recover_active_client() {
+        let a_cfg = get_basic_chain_config("chain_a");
+        let b_cfg = get_basic_chain_config("chain_b");
+
+        let rt = Arc::new(TokioRuntime::new().unwrap());
+        let a_chain =
+            ChainRuntime::<MockChain>::spawn::<ProdChainHandle>(a_cfg, rt.clone()).unwrap();
+        let b_chain = ChainRuntime::<MockChain>::spawn::<ProdChainHandle>(b_cfg, rt).unwrap();
+
+        let client = ForeignClient::new(a_chain, b_chain).unwrap();
+
+        let opts = RecoverClientOptions {
+            title: "Recover client".to_string(),
+            description: "The client expired".to_string(),
+            deposit_amount: 10000000,
+            deposit_denom: "stake".to_string(),
+        };
+
+        let res = build_and_send_recover_client_proposal(&client, &opts);
+
+        assert!(matches!(
+            res,
+            Err(RecoverClientError(
+                RecoverClientErrorDetail::ClientActive(_),
+                _
+            ))
+        ));
+    }
+
+    #[test]
+    fn chain_errors_fail_tx_response() {
+        assert!(check_tx_response(&[]).is_ok());
+
+        let events = vec![IbcEvent::ChainError("insufficient deposit".to_string())];
+        assert!(matches!(
+            check_tx_response(&events),
+            Err(RecoverClientError(
+                RecoverClientErrorDetail::TxResponse(_),
+                _
+            ))
+        ));
+    }
+}
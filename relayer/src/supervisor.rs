@@ -22,6 +22,7 @@ use crate::{
     config::{ChainConfig, Config},
     event,
     event::monitor::{Error as EventError, ErrorDetail as EventErrorDetail, EventBatch},
+    event::recording::EventRecorder,
     object::Object,
     registry::{Registry, SharedRegistry},
     rest,
@@ -41,6 +42,8 @@ use dump_state::SupervisorState;
 pub mod spawn;
 use spawn::SpawnContext;
 
+pub mod scan;
+
 pub mod cmd;
 use cmd::{CmdEffect, ConfigUpdate, SupervisorCmd};
 
@@ -65,16 +68,27 @@ pub struct SupervisorHandle {
    [`SharedConfig`] and [`SharedRegistry`]. Returns a
    [`SupervisorHandle`] that stops the supervisor when the
    value is dropped.
+
+   The event batches received from the chains are recorded
+   by the given [`EventRecorder`], if any.
 */
 pub fn spawn_supervisor(
     config: Arc<RwLock<Config>>,
     registry: SharedRegistry<impl ChainHandle>,
     rest_rx: Option<rest::Receiver>,
+    recorder: Option<EventRecorder>,
     do_health_check: bool,
 ) -> Result<SupervisorHandle, Error> {
     let (sender, receiver) = unbounded();
 
-    let tasks = spawn_supervisor_tasks(config, registry, rest_rx, receiver, do_health_check)?;
+    let tasks = spawn_supervisor_tasks(
+        config,
+        registry,
+        rest_rx,
+        recorder,
+        receiver,
+        do_health_check,
+    )?;
 
     Ok(SupervisorHandle { sender, tasks })
 }
@@ -107,6 +121,7 @@ pub fn spawn_supervisor_tasks<Chain: ChainHandle>(
     config: Arc<RwLock<Config>>,
     registry: SharedRegistry<Chain>,
     rest_rx: Option<rest::Receiver>,
+    recorder: Option<EventRecorder>,
     cmd_rx: Receiver<SupervisorCmd>,
     do_health_check: bool,
 ) -> Result<Vec<TaskHandle>, Error> {
@@ -115,7 +130,7 @@ pub fn spawn_supervisor_tasks<Chain: ChainHandle>(
     }
 
     let workers = Arc::new(RwLock::new(WorkerMap::new()));
-    let client_state_filter = Arc::new(RwLock::new(FilterPolicy::default()));
+    let client_state_filter = Arc::new(RwLock::new(FilterPolicy::new(&config.acquire_read())));
 
     spawn_context(
         &config.acquire_read(),
@@ -137,6 +152,7 @@ pub fn spawn_supervisor_tasks<Chain: ChainHandle>(
         client_state_filter.clone(),
         workers.clone(),
         subscriptions.clone(),
+        recorder,
     );
 
     let cmd_task = spawn_cmd_worker(
@@ -164,12 +180,17 @@ fn spawn_batch_worker<Chain: ChainHandle>(
     client_state_filter: Arc<RwLock<FilterPolicy>>,
     workers: Arc<RwLock<WorkerMap>>,
     subscriptions: Arc<RwLock<Vec<(Chain, Subscription)>>>,
+    mut recorder: Option<EventRecorder>,
 ) -> TaskHandle {
     spawn_background_task(
         "supervisor_batch".to_string(),
         Some(Duration::from_millis(500)),
         move || -> Result<Next, TaskError<Infallible>> {
             if let Some((chain, batch)) = try_recv_multiple(&subscriptions.acquire_read()) {
+                if let (Some(recorder), Ok(batch)) = (recorder.as_mut(), batch.deref()) {
+                    recorder.record(batch);
+                }
+
                 handle_batch(
                     &config.acquire_read(),
                     &mut registry.write(),
@@ -281,13 +302,64 @@ fn relay_packets_on_channel(
     config.packets_on_channel_allowed(chain_id, port_id, channel_id)
 }
 
+/// Returns `true` if the object belongs to one of the paths listed
+/// in the configuration, or if no path is listed.
+/// Returns `false` otherwise.
+fn object_on_configured_path(config: &Config, object: &Object) -> bool {
+    if !config.has_paths() {
+        return true;
+    }
+
+    config.paths.iter().any(|path| match object {
+        Object::Client(client) => {
+            path.ends_from(&client.dst_chain_id)
+                .map_or(false, |(end, counterparty)| {
+                    end.client_id == client.dst_client_id
+                        && counterparty.chain_id == client.src_chain_id
+                })
+        }
+        Object::Connection(conn) => {
+            path.ends_from(&conn.src_chain_id)
+                .map_or(false, |(end, counterparty)| {
+                    end.connection_id == conn.src_connection_id
+                        && counterparty.chain_id == conn.dst_chain_id
+                })
+        }
+        Object::Channel(chan) => {
+            path.ends_from(&chan.src_chain_id)
+                .map_or(false, |(end, counterparty)| {
+                    end.port_id == chan.src_port_id
+                        && end.channel_id == chan.src_channel_id
+                        && counterparty.chain_id == chan.dst_chain_id
+                })
+        }
+        Object::Packet(packet) => {
+            path.ends_from(&packet.src_chain_id)
+                .map_or(false, |(end, counterparty)| {
+                    end.port_id == packet.src_port_id
+                        && end.channel_id == packet.src_channel_id
+                        && counterparty.chain_id == packet.dst_chain_id
+                })
+        }
+    })
+}
+
 fn relay_on_object<Chain: ChainHandle>(
     config: &Config,
     registry: &mut Registry<Chain>,
     client_state_filter: &mut FilterPolicy,
-    chain_id: &ChainId,
     object: &Object,
 ) -> bool {
+    // Only relay on the configured paths, if any
+    if !object_on_configured_path(config, object) {
+        trace!(
+            "skipping object {}, reason: it does not belong to any configured path",
+            object.short_name()
+        );
+
+        return false;
+    }
+
     // No filter is enabled, bail fast.
     if !channel_filter_enabled(config) && !client_filter_enabled(config) {
         return true;
@@ -295,7 +367,12 @@ fn relay_on_object<Chain: ChainHandle>(
 
     // First, apply the channel filter
     if let Object::Packet(u) = object {
-        if !relay_packets_on_channel(config, chain_id, u.src_port_id(), u.src_channel_id()) {
+        if !relay_packets_on_channel(
+            config,
+            u.src_chain_id(),
+            u.src_port_id(),
+            u.src_channel_id(),
+        ) {
             return false;
         }
     }
@@ -429,6 +506,12 @@ fn collect_events(
                     Object::for_timeout_packet(packet, src_chain).ok()
                 });
             }
+            IbcEvent::AcknowledgePacket(ref packet) => {
+                // Stops tracking the timeout of the packet
+                collect_event(&mut collected, event, mode.packets.enabled, || {
+                    Object::for_ack_packet(packet, src_chain).ok()
+                });
+            }
             IbcEvent::WriteAcknowledgement(ref packet) => {
                 collect_event(&mut collected, event, mode.packets.enabled, || {
                     Object::for_write_ack(packet, src_chain).ok()
@@ -438,6 +521,17 @@ fn collect_events(
                 collect_event(&mut collected, event, mode.packets.enabled, || {
                     Object::for_close_init_channel(packet, src_chain).ok()
                 });
+
+                // Time out on close the packets sent by the counterparty which are still in flight
+                collect_event(&mut collected, event, mode.packets.enabled, || {
+                    Object::for_closed_counterparty_channel(packet.attributes(), src_chain).ok()
+                });
+            }
+            IbcEvent::CloseConfirmChannel(ref close_confirm) => {
+                collect_event(&mut collected, event, mode.packets.enabled, || {
+                    Object::for_closed_counterparty_channel(close_confirm.attributes(), src_chain)
+                        .ok()
+                });
             }
             _ => (),
         }
@@ -598,13 +692,7 @@ fn process_batch<Chain: ChainHandle>(
 
     // Forward the IBC events.
     for (object, events) in collected.per_object.into_iter() {
-        if !relay_on_object(
-            config,
-            registry,
-            client_state_filter,
-            &src_chain.id(),
-            &object,
-        ) {
+        if !relay_on_object(config, registry, client_state_filter, &object) {
             trace!(
                 "skipping events for '{}'. \
                 reason: filtering is enabled and channel does not match any allowed channels",
@@ -689,6 +777,7 @@ fn remove_chain<Chain: ChainHandle>(
     info!(chain.id=%id, "removing existing chain");
 
     config.chains.retain(|c| &c.id != id);
+    client_state_filter.configure(config);
 
     debug!(chain.id=%id, "shutting down workers");
 
@@ -745,6 +834,8 @@ fn add_chain<Chain: ChainHandle>(
         return CmdEffect::Nothing;
     }
 
+    client_state_filter.configure(config);
+
     debug!(chain.id=%id, "spawning workers");
 
     let mut ctx = spawn_context(
@@ -842,3 +933,226 @@ impl CollectedEvents {
         self.new_block.is_some()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::str::FromStr;
+
+    use crate::chain::handle::ProdChainHandle;
+    use ibc::core::ics24_host::identifier::{ClientId, ConnectionId};
+
+    use crate::chain::mock::test_utils::get_basic_chain_config;
+    use crate::object::{Channel, Client, Connection, Packet};
+
+    /// A configuration where `chain_b` only relays the packets of `transfer/channel-1`
+    fn config(filter: bool) -> Config {
+        let mut chain_b = get_basic_chain_config("chain_b");
+        chain_b.packet_filter =
+            toml::from_str("policy = 'allow'\nlist = [['transfer', 'channel-1']]").unwrap();
+
+        let mut config = Config {
+            chains: vec![get_basic_chain_config("chain_a"), chain_b],
+            ..Default::default()
+        };
+        config.mode.packets.filter = filter;
+        config
+    }
+
+    #[test]
+    fn packet_filter_applies_to_source_chain() {
+        // The packets sent by `chain_b` over a channel closed on `chain_a`, whose
+        // events are the ones collected, are still subject to the filter of `chain_b`
+        let object = Object::Packet(Packet {
+            dst_chain_id: ChainId::from_str("chain_a").unwrap(),
+            src_chain_id: ChainId::from_str("chain_b").unwrap(),
+            src_channel_id: ChannelId::new(0),
+            src_port_id: PortId::transfer(),
+        });
+
+        let filtered = config(true);
+        let mut registry = Registry::<ProdChainHandle>::from_owned(filtered.clone());
+        let mut policy = FilterPolicy::new(&filtered);
+
+        assert!(!relay_on_object(
+            &filtered,
+            &mut registry,
+            &mut policy,
+            &object
+        ));
+        assert_eq!(registry.size(), 0);
+
+        let unfiltered = config(false);
+        let mut policy = FilterPolicy::new(&unfiltered);
+
+        assert!(relay_on_object(
+            &unfiltered,
+            &mut registry,
+            &mut policy,
+            &object
+        ));
+    }
+
+    fn chain_id(id: &str) -> ChainId {
+        ChainId::from_str(id).unwrap()
+    }
+
+    /// A configuration listing the path between `transfer/channel-0` on `chain_a`,
+    /// over `connection-0` and `07-tendermint-0`, and the same identifiers on `chain_b`
+    fn config_with_path() -> Config {
+        let mut config = config(false);
+        config.paths = vec![toml::from_str(
+            r#"
+            a = { chain_id = 'chain_a', client_id = '07-tendermint-0', connection_id = 'connection-0', port_id = 'transfer', channel_id = 'channel-0' }
+            b = { chain_id = 'chain_b', client_id = '07-tendermint-0', connection_id = 'connection-0', port_id = 'transfer', channel_id = 'channel-0' }
+            "#,
+        )
+        .unwrap()];
+        config
+    }
+
+    fn client(dst: &str, src: &str, client_id: &str) -> Object {
+        Object::Client(Client {
+            dst_chain_id: chain_id(dst),
+            dst_client_id: ClientId::from_str(client_id).unwrap(),
+            src_chain_id: chain_id(src),
+        })
+    }
+
+    fn connection(src: &str, dst: &str, connection_id: u64) -> Object {
+        Object::Connection(Connection {
+            dst_chain_id: chain_id(dst),
+            src_chain_id: chain_id(src),
+            src_connection_id: ConnectionId::new(connection_id),
+        })
+    }
+
+    fn channel(src: &str, dst: &str, port_id: &str, channel_id: u64) -> Object {
+        Object::Channel(Channel {
+            dst_chain_id: chain_id(dst),
+            src_chain_id: chain_id(src),
+            src_channel_id: ChannelId::new(channel_id),
+            src_port_id: PortId::from_str(port_id).unwrap(),
+        })
+    }
+
+    fn packet(src: &str, dst: &str, port_id: &str, channel_id: u64) -> Object {
+        Object::Packet(Packet {
+            dst_chain_id: chain_id(dst),
+            src_chain_id: chain_id(src),
+            src_channel_id: ChannelId::new(channel_id),
+            src_port_id: PortId::from_str(port_id).unwrap(),
+        })
+    }
+
+    #[test]
+    fn objects_on_configured_path() {
+        let config = config_with_path();
+
+        for (a, b) in [("chain_a", "chain_b"), ("chain_b", "chain_a")] {
+            assert!(object_on_configured_path(
+                &config,
+                &client(a, b, "07-tendermint-0")
+            ));
+            assert!(object_on_configured_path(&config, &connection(a, b, 0)));
+            assert!(object_on_configured_path(
+                &config,
+                &channel(a, b, "transfer", 0)
+            ));
+            assert!(object_on_configured_path(
+                &config,
+                &packet(a, b, "transfer", 0)
+            ));
+        }
+    }
+
+    #[test]
+    fn objects_off_configured_path() {
+        let config = config_with_path();
+
+        // Another identifier on a chain of the path
+        assert!(!object_on_configured_path(
+            &config,
+            &client("chain_a", "chain_b", "07-tendermint-1")
+        ));
+        assert!(!object_on_configured_path(
+            &config,
+            &connection("chain_a", "chain_b", 1)
+        ));
+        assert!(!object_on_configured_path(
+            &config,
+            &channel("chain_a", "chain_b", "transfer", 1)
+        ));
+        assert!(!object_on_configured_path(
+            &config,
+            &channel("chain_a", "chain_b", "other", 0)
+        ));
+        assert!(!object_on_configured_path(
+            &config,
+            &packet("chain_a", "chain_b", "transfer", 1)
+        ));
+        assert!(!object_on_configured_path(
+            &config,
+            &packet("chain_a", "chain_b", "other", 0)
+        ));
+
+        // The same identifiers, with a counterparty which is not on the path
+        assert!(!object_on_configured_path(
+            &config,
+            &client("chain_a", "chain_c", "07-tendermint-0")
+        ));
+        assert!(!object_on_configured_path(
+            &config,
+            &connection("chain_a", "chain_c", 0)
+        ));
+        assert!(!object_on_configured_path(
+            &config,
+            &channel("chain_a", "chain_c", "transfer", 0)
+        ));
+        assert!(!object_on_configured_path(
+            &config,
+            &packet("chain_a", "chain_c", "transfer", 0)
+        ));
+
+        // The same identifiers, on a chain which is not on the path
+        assert!(!object_on_configured_path(
+            &config,
+            &client("chain_c", "chain_a", "07-tendermint-0")
+        ));
+        assert!(!object_on_configured_path(
+            &config,
+            &connection("chain_c", "chain_a", 0)
+        ));
+        assert!(!object_on_configured_path(
+            &config,
+            &channel("chain_c", "chain_a", "transfer", 0)
+        ));
+        assert!(!object_on_configured_path(
+            &config,
+            &packet("chain_c", "chain_a", "transfer", 0)
+        ));
+    }
+
+    #[test]
+    fn objects_without_configured_paths() {
+        let config = config(false);
+
+        assert!(object_on_configured_path(
+            &config,
+            &client("chain_a", "chain_c", "07-tendermint-1")
+        ));
+        assert!(object_on_configured_path(
+            &config,
+            &connection("chain_c", "chain_a", 1)
+        ));
+        assert!(object_on_configured_path(
+            &config,
+            &channel("chain_a", "chain_b", "other", 1)
+        ));
+        assert!(object_on_configured_path(
+            &config,
+            &packet("chain_b", "chain_c", "transfer", 1)
+        ));
+    }
+}
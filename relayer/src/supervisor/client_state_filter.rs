@@ -11,6 +11,7 @@ use ibc::core::ics24_host::identifier::{ChainId, ChannelId, ClientId, Connection
 use ibc::Height;
 
 use crate::chain::handle::ChainHandle;
+use crate::config::{Config, PacketFilter};
 use crate::error::Error as RelayerError;
 use crate::object;
 use crate::registry::{Registry, SpawnError};
@@ -63,9 +64,49 @@ pub struct FilterPolicy {
     /// client id, channel id, or connection id, with an
     /// [`Allow`](Permission::Allow) status.
     permission_cache: HashMap<CacheKey, Permission>,
+    /// The packet filters of the chains, whose entries may be wildcard patterns
+    /// such as `icacontroller-*`. Empty if channel filtering is disabled.
+    channel_filters: HashMap<ChainId, PacketFilter>,
 }
 
 impl FilterPolicy {
+    pub fn new(config: &Config) -> Self {
+        let mut policy = Self::default();
+        policy.configure(config);
+        policy
+    }
+
+    /// Picks up the packet filters of the chains in the given configuration,
+    /// and forgets all the permissions cached so far.
+    pub fn configure(&mut self, config: &Config) {
+        self.channel_filters = if config.mode.packets.filter {
+            config
+                .chains
+                .iter()
+                .map(|chain_config| (chain_config.id.clone(), chain_config.packet_filter.clone()))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+
+        self.permission_cache.clear();
+    }
+
+    /// Controls the port and channel identifiers of a channel against the
+    /// packet filter of the chain hosting it, if any.
+    /// Does not query the chain, and does not cache the result.
+    pub fn control_channel_id(
+        &self,
+        chain_id: &ChainId,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+    ) -> Permission {
+        match self.channel_filters.get(chain_id) {
+            Some(filter) if !filter.is_allowed(port_id, channel_id) => Permission::Deny,
+            _ => Permission::Allow,
+        }
+    }
+
     /// Given a connection end and the underlying client for that
     /// connection, controls both the client as well as the
     /// client on the counterparty chain.
@@ -284,6 +325,21 @@ impl FilterPolicy {
             return Ok(*p);
         }
 
+        // Deny the channels filtered out by their identifiers without querying the chain
+        if let Permission::Deny = self.control_channel_id(chain_id, port_id, channel_id) {
+            debug!(
+                "[client filter] {:?}: relay for channel {:?}, reason: packet filter",
+                Permission::Deny,
+                identifier
+            );
+
+            self.permission_cache
+                .entry(identifier)
+                .or_insert(Permission::Deny);
+
+            return Ok(Permission::Deny);
+        }
+
         let src_chain = registry
             .get_or_spawn(chain_id)
             .map_err(FilterError::spawn)?;
@@ -353,3 +409,116 @@ impl FilterPolicy {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::str::FromStr;
+
+    use crate::chain::handle::ProdChainHandle;
+    use crate::chain::mock::test_utils::get_basic_chain_config;
+
+    fn config(filter: bool, packet_filter: &str) -> Config {
+        let mut chain_config = get_basic_chain_config("chain_a");
+        chain_config.packet_filter = toml::from_str(packet_filter).unwrap();
+
+        let mut config = Config {
+            chains: vec![chain_config],
+            ..Default::default()
+        };
+        config.mode.packets.filter = filter;
+        config
+    }
+
+    fn channel(port_id: &str, channel_id: u64) -> object::Channel {
+        object::Channel {
+            dst_chain_id: ChainId::from_str("chain_b").unwrap(),
+            src_chain_id: ChainId::from_str("chain_a").unwrap(),
+            src_channel_id: ChannelId::new(channel_id),
+            src_port_id: PortId::from_str(port_id).unwrap(),
+        }
+    }
+
+    fn control_channel_id(policy: &FilterPolicy, port_id: &str, channel_id: u64) -> Permission {
+        let chan = channel(port_id, channel_id);
+        policy.control_channel_id(&chan.src_chain_id, &chan.src_port_id, &chan.src_channel_id)
+    }
+
+    #[test]
+    fn channel_wildcard_patterns() {
+        let allow = FilterPolicy::new(&config(
+            true,
+            "policy = 'allow'\nlist = [['icacontroller-*', '*'], ['transfer', 'channel-1?']]",
+        ));
+
+        assert_eq!(
+            control_channel_id(&allow, "icacontroller-cosmos1abc", 42),
+            Permission::Allow
+        );
+        assert_eq!(
+            control_channel_id(&allow, "transfer", 12),
+            Permission::Allow
+        );
+        assert_eq!(control_channel_id(&allow, "transfer", 2), Permission::Deny);
+        assert_eq!(control_channel_id(&allow, "icahost", 42), Permission::Deny);
+
+        let deny = FilterPolicy::new(&config(
+            true,
+            "policy = 'deny'\nlist = [['icacontroller-*', '*']]",
+        ));
+
+        assert_eq!(
+            control_channel_id(&deny, "icacontroller-cosmos1abc", 42),
+            Permission::Deny
+        );
+        assert_eq!(control_channel_id(&deny, "transfer", 0), Permission::Allow);
+    }
+
+    #[test]
+    fn channel_patterns_require_filtering() {
+        let mut policy = FilterPolicy::new(&config(
+            false,
+            "policy = 'allow'\nlist = [['icacontroller-*', '*']]",
+        ));
+
+        assert_eq!(
+            control_channel_id(&policy, "transfer", 0),
+            Permission::Allow
+        );
+
+        policy.configure(&config(
+            true,
+            "policy = 'allow'\nlist = [['icacontroller-*', '*']]",
+        ));
+
+        assert_eq!(control_channel_id(&policy, "transfer", 0), Permission::Deny);
+    }
+
+    #[test]
+    fn denied_channel_is_not_queried() {
+        let config = config(true, "policy = 'allow'\nlist = [['icacontroller-*', '*']]");
+        let mut registry = Registry::<ProdChainHandle>::from_owned(config.clone());
+        let mut policy = FilterPolicy::new(&config);
+
+        let permission = policy
+            .control_chan_object(&mut registry, &channel("transfer", 0))
+            .unwrap();
+
+        assert_eq!(permission, Permission::Deny);
+        assert_eq!(registry.size(), 0);
+
+        // The decision is cached until the configuration changes
+        assert_eq!(
+            policy.permission_cache.get(&CacheKey::Channel(
+                ChainId::from_str("chain_a").unwrap(),
+                PortId::transfer(),
+                ChannelId::new(0)
+            )),
+            Some(&Permission::Deny)
+        );
+
+        policy.configure(&config);
+        assert!(policy.permission_cache.is_empty());
+    }
+}
@@ -55,6 +55,17 @@ define_error! {
         MissingCounterpartyChannelId
             |_| { "failed due to missing counterparty channel id" },
 
+        PathMismatch
+            {
+                chain_id: ChainId,
+                port_id: PortId,
+                channel_id: ChannelId,
+            }
+            |e| {
+                format_args!("the configured path of channel {0}/{1} on chain {2} does not match the client, connection and channel on chain",
+                    e.port_id, e.channel_id, e.chain_id)
+            },
+
         Relayer
             [ RelayerError ]
             |_| { "relayer error" },
@@ -0,0 +1,631 @@
+//! Scan of the clients, connections and channels of the chains, performed
+//! on startup to find out which workers to spawn.
+//!
+//! Chains are scanned concurrently, each chain in a single thread, and the
+//! results are cached on disk. On the next scan, the connections which were open
+//! on both ends are taken from the cache instead of being queried again, since open
+//! connections never change. The channels of a connection, and their counterparty
+//! channel ends, are always queried, as either end may have been closed since.
+//!
+//! The cache is discarded altogether if the chain was reset since it was written,
+//! i.e. if its revision changed, or if its latest height went backwards.
+
+use alloc::collections::btree_map::BTreeMap as HashMap;
+use alloc::sync::Arc;
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Instant;
+
+use crossbeam_channel::unbounded;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error, info, warn};
+
+use ibc::core::ics02_client::client_state::{ClientState, IdentifiedAnyClientState};
+use ibc::core::ics03_connection::connection::{IdentifiedConnectionEnd, State as ConnectionState};
+use ibc::core::ics04_channel::channel::IdentifiedChannelEnd;
+use ibc::core::ics24_host::identifier::{ChainId, ChannelId, ClientId, ConnectionId, PortId};
+use ibc::Height;
+use ibc_proto::ibc::core::channel::v1::QueryConnectionChannelsRequest;
+use ibc_proto::ibc::core::client::v1::QueryClientStatesRequest;
+use ibc_proto::ibc::core::connection::v1::QueryClientConnectionsRequest;
+
+use crate::chain::counterparty::{
+    channel_on_destination, connection_state_on_destination, has_pending_packets,
+};
+use crate::chain::handle::ChainHandle;
+use crate::config::Config;
+use crate::supervisor::Error;
+use crate::telemetry;
+
+/// The clients of a chain whose counterparty chain is present in the configuration,
+/// along with their connections and channels.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChainScan {
+    pub chain_id: ChainId,
+    /// The latest height of the chain when the scan started
+    pub height: Height,
+    pub clients: Vec<ClientScan>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ClientScan {
+    pub client: IdentifiedAnyClientState,
+    pub connections: Vec<ConnectionScan>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ConnectionScan {
+    pub connection: IdentifiedConnectionEnd,
+    /// The state of the connection on the counterparty chain
+    pub counterparty_state: ConnectionState,
+    /// The channels of the connection, only scanned if the connection is open on both ends
+    pub channels: Vec<ChannelScan>,
+}
+
+impl ConnectionScan {
+    pub fn is_open(&self) -> bool {
+        self.connection.connection_end.is_open() && self.counterparty_state.is_open()
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChannelScan {
+    pub channel: IdentifiedChannelEnd,
+    /// The channel end on the counterparty chain, if known
+    pub counterparty: Option<IdentifiedChannelEnd>,
+    /// Whether there are packets or acknowledgements to relay on the channel.
+    /// Only checked for open channels on which packets are allowed, and never cached.
+    #[serde(skip)]
+    pub has_pending_packets: bool,
+}
+
+impl ChannelScan {
+    pub fn is_open(&self) -> bool {
+        self.channel.channel_end.is_open()
+            && self
+                .counterparty
+                .as_ref()
+                .map_or(false, |c| c.channel_end.is_open())
+    }
+}
+
+/// The results of the previous scan of a chain, indexed by object identifier.
+#[derive(Default)]
+struct CachedScan {
+    connections: HashMap<(ClientId, ConnectionId), ConnectionScan>,
+}
+
+impl CachedScan {
+    /// Indexes the given scan of a chain whose latest height is now `height`,
+    /// unless the chain has been reset since that scan.
+    fn new(scan: ChainScan, height: Height) -> Self {
+        let mut cached = Self::default();
+
+        if scan.height.revision_number != height.revision_number || scan.height > height {
+            return cached;
+        }
+
+        for client in scan.clients {
+            for connection in client.connections {
+                let key = (
+                    client.client.client_id.clone(),
+                    connection.connection.connection_id.clone(),
+                );
+                cached.connections.insert(key, connection);
+            }
+        }
+
+        cached
+    }
+
+    /// Returns the cached connection if it was open on both ends.
+    fn open_connection(
+        &self,
+        client_id: &ClientId,
+        connection_id: &ConnectionId,
+    ) -> Option<&ConnectionScan> {
+        self.connections
+            .get(&(client_id.clone(), connection_id.clone()))
+            .filter(|c| c.is_open())
+    }
+}
+
+/// The on-disk cache of the scans, one JSON file per chain.
+#[derive(Clone, Debug)]
+pub struct ScanCache {
+    dir: PathBuf,
+}
+
+impl ScanCache {
+    /// Returns the cache configured in `config`, if caching is enabled and
+    /// the cache directory is known.
+    pub fn from_config(config: &Config) -> Option<Self> {
+        if !config.scan.cache {
+            return None;
+        }
+
+        let dir = match &config.scan.cache_dir {
+            Some(dir) => dir.clone(),
+            None => dirs_next::home_dir()?.join(".hermes").join("scan"),
+        };
+
+        Some(Self { dir })
+    }
+
+    fn path(&self, chain_id: &ChainId) -> PathBuf {
+        self.dir.join(format!("{}.json", chain_id))
+    }
+
+    fn load(&self, chain_id: &ChainId) -> Option<ChainScan> {
+        let path = self.path(chain_id);
+
+        let contents = fs::read_to_string(&path).ok()?;
+
+        match serde_json::from_str(&contents) {
+            Ok(scan) => Some(scan),
+            Err(e) => {
+                warn!(
+                    "ignoring the scan cache at {} for chain {}, reason: {}",
+                    path.display(),
+                    chain_id,
+                    e
+                );
+
+                None
+            }
+        }
+    }
+
+    fn store(&self, scan: &ChainScan) {
+        let path = self.path(&scan.chain_id);
+
+        let res = fs::create_dir_all(&self.dir)
+            .map_err(|e| e.to_string())
+            .and_then(|_| serde_json::to_string(scan).map_err(|e| e.to_string()))
+            .and_then(|json| fs::write(&path, json).map_err(|e| e.to_string()));
+
+        if let Err(e) = res {
+            warn!(
+                "failed to write the scan cache at {} for chain {}, reason: {}",
+                path.display(),
+                scan.chain_id,
+                e
+            );
+        }
+    }
+}
+
+/// Scans the given chains, with at most `config.scan.parallelism` chains being
+/// scanned at the same time. The handles of the counterparty chains must be among
+/// `chains`, the clients of other counterparty chains are skipped.
+/// Returns the scan of each chain, in no particular order.
+pub fn scan_chains<Chain: ChainHandle>(
+    config: &Config,
+    chains: Vec<Chain>,
+) -> Vec<(Chain, Result<ChainScan, Error>)> {
+    let config = Arc::new(config.clone());
+    let handles: Arc<HashMap<ChainId, Chain>> =
+        Arc::new(chains.iter().map(|c| (c.id(), c.clone())).collect());
+    let cache = ScanCache::from_config(&config);
+
+    let (work_tx, work_rx) = unbounded::<Chain>();
+    let (result_tx, result_rx) = unbounded();
+
+    let total = chains.len();
+    for chain in chains {
+        let _ = work_tx.send(chain);
+    }
+    drop(work_tx);
+
+    let threads = config.scan.parallelism.max(1).min(total.max(1));
+
+    for _ in 0..threads {
+        let work_rx = work_rx.clone();
+        let result_tx = result_tx.clone();
+        let config = config.clone();
+        let handles = handles.clone();
+        let cache = cache.clone();
+
+        thread::spawn(move || {
+            for chain in work_rx.iter() {
+                let res = scan_chain(&config, &chain, &handles, cache.as_ref());
+                let _ = result_tx.send((chain, res));
+            }
+        });
+    }
+    drop(result_tx);
+
+    let mut scans = Vec::with_capacity(total);
+
+    for (chain, res) in result_rx.iter() {
+        scans.push((chain, res));
+
+        info!("scanned {}/{} chains", scans.len(), total);
+    }
+
+    scans
+}
+
+/// Scans a single chain, reusing the cached scan of the chain if any.
+pub fn scan_chain<Chain: ChainHandle>(
+    config: &Config,
+    chain: &Chain,
+    handles: &HashMap<ChainId, Chain>,
+    cache: Option<&ScanCache>,
+) -> Result<ChainScan, Error> {
+    let chain_id = chain.id();
+    let start = Instant::now();
+
+    let height = chain.query_latest_height().map_err(Error::relayer)?;
+
+    let cached = cache
+        .and_then(|cache| cache.load(&chain_id))
+        .map(|scan| CachedScan::new(scan, height))
+        .unwrap_or_default();
+
+    let clients_req = QueryClientStatesRequest {
+        pagination: ibc_proto::cosmos::base::query::pagination::all(),
+    };
+
+    let clients = chain.query_clients(clients_req).map_err(Error::relayer)?;
+
+    // Only keep the clients whose counterparty chain is also scanned
+    let clients: Vec<_> = clients
+        .into_iter()
+        .filter_map(|client| {
+            handles
+                .get(&client.client_state.chain_id())
+                .map(|counterparty_chain| (client, counterparty_chain))
+        })
+        .collect();
+
+    info!(
+        "scanning {} clients on chain {} at height {}",
+        clients.len(),
+        chain_id,
+        height
+    );
+
+    let mut scan = ChainScan {
+        chain_id: chain_id.clone(),
+        height,
+        clients: Vec::with_capacity(clients.len()),
+    };
+
+    let total = clients.len();
+
+    for (index, (client, counterparty_chain)) in clients.into_iter().enumerate() {
+        telemetry!(startup_scanned_objects, &chain_id, "client", false, 1);
+
+        let connections =
+            scan_client_connections(config, chain, counterparty_chain, &client, &cached);
+
+        debug!(
+            "scanned client {} on chain {} ({}/{})",
+            client.client_id,
+            chain_id,
+            index + 1,
+            total
+        );
+
+        scan.clients.push(ClientScan {
+            client,
+            connections,
+        });
+    }
+
+    if let Some(cache) = cache {
+        cache.store(&scan);
+    }
+
+    let elapsed = start.elapsed();
+
+    telemetry!(startup_scan_duration, &chain_id, elapsed.as_millis() as u64);
+
+    info!(
+        "scanned {} clients on chain {} in {}ms",
+        total,
+        chain_id,
+        elapsed.as_millis()
+    );
+
+    Ok(scan)
+}
+
+fn scan_client_connections<Chain: ChainHandle>(
+    config: &Config,
+    chain: &Chain,
+    counterparty_chain: &Chain,
+    client: &IdentifiedAnyClientState,
+    cached: &CachedScan,
+) -> Vec<ConnectionScan> {
+    let chain_id = chain.id();
+
+    let conns_req = QueryClientConnectionsRequest {
+        client_id: client.client_id.to_string(),
+    };
+
+    let connection_ids = match chain.query_client_connections(conns_req) {
+        Ok(connection_ids) => connection_ids,
+        Err(e) => {
+            error!(
+                "skipping client {} on chain {}, reason: failed to query client connections: {}",
+                client.client_id, chain_id, e
+            );
+
+            return vec![];
+        }
+    };
+
+    let mut connections = Vec::with_capacity(connection_ids.len());
+
+    for connection_id in connection_ids {
+        let res = match cached.open_connection(&client.client_id, &connection_id) {
+            Some(cached_connection) => {
+                telemetry!(startup_scanned_objects, &chain_id, "connection", true, 1);
+
+                Ok((
+                    cached_connection.connection.clone(),
+                    cached_connection.counterparty_state,
+                ))
+            }
+            None => {
+                telemetry!(startup_scanned_objects, &chain_id, "connection", false, 1);
+
+                query_connection(chain, counterparty_chain, &connection_id)
+            }
+        };
+
+        let (connection, counterparty_state) = match res {
+            Ok(connection) => connection,
+            Err(e) => {
+                error!(
+                    "skipping connection {} on chain {}, reason: {}",
+                    connection_id, chain_id, e
+                );
+
+                continue;
+            }
+        };
+
+        let mut connection = ConnectionScan {
+            connection,
+            counterparty_state,
+            channels: vec![],
+        };
+
+        if connection.is_open() {
+            connection.channels =
+                scan_connection_channels(config, chain, counterparty_chain, &connection);
+        }
+
+        connections.push(connection);
+    }
+
+    connections
+}
+
+fn query_connection<Chain: ChainHandle>(
+    chain: &Chain,
+    counterparty_chain: &Chain,
+    connection_id: &ConnectionId,
+) -> Result<(IdentifiedConnectionEnd, ConnectionState), Error> {
+    let connection_end = chain
+        .query_connection(connection_id, Height::zero())
+        .map_err(Error::relayer)?;
+
+    let connection = IdentifiedConnectionEnd {
+        connection_id: connection_id.clone(),
+        connection_end,
+    };
+
+    let counterparty_state =
+        connection_state_on_destination(connection.clone(), counterparty_chain)?;
+
+    Ok((connection, counterparty_state))
+}
+
+fn scan_connection_channels<Chain: ChainHandle>(
+    config: &Config,
+    chain: &Chain,
+    counterparty_chain: &Chain,
+    connection: &ConnectionScan,
+) -> Vec<ChannelScan> {
+    let chain_id = chain.id();
+    let connection_id = &connection.connection.connection_id;
+
+    let chans_req = QueryConnectionChannelsRequest {
+        connection: connection_id.to_string(),
+        pagination: ibc_proto::cosmos::base::query::pagination::all(),
+    };
+
+    let channels = match chain.query_connection_channels(chans_req) {
+        Ok(channels) => channels,
+        Err(e) => {
+            error!(
+                "skipping channels of connection {} on chain {}, reason: failed to query them: {}",
+                connection_id, chain_id, e
+            );
+
+            return vec![];
+        }
+    };
+
+    let mut scans = Vec::with_capacity(channels.len());
+
+    for channel in channels {
+        telemetry!(startup_scanned_objects, &chain_id, "channel", false, 1);
+
+        let counterparty =
+            match channel_on_destination(&channel, &connection.connection, counterparty_chain) {
+                Ok(counterparty) => counterparty,
+                Err(e) => {
+                    error!(
+                    "skipping channel {} on chain {}, reason: failed to query its counterparty: {}",
+                    channel.channel_id, chain_id, e
+                );
+
+                    continue;
+                }
+            };
+
+        let mut scan = ChannelScan {
+            channel,
+            counterparty,
+            has_pending_packets: false,
+        };
+
+        let packets_allowed = config.packets_on_channel_allowed(
+            &chain_id,
+            &scan.channel.port_id,
+            &scan.channel.channel_id,
+        );
+
+        if config.mode.packets.enabled && packets_allowed && scan.is_open() {
+            scan.has_pending_packets =
+                scan.counterparty
+                    .as_ref()
+                    .map_or(false, |counterparty_channel| {
+                        has_pending_packets(chain, counterparty_chain, counterparty_channel)
+                    });
+        }
+
+        scans.push(scan);
+    }
+
+    scans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ibc::core::ics02_client::client_state::AnyClientState;
+    use ibc::core::ics03_connection::connection::{ConnectionEnd, Counterparty};
+    use ibc::core::ics03_connection::version::get_compatible_versions;
+    use ibc::mock::client_state::MockClientState;
+    use ibc::mock::header::MockHeader;
+    use ibc::timestamp::ZERO_DURATION;
+
+    fn connection(
+        id: u64,
+        state: ConnectionState,
+        counterparty_state: ConnectionState,
+    ) -> ConnectionScan {
+        ConnectionScan {
+            connection: IdentifiedConnectionEnd::new(
+                ConnectionId::new(id),
+                ConnectionEnd::new(
+                    state,
+                    ClientId::default(),
+                    Counterparty::new(ClientId::default(), None, Default::default()),
+                    get_compatible_versions(),
+                    ZERO_DURATION,
+                ),
+            ),
+            counterparty_state,
+            channels: vec![],
+        }
+    }
+
+    fn chain_scan(height: Height) -> ChainScan {
+        let client = IdentifiedAnyClientState::new(
+            ClientId::default(),
+            AnyClientState::Mock(MockClientState::new(MockHeader::new(Height::new(0, 5)))),
+        );
+
+        ChainScan {
+            chain_id: ChainId::new("chain_a".to_string(), 0),
+            height,
+            clients: vec![ClientScan {
+                client,
+                connections: vec![
+                    connection(0, ConnectionState::Open, ConnectionState::Open),
+                    connection(1, ConnectionState::Open, ConnectionState::TryOpen),
+                ],
+            }],
+        }
+    }
+
+    #[test]
+    fn cached_scan_serves_open_connections() {
+        let cached = CachedScan::new(chain_scan(Height::new(0, 10)), Height::new(0, 20));
+
+        assert!(cached
+            .open_connection(&ClientId::default(), &ConnectionId::new(0))
+            .is_some());
+
+        // Connections which were not open on both ends are queried again
+        assert!(cached
+            .open_connection(&ClientId::default(), &ConnectionId::new(1))
+            .is_none());
+        assert!(cached
+            .open_connection(&ClientId::default(), &ConnectionId::new(2))
+            .is_none());
+    }
+
+    #[test]
+    fn cached_scan_is_discarded_after_chain_reset() {
+        let open = |cached: &CachedScan| {
+            cached
+                .open_connection(&ClientId::default(), &ConnectionId::new(0))
+                .is_some()
+        };
+
+        assert!(open(&CachedScan::new(
+            chain_scan(Height::new(0, 10)),
+            Height::new(0, 10)
+        )));
+        assert!(!open(&CachedScan::new(
+            chain_scan(Height::new(0, 10)),
+            Height::new(0, 9)
+        )));
+        assert!(!open(&CachedScan::new(
+            chain_scan(Height::new(0, 10)),
+            Height::new(1, 20)
+        )));
+    }
+
+    #[test]
+    fn scan_cache_load_and_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ScanCache {
+            dir: dir.path().join("scan"),
+        };
+
+        let chain_id = ChainId::new("chain_a".to_string(), 0);
+        assert!(cache.load(&chain_id).is_none());
+
+        let scan = chain_scan(Height::new(0, 10));
+        cache.store(&scan);
+
+        let loaded = cache.load(&chain_id).unwrap();
+        assert_eq!(loaded.chain_id, scan.chain_id);
+        assert_eq!(loaded.height, scan.height);
+        assert_eq!(loaded.clients.len(), 1);
+        assert_eq!(
+            loaded.clients[0].connections[0].connection,
+            scan.clients[0].connections[0].connection
+        );
+
+        // A corrupted cache is ignored
+        fs::write(cache.path(&chain_id), "{").unwrap();
+        assert!(cache.load(&chain_id).is_none());
+    }
+
+    #[test]
+    fn scan_cache_from_config() {
+        let mut config = Config::default();
+        config.scan.cache_dir = Some(PathBuf::from("/tmp/hermes-scan"));
+
+        config.scan.cache = true;
+        assert_eq!(
+            ScanCache::from_config(&config).unwrap().dir,
+            PathBuf::from("/tmp/hermes-scan")
+        );
+
+        config.scan.cache = false;
+        assert!(ScanCache::from_config(&config).is_none());
+    }
+}
@@ -3,25 +3,30 @@ use tracing::{debug, error, warn};
 
 use ibc::{
     core::{
-        ics02_client::client_state::{ClientState, IdentifiedAnyClientState},
-        ics03_connection::connection::{IdentifiedConnectionEnd, State as ConnectionState},
-        ics04_channel::channel::{IdentifiedChannelEnd, State as ChannelState},
+        ics02_client::client_state::{AnyClientState, ClientState, IdentifiedAnyClientState},
+        ics03_connection::connection::{
+            ConnectionEnd, IdentifiedConnectionEnd, State as ConnectionState,
+        },
+        ics04_channel::channel::{ChannelEnd, IdentifiedChannelEnd, State as ChannelState},
         ics24_host::identifier::{ChainId, ConnectionId},
     },
     Height,
 };
 
 use ibc_proto::ibc::core::{
-    channel::v1::QueryConnectionChannelsRequest, client::v1::QueryClientStatesRequest,
-    connection::v1::QueryClientConnectionsRequest,
+    channel::v1::{QueryChannelsRequest, QueryConnectionChannelsRequest},
+    client::v1::QueryClientStatesRequest,
+    connection::v1::{QueryClientConnectionsRequest, QueryConnectionsRequest},
 };
 
 use crate::{
     chain::{
-        counterparty::{channel_on_destination, connection_state_on_destination},
+        counterparty::{
+            channel_on_destination, connection_state_on_destination, has_pending_packets,
+        },
         handle::ChainHandle,
     },
-    config::Config,
+    config::{Config, PathConfig, PathEndConfig},
     object::{Channel, Client, Connection, Object, Packet},
     registry::Registry,
     supervisor::client_state_filter::{FilterPolicy, Permission},
@@ -29,8 +34,8 @@ use crate::{
     worker::WorkerMap,
 };
 
+use super::scan::{scan_chains, ChainScan};
 use super::Error;
-use crate::chain::counterparty::{unreceived_acknowledgements, unreceived_packets};
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum SpawnMode {
@@ -70,6 +75,16 @@ impl<'a, Chain: ChainHandle> SpawnContext<'a, Chain> {
     }
 
     pub fn spawn_workers(&mut self) {
+        if self.config.has_paths() {
+            let paths = self.config.paths.clone();
+
+            for path in paths {
+                self.spawn_workers_for_path(&path);
+            }
+
+            return;
+        }
+
         let chain_ids = self
             .config
             .chains
@@ -78,8 +93,94 @@ impl<'a, Chain: ChainHandle> SpawnContext<'a, Chain> {
             .cloned()
             .collect_vec();
 
+        let mut chains = Vec::with_capacity(chain_ids.len());
+
         for chain_id in chain_ids {
-            self.spawn_workers_for_chain(&chain_id);
+            match self.registry.get_or_spawn(&chain_id) {
+                Ok(chain) => chains.push(chain),
+                Err(e) => error!(
+                    "skipping workers for chain {}, reason: failed to spawn chain runtime with error: {}",
+                    chain_id, e
+                ),
+            }
+        }
+
+        for (chain, scan) in scan_chains(self.config, chains) {
+            match scan {
+                Ok(scan) => self.spawn_workers_from_scan(chain.clone(), scan),
+                Err(e) => error!(
+                    "skipping workers for chain {}, reason: failed to scan chain: {}",
+                    chain.id(),
+                    e
+                ),
+            }
+
+            self.spawn_handshake_workers_for_chain(chain);
+        }
+    }
+
+    /// Spawns the workers for the clients, connections and channels found
+    /// by the startup scan of the given chain.
+    fn spawn_workers_from_scan(&mut self, chain: Chain, scan: ChainScan) {
+        for client_scan in scan.clients {
+            let client = client_scan.client;
+
+            if !self.client_allowed(&chain, &client) {
+                continue;
+            }
+
+            let counterparty_chain = match self
+                .registry
+                .get_or_spawn(&client.client_state.chain_id())
+            {
+                Ok(counterparty_chain) => counterparty_chain,
+                Err(e) => {
+                    error!(
+                        "skipping workers for client {} on chain {}, reason: failed to spawn counterparty chain runtime: {}",
+                        client.client_id, chain.id(), e
+                    );
+
+                    continue;
+                }
+            };
+
+            for connection_scan in client_scan.connections {
+                let connection = &connection_scan.connection;
+
+                if !self.connection_allowed(&chain, &client, connection) {
+                    continue;
+                }
+
+                self.spawn_connection_workers_with_state(
+                    chain.clone(),
+                    counterparty_chain.clone(),
+                    &client,
+                    connection.clone(),
+                    connection_scan.counterparty_state,
+                );
+
+                if !connection_scan.is_open() {
+                    debug!(
+                        "connection {} not open, skip workers for channels over this connection",
+                        connection.connection_id
+                    );
+
+                    continue;
+                }
+
+                for channel_scan in connection_scan.channels {
+                    let has_pending_packets = channel_scan.has_pending_packets;
+
+                    self.spawn_channel_workers(
+                        chain.clone(),
+                        counterparty_chain.clone(),
+                        &client,
+                        channel_scan.channel,
+                        channel_scan.counterparty,
+                        || has_pending_packets,
+                    );
+                }
+            }
         }
     }
 
@@ -124,6 +225,16 @@ impl<'a, Chain: ChainHandle> SpawnContext<'a, Chain> {
     }
 
     pub fn spawn_workers_for_chain(&mut self, chain_id: &ChainId) {
+        if self.config.has_paths() {
+            let paths = self.config.paths_of_chain(chain_id).cloned().collect_vec();
+
+            for path in paths {
+                self.spawn_workers_for_path(&path);
+            }
+
+            return;
+        }
+
         let clients_req = QueryClientStatesRequest {
             pagination: ibc_proto::cosmos::base::query::pagination::all(),
         };
@@ -156,6 +267,8 @@ impl<'a, Chain: ChainHandle> SpawnContext<'a, Chain> {
             self.spawn_workers_for_client(chain.clone(), client);
         }
 
+        self.spawn_handshake_workers_for_chain(chain.clone());
+
         if self.mode != SpawnMode::Reload {
             return;
         }
@@ -176,39 +289,270 @@ impl<'a, Chain: ChainHandle> SpawnContext<'a, Chain> {
         }
     }
 
-    pub fn spawn_workers_for_client(&mut self, chain: Chain, client: IdentifiedAnyClientState) {
-        // Potentially ignore the client
-        if self.client_filter_enabled()
-            && matches!(
-                self.client_state_filter.control_client(
-                    &chain.id(),
-                    &client.client_id,
-                    &client.client_state
+    /// Spawns the workers for both ends of a path listed in the configuration,
+    /// without scanning the chains.
+    pub fn spawn_workers_for_path(&mut self, path: &PathConfig) {
+        for (end, counterparty) in [(&path.a, &path.b), (&path.b, &path.a)] {
+            if let Err(e) = self.spawn_workers_for_path_end(end, counterparty) {
+                error!(
+                    "skipped workers for path end {}/{} on chain {}, reason: {}",
+                    end.port_id, end.channel_id, end.chain_id, e
+                );
+            }
+        }
+    }
+
+    fn spawn_workers_for_path_end(
+        &mut self,
+        end: &PathEndConfig,
+        counterparty: &PathEndConfig,
+    ) -> Result<(), Error> {
+        let chain = self
+            .registry
+            .get_or_spawn(&end.chain_id)
+            .map_err(Error::spawn)?;
+
+        let client_state = chain
+            .query_client_state(&end.client_id, Height::zero())
+            .map_err(Error::relayer)?;
+
+        let connection_end = chain
+            .query_connection(&end.connection_id, Height::zero())
+            .map_err(Error::relayer)?;
+
+        let channel_end = chain
+            .query_channel(&end.port_id, &end.channel_id, Height::zero())
+            .map_err(Error::relayer)?;
+
+        // Check that the identifiers in the configuration match the state of the chain
+        let consistent = path_end_matches(
+            end,
+            counterparty,
+            &client_state,
+            &connection_end,
+            &channel_end,
+        );
+
+        if !consistent {
+            return Err(Error::path_mismatch(
+                end.chain_id.clone(),
+                end.port_id.clone(),
+                end.channel_id.clone(),
+            ));
+        }
+
+        let client = IdentifiedAnyClientState {
+            client_id: end.client_id.clone(),
+            client_state,
+        };
+
+        let connection = IdentifiedConnectionEnd {
+            connection_id: end.connection_id.clone(),
+            connection_end,
+        };
+
+        let channel = IdentifiedChannelEnd {
+            port_id: end.port_id.clone(),
+            channel_id: end.channel_id.clone(),
+            channel_end,
+        };
+
+        self.spawn_connection_workers(chain.clone(), client.clone(), connection.clone())?;
+
+        self.spawn_workers_for_channel(chain, &client, &connection, channel)
+    }
+
+    /// Spawns the workers completing the handshakes of the connections and channels
+    /// of the given chain which were left half-open, including the channels whose
+    /// connection is not yet open, which are otherwise skipped by the scan of clients.
+    pub fn spawn_handshake_workers_for_chain(&mut self, chain: Chain) {
+        let mode = self.config.mode;
+
+        if mode.connections.enabled {
+            let connections_req = QueryConnectionsRequest {
+                pagination: ibc_proto::cosmos::base::query::pagination::all(),
+            };
+
+            match chain.query_connections(connections_req) {
+                Ok(connections) => {
+                    for connection in connections {
+                        if matches!(
+                            connection.connection_end.state,
+                            ConnectionState::Init | ConnectionState::TryOpen
+                        ) {
+                            self.spawn_handshake_worker_for_connection(chain.clone(), connection);
+                        }
+                    }
+                }
+                Err(e) => error!(
+                    "skipping connection handshake workers for chain {}, reason: failed to query connections: {}",
+                    chain.id(), e
                 ),
-                Permission::Deny
-            )
-        {
-            warn!(
-                "skipping workers for chain {}, client {}. \
-                             reason: client is not allowed (client trust level={:?})",
+            }
+        }
+
+        if mode.channels.enabled {
+            let channels_req = QueryChannelsRequest {
+                pagination: ibc_proto::cosmos::base::query::pagination::all(),
+            };
+
+            match chain.query_channels(channels_req) {
+                Ok(channels) => {
+                    for channel in channels {
+                        if matches!(
+                            channel.channel_end.state,
+                            ChannelState::Init | ChannelState::TryOpen
+                        ) {
+                            self.spawn_handshake_worker_for_channel(chain.clone(), channel);
+                        }
+                    }
+                }
+                Err(e) => error!(
+                    "skipping channel handshake workers for chain {}, reason: failed to query channels: {}",
+                    chain.id(), e
+                ),
+            }
+        }
+    }
+
+    fn spawn_handshake_worker_for_connection(
+        &mut self,
+        chain: Chain,
+        connection: IdentifiedConnectionEnd,
+    ) {
+        let client = match self.allowed_client_of_connection(&chain, &connection) {
+            Some(client) => client,
+            None => return,
+        };
+
+        if let Err(e) = self.spawn_connection_workers(chain.clone(), client, connection.clone()) {
+            error!(
+                "skipped handshake worker for connection {} on chain {}, reason: {}",
+                connection.connection_id,
                 chain.id(),
-                client.client_id,
-                client.client_state.trust_threshold()
+                e
+            );
+        }
+    }
+
+    fn spawn_handshake_worker_for_channel(&mut self, chain: Chain, channel: IdentifiedChannelEnd) {
+        if !self.relay_packets_on_channel(&chain, &channel) {
+            debug!(
+                "skipping handshake worker for channel {}/{} on chain {}, reason: channel is not allowed by the packet filter",
+                channel.port_id, channel.channel_id, chain.id()
             );
 
             return;
         }
 
-        let counterparty_chain_id = client.client_state.chain_id();
+        let connection_id = match channel.channel_end.connection_hops().first() {
+            Some(connection_id) => connection_id.clone(),
+            None => return,
+        };
 
-        let has_counterparty = self.config.has_chain(&counterparty_chain_id);
+        let connection_end = match chain.query_connection(&connection_id, Height::zero()) {
+            Ok(connection_end) => connection_end,
+            Err(e) => {
+                error!(
+                    "skipping handshake worker for channel {} on chain {}, reason: failed to query connection {}: {}",
+                    channel.channel_id, chain.id(), connection_id, e
+                );
 
-        if !has_counterparty {
+                return;
+            }
+        };
+
+        let connection = IdentifiedConnectionEnd {
+            connection_id,
+            connection_end,
+        };
+
+        let client = match self.allowed_client_of_connection(&chain, &connection) {
+            Some(client) => client,
+            None => return,
+        };
+
+        let channel_id = channel.channel_id.clone();
+
+        if let Err(e) = self.spawn_workers_for_channel(chain.clone(), &client, &connection, channel)
+        {
+            error!(
+                "skipped handshake worker for channel {} on chain {}, reason: {}",
+                channel_id,
+                chain.id(),
+                e
+            );
+        }
+    }
+
+    /// Returns the client underlying the given connection, if its counterparty chain
+    /// is present in the configuration, and if the client filter allows it.
+    fn allowed_client_of_connection(
+        &mut self,
+        chain: &Chain,
+        connection: &IdentifiedConnectionEnd,
+    ) -> Option<IdentifiedAnyClientState> {
+        let client_id = connection.connection_end.client_id();
+
+        let client_state = match chain.query_client_state(client_id, Height::zero()) {
+            Ok(client_state) => client_state,
+            Err(e) => {
+                error!(
+                        "skipping handshake workers for connection {} on chain {}, reason: failed to query client {}: {}",
+                        connection.connection_id, chain.id(), client_id, e
+                    );
+
+                return None;
+            }
+        };
+
+        if !self.config.has_chain(&client_state.chain_id()) {
             debug!(
-                "skipping client worker for client {} on chain {} has its counterparty ({}) is not present in config",
-                client.client_id, chain.id(), counterparty_chain_id
+                "skipping handshake workers for connection {} on chain {}, reason: its counterparty ({}) is not present in config",
+                connection.connection_id, chain.id(), client_state.chain_id()
             );
 
+            return None;
+        }
+
+        if self.client_filter_enabled() {
+            match self.client_state_filter.control_connection_end_and_client(
+                self.registry,
+                &chain.id(),
+                &client_state,
+                &connection.connection_end,
+                &connection.connection_id,
+            ) {
+                Ok(Permission::Allow) => {}
+                Ok(Permission::Deny) => {
+                    warn!(
+                        "skipping handshake workers for connection {} on chain {}, reason: client or counterparty client is not allowed",
+                        connection.connection_id, chain.id()
+                    );
+
+                    return None;
+                }
+                Err(e) => {
+                    error!(
+                        "skipping handshake workers for connection {} on chain {}, reason: {}",
+                        connection.connection_id,
+                        chain.id(),
+                        e
+                    );
+
+                    return None;
+                }
+            }
+        }
+
+        Some(IdentifiedAnyClientState {
+            client_id: client_id.clone(),
+            client_state,
+        })
+    }
+
+    pub fn spawn_workers_for_client(&mut self, chain: Chain, client: IdentifiedAnyClientState) {
+        if !self.client_allowed(&chain, &client) {
             return;
         }
 
@@ -235,6 +579,47 @@ impl<'a, Chain: ChainHandle> SpawnContext<'a, Chain> {
         }
     }
 
+    /// Returns true if the client filter allows the given client, and if its
+    /// counterparty chain is present in the configuration.
+    fn client_allowed(&mut self, chain: &Chain, client: &IdentifiedAnyClientState) -> bool {
+        // Potentially ignore the client
+        if self.client_filter_enabled()
+            && matches!(
+                self.client_state_filter.control_client(
+                    &chain.id(),
+                    &client.client_id,
+                    &client.client_state
+                ),
+                Permission::Deny
+            )
+        {
+            warn!(
+                "skipping workers for chain {}, client {}. \
+                             reason: client is not allowed (client trust level={:?})",
+                chain.id(),
+                client.client_id,
+                client.client_state.trust_threshold()
+            );
+
+            return false;
+        }
+
+        let counterparty_chain_id = client.client_state.chain_id();
+
+        let has_counterparty = self.config.has_chain(&counterparty_chain_id);
+
+        if !has_counterparty {
+            debug!(
+                "skipping client worker for client {} on chain {} has its counterparty ({}) is not present in config",
+                client.client_id, chain.id(), counterparty_chain_id
+            );
+
+            return false;
+        }
+
+        true
+    }
+
     pub fn spawn_workers_for_connection(
         &mut self,
         chain: Chain,
@@ -259,32 +644,8 @@ impl<'a, Chain: ChainHandle> SpawnContext<'a, Chain> {
             connection_end: connection_end.clone(),
         };
 
-        // Apply the client state filter
-        if self.client_filter_enabled() {
-            match self.client_state_filter.control_connection_end_and_client(
-                self.registry,
-                &chain_id,
-                &client.client_state,
-                &connection_end,
-                &connection_id,
-            ) {
-                Ok(Permission::Deny) => {
-                    warn!(
-                        "skipping workers for chain {}, client {} & conn {}. \
-                                 reason: client or counterparty client is not allowed",
-                        chain_id, client.client_id, connection_id
-                    );
-                    return;
-                }
-                Err(e) => {
-                    error!(
-                        "skipping workers for chain {}, client {} & conn {}. reason: {}",
-                        chain_id, client.client_id, connection_id, e
-                    );
-                    return;
-                }
-                _ => {} // allowed
-            }
+        if !self.connection_allowed(&chain, client, &connection) {
+            return;
         }
 
         match self.spawn_connection_workers(chain.clone(), client.clone(), connection.clone()) {
@@ -367,6 +728,47 @@ impl<'a, Chain: ChainHandle> SpawnContext<'a, Chain> {
         }
     }
 
+    /// Returns true if the client state filter allows the given connection and its client.
+    fn connection_allowed(
+        &mut self,
+        chain: &Chain,
+        client: &IdentifiedAnyClientState,
+        connection: &IdentifiedConnectionEnd,
+    ) -> bool {
+        if !self.client_filter_enabled() {
+            return true;
+        }
+
+        let chain_id = chain.id();
+        let connection_id = &connection.connection_id;
+
+        // Apply the client state filter
+        match self.client_state_filter.control_connection_end_and_client(
+            self.registry,
+            &chain_id,
+            &client.client_state,
+            &connection.connection_end,
+            connection_id,
+        ) {
+            Ok(Permission::Deny) => {
+                warn!(
+                    "skipping workers for chain {}, client {} & conn {}. \
+                             reason: client or counterparty client is not allowed",
+                    chain_id, client.client_id, connection_id
+                );
+                false
+            }
+            Err(e) => {
+                error!(
+                    "skipping workers for chain {}, client {} & conn {}. reason: {}",
+                    chain_id, client.client_id, connection_id, e
+                );
+                false
+            }
+            _ => true, // allowed
+        }
+    }
+
     fn counterparty_connection_state(
         &mut self,
         client: IdentifiedAnyClientState,
@@ -386,17 +788,39 @@ impl<'a, Chain: ChainHandle> SpawnContext<'a, Chain> {
         client: IdentifiedAnyClientState,
         connection: IdentifiedConnectionEnd,
     ) -> Result<(), Error> {
-        let config_conn_enabled = self.config.mode.connections.enabled;
-
         let counterparty_chain = self
             .registry
             .get_or_spawn(&client.client_state.chain_id())
             .map_err(Error::spawn)?;
 
-        let conn_state_src = connection.connection_end.state;
         let conn_state_dst =
             connection_state_on_destination(connection.clone(), &counterparty_chain)?;
 
+        self.spawn_connection_workers_with_state(
+            chain,
+            counterparty_chain,
+            &client,
+            connection,
+            conn_state_dst,
+        );
+
+        Ok(())
+    }
+
+    /// Spawns the worker completing the handshake of the given connection, if needed,
+    /// given the state of the connection on the counterparty chain.
+    fn spawn_connection_workers_with_state(
+        &mut self,
+        chain: Chain,
+        counterparty_chain: Chain,
+        client: &IdentifiedAnyClientState,
+        connection: IdentifiedConnectionEnd,
+        conn_state_dst: ConnectionState,
+    ) {
+        let config_conn_enabled = self.config.mode.connections.enabled;
+
+        let conn_state_src = connection.connection_end.state;
+
         debug!(
             "connection {} on chain {} is: {:?}, state on dest. chain ({}) is: {:?}",
             connection.connection_id,
@@ -432,8 +856,6 @@ impl<'a, Chain: ChainHandle> SpawnContext<'a, Chain> {
                     );
                 });
         }
-
-        Ok(())
     }
 
     /// Spawns all the [`Worker`](crate::worker::Worker)s that will
@@ -445,8 +867,6 @@ impl<'a, Chain: ChainHandle> SpawnContext<'a, Chain> {
         connection: &IdentifiedConnectionEnd,
         channel: IdentifiedChannelEnd,
     ) -> Result<(), Error> {
-        let mode = &self.config.mode;
-
         let counterparty_chain = self
             .registry
             .get_or_spawn(&client.client_state.chain_id())
@@ -455,6 +875,42 @@ impl<'a, Chain: ChainHandle> SpawnContext<'a, Chain> {
         let counterparty_channel =
             channel_on_destination(&channel, connection, &counterparty_chain)?;
 
+        // Only called if the channel is open on both ends, in which case the
+        // counterparty channel end is known
+        let pending_packets = || -> bool {
+            counterparty_channel
+                .as_ref()
+                .map_or(false, |counterparty_channel| {
+                    has_pending_packets(&chain, &counterparty_chain, counterparty_channel)
+                })
+        };
+
+        self.spawn_channel_workers(
+            chain.clone(),
+            counterparty_chain.clone(),
+            client,
+            channel,
+            counterparty_channel.clone(),
+            pending_packets,
+        );
+
+        Ok(())
+    }
+
+    /// Spawns the workers for the given channel, given its counterparty channel end.
+    /// The `has_pending_packets` closure is only called when a packet worker may be
+    /// spawned, to check whether there are packets or acknowledgements to relay.
+    fn spawn_channel_workers(
+        &mut self,
+        chain: Chain,
+        counterparty_chain: Chain,
+        client: &IdentifiedAnyClientState,
+        channel: IdentifiedChannelEnd,
+        counterparty_channel: Option<IdentifiedChannelEnd>,
+        has_pending_packets: impl FnOnce() -> bool,
+    ) {
+        let mode = &self.config.mode;
+
         let chan_state_src = channel.channel_end.state;
         let chan_state_dst = counterparty_channel
             .as_ref()
@@ -492,24 +948,8 @@ impl<'a, Chain: ChainHandle> SpawnContext<'a, Chain> {
             }
 
             if mode.packets.enabled {
-                // SAFETY: Safe to unwrap because the inner channel end has state open
-                let counterparty_channel =
-                    counterparty_channel.expect("inner channel end is in state OPEN");
-
-                let has_packets = || -> bool {
-                    !unreceived_packets(&counterparty_chain, &chain, &counterparty_channel)
-                        .unwrap_or_default()
-                        .is_empty()
-                };
-
-                let has_acks = || -> bool {
-                    !unreceived_acknowledgements(&counterparty_chain, &chain, &counterparty_channel)
-                        .unwrap_or_default()
-                        .is_empty()
-                };
-
                 // If there are any outstanding packets or acks to send, spawn the worker
-                if has_packets() || has_acks() {
+                if has_pending_packets() {
                     // Create the Packet object and spawn worker
                     let path_object = Object::Packet(Packet {
                         dst_chain_id: counterparty_chain.id(),
@@ -544,8 +984,6 @@ impl<'a, Chain: ChainHandle> SpawnContext<'a, Chain> {
                 .spawn(chain, counterparty_chain, &channel_object, self.config)
                 .then(|| debug!("spawned Channel worker: {}", channel_object.short_name()));
         }
-
-        Ok(())
     }
 
     fn relay_packets_on_channel(
@@ -564,3 +1002,230 @@ impl<'a, Chain: ChainHandle> SpawnContext<'a, Chain> {
         }
     }
 }
+
+/// Returns `true` if the client, connection and channel found at one end of a path
+/// match the identifiers of that end and of its counterparty in the configuration.
+fn path_end_matches(
+    end: &PathEndConfig,
+    counterparty: &PathEndConfig,
+    client_state: &AnyClientState,
+    connection_end: &ConnectionEnd,
+    channel_end: &ChannelEnd,
+) -> bool {
+    client_state.chain_id() == counterparty.chain_id
+        && connection_end.client_id() == &end.client_id
+        && connection_end.counterparty().client_id() == &counterparty.client_id
+        && connection_end
+            .counterparty()
+            .connection_id()
+            .map_or(true, |id| id == &counterparty.connection_id)
+        && channel_end.connection_hops().first() == Some(&end.connection_id)
+        && channel_end.counterparty().port_id() == &counterparty.port_id
+        && channel_end
+            .counterparty()
+            .channel_id()
+            .map_or(true, |id| id == &counterparty.channel_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::str::FromStr;
+    use core::time::Duration;
+
+    use ibc::clients::ics07_tendermint::client_state::{
+        AllowUpdate, ClientState as TendermintClientState,
+    };
+    use ibc::core::ics02_client::trust_threshold::TrustThreshold;
+    use ibc::core::ics03_connection::connection::Counterparty as ConnectionCounterparty;
+    use ibc::core::ics03_connection::version::get_compatible_versions;
+    use ibc::core::ics04_channel::channel::{Counterparty as ChannelCounterparty, Order};
+    use ibc::core::ics04_channel::Version;
+    use ibc::core::ics23_commitment::specs::ProofSpecs;
+    use ibc::core::ics24_host::identifier::{ChannelId, ClientId, PortId};
+    use ibc::timestamp::ZERO_DURATION;
+
+    fn path_end(chain_id: &str, counter: u64) -> PathEndConfig {
+        PathEndConfig {
+            chain_id: ChainId::from_str(chain_id).unwrap(),
+            client_id: ClientId::from_str(&format!("07-tendermint-{}", counter)).unwrap(),
+            connection_id: ConnectionId::new(counter),
+            port_id: PortId::transfer(),
+            channel_id: ChannelId::new(counter),
+        }
+    }
+
+    fn client_state(chain_id: &ChainId) -> AnyClientState {
+        let trusting_period = Duration::from_secs(14 * 24 * 3600);
+
+        let client_state = TendermintClientState::new(
+            chain_id.clone(),
+            TrustThreshold::ONE_THIRD,
+            trusting_period,
+            trusting_period * 2,
+            Duration::from_secs(3),
+            Height::new(chain_id.version(), 10),
+            ProofSpecs::default(),
+            vec![],
+            AllowUpdate {
+                after_expiry: false,
+                after_misbehaviour: false,
+            },
+        )
+        .unwrap();
+
+        AnyClientState::Tendermint(client_state)
+    }
+
+    fn connection_end(client_id: &ClientId, counterparty: &PathEndConfig) -> ConnectionEnd {
+        ConnectionEnd::new(
+            ConnectionState::Open,
+            client_id.clone(),
+            ConnectionCounterparty::new(
+                counterparty.client_id.clone(),
+                Some(counterparty.connection_id.clone()),
+                Default::default(),
+            ),
+            get_compatible_versions(),
+            ZERO_DURATION,
+        )
+    }
+
+    fn channel_end(connection_id: &ConnectionId, counterparty: &PathEndConfig) -> ChannelEnd {
+        ChannelEnd::new(
+            ChannelState::Open,
+            Order::Unordered,
+            ChannelCounterparty::new(
+                counterparty.port_id.clone(),
+                Some(counterparty.channel_id.clone()),
+            ),
+            vec![connection_id.clone()],
+            Version::ics20(),
+        )
+    }
+
+    #[test]
+    fn path_end_matching_the_chain() {
+        let (end, counterparty) = (path_end("chain_a", 0), path_end("chain_b", 1));
+
+        assert!(path_end_matches(
+            &end,
+            &counterparty,
+            &client_state(&counterparty.chain_id),
+            &connection_end(&end.client_id, &counterparty),
+            &channel_end(&end.connection_id, &counterparty),
+        ));
+
+        // The counterparty identifiers are not yet known during the handshakes
+        let connection = ConnectionEnd::new(
+            ConnectionState::Init,
+            end.client_id.clone(),
+            ConnectionCounterparty::new(counterparty.client_id.clone(), None, Default::default()),
+            get_compatible_versions(),
+            ZERO_DURATION,
+        );
+        let channel = ChannelEnd::new(
+            ChannelState::Init,
+            Order::Unordered,
+            ChannelCounterparty::new(counterparty.port_id.clone(), None),
+            vec![end.connection_id.clone()],
+            Version::ics20(),
+        );
+
+        assert!(path_end_matches(
+            &end,
+            &counterparty,
+            &client_state(&counterparty.chain_id),
+            &connection,
+            &channel,
+        ));
+    }
+
+    #[test]
+    fn path_end_mismatching_the_chain() {
+        let (end, counterparty) = (path_end("chain_a", 0), path_end("chain_b", 1));
+        let other = path_end("chain_c", 2);
+
+        let matches = |client_chain_id: &ChainId,
+                       client_id: &ClientId,
+                       connection_counterparty: &PathEndConfig,
+                       connection_id: &ConnectionId,
+                       channel_counterparty: &PathEndConfig| {
+            path_end_matches(
+                &end,
+                &counterparty,
+                &client_state(client_chain_id),
+                &connection_end(client_id, connection_counterparty),
+                &channel_end(connection_id, channel_counterparty),
+            )
+        };
+
+        let mut counterparty_client = counterparty.clone();
+        counterparty_client.client_id = other.client_id.clone();
+
+        let mut counterparty_connection = counterparty.clone();
+        counterparty_connection.connection_id = other.connection_id.clone();
+
+        let mut counterparty_port = counterparty.clone();
+        counterparty_port.port_id = PortId::from_str("other").unwrap();
+
+        let mut counterparty_channel = counterparty.clone();
+        counterparty_channel.channel_id = other.channel_id.clone();
+
+        // The client tracks another chain
+        assert!(!matches(
+            &other.chain_id,
+            &end.client_id,
+            &counterparty,
+            &end.connection_id,
+            &counterparty,
+        ));
+        // The connection is over another client
+        assert!(!matches(
+            &counterparty.chain_id,
+            &other.client_id,
+            &counterparty,
+            &end.connection_id,
+            &counterparty,
+        ));
+        // The connection ends on another client or connection of the counterparty
+        assert!(!matches(
+            &counterparty.chain_id,
+            &end.client_id,
+            &counterparty_client,
+            &end.connection_id,
+            &counterparty,
+        ));
+        assert!(!matches(
+            &counterparty.chain_id,
+            &end.client_id,
+            &counterparty_connection,
+            &end.connection_id,
+            &counterparty,
+        ));
+        // The channel is over another connection
+        assert!(!matches(
+            &counterparty.chain_id,
+            &end.client_id,
+            &counterparty,
+            &other.connection_id,
+            &counterparty,
+        ));
+        // The channel ends on another port or channel of the counterparty
+        assert!(!matches(
+            &counterparty.chain_id,
+            &end.client_id,
+            &counterparty,
+            &end.connection_id,
+            &counterparty_port,
+        ));
+        assert!(!matches(
+            &counterparty.chain_id,
+            &end.client_id,
+            &counterparty,
+            &end.connection_id,
+            &counterparty_channel,
+        ));
+    }
+}
@@ -9,6 +9,7 @@ use ibc::events::IbcEvent;
 use ibc::timestamp::{Timestamp, TimestampOverflowError};
 use ibc::tx_msg::Msg;
 use ibc::Height;
+use prost_types::Any;
 use uint::FromStrRadixErr;
 
 use crate::chain::handle::ChainHandle;
@@ -82,11 +83,11 @@ pub struct TransferOptions {
     pub number_msgs: usize,
 }
 
-pub fn build_and_send_transfer_messages<SrcChain: ChainHandle, DstChain: ChainHandle>(
+pub fn build_transfer_messages<SrcChain: ChainHandle, DstChain: ChainHandle>(
     packet_src_chain: &SrcChain, // the chain whose account is debited
     packet_dst_chain: &DstChain, // the chain whose account eventually gets credited
     opts: &TransferOptions,
-) -> Result<Vec<IbcEvent>, PacketError> {
+) -> Result<Vec<Any>, PacketError> {
     let receiver = match &opts.receiver {
         None => packet_dst_chain.get_signer().map_err(PacketError::key)?,
         Some(r) => r.clone().into(),
@@ -123,7 +124,16 @@ pub fn build_and_send_transfer_messages<SrcChain: ChainHandle, DstChain: ChainHa
     };
 
     let raw_msg = msg.to_any();
-    let msgs = vec![raw_msg; opts.number_msgs];
+
+    Ok(vec![raw_msg; opts.number_msgs])
+}
+
+pub fn build_and_send_transfer_messages<SrcChain: ChainHandle, DstChain: ChainHandle>(
+    packet_src_chain: &SrcChain, // the chain whose account is debited
+    packet_dst_chain: &DstChain, // the chain whose account eventually gets credited
+    opts: &TransferOptions,
+) -> Result<Vec<IbcEvent>, PacketError> {
+    let msgs = build_transfer_messages(packet_src_chain, packet_dst_chain, opts)?;
 
     let events = packet_src_chain
         .send_messages_and_wait_commit(msgs)
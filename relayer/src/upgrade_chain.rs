@@ -60,11 +60,11 @@ pub struct UpgradePlanOptions {
     pub legacy: bool,
 }
 
-pub fn build_and_send_ibc_upgrade_proposal(
-    mut dst_chain: CosmosSdkChain, // the chain which will undergo an upgrade
-    src_chain: CosmosSdkChain, // the source chain; supplies a client state for building the upgrade plan
+pub fn build_ibc_upgrade_proposal(
+    dst_chain: &mut CosmosSdkChain, // the chain which will undergo an upgrade
+    src_chain: &CosmosSdkChain, // the source chain; supplies a client state for building the upgrade plan
     opts: &UpgradePlanOptions,
-) -> Result<Vec<IbcEvent>, UpgradeChainError> {
+) -> Result<Any, UpgradeChainError> {
     let upgrade_height = dst_chain
         .query_latest_height()
         .map_err(UpgradeChainError::query)?
@@ -126,10 +126,19 @@ pub fn build_and_send_ibc_upgrade_proposal(
 
     let mut buf_msg = Vec::new();
     prost::Message::encode(&msg, &mut buf_msg).unwrap();
-    let any_msg = Any {
+
+    Ok(Any {
         type_url: "/cosmos.gov.v1beta1.MsgSubmitProposal".to_string(),
         value: buf_msg,
-    };
+    })
+}
+
+pub fn build_and_send_ibc_upgrade_proposal(
+    mut dst_chain: CosmosSdkChain, // the chain which will undergo an upgrade
+    src_chain: CosmosSdkChain, // the source chain; supplies a client state for building the upgrade plan
+    opts: &UpgradePlanOptions,
+) -> Result<Vec<IbcEvent>, UpgradeChainError> {
+    let any_msg = build_ibc_upgrade_proposal(&mut dst_chain, &src_chain, opts)?;
 
     let events = dst_chain
         .send_messages_and_wait_commit(vec![any_msg])
@@ -39,6 +39,11 @@ impl<T> Queue<T> {
         self.0.acquire_read().is_empty()
     }
 
+    /// Folds the elements of the queue, from front to back.
+    pub fn fold<B>(&self, init: B, f: impl FnMut(B, &T) -> B) -> B {
+        self.0.acquire_read().iter().fold(init, f)
+    }
+
     pub fn into_vec(self) -> VecDeque<T> {
         self.0.acquire_write().drain(..).collect()
     }
@@ -32,6 +32,9 @@ pub mod channel;
 
 pub mod packet;
 
+pub mod scheduler;
+use scheduler::TxSchedulers;
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 #[serde(transparent)]
 pub struct WorkerId(u64);
@@ -57,6 +60,7 @@ pub fn spawn_worker_tasks<ChainA: ChainHandle, ChainB: ChainHandle>(
     id: WorkerId,
     object: Object,
     config: &Config,
+    schedulers: &TxSchedulers,
 ) -> WorkerHandle {
     let mut task_handles = Vec::new();
     let (cmd_tx, cmd_rx) = crossbeam_channel::unbounded();
@@ -70,6 +74,10 @@ pub fn spawn_worker_tasks<ChainA: ChainHandle, ChainB: ChainHandle>(
                 task_handles.push(refresh_task);
             }
 
+            if config.mode.clients.upgrade {
+                task_handles.push(client::spawn_upgrade_client_task(client.clone()));
+            }
+
             let misbehavior_task = client::detect_misbehavior_task(cmd_rx, client);
             if let Some(task) = misbehavior_task {
                 task_handles.push(task);
@@ -107,7 +115,22 @@ pub fn spawn_worker_tasks<ChainA: ChainHandle, ChainB: ChainHandle>(
                 );
                 task_handles.push(packet_task);
 
-                let link_task = packet::spawn_packet_worker(path.clone(), link);
+                // Register with the transaction scheduler of the destination chain, if enabled
+                let scheduled = scheduler::scheduler_config(config, &path.dst_chain_id).map(
+                    |scheduler_config| {
+                        let weight = scheduler_config
+                            .weight(link.a_to_b.dst_port_id(), link.a_to_b.dst_channel_id());
+
+                        schedulers
+                            .get_or_create(&path.dst_chain_id, scheduler_config)
+                            .register(path.clone(), weight)
+                    },
+                );
+
+                let timeout_task = packet::spawn_packet_timeout_worker(link.clone());
+                task_handles.push(timeout_task);
+
+                let link_task = packet::spawn_packet_worker(path.clone(), link, scheduled);
                 task_handles.push(link_task);
             }
         }
@@ -2,7 +2,12 @@ use core::time::Duration;
 use crossbeam_channel::Receiver;
 use tracing::debug;
 
-use crate::channel::Channel as RelayChannel;
+use ibc::core::ics03_connection::connection::IdentifiedConnectionEnd;
+use ibc::Height;
+
+use crate::chain::counterparty::connection_state_on_destination;
+use crate::channel::{Channel as RelayChannel, ChannelError};
+use crate::supervisor::Error as SupervisorError;
 use crate::util::task::{spawn_background_task, Next, TaskError, TaskHandle};
 use crate::{
     chain::handle::{ChainHandle, ChainHandlePair},
@@ -63,6 +68,22 @@ pub fn spawn_channel_worker<ChainA: ChainHandle, ChainB: ChainHandle>(
                             .decrement()
                             .map_err(|e| TaskError::Fatal(RunError::ics02(e)))?;
 
+                        // Channels found half-open at startup may be waiting
+                        // for the handshake of their connection to complete.
+                        // Failing to query the connection is retried on the next block.
+                        let connection_open =
+                            connection_open_on_both_chains(&chains, &channel, height)
+                                .map_err(|e| TaskError::Ignore(RunError::channel(e)))?;
+
+                        if !connection_open {
+                            debug!(
+                                channel = %channel.short_name(),
+                                "waiting for the connection of the channel to be open"
+                            );
+
+                            return Ok(Next::Continue);
+                        }
+
                         let (mut handshake_channel, state) = RelayChannel::restore_from_state(
                             chains.a.clone(),
                             chains.b.clone(),
@@ -86,3 +107,151 @@ pub fn spawn_channel_worker<ChainA: ChainHandle, ChainB: ChainHandle>(
         },
     )
 }
+
+/// Returns true if the connection underlying the channel is open on both chains,
+/// false otherwise.
+fn connection_open_on_both_chains<ChainA: ChainHandle, ChainB: ChainHandle>(
+    chains: &ChainHandlePair<ChainA, ChainB>,
+    channel: &Channel,
+    height: Height,
+) -> Result<bool, ChannelError> {
+    let channel_end = chains
+        .a
+        .query_channel(&channel.src_port_id, &channel.src_channel_id, height)
+        .map_err(ChannelError::relayer)?;
+
+    let connection_id = channel_end.connection_hops().first().ok_or_else(|| {
+        ChannelError::supervisor(SupervisorError::missing_connection_hops(
+            channel.src_channel_id.clone(),
+            chains.a.id(),
+        ))
+    })?;
+
+    let connection_end = chains
+        .a
+        .query_connection(connection_id, Height::zero())
+        .map_err(ChannelError::relayer)?;
+
+    if !connection_end.is_open() {
+        return Ok(false);
+    }
+
+    let connection = IdentifiedConnectionEnd {
+        connection_id: connection_id.clone(),
+        connection_end,
+    };
+
+    let counterparty_state =
+        connection_state_on_destination(connection, &chains.b).map_err(ChannelError::supervisor)?;
+
+    Ok(counterparty_state.is_open())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::sync::Arc;
+    use std::sync::RwLock;
+
+    use tokio::runtime::Runtime as TokioRuntime;
+
+    use ibc::core::ics03_connection::connection::{
+        ConnectionEnd, Counterparty as ConnectionCounterparty, State as ConnectionState,
+    };
+    use ibc::core::ics03_connection::version::get_compatible_versions;
+    use ibc::core::ics04_channel::channel::{
+        ChannelEnd, Counterparty as ChannelCounterparty, Order, State as ChannelState,
+    };
+    use ibc::core::ics04_channel::Version;
+    use ibc::core::ics24_host::identifier::{ChannelId, ClientId, ConnectionId, PortId};
+    use ibc::mock::context::MockContext;
+    use ibc::mock::host::HostType;
+    use ibc::timestamp::ZERO_DURATION;
+
+    use crate::chain::handle::ProdChainHandle;
+    use crate::chain::mock::test_utils::get_basic_chain_config;
+    use crate::chain::mock::MockChain;
+    use crate::chain::runtime::ChainRuntime;
+
+    fn connection_end(state: ConnectionState) -> ConnectionEnd {
+        ConnectionEnd::new(
+            state,
+            ClientId::default(),
+            ConnectionCounterparty::new(
+                ClientId::default(),
+                Some(ConnectionId::default()),
+                Default::default(),
+            ),
+            get_compatible_versions(),
+            ZERO_DURATION,
+        )
+    }
+
+    fn channel_end() -> ChannelEnd {
+        ChannelEnd::new(
+            ChannelState::Init,
+            Order::Unordered,
+            ChannelCounterparty::new(PortId::transfer(), None),
+            vec![ConnectionId::default()],
+            Version::ics20(),
+        )
+    }
+
+    /// Spawns a mock chain, whose connection `connection-0` is in the given state, if any.
+    fn chain(
+        id: &str,
+        connection: Option<ConnectionState>,
+        rt: &Arc<TokioRuntime>,
+    ) -> ProdChainHandle {
+        let config = get_basic_chain_config(id);
+
+        let mut context = MockContext::new(
+            config.id.clone(),
+            HostType::SyntheticTendermint,
+            5,
+            Height::new(config.id.version(), 20),
+        )
+        .with_channel(PortId::transfer(), ChannelId::default(), channel_end());
+
+        if let Some(state) = connection {
+            context = context.with_connection(ConnectionId::default(), connection_end(state));
+        }
+
+        let chain = MockChain::new(config, Arc::new(RwLock::new(context)));
+        ChainRuntime::<MockChain>::spawn_with_endpoint(chain, rt.clone()).unwrap()
+    }
+
+    fn connection_open(
+        a_connection: Option<ConnectionState>,
+        b_connection: Option<ConnectionState>,
+    ) -> Result<bool, ChannelError> {
+        let rt = Arc::new(TokioRuntime::new().unwrap());
+        let chains = ChainHandlePair {
+            a: chain("chain_a", a_connection, &rt),
+            b: chain("chain_b", b_connection, &rt),
+        };
+
+        let channel = Channel {
+            dst_chain_id: chains.b.id(),
+            src_chain_id: chains.a.id(),
+            src_channel_id: ChannelId::default(),
+            src_port_id: PortId::transfer(),
+        };
+
+        connection_open_on_both_chains(&chains, &channel, Height::zero())
+    }
+
+    #[test]
+    fn connection_open_on_both_chains_gate() {
+        use ConnectionState::*;
+
+        assert!(connection_open(Some(Open), Some(Open)).unwrap());
+        assert!(!connection_open(Some(Init), Some(TryOpen)).unwrap());
+        assert!(!connection_open(Some(Open), Some(TryOpen)).unwrap());
+
+        // Failed queries are reported, and ignored by the channel worker
+        assert!(connection_open(None, Some(Open)).is_err());
+        assert!(connection_open(Some(Open), None).is_err());
+    }
+}
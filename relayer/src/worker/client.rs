@@ -1,19 +1,29 @@
 use core::convert::Infallible;
 use core::time::Duration;
 use crossbeam_channel::Receiver;
-use tracing::{debug, trace, warn};
+use std::time::Instant;
+use tracing::{debug, error, info, trace, warn};
 
 use ibc::events::IbcEvent;
+use ibc::Height;
 
 use crate::util::task::{spawn_background_task, Next, TaskError, TaskHandle};
 use crate::{
     chain::handle::ChainHandle,
-    foreign_client::{ForeignClient, HasExpiredOrFrozenError, MisbehaviourResults},
+    foreign_client::{
+        ForeignClient, ForeignClientError, HasExpiredOrFrozenError, MisbehaviourResults,
+    },
     telemetry,
 };
 
 use super::WorkerCmd;
 
+/// How often the health of a client is reported via telemetry
+const CLIENT_HEALTH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How often the source chain is checked for a scheduled upgrade
+const UPGRADE_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
 pub fn spawn_refresh_client<ChainA: ChainHandle, ChainB: ChainHandle>(
     mut client: ForeignClient<ChainA, ChainB>,
 ) -> Option<TaskHandle> {
@@ -22,12 +32,23 @@ pub fn spawn_refresh_client<ChainA: ChainHandle, ChainB: ChainHandle>(
             "skipping refresh client task on frozen client: {}",
             client.id()
         );
+
+        // Report the health once, so that the expiry of the client is visible.
+        telemetry!(client_health_metrics(&client));
+
         None
     } else {
+        let mut last_health_report: Option<Instant> = None;
+
         Some(spawn_background_task(
             format!("RefreshClientWorker({})", client),
             Some(Duration::from_secs(1)),
             move || {
+                if last_health_report.map_or(true, |t| t.elapsed() >= CLIENT_HEALTH_INTERVAL) {
+                    telemetry!(client_health_metrics(&client));
+                    last_health_report = Some(Instant::now());
+                }
+
                 let res = client.refresh().map_err(|e| {
                     if e.is_expired_or_frozen_error() {
                         TaskError::Fatal(e)
@@ -117,3 +138,126 @@ pub fn detect_misbehavior_task<ChainA: ChainHandle, ChainB: ChainHandle>(
 
     Some(handle)
 }
+
+#[cfg(feature = "telemetry")]
+fn client_health_metrics<ChainA: ChainHandle, ChainB: ChainHandle>(
+    client: &ForeignClient<ChainA, ChainB>,
+) {
+    let health = match client.health() {
+        Ok(health) => health,
+        Err(e) => {
+            debug!("[{}] failed to collect client health: {}", client, e);
+            return;
+        }
+    };
+
+    let dst_chain_id = client.dst_chain.id();
+
+    if let Some(seconds) = health.seconds_until_expiry {
+        telemetry!(client_expiry, &dst_chain_id, &client.id, seconds);
+    }
+
+    telemetry!(
+        client_height_lag,
+        &dst_chain_id,
+        &client.id,
+        &client.src_chain.id(),
+        health.height_lag(),
+    );
+}
+
+/// Watches the source chain of the client for a scheduled upgrade plan, and
+/// upgrades the client once the source chain has reached the upgrade height.
+///
+/// Since the source chain halts at the upgrade height, the upgrade is typically
+/// performed once the chain resumes with the upgraded software. Failed upgrade
+/// attempts are retried until the client is upgraded.
+pub fn spawn_upgrade_client_task<ChainA: ChainHandle, ChainB: ChainHandle>(
+    client: ForeignClient<ChainA, ChainB>,
+) -> TaskHandle {
+    let mut upgrade_height: Option<Height> = None;
+
+    spawn_background_task(
+        format!("UpgradeClientWorker({})", client),
+        Some(UPGRADE_CHECK_INTERVAL),
+        move || {
+            let src_height = client.src_chain.query_latest_height().map_err(|e| {
+                TaskError::Ignore(ForeignClientError::client_upgrade(
+                    client.id.clone(),
+                    client.src_chain.id(),
+                    "failed while querying src chain for latest height".to_string(),
+                    e,
+                ))
+            })?;
+
+            // Until the source chain reaches the upgrade height, keep track of
+            // the upgrade plan, as it may be scheduled, changed or cancelled.
+            if upgrade_height.map_or(true, |height| src_height < height) {
+                let pending = client.pending_upgrade_height().map_err(TaskError::Ignore)?;
+
+                if pending != upgrade_height {
+                    match pending {
+                        Some(height) => info!(
+                            "[{}] source chain is scheduled to upgrade at height {}",
+                            client, height
+                        ),
+                        None => info!("[{}] source chain upgrade was cancelled", client),
+                    }
+
+                    telemetry!(upgrade_plan_metrics(&client, pending));
+                    upgrade_height = pending;
+                }
+
+                return Ok(Next::Continue);
+            }
+
+            let height = match upgrade_height {
+                Some(height) => height,
+                None => return Ok(Next::Continue),
+            };
+
+            if client.is_upgraded(height).map_err(TaskError::Ignore)? {
+                info!(
+                    "[{}] client is already past the upgrade height {}",
+                    client, height
+                );
+            } else {
+                let events = client.upgrade_at(height).map_err(TaskError::Ignore)?;
+
+                if let Some(IbcEvent::ChainError(e)) =
+                    events.iter().find(|e| matches!(e, IbcEvent::ChainError(_)))
+                {
+                    error!(
+                        "[{}] failed to upgrade client at height {}: {}",
+                        client, height, e
+                    );
+
+                    return Ok(Next::Continue);
+                }
+
+                info!("[{}] client upgraded at height {}", client, height);
+
+                telemetry!(ibc_client_upgrades, &client.dst_chain.id(), &client.id, 1);
+            }
+
+            telemetry!(upgrade_plan_metrics(&client, None));
+            upgrade_height = None;
+
+            Ok(Next::Continue)
+        },
+    )
+}
+
+#[cfg(feature = "telemetry")]
+fn upgrade_plan_metrics<ChainA: ChainHandle, ChainB: ChainHandle>(
+    client: &ForeignClient<ChainA, ChainB>,
+    upgrade_height: Option<Height>,
+) {
+    telemetry!(
+        ibc_client_upgrade_plan_height,
+        &client.dst_chain.id(),
+        &client.id,
+        &client.src_chain.id(),
+        upgrade_height.map_or(0, |h| h.revision_height),
+    );
+}
@@ -13,13 +13,14 @@ use crate::{
     telemetry,
 };
 
-use super::{spawn_worker_tasks, WorkerHandle, WorkerId};
+use super::{scheduler::TxSchedulers, spawn_worker_tasks, WorkerHandle, WorkerId};
 
 /// Manage the lifecycle of [`Worker`]s associated with [`Object`]s.
 #[derive(Debug)]
 pub struct WorkerMap {
     workers: HashMap<Object, WorkerHandle>,
     latest_worker_id: WorkerId,
+    schedulers: TxSchedulers,
 }
 
 impl Default for WorkerMap {
@@ -27,6 +28,7 @@ impl Default for WorkerMap {
         Self {
             workers: HashMap::new(),
             latest_worker_id: WorkerId::new(0),
+            schedulers: TxSchedulers::default(),
         }
     }
 }
@@ -166,6 +168,7 @@ impl WorkerMap {
             self.next_worker_id(),
             object.clone(),
             config,
+            &self.schedulers,
         )
     }
 
@@ -14,6 +14,7 @@ use crate::util::task::{spawn_background_task, Next, TaskError, TaskHandle};
 use crate::worker::retry_strategy;
 
 use super::error::RunError;
+use super::scheduler::ScheduledPath;
 use super::WorkerCmd;
 
 /// Whether or not to clear pending packets at this `step` for the given height.
@@ -74,9 +75,13 @@ fn handle_link_error_in_task(e: LinkError) -> TaskError<RunError> {
     }
 }
 
+/// Relays the scheduled packets of the link. If `scheduled` is set, every
+/// transaction to the destination chain waits for a slot from the scheduler
+/// of that chain.
 pub fn spawn_packet_worker<ChainA: ChainHandle, ChainB: ChainHandle>(
     path: Packet,
     link: Arc<Link<ChainA, ChainB>>,
+    scheduled: Option<ScheduledPath>,
 ) -> TaskHandle {
     spawn_background_task(
         format!("PacketWorker({})", link.a_to_b),
@@ -86,12 +91,20 @@ pub fn spawn_packet_worker<ChainA: ChainHandle, ChainB: ChainHandle>(
                 .refresh_schedule()
                 .map_err(handle_link_error_in_task)?;
 
-            link.a_to_b
-                .execute_schedule()
-                .map_err(handle_link_error_in_task)?;
+            match &scheduled {
+                Some(scheduled) => link
+                    .a_to_b
+                    .execute_schedule_with(|| scheduled.try_acquire()),
+                None => link.a_to_b.execute_schedule(),
+            }
+            .map_err(handle_link_error_in_task)?;
 
             let summary = link.a_to_b.process_pending_txs();
 
+            if let Some(scheduled) = &scheduled {
+                scheduled.report_inflight(link.a_to_b.pending_dst_txs_count());
+            }
+
             if !summary.is_empty() {
                 trace!("Packet worker produced relay summary: {:?}", summary);
             }
@@ -103,6 +116,25 @@ pub fn spawn_packet_worker<ChainA: ChainHandle, ChainB: ChainHandle>(
     )
 }
 
+/// Schedules the timeouts of the packets relayed by the link which expired on the destination
+/// chain before being received, without waiting for new events on the path to process them.
+/// The scheduled timeouts are then submitted by the packet worker.
+pub fn spawn_packet_timeout_worker<ChainA: ChainHandle, ChainB: ChainHandle>(
+    link: Arc<Link<ChainA, ChainB>>,
+) -> TaskHandle {
+    spawn_background_task(
+        format!("PacketTimeoutWorker({})", link.a_to_b),
+        Some(Duration::from_secs(5)),
+        move || {
+            link.a_to_b
+                .schedule_expired_timeouts()
+                .map_err(handle_link_error_in_task)?;
+
+            Ok(Next::Continue)
+        },
+    )
+}
+
 /// Receives worker commands, which may be:
 ///     - IbcEvent => then it updates schedule
 ///     - NewBlock => schedules packet clearing
@@ -0,0 +1,419 @@
+//! Scheduling of the transactions submitted by the packet workers
+//! relaying to the same destination chain.
+//!
+//! The transaction slots of a destination chain are allocated across its
+//! packet workers by stride scheduling: every time a worker is granted a slot,
+//! its pass is advanced by a stride inversely proportional to its weight, and
+//! free slots go to the waiting workers with the lowest pass first. Workers
+//! which have been waiting for longer than the starvation timeout are served
+//! before all the others.
+
+use alloc::collections::btree_map::BTreeMap as HashMap;
+use core::time::Duration;
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
+
+use tracing::{debug, warn};
+
+use ibc::core::ics24_host::identifier::ChainId;
+
+use crate::config::{Config, PacketScheduler};
+use crate::object::Packet;
+use crate::util::lock::LockExt;
+
+/// The pass advance of a worker with weight `1`
+const STRIDE: u64 = 1 << 20;
+
+/// How long a worker is considered to be competing for slots after its
+/// latest request. Packet workers request slots every half second while
+/// they have messages to submit.
+const REQUEST_WINDOW: Duration = Duration::from_secs(2);
+
+/// Returns the configuration of the scheduler of the destination chain `chain_id`,
+/// if scheduling is enabled for it.
+///
+/// A transaction slot is only released once the packet worker sees its transaction
+/// confirmed, so scheduling is disabled unless `tx_confirmation` is enabled.
+pub fn scheduler_config<'a>(config: &'a Config, chain_id: &ChainId) -> Option<&'a PacketScheduler> {
+    let scheduler_config = config
+        .find_chain(chain_id)
+        .map(|chain_config| &chain_config.packet_scheduler)
+        .filter(|scheduler_config| scheduler_config.is_enabled())?;
+
+    if !config.mode.packets.tx_confirmation {
+        warn!(
+            chain = %chain_id,
+            "packet scheduling requires `tx_confirmation = true`, scheduling is disabled"
+        );
+        return None;
+    }
+
+    Some(scheduler_config)
+}
+
+/// The transaction schedulers of all the destination chains, shared by the packet workers.
+#[derive(Clone, Debug, Default)]
+pub struct TxSchedulers(Arc<RwLock<HashMap<ChainId, TxScheduler>>>);
+
+impl TxSchedulers {
+    /// Returns the scheduler of the given chain, configured with `config`.
+    pub fn get_or_create(&self, chain_id: &ChainId, config: &PacketScheduler) -> TxScheduler {
+        let mut schedulers = self.0.acquire_write();
+
+        let scheduler = schedulers
+            .entry(chain_id.clone())
+            .or_insert_with(|| TxScheduler::new(config));
+
+        // Pick up changes to the configuration of the chain
+        scheduler.configure(config);
+
+        scheduler.clone()
+    }
+}
+
+/// A handle to the transaction scheduler of a destination chain.
+#[derive(Clone, Debug)]
+pub struct TxScheduler(Arc<RwLock<SchedulerState>>);
+
+impl TxScheduler {
+    pub fn new(config: &PacketScheduler) -> Self {
+        Self(Arc::new(RwLock::new(SchedulerState {
+            max_inflight_txs: config.max_inflight_txs,
+            starvation_timeout: config.starvation_timeout,
+            members: HashMap::new(),
+            next_registration: 0,
+        })))
+    }
+
+    fn configure(&self, config: &PacketScheduler) {
+        let mut state = self.0.acquire_write();
+        state.max_inflight_txs = config.max_inflight_txs;
+        state.starvation_timeout = config.starvation_timeout;
+    }
+
+    /// Registers the packet worker for `path` with the given weight. The worker
+    /// is unregistered once the returned [`ScheduledPath`] is dropped.
+    pub fn register(&self, path: Packet, weight: u64) -> ScheduledPath {
+        let mut state = self.0.acquire_write();
+
+        // Start newcomers on par with the existing members, so that
+        // they neither wait for nor overtake them for long.
+        let pass = state.members.values().map(|m| m.pass).min().unwrap_or(0);
+
+        let registration = state.next_registration;
+        state.next_registration += 1;
+
+        state.members.insert(
+            path.clone(),
+            Member {
+                registration,
+                weight: weight.max(1),
+                pass,
+                inflight: 0,
+                waiting_since: Instant::now(),
+                last_request: None,
+            },
+        );
+
+        ScheduledPath {
+            scheduler: self.clone(),
+            path,
+            registration,
+        }
+    }
+}
+
+/// The registration of a packet worker with the scheduler of its destination chain.
+#[derive(Debug)]
+pub struct ScheduledPath {
+    scheduler: TxScheduler,
+    path: Packet,
+    registration: u64,
+}
+
+impl ScheduledPath {
+    /// Requests a slot for submitting one transaction. Returns `true` if the slot
+    /// is granted, otherwise the worker should request it again later on.
+    pub fn try_acquire(&self) -> bool {
+        self.try_acquire_at(Instant::now())
+    }
+
+    fn try_acquire_at(&self, now: Instant) -> bool {
+        let granted = self
+            .scheduler
+            .0
+            .acquire_write()
+            .try_acquire(&self.path, now);
+
+        if !granted {
+            debug!(path = %self.path.short_name(), "waiting for a transaction slot");
+        }
+
+        granted
+    }
+
+    /// Reports the number of transactions submitted by this worker
+    /// which are not confirmed yet.
+    pub fn report_inflight(&self, inflight: usize) {
+        if let Some(member) = self.scheduler.0.acquire_write().members.get_mut(&self.path) {
+            member.inflight = inflight;
+        }
+    }
+}
+
+impl Drop for ScheduledPath {
+    fn drop(&mut self) {
+        let mut state = self.scheduler.0.acquire_write();
+
+        // Do not remove a newer registration for the same path
+        if state
+            .members
+            .get(&self.path)
+            .map_or(false, |m| m.registration == self.registration)
+        {
+            state.members.remove(&self.path);
+        }
+    }
+}
+
+#[derive(Debug)]
+struct SchedulerState {
+    max_inflight_txs: usize,
+    starvation_timeout: Duration,
+    members: HashMap<Packet, Member>,
+    next_registration: u64,
+}
+
+#[derive(Debug)]
+struct Member {
+    registration: u64,
+    weight: u64,
+    pass: u64,
+    inflight: usize,
+    /// Since when the member has been waiting for a slot, i.e. the time of its latest
+    /// grant, or of its first request after being idle
+    waiting_since: Instant,
+    last_request: Option<Instant>,
+}
+
+/// The rank of a waiting member, lowest first
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Rank {
+    Starving(Instant),
+    Pass(u64),
+}
+
+impl Member {
+    fn is_waiting(&self, now: Instant) -> bool {
+        self.last_request.map_or(false, |last| {
+            now.saturating_duration_since(last) < REQUEST_WINDOW
+        })
+    }
+
+    fn rank(&self, now: Instant, starvation_timeout: Duration) -> Rank {
+        if now.saturating_duration_since(self.waiting_since) > starvation_timeout {
+            Rank::Starving(self.waiting_since)
+        } else {
+            Rank::Pass(self.pass)
+        }
+    }
+}
+
+impl SchedulerState {
+    fn try_acquire(&mut self, path: &Packet, now: Instant) -> bool {
+        if self.max_inflight_txs == 0 {
+            return true;
+        }
+
+        let rank = match self.members.get_mut(path) {
+            Some(member) => {
+                if !member.is_waiting(now) {
+                    member.waiting_since = now;
+                }
+                member.last_request = Some(now);
+                member.rank(now, self.starvation_timeout)
+            }
+            // Workers which are not registered are not scheduled
+            None => return true,
+        };
+
+        let inflight: usize = self.members.values().map(|m| m.inflight).sum();
+        let free = self.max_inflight_txs.saturating_sub(inflight);
+
+        // Leave enough free slots for the waiting members ranked ahead
+        let ahead = self
+            .members
+            .iter()
+            .filter(|(p, m)| {
+                *p != path
+                    && m.is_waiting(now)
+                    && (m.rank(now, self.starvation_timeout), *p) < (rank, path)
+            })
+            .count();
+
+        if ahead >= free {
+            return false;
+        }
+
+        if let Some(member) = self.members.get_mut(path) {
+            member.pass += STRIDE / member.weight;
+            member.inflight += 1;
+            member.waiting_since = now;
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ibc::core::ics24_host::identifier::{ChannelId, PortId};
+
+    use crate::chain::mock::test_utils::get_basic_chain_config;
+
+    fn path(channel: u64) -> Packet {
+        Packet {
+            dst_chain_id: ChainId::new("ibc-1".to_string(), 1),
+            src_chain_id: ChainId::new("ibc-0".to_string(), 0),
+            src_channel_id: ChannelId::new(channel),
+            src_port_id: PortId::transfer(),
+        }
+    }
+
+    fn scheduler(max_inflight_txs: usize) -> TxScheduler {
+        TxScheduler::new(&PacketScheduler {
+            max_inflight_txs,
+            ..Default::default()
+        })
+    }
+
+    fn config(max_inflight_txs: usize, tx_confirmation: bool) -> Config {
+        let mut chain_config = get_basic_chain_config("ibc-1");
+        chain_config.packet_scheduler.max_inflight_txs = max_inflight_txs;
+
+        let mut config = Config {
+            chains: vec![chain_config],
+            ..Default::default()
+        };
+        config.mode.packets.tx_confirmation = tx_confirmation;
+        config
+    }
+
+    #[test]
+    fn scheduling_requires_tx_confirmation() {
+        let chain_id = path(0).dst_chain_id;
+
+        assert!(scheduler_config(&config(2, true), &chain_id).is_some());
+        assert!(scheduler_config(&config(2, false), &chain_id).is_none());
+        assert!(scheduler_config(&config(0, true), &chain_id).is_none());
+
+        let other_chain = ChainId::new("ibc-2".to_string(), 2);
+        assert!(scheduler_config(&config(2, true), &other_chain).is_none());
+    }
+
+    #[test]
+    fn confirmed_txs_release_slots() {
+        // Slots are only released once the confirmed transactions are reported
+        let scheduler = scheduler(1);
+        let a = scheduler.register(path(0), 1);
+        let b = scheduler.register(path(1), 1);
+
+        assert!(a.try_acquire());
+        assert!(!b.try_acquire());
+        assert!(!b.try_acquire());
+
+        a.report_inflight(0);
+        assert!(b.try_acquire());
+    }
+
+    #[test]
+    fn disabled_scheduler_grants_all() {
+        let scheduler = scheduler(0);
+        let a = scheduler.register(path(0), 1);
+
+        assert!((0..100).all(|_| a.try_acquire()));
+    }
+
+    #[test]
+    fn max_inflight_txs() {
+        let scheduler = scheduler(2);
+        let a = scheduler.register(path(0), 1);
+
+        assert!(a.try_acquire());
+        assert!(a.try_acquire());
+        assert!(!a.try_acquire());
+
+        a.report_inflight(1);
+        assert!(a.try_acquire());
+        assert!(!a.try_acquire());
+    }
+
+    #[test]
+    fn slots_follow_weights() {
+        let scheduler = scheduler(1);
+        let busy = scheduler.register(path(0), 1);
+        let critical = scheduler.register(path(1), 3);
+
+        let now = Instant::now();
+        let mut granted = (0, 0);
+
+        for _ in 0..40 {
+            // Both workers compete for the single slot, which is released after each round
+            if busy.try_acquire_at(now) {
+                granted.0 += 1;
+            }
+            if critical.try_acquire_at(now) {
+                granted.1 += 1;
+            }
+
+            busy.report_inflight(0);
+            critical.report_inflight(0);
+        }
+
+        assert_eq!(granted, (10, 30));
+    }
+
+    #[test]
+    fn starving_member_is_served_first() {
+        let scheduler = scheduler(1);
+        let heavy = scheduler.register(path(0), 1000);
+        let light = scheduler.register(path(1), 1);
+
+        let start = Instant::now();
+
+        // Once served, the light worker falls far behind the heavy one
+        assert!(light.try_acquire_at(start));
+        light.report_inflight(0);
+
+        let mut light_granted_after = None;
+
+        for i in 1..=70 {
+            let now = start + Duration::from_millis(500) * i;
+
+            heavy.try_acquire_at(now);
+            if light.try_acquire_at(now) {
+                light_granted_after = Some(now.duration_since(start));
+                break;
+            }
+
+            heavy.report_inflight(0);
+        }
+
+        assert_eq!(light_granted_after, Some(Duration::from_millis(30_500)));
+    }
+
+    #[test]
+    fn dropped_member_releases_slots() {
+        let scheduler = scheduler(1);
+        let a = scheduler.register(path(0), 1);
+        let b = scheduler.register(path(1), 1);
+
+        assert!(a.try_acquire());
+        assert!(!b.try_acquire());
+
+        drop(a);
+        assert!(b.try_acquire());
+    }
+}
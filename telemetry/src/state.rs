@@ -2,7 +2,7 @@ use core::fmt;
 
 use opentelemetry::{
     global,
-    metrics::{Counter, UpDownCounter},
+    metrics::{Counter, UpDownCounter, ValueRecorder},
     KeyValue,
 };
 use opentelemetry_prometheus::PrometheusExporter;
@@ -42,6 +42,12 @@ pub struct TelemetryState {
     /// Number of client misbehaviours per client
     ibc_client_misbehaviours: Counter<u64>,
 
+    /// Number of client upgrades per client
+    ibc_client_upgrades: Counter<u64>,
+
+    /// Height of the upgrade plan scheduled on the source chain of a client, per client
+    ibc_client_upgrade_plan_height: ValueRecorder<u64>,
+
     /// Number of receive packets relayed, per channel
     receive_packets: Counter<u64>,
 
@@ -50,6 +56,28 @@ pub struct TelemetryState {
 
     /// Number of timeout packets relayed, per channel
     timeout_packets: Counter<u64>,
+
+    /// Seconds left until the trusting period of a client expires, per client
+    client_expiry: ValueRecorder<i64>,
+
+    /// Difference between the latest height of the source chain
+    /// and the latest height of a client, per client
+    client_height_lag: ValueRecorder<u64>,
+
+    /// UNIX timestamp (in seconds) of the last `NewBlock` event seen, per chain
+    chain_last_new_block: ValueRecorder<u64>,
+
+    /// Number of objects scanned on startup, per chain and object type
+    startup_scanned_objects: Counter<u64>,
+
+    /// Duration (in milliseconds) of the startup scan, per chain
+    startup_scan_duration: ValueRecorder<u64>,
+
+    /// Number of queries served from the query cache, per chain and query type
+    query_cache_hits: Counter<u64>,
+
+    /// Number of queries not found in the query cache, per chain and query type
+    query_cache_misses: Counter<u64>,
 }
 
 impl TelemetryState {
@@ -84,6 +112,34 @@ impl TelemetryState {
         self.ibc_client_misbehaviours.add(count, labels);
     }
 
+    /// Number of client upgrades per client
+    pub fn ibc_client_upgrades(&self, chain: &ChainId, client: &ClientId, count: u64) {
+        let labels = &[
+            KeyValue::new("chain", chain.to_string()),
+            KeyValue::new("client", client.to_string()),
+        ];
+
+        self.ibc_client_upgrades.add(count, labels);
+    }
+
+    /// Height of the upgrade plan scheduled on the source chain of a client,
+    /// or zero once the client has been upgraded
+    pub fn ibc_client_upgrade_plan_height(
+        &self,
+        chain: &ChainId,
+        client: &ClientId,
+        src_chain: &ChainId,
+        height: u64,
+    ) {
+        let labels = &[
+            KeyValue::new("chain", chain.to_string()),
+            KeyValue::new("client", client.to_string()),
+            KeyValue::new("src_chain", src_chain.to_string()),
+        ];
+
+        self.ibc_client_upgrade_plan_height.record(height, labels);
+    }
+
     /// Number of receive packets relayed, per channel
     pub fn ibc_receive_packets(
         &self,
@@ -132,6 +188,86 @@ impl TelemetryState {
 
         self.timeout_packets.add(count, labels);
     }
+
+    /// Seconds left until the trusting period of a client expires.
+    /// A negative value means the client has already expired.
+    pub fn client_expiry(&self, chain: &ChainId, client: &ClientId, seconds: i64) {
+        let labels = &[
+            KeyValue::new("chain", chain.to_string()),
+            KeyValue::new("client", client.to_string()),
+        ];
+
+        self.client_expiry.record(seconds, labels);
+    }
+
+    /// Number of blocks the client is lagging behind the source chain
+    pub fn client_height_lag(
+        &self,
+        chain: &ChainId,
+        client: &ClientId,
+        src_chain: &ChainId,
+        lag: u64,
+    ) {
+        let labels = &[
+            KeyValue::new("chain", chain.to_string()),
+            KeyValue::new("client", client.to_string()),
+            KeyValue::new("src_chain", src_chain.to_string()),
+        ];
+
+        self.client_height_lag.record(lag, labels);
+    }
+
+    /// UNIX timestamp (in seconds) at which the last `NewBlock` event was seen for a chain
+    pub fn chain_last_new_block(&self, chain: &ChainId, timestamp: u64) {
+        let labels = &[KeyValue::new("chain", chain.to_string())];
+
+        self.chain_last_new_block.record(timestamp, labels);
+    }
+
+    /// Number of clients, connections or channels scanned on startup, either
+    /// by querying the chain or from the cache of a previous scan
+    pub fn startup_scanned_objects(
+        &self,
+        chain: &ChainId,
+        object_type: &'static str,
+        cached: bool,
+        count: u64,
+    ) {
+        let labels = &[
+            KeyValue::new("chain", chain.to_string()),
+            KeyValue::new("type", object_type),
+            KeyValue::new("cached", cached.to_string()),
+        ];
+
+        self.startup_scanned_objects.add(count, labels);
+    }
+
+    /// Duration (in milliseconds) of the scan of a chain on startup
+    pub fn startup_scan_duration(&self, chain: &ChainId, millis: u64) {
+        let labels = &[KeyValue::new("chain", chain.to_string())];
+
+        self.startup_scan_duration.record(millis, labels);
+    }
+
+    /// A query served from the query cache of a chain
+    pub fn query_cache_hit(&self, chain: &ChainId, query_type: &'static str) {
+        let labels = &[
+            KeyValue::new("chain", chain.to_string()),
+            KeyValue::new("query_type", query_type),
+        ];
+
+        self.query_cache_hits.add(1, labels);
+    }
+
+    /// A query which was not found in the query cache of a chain
+    pub fn query_cache_miss(&self, chain: &ChainId, query_type: &'static str) {
+        let labels = &[
+            KeyValue::new("chain", chain.to_string()),
+            KeyValue::new("query_type", query_type),
+        ];
+
+        self.query_cache_misses.add(1, labels);
+    }
 }
 
 impl Default for TelemetryState {
@@ -157,6 +293,18 @@ impl Default for TelemetryState {
                 .with_description("Number of misbehaviours detected per client")
                 .init(),
 
+            ibc_client_upgrades: meter
+                .u64_counter("ibc_client_upgrades")
+                .with_description("Number of client upgrades performed per client")
+                .init(),
+
+            ibc_client_upgrade_plan_height: meter
+                .u64_value_recorder("ibc_client_upgrade_plan_height")
+                .with_description(
+                    "Height of the upgrade plan scheduled on the source chain of a client",
+                )
+                .init(),
+
             receive_packets: meter
                 .u64_counter("ibc_receive_packets")
                 .with_description("Number of receive packets relayed per channel")
@@ -171,6 +319,45 @@ impl Default for TelemetryState {
                 .u64_counter("ibc_timeout_packets")
                 .with_description("Number of timeout packets relayed per channel")
                 .init(),
+
+            client_expiry: meter
+                .i64_value_recorder("ibc_client_expiry_seconds")
+                .with_description("Seconds until the trusting period of a client expires")
+                .init(),
+
+            client_height_lag: meter
+                .u64_value_recorder("ibc_client_height_lag")
+                .with_description(
+                    "Number of blocks a client is lagging behind the latest height of its source chain",
+                )
+                .init(),
+
+            chain_last_new_block: meter
+                .u64_value_recorder("ibc_chain_last_new_block_timestamp")
+                .with_description("UNIX timestamp of the last NewBlock event seen per chain")
+                .init(),
+
+            startup_scanned_objects: meter
+                .u64_counter("startup_scanned_objects")
+                .with_description(
+                    "Number of clients, connections and channels scanned on startup per chain",
+                )
+                .init(),
+
+            startup_scan_duration: meter
+                .u64_value_recorder("startup_scan_duration_milliseconds")
+                .with_description("Duration of the startup scan per chain")
+                .init(),
+
+            query_cache_hits: meter
+                .u64_counter("query_cache_hits")
+                .with_description("Number of queries served from the query cache per chain")
+                .init(),
+
+            query_cache_misses: meter
+                .u64_counter("query_cache_misses")
+                .with_description("Number of queries not found in the query cache per chain")
+                .init(),
         }
     }
 }
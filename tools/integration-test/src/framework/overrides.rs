@@ -75,7 +75,7 @@ pub trait TestOverrides {
         config: &SharedConfig,
         registry: &SharedRegistry<impl ChainHandle>,
     ) -> Result<Option<SupervisorHandle>, Error> {
-        let handle = spawn_supervisor(config.clone(), registry.clone(), None, false)?;
+        let handle = spawn_supervisor(config.clone(), registry.clone(), None, None, false)?;
         Ok(Some(handle))
     }
 
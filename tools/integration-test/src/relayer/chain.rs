@@ -27,7 +27,7 @@ use ibc::core::ics02_client::events::UpdateClient;
 use ibc::core::ics02_client::misbehaviour::MisbehaviourEvidence;
 use ibc::core::ics03_connection::connection::IdentifiedConnectionEnd;
 use ibc::core::ics04_channel;
-use ibc::core::ics04_channel::channel::IdentifiedChannelEnd;
+use ibc::core::ics04_channel::channel::{IdentifiedChannelEnd, QueryPacketEventDataRequest};
 use ibc::core::ics04_channel::packet::{PacketMsgType, Sequence};
 use ibc::query::QueryTxRequest;
 use ibc::{
@@ -45,6 +45,7 @@ use ibc::{
     signer::Signer,
     Height,
 };
+use ibc_proto::cosmos::upgrade::v1beta1::Plan;
 use ibc_proto::ibc::core::channel::v1::{
     PacketState, QueryChannelClientStateRequest, QueryChannelsRequest,
     QueryConnectionChannelsRequest, QueryNextSequenceReceiveRequest,
@@ -57,7 +58,7 @@ use ibc_proto::ibc::core::connection::v1::QueryClientConnectionsRequest;
 use ibc_proto::ibc::core::connection::v1::QueryConnectionsRequest;
 use ibc_relayer::chain::handle::requests::AppVersion;
 use ibc_relayer::chain::handle::{ChainHandle, ChainRequest, Subscription};
-use ibc_relayer::chain::{HealthCheck, StatusResponse};
+use ibc_relayer::chain::{HealthCheck, PacketTx, StatusResponse, TxSimulation};
 use ibc_relayer::config::ChainConfig;
 use ibc_relayer::error::Error;
 use ibc_relayer::{connection::ConnectionMsgType, keyring::KeyEntry};
@@ -103,6 +104,10 @@ where
         self.value().send_messages_and_wait_check_tx(proto_msgs)
     }
 
+    fn simulate_messages(&self, proto_msgs: Vec<prost_types::Any>) -> Result<TxSimulation, Error> {
+        self.value().simulate_messages(proto_msgs)
+    }
+
     fn get_signer(&self) -> Result<Signer, Error> {
         self.value().get_signer()
     }
@@ -184,6 +189,10 @@ where
         self.value().query_upgraded_consensus_state(height)
     }
 
+    fn query_upgrade_plan(&self) -> Result<Option<Plan>, Error> {
+        self.value().query_upgrade_plan()
+    }
+
     fn query_commitment_prefix(&self) -> Result<CommitmentPrefix, Error> {
         self.value().query_commitment_prefix()
     }
@@ -377,6 +386,13 @@ where
         self.value().query_txs(request)
     }
 
+    fn query_packet_txs(
+        &self,
+        request: QueryPacketEventDataRequest,
+    ) -> Result<Vec<PacketTx>, Error> {
+        self.value().query_packet_txs(request)
+    }
+
     fn query_blocks(
         &self,
         request: QueryBlockRequest,
@@ -107,6 +107,7 @@ impl TestOverrides for ExpirationTestOverrides {
                 enabled: true,
                 refresh: true,
                 misbehaviour: true,
+                upgrade: false,
             },
             connections: config::Connections { enabled: true },
             channels: config::Channels { enabled: true },
@@ -151,8 +152,13 @@ impl BinaryChainTest for ChannelExpirationTest {
 
         wait_for_client_expiry();
 
-        let _supervisor =
-            spawn_supervisor(chains.config.clone(), chains.registry.clone(), None, false)?;
+        let _supervisor = spawn_supervisor(
+            chains.config.clone(),
+            chains.registry.clone(),
+            None,
+            None,
+            false,
+        )?;
 
         let port_a = tagged_transfer_port();
         let port_b = tagged_transfer_port();
@@ -297,8 +303,13 @@ impl BinaryChainTest for PacketExpirationTest {
 
         wait_for_client_expiry();
 
-        let _supervisor =
-            spawn_supervisor(chains.config.clone(), chains.registry.clone(), None, false)?;
+        let _supervisor = spawn_supervisor(
+            chains.config.clone(),
+            chains.registry.clone(),
+            None,
+            None,
+            false,
+        )?;
 
         let denom_a = chains.node_a.denom();
         let balance_a = chains
@@ -22,6 +22,7 @@ impl TestOverrides for SupervisorTest {
                 enabled: true,
                 refresh: true,
                 misbehaviour: true,
+                upgrade: false,
             },
             connections: config::Connections { enabled: true },
             channels: config::Channels { enabled: true },
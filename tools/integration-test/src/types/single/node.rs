@@ -143,10 +143,19 @@ impl FullNode {
             max_block_time: Default::default(),
             clock_drift: Duration::from_secs(5),
             trusting_period: Some(Duration::from_secs(14 * 24 * 3600)),
+            verify_query_proofs: false,
             trust_threshold: Default::default(),
             gas_price: config::GasPrice::new(0.001, "stake".to_string()),
             packet_filter: Default::default(),
+            ics20_filter: Default::default(),
+            packet_scheduler: Default::default(),
+            query_cache: Default::default(),
+            light_store: Default::default(),
+            query_trust_anchor: None,
+            failover: Default::default(),
+            event_source: Default::default(),
             address_type: Default::default(),
+            backup_nodes: vec![],
             memo_prefix: Default::default(),
             proof_specs: Default::default(),
         })